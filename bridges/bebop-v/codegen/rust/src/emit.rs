@@ -0,0 +1,576 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+//
+// emit.rs - Rust source generation from a validated Schema
+//
+// Each schema becomes one `pub mod <name>` holding a private copy of
+// runtime.rs (`bebop_runtime`) and, per definition:
+//
+//   enum     `#[repr(<base>)]` enum, `from_raw`, Wire impl
+//   struct   struct with plain fields, Wire impl, `encode`/`decode`
+//   message  struct with `Option` fields (absent = not on the wire), Wire impl,
+//            `encode`/`decode`
+//
+// plus a `#[repr(C)] V<Name>` view for every struct and message whose fields
+// have a flat C representation, following the conventions of
+// include/bebop_v_ffi.h: strings and byte arrays are `VBytes`, enums are their
+// base integer, arrays are `<name>_count` + `<name>` pointer, maps are
+// `<name>_count` + `<name>_keys` + `<name>_values`, nested types are inlined,
+// and messages end with `error_code` / `error_message`. Arrays of arrays and
+// maps of arrays have no such representation; their types get no view.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::schema::{Definition, Enum, Field, Message, Schema, Struct, Type};
+
+const RUNTIME: &str = include_str!("runtime.rs");
+
+/// Rust keywords, used as field names via raw identifiers.
+const KEYWORDS: [&str; 48] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Keywords that cannot be raw identifiers.
+const RESERVED: [&str; 5] = ["self", "Self", "super", "crate", "_"];
+
+/// `sensorId` -> `sensor_id`, `HTTPServer` -> `http_server`.
+pub(crate) fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let next = chars.get(i + 1);
+            let boundary = match prev {
+                Some(p) if p.is_ascii_lowercase() || p.is_ascii_digit() => true,
+                Some(p) if p.is_ascii_uppercase() => next.is_some_and(|n| n.is_ascii_lowercase()),
+                _ => false,
+            };
+            if boundary && !out.ends_with('_') {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Field name as a Rust identifier.
+fn ident(name: &str) -> String {
+    let snake = snake_case(name);
+    if RESERVED.contains(&snake.as_str()) {
+        format!("{snake}_")
+    } else if KEYWORDS.contains(&snake.as_str()) {
+        format!("r#{snake}")
+    } else {
+        snake
+    }
+}
+
+/// Rust type of a scalar.
+fn int_type(ty: &Type) -> &'static str {
+    match ty {
+        Type::Bool => "bool",
+        Type::Byte => "u8",
+        Type::UInt16 => "u16",
+        Type::UInt32 => "u32",
+        Type::UInt64 | Type::Date => "u64",
+        Type::Int8 => "i8",
+        Type::Int16 => "i16",
+        Type::Int32 => "i32",
+        Type::Int64 => "i64",
+        Type::Float32 => "f32",
+        Type::Float64 => "f64",
+        _ => unreachable!("{ty} is not a scalar"),
+    }
+}
+
+fn rust_type(ty: &Type) -> String {
+    match ty {
+        Type::String => "String".into(),
+        Type::Guid => "bebop_runtime::Guid".into(),
+        Type::Array(item) => format!("Vec<{}>", rust_type(item)),
+        Type::Map(k, v) => format!("BTreeMap<{}, {}>", rust_type(k), rust_type(v)),
+        Type::Named(name) => name.clone(),
+        scalar => int_type(scalar).into(),
+    }
+}
+
+/// Line-oriented output with indentation.
+struct Out {
+    buf: String,
+    indent: usize,
+}
+
+impl Out {
+    fn line(&mut self, text: impl AsRef<str>) {
+        let text = text.as_ref();
+        if !text.is_empty() {
+            for _ in 0..self.indent {
+                self.buf.push_str("    ");
+            }
+            self.buf.push_str(text);
+        }
+        self.buf.push('\n');
+    }
+
+    fn open(&mut self, text: impl AsRef<str>) {
+        self.line(text);
+        self.indent += 1;
+    }
+
+    fn close(&mut self, text: &str) {
+        self.indent -= 1;
+        self.line(text);
+    }
+}
+
+struct Emitter<'a> {
+    defs: HashMap<&'a str, &'a Definition>,
+    /// Structs and messages that get a `V<Name>` view.
+    viewable: HashSet<&'a str>,
+    out: Out,
+}
+
+/// Generate `pub mod <module> { ... }` for `schema`.
+pub(crate) fn module(schema: &Schema, module: &str) -> String {
+    let defs: HashMap<&str, &Definition> = schema
+        .definitions
+        .iter()
+        .map(|def| (def.name(), def))
+        .collect();
+    let mut emitter = Emitter {
+        viewable: viewable(schema, &defs),
+        defs,
+        out: Out {
+            buf: String::new(),
+            indent: 0,
+        },
+    };
+    let out = &mut emitter.out;
+    out.line("// @generated by bebop-v-codegen. Do not edit; regenerate from the .bop schema.");
+    out.line("");
+    out.open(format!("pub mod {} {{", ident(module)));
+    out.line("#![allow(dead_code, unused_imports, clippy::all)]");
+    out.line("");
+    out.open("pub mod bebop_runtime {");
+    let runtime = RUNTIME.split("\n#[cfg(test)]").next().unwrap_or(RUNTIME);
+    for line in runtime.trim_end().lines() {
+        out.line(line);
+    }
+    out.close("}");
+    out.line("");
+    out.line("use std::collections::BTreeMap;");
+    out.line("");
+    out.line("use bebop_runtime::{DecodeError, Reader, Wire};");
+
+    for def in &schema.definitions {
+        emitter.out.line("");
+        match def {
+            Definition::Enum(e) => emitter.enumeration(e),
+            Definition::Struct(s) => emitter.structure(s),
+            Definition::Message(m) => emitter.message(m),
+        }
+    }
+    for def in &schema.definitions {
+        match def {
+            Definition::Struct(Struct { name, fields }) => emitter.view(name, fields, false),
+            Definition::Message(Message { name, fields }) => emitter.view(name, fields, true),
+            Definition::Enum(_) => {}
+        }
+    }
+    emitter.out.close("}");
+    emitter.out.buf
+}
+
+/// Structs and messages whose fields all have a view representation,
+/// found by removing offenders until nothing changes.
+fn viewable<'a>(schema: &'a Schema, defs: &HashMap<&'a str, &'a Definition>) -> HashSet<&'a str> {
+    let mut set: HashSet<&str> = schema
+        .definitions
+        .iter()
+        .filter(|def| !matches!(def, Definition::Enum(_)))
+        .map(Definition::name)
+        .collect();
+    loop {
+        let before = set.len();
+        let snapshot = set.clone();
+        set.retain(|name| {
+            let fields = match defs[name] {
+                Definition::Struct(s) => &s.fields,
+                Definition::Message(m) => &m.fields,
+                Definition::Enum(_) => unreachable!(),
+            };
+            fields
+                .iter()
+                .all(|f| view_fields(&f.ty, "", defs, &snapshot).is_some())
+        });
+        if set.len() == before {
+            return set;
+        }
+    }
+}
+
+/// C type of a single array element or map entry in a view.
+fn view_elem(
+    ty: &Type,
+    defs: &HashMap<&str, &Definition>,
+    viewable: &HashSet<&str>,
+) -> Option<(String, String)> {
+    Some(match ty {
+        Type::String => (
+            "bebop_runtime::VBytes".into(),
+            "bebop_runtime::VBytes::empty()".into(),
+        ),
+        Type::Guid => ("[u8; 16]".into(), "[0; 16]".into()),
+        Type::Bool => ("bool".into(), "false".into()),
+        Type::Float32 | Type::Float64 => (int_type(ty).into(), "0.0".into()),
+        Type::Array(_) | Type::Map(..) => return None,
+        Type::Named(name) => match defs[name.as_str()] {
+            Definition::Enum(e) => (int_type(&e.base).into(), "0".into()),
+            _ if viewable.contains(name.as_str()) => {
+                (format!("V{name}"), format!("V{name}::empty()"))
+            }
+            _ => return None,
+        },
+        scalar => (int_type(scalar).into(), "0".into()),
+    })
+}
+
+/// View fields for a schema field named `name`: (name, type, zero value).
+fn view_fields(
+    ty: &Type,
+    name: &str,
+    defs: &HashMap<&str, &Definition>,
+    viewable: &HashSet<&str>,
+) -> Option<Vec<(String, String, String)>> {
+    let base = snake_case(name);
+    Some(match ty {
+        Type::Array(item) if **item == Type::Byte => vec![(
+            ident(name),
+            "bebop_runtime::VBytes".into(),
+            "bebop_runtime::VBytes::empty()".into(),
+        )],
+        Type::Array(item) => {
+            let (elem, _) = view_elem(item, defs, viewable)?;
+            vec![
+                (format!("{base}_count"), "usize".into(), "0".into()),
+                (
+                    ident(name),
+                    format!("*mut {elem}"),
+                    "std::ptr::null_mut()".into(),
+                ),
+            ]
+        }
+        Type::Map(k, v) => {
+            let (key, _) = view_elem(k, defs, viewable)?;
+            let (value, _) = view_elem(v, defs, viewable)?;
+            vec![
+                (format!("{base}_count"), "usize".into(), "0".into()),
+                (
+                    format!("{base}_keys"),
+                    format!("*mut {key}"),
+                    "std::ptr::null_mut()".into(),
+                ),
+                (
+                    format!("{base}_values"),
+                    format!("*mut {value}"),
+                    "std::ptr::null_mut()".into(),
+                ),
+            ]
+        }
+        _ => {
+            let (elem, zero) = view_elem(ty, defs, viewable)?;
+            vec![(ident(name), elem, zero)]
+        }
+    })
+}
+
+impl Emitter<'_> {
+    /// Whether `ty` can derive `Eq` (no floats anywhere inside).
+    fn is_eq(&self, ty: &Type, seen: &mut HashSet<String>) -> bool {
+        match ty {
+            Type::Float32 | Type::Float64 => false,
+            Type::Array(item) => self.is_eq(item, seen),
+            Type::Map(k, v) => self.is_eq(k, seen) && self.is_eq(v, seen),
+            Type::Named(name) => {
+                if !seen.insert(name.clone()) {
+                    return true;
+                }
+                match self.defs[name.as_str()] {
+                    Definition::Enum(_) => true,
+                    Definition::Struct(Struct { fields, .. })
+                    | Definition::Message(Message { fields, .. }) => {
+                        fields.iter().all(|f| self.is_eq(&f.ty, seen))
+                    }
+                }
+            }
+            _ => true,
+        }
+    }
+
+    fn derives(&self, fields: &[Field]) -> &'static str {
+        let mut seen = HashSet::new();
+        if fields.iter().all(|f| self.is_eq(&f.ty, &mut seen)) {
+            "#[derive(Debug, Clone, PartialEq, Eq, Default)]"
+        } else {
+            "#[derive(Debug, Clone, PartialEq, Default)]"
+        }
+    }
+
+    fn enumeration(&mut self, e: &Enum) {
+        let name = &e.name;
+        let base = int_type(&e.base);
+        let out = &mut self.out;
+        out.line(format!("#[repr({base})]"));
+        out.line("#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]");
+        out.open(format!("pub enum {name} {{"));
+        for (i, (variant, value)) in e.variants.iter().enumerate() {
+            if i == 0 {
+                out.line("#[default]");
+            }
+            out.line(format!("{variant} = {value},"));
+        }
+        out.close("}");
+        out.line("");
+        out.open(format!("impl {name} {{"));
+        out.open(format!(
+            "pub const fn from_raw(value: {base}) -> Option<{name}> {{"
+        ));
+        out.open("match value {");
+        for (variant, value) in &e.variants {
+            out.line(format!("{value} => Some({name}::{variant}),"));
+        }
+        out.line("_ => None,");
+        out.close("}");
+        out.close("}");
+        out.close("}");
+        out.line("");
+        out.open(format!("impl Wire for {name} {{"));
+        out.open("fn encode_into(&self, out: &mut Vec<u8>) {");
+        out.line(format!("(*self as {base}).encode_into(out);"));
+        out.close("}");
+        out.line("");
+        out.open(format!(
+            "fn decode_from(r: &mut Reader<'_>) -> Result<{name}, DecodeError> {{"
+        ));
+        out.line(format!("let value = {base}::decode_from(r)?;"));
+        out.line(format!(
+            "{name}::from_raw(value).ok_or(DecodeError::InvalidEnum {{ name: \"{name}\", value: value as u64 }})"
+        ));
+        out.close("}");
+        out.close("}");
+    }
+
+    fn structure(&mut self, s: &Struct) {
+        let name = &s.name;
+        let derives = self.derives(&s.fields);
+        let out = &mut self.out;
+        out.line(derives);
+        out.open(format!("pub struct {name} {{"));
+        for f in &s.fields {
+            out.line(format!("pub {}: {},", ident(&f.name), rust_type(&f.ty)));
+        }
+        out.close("}");
+        out.line("");
+        out.open(format!("impl Wire for {name} {{"));
+        out.open("fn encode_into(&self, out: &mut Vec<u8>) {");
+        for f in &s.fields {
+            out.line(format!("self.{}.encode_into(out);", ident(&f.name)));
+        }
+        out.close("}");
+        out.line("");
+        out.open(format!(
+            "fn decode_from(r: &mut Reader<'_>) -> Result<{name}, DecodeError> {{"
+        ));
+        out.open(format!("Ok({name} {{"));
+        for f in &s.fields {
+            out.line(format!("{}: Wire::decode_from(r)?,", ident(&f.name)));
+        }
+        out.close("})");
+        out.close("}");
+        out.close("}");
+        self.inherent(name);
+    }
+
+    fn message(&mut self, m: &Message) {
+        let name = &m.name;
+        let derives = self.derives(&m.fields);
+        let out = &mut self.out;
+        out.line(derives);
+        out.open(format!("pub struct {name} {{"));
+        for f in &m.fields {
+            out.line(format!(
+                "pub {}: Option<{}>,",
+                ident(&f.name),
+                rust_type(&f.ty)
+            ));
+        }
+        out.close("}");
+        out.line("");
+        out.open(format!("impl Wire for {name} {{"));
+        out.open("fn encode_into(&self, out: &mut Vec<u8>) {");
+        for f in &m.fields {
+            out.open(format!("if let Some(value) = &self.{} {{", ident(&f.name)));
+            out.line(format!("out.push({});", f.index));
+            out.line("value.encode_into(out);");
+            out.close("}");
+        }
+        out.line("out.push(0);");
+        out.close("}");
+        out.line("");
+        out.open(format!(
+            "fn decode_from(r: &mut Reader<'_>) -> Result<{name}, DecodeError> {{"
+        ));
+        out.line(format!("let mut message = {name}::default();"));
+        out.open("while let Some(index) = r.field_index()? {");
+        out.open("match index {");
+        for f in &m.fields {
+            out.line(format!(
+                "{} => message.{} = Some(Wire::decode_from(r)?),",
+                f.index,
+                ident(&f.name)
+            ));
+        }
+        out.line("other => return Err(DecodeError::InvalidFieldIndex(other)),");
+        out.close("}");
+        out.close("}");
+        out.line("Ok(message)");
+        out.close("}");
+        out.close("}");
+        self.inherent(name);
+    }
+
+    /// `encode`/`decode` callable without importing `Wire`.
+    fn inherent(&mut self, name: &str) {
+        let out = &mut self.out;
+        out.line("");
+        out.open(format!("impl {name} {{"));
+        out.open("pub fn encode(&self) -> Vec<u8> {");
+        out.line("Wire::encode(self)");
+        out.close("}");
+        out.line("");
+        out.open(format!(
+            "pub fn decode(bytes: &[u8]) -> Result<{name}, DecodeError> {{"
+        ));
+        out.line("<Self as Wire>::decode(bytes)");
+        out.close("}");
+        out.close("}");
+    }
+
+    fn view(&mut self, name: &str, fields: &[Field], message: bool) {
+        self.out.line("");
+        if !self.viewable.contains(name) {
+            self.out.line(format!(
+                "// No V{name}: a field has no flat C representation."
+            ));
+            return;
+        }
+        let mut members = Vec::new();
+        for f in fields {
+            let view = view_fields(&f.ty, &f.name, &self.defs, &self.viewable);
+            members.extend(view.expect("viewable types have view fields"));
+        }
+        if message {
+            members.push(("error_code".into(), "i32".into(), "0".into()));
+            members.push((
+                "error_message".into(),
+                "*const std::ffi::c_char".into(),
+                "std::ptr::null()".into(),
+            ));
+        }
+
+        let out = &mut self.out;
+        out.line(format!(
+            "/// C layout of [`{name}`] (see include/bebop_v_ffi.h)."
+        ));
+        out.line("#[repr(C)]");
+        out.line("#[derive(Debug, Clone, Copy)]");
+        out.open(format!("pub struct V{name} {{"));
+        for (field, ty, _) in &members {
+            out.line(format!("pub {field}: {ty},"));
+        }
+        out.close("}");
+        out.line("");
+        out.open(format!("impl V{name} {{"));
+        out.open(format!("pub const fn empty() -> V{name} {{"));
+        out.open(format!("V{name} {{"));
+        for (field, _, zero) in &members {
+            out.line(format!("{field}: {zero},"));
+        }
+        out.close("}");
+        out.close("}");
+        out.close("}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::parse;
+
+    fn generate(src: &str) -> String {
+        module(&parse(src).unwrap(), "test")
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(snake_case("sensorId"), "sensor_id");
+        assert_eq!(snake_case("BatchReadings"), "batch_readings");
+        assert_eq!(snake_case("HTTPServer2Go"), "http_server2_go");
+        assert_eq!(snake_case("already_snake"), "already_snake");
+        assert_eq!(ident("type"), "r#type");
+        assert_eq!(ident("self"), "self_");
+    }
+
+    #[test]
+    fn float_types_do_not_derive_eq() {
+        let code = generate("struct P { x: float32; } struct Q { p: P[]; } struct R { n: int32; }");
+        let derives: Vec<_> = code
+            .lines()
+            .filter(|l| l.contains("derive(Debug, Clone, P"))
+            .collect();
+        assert_eq!(derives.len(), 3);
+        assert!(!derives[0].contains(" Eq,"));
+        assert!(!derives[1].contains(" Eq,"));
+        assert!(derives[2].contains(" Eq,"));
+    }
+
+    #[test]
+    fn views_follow_header_conventions() {
+        let code = generate(
+            "enum Kind : uint8 { A = 1 }
+             struct Tag { kind: Kind; raw: byte[]; }
+             message M { tags: Tag[]; names: map<string, guid>; }
+             message Nested { grid: int32[][]; }
+             struct Outer { n: Nested; }",
+        );
+        for expected in [
+            "pub struct VTag {",
+            "pub kind: u8,",
+            "pub raw: bebop_runtime::VBytes,",
+            "pub tags_count: usize,",
+            "pub tags: *mut VTag,",
+            "pub names_keys: *mut bebop_runtime::VBytes,",
+            "pub names_values: *mut [u8; 16],",
+            "pub error_message: *const std::ffi::c_char,",
+            "// No VNested: a field has no flat C representation.",
+            "// No VOuter: a field has no flat C representation.",
+        ] {
+            assert!(code.contains(expected), "missing {expected:?}");
+        }
+    }
+
+    #[test]
+    fn runtime_tests_are_not_embedded() {
+        let code = generate("struct S { a: bool; }");
+        assert!(code.contains("pub trait Wire"));
+        assert!(!code.contains("#[cfg(test)]"));
+    }
+}
@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+//
+// bebop-v-codegen - Rust code generator for Bebop .bop schemas
+//
+// Turns a schema such as schemas/sensors.bop into a self-contained Rust module
+// with:
+//
+//   - owned types (enums, structs, messages with `Option` fields),
+//   - `encode` / `decode` for the Bebop wire format used by the Zig bridge,
+//   - `#[repr(C)]` `V*` views laid out like the structs in
+//     include/bebop_v_ffi.h (e.g. `VSensorReading`).
+//
+// Use it from build.rs:
+//
+//   fn main() {
+//       bebop_v_codegen::compile("../../schemas/sensors.bop").unwrap();
+//   }
+//
+// and `include!(concat!(env!("OUT_DIR"), "/sensors.rs"));` in the crate.
+
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+mod emit;
+pub mod runtime;
+pub mod schema;
+
+/// Errors from parsing a schema or writing generated code.
+#[derive(Debug)]
+pub enum Error {
+    /// Malformed schema text.
+    Parse {
+        line: usize,
+        message: String,
+    },
+    /// Well-formed schema that cannot be generated (unknown types,
+    /// duplicate indices, ...).
+    Schema(String),
+    Io(io::Error),
+}
+
+impl Error {
+    pub(crate) fn parse(line: usize, message: impl Into<String>) -> Error {
+        Error::Parse {
+            line,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn schema(message: impl Into<String>) -> Error {
+        Error::Schema(message.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse { line, message } => write!(f, "line {line}: {message}"),
+            Error::Schema(message) => f.write_str(message),
+            Error::Io(err) => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+/// Generate Rust source for `source`, wrapped in `pub mod <module>`.
+pub fn generate(source: &str, module: &str) -> Result<String, Error> {
+    let schema = schema::parse(source)?;
+    Ok(emit::module(&schema, module))
+}
+
+/// build.rs helper: generate `$OUT_DIR/<stem>.rs` from the schema at `path`
+/// (module name `<stem>`) and ask Cargo to rerun when the schema changes.
+/// Returns the path written.
+///
+/// # Panics
+///
+/// If `OUT_DIR` is not set, i.e. when not called from a build script.
+pub fn compile(path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+    let path = path.as_ref();
+    let out_dir = std::env::var_os("OUT_DIR").expect("OUT_DIR is set for build scripts");
+    println!("cargo:rerun-if-changed={}", path.display());
+    compile_to(path, Path::new(&out_dir))
+}
+
+/// Generate `<out_dir>/<stem>.rs` from the schema at `path`.
+pub fn compile_to(path: &Path, out_dir: &Path) -> Result<PathBuf, Error> {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| Error::schema(format!("{}: no usable file name", path.display())))?;
+    let module = emit::snake_case(stem);
+    let source = fs::read_to_string(path)?;
+    let code = generate(&source, &module).map_err(|err| match err {
+        Error::Parse { line, message } => Error::Parse {
+            line,
+            message: format!("{}: {message}", path.display()),
+        },
+        Error::Schema(message) => Error::Schema(format!("{}: {message}", path.display())),
+        err => err,
+    })?;
+    let out = out_dir.join(format!("{module}.rs"));
+    fs::write(&out, code)?;
+    Ok(out)
+}
@@ -0,0 +1,300 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+//
+// runtime.rs - Wire support copied into every generated module
+//
+// The generator embeds this file verbatim as `bebop_runtime`, so generated
+// code has no dependency on this crate. Encoding follows the Bebop-V wire
+// format used by implementations/zig/src/bridge.zig: little-endian scalars,
+// u32-length-prefixed strings and arrays, structs as their fields in order,
+// and messages as (index byte, value) pairs closed by a 0 byte.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+/// 16 raw GUID bytes in wire order.
+pub type Guid = [u8; 16];
+
+/// Errors returned by generated `decode` functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    UnexpectedEnd,
+    InvalidUtf8,
+    /// A message field index not defined by the schema.
+    InvalidFieldIndex(u8),
+    /// A value not defined by the named enum.
+    InvalidEnum {
+        name: &'static str,
+        value: u64,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => f.write_str("unexpected end of data"),
+            DecodeError::InvalidUtf8 => f.write_str("invalid UTF-8 string"),
+            DecodeError::InvalidFieldIndex(index) => write!(f, "invalid field index {index}"),
+            DecodeError::InvalidEnum { name, value } => {
+                write!(f, "{value} is not a valid {name}")
+            }
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// Cursor over an input buffer.
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data, pos: 0 }
+    }
+
+    /// Bytes consumed so far.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    pub fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        if n > self.remaining() {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let bytes = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    pub fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    /// A u32 element count, capped for preallocation by the bytes left.
+    pub fn count(&mut self) -> Result<(usize, usize), DecodeError> {
+        let count = u32::decode_from(self)? as usize;
+        Ok((count, count.min(self.remaining())))
+    }
+
+    /// Next message field index; `None` at the 0 terminator or end of input.
+    pub fn field_index(&mut self) -> Result<Option<u8>, DecodeError> {
+        if self.remaining() == 0 {
+            return Ok(None);
+        }
+        match self.take(1)?[0] {
+            0 => Ok(None),
+            index => Ok(Some(index)),
+        }
+    }
+
+    /// A string borrowed from the input.
+    pub fn str(&mut self) -> Result<&'a str, DecodeError> {
+        let len = u32::decode_from(self)? as usize;
+        std::str::from_utf8(self.take(len)?).map_err(|_| DecodeError::InvalidUtf8)
+    }
+}
+
+/// Write a u32 length or count.
+///
+/// # Panics
+///
+/// If `len` does not fit in a u32, which the wire format cannot express.
+pub fn encode_len(len: usize, out: &mut Vec<u8>) {
+    let len = u32::try_from(len).expect("Bebop lengths are limited to u32::MAX");
+    out.extend_from_slice(&len.to_le_bytes());
+}
+
+/// A type with a Bebop wire representation.
+pub trait Wire: Sized {
+    fn encode_into(&self, out: &mut Vec<u8>);
+    fn decode_from(r: &mut Reader<'_>) -> Result<Self, DecodeError>;
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_into(&mut out);
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::decode_from(&mut Reader::new(bytes))
+    }
+}
+
+macro_rules! scalar {
+    ($($ty:ty),*) => {$(
+        impl Wire for $ty {
+            fn encode_into(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn decode_from(r: &mut Reader<'_>) -> Result<$ty, DecodeError> {
+                r.array().map(<$ty>::from_le_bytes)
+            }
+        }
+    )*};
+}
+
+scalar!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl Wire for bool {
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        out.push(u8::from(*self));
+    }
+
+    fn decode_from(r: &mut Reader<'_>) -> Result<bool, DecodeError> {
+        Ok(r.take(1)?[0] != 0)
+    }
+}
+
+impl Wire for String {
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        encode_len(self.len(), out);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode_from(r: &mut Reader<'_>) -> Result<String, DecodeError> {
+        r.str().map(str::to_owned)
+    }
+}
+
+impl Wire for Guid {
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self);
+    }
+
+    fn decode_from(r: &mut Reader<'_>) -> Result<Guid, DecodeError> {
+        r.array()
+    }
+}
+
+impl<T: Wire> Wire for Vec<T> {
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        encode_len(self.len(), out);
+        for item in self {
+            item.encode_into(out);
+        }
+    }
+
+    fn decode_from(r: &mut Reader<'_>) -> Result<Vec<T>, DecodeError> {
+        let (count, capacity) = r.count()?;
+        let mut items = Vec::with_capacity(capacity);
+        for _ in 0..count {
+            items.push(T::decode_from(r)?);
+        }
+        Ok(items)
+    }
+}
+
+/// Maps are written in key order.
+impl<K: Wire + Ord, V: Wire> Wire for BTreeMap<K, V> {
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        encode_len(self.len(), out);
+        for (k, v) in self {
+            k.encode_into(out);
+            v.encode_into(out);
+        }
+    }
+
+    fn decode_from(r: &mut Reader<'_>) -> Result<BTreeMap<K, V>, DecodeError> {
+        let (count, _) = r.count()?;
+        let mut map = BTreeMap::new();
+        for _ in 0..count {
+            let k = K::decode_from(r)?;
+            map.insert(k, V::decode_from(r)?);
+        }
+        Ok(map)
+    }
+}
+
+/// Byte slice in FFI views; layout of `VBytes` in include/bebop_v_ffi.h.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VBytes {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+impl VBytes {
+    pub const fn empty() -> VBytes {
+        VBytes {
+            ptr: std::ptr::null(),
+            len: 0,
+        }
+    }
+
+    pub fn from_slice(slice: &[u8]) -> VBytes {
+        VBytes {
+            ptr: if slice.is_empty() {
+                std::ptr::null()
+            } else {
+                slice.as_ptr()
+            },
+            len: slice.len(),
+        }
+    }
+}
+
+impl Default for VBytes {
+    fn default() -> VBytes {
+        VBytes::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scalars_are_little_endian() {
+        assert_eq!(0x0102u16.encode(), [2, 1]);
+        assert_eq!(23.5f64.encode(), [0, 0, 0, 0, 0, 0x80, 0x37, 0x40]);
+        assert_eq!(u64::decode(&2_000_000_000u64.encode()), Ok(2_000_000_000));
+        assert_eq!(u32::decode(&[1, 2]), Err(DecodeError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn strings_arrays_and_maps() {
+        assert_eq!("ok".to_owned().encode(), [2, 0, 0, 0, b'o', b'k']);
+        assert_eq!(
+            String::decode(&[1, 0, 0, 0, 0xff]),
+            Err(DecodeError::InvalidUtf8)
+        );
+
+        let items = vec![1u16, 2, 3];
+        assert_eq!(Vec::<u16>::decode(&items.encode()).unwrap(), items);
+
+        let map: BTreeMap<String, i32> = [("b".into(), -1), ("a".into(), 7)].into();
+        let bytes = map.encode();
+        assert_eq!(&bytes[4..9], [1, 0, 0, 0, b'a']);
+        assert_eq!(BTreeMap::decode(&bytes).unwrap(), map);
+    }
+
+    #[test]
+    fn huge_count_fails_without_allocating() {
+        assert_eq!(
+            Vec::<u64>::decode(&[0xff, 0xff, 0xff, 0xff]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn field_index_ends_at_zero_or_end() {
+        let mut r = Reader::new(&[3, 0, 9]);
+        assert_eq!(r.field_index(), Ok(Some(3)));
+        assert_eq!(r.field_index(), Ok(None));
+        assert_eq!(r.position(), 2);
+        let mut r = Reader::new(&[]);
+        assert_eq!(r.field_index(), Ok(None));
+    }
+}
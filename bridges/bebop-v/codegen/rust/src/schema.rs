@@ -0,0 +1,759 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+//
+// schema.rs - Parser for .bop schemas
+//
+// Accepts the subset of Bebop used in this repository plus upstream syntax:
+//
+//   enum Name : uint16 { A = 1  B = 2; }         // `;` / `,` optional
+//   [readonly] struct Name { x: float64;  int32 y; }
+//   message Name { a: string;  3 -> uint64 b;  c: map<string, string>; }
+//
+// Fields may be written `name: type` or `type name`. Message fields without
+// an explicit `N ->` index take the previous index plus one, starting at 1,
+// which is how sensors.bop numbers its fields. Types are the Bebop scalars,
+// `string`, `guid`, `date`, `T[]`, `array[T]`, `map[K, V]` / `map<K, V>` and
+// named enums, structs and messages. `[attribute(...)]` annotations are
+// skipped; `union`, `const` and `import` are rejected.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::Error;
+
+/// A parsed and validated schema, in definition order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    pub definitions: Vec<Definition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Definition {
+    Enum(Enum),
+    Struct(Struct),
+    Message(Message),
+}
+
+impl Definition {
+    pub fn name(&self) -> &str {
+        match self {
+            Definition::Enum(e) => &e.name,
+            Definition::Struct(s) => &s.name,
+            Definition::Message(m) => &m.name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enum {
+    pub name: String,
+    /// Underlying integer type; `uint32` when not given.
+    pub base: Type,
+    pub variants: Vec<(String, i128)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Struct {
+    pub name: String,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub name: String,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub ty: Type,
+    /// Wire index for message fields; 0 in structs.
+    pub index: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Bool,
+    Byte,
+    UInt16,
+    UInt32,
+    UInt64,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Float32,
+    Float64,
+    String,
+    Guid,
+    /// Bebop `date`: u64 ticks.
+    Date,
+    Array(Box<Type>),
+    Map(Box<Type>, Box<Type>),
+    Named(String),
+}
+
+impl Type {
+    fn scalar(name: &str) -> Option<Type> {
+        Some(match name {
+            "bool" => Type::Bool,
+            "byte" | "uint8" => Type::Byte,
+            "uint16" => Type::UInt16,
+            "uint32" => Type::UInt32,
+            "uint64" => Type::UInt64,
+            "int8" => Type::Int8,
+            "int16" => Type::Int16,
+            "int32" => Type::Int32,
+            "int64" => Type::Int64,
+            "float32" => Type::Float32,
+            "float64" => Type::Float64,
+            "string" => Type::String,
+            "guid" => Type::Guid,
+            "date" => Type::Date,
+            _ => return None,
+        })
+    }
+
+    /// Value range of an integer type usable as an enum base.
+    fn int_range(&self) -> Option<(i128, i128)> {
+        Some(match self {
+            Type::Byte => (0, u8::MAX.into()),
+            Type::UInt16 => (0, u16::MAX.into()),
+            Type::UInt32 => (0, u32::MAX.into()),
+            Type::UInt64 => (0, u64::MAX.into()),
+            Type::Int8 => (i8::MIN.into(), i8::MAX.into()),
+            Type::Int16 => (i16::MIN.into(), i16::MAX.into()),
+            Type::Int32 => (i32::MIN.into(), i32::MAX.into()),
+            Type::Int64 => (i64::MIN.into(), i64::MAX.into()),
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Bool => f.write_str("bool"),
+            Type::Byte => f.write_str("byte"),
+            Type::UInt16 => f.write_str("uint16"),
+            Type::UInt32 => f.write_str("uint32"),
+            Type::UInt64 => f.write_str("uint64"),
+            Type::Int8 => f.write_str("int8"),
+            Type::Int16 => f.write_str("int16"),
+            Type::Int32 => f.write_str("int32"),
+            Type::Int64 => f.write_str("int64"),
+            Type::Float32 => f.write_str("float32"),
+            Type::Float64 => f.write_str("float64"),
+            Type::String => f.write_str("string"),
+            Type::Guid => f.write_str("guid"),
+            Type::Date => f.write_str("date"),
+            Type::Array(t) => write!(f, "{t}[]"),
+            Type::Map(k, v) => write!(f, "map[{k}, {v}]"),
+            Type::Named(name) => f.write_str(name),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Lexer
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Int(i128),
+    Str,
+    Punct(&'static str),
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tok::Ident(s) => write!(f, "`{s}`"),
+            Tok::Int(n) => write!(f, "`{n}`"),
+            Tok::Str => f.write_str("string literal"),
+            Tok::Punct(p) => write!(f, "`{p}`"),
+        }
+    }
+}
+
+const PUNCT: [&str; 14] = [
+    "->", ":", ";", ",", "{", "}", "[", "]", "<", ">", "(", ")", "=", ".",
+];
+
+fn lex(src: &str) -> Result<Vec<(Tok, usize)>, Error> {
+    let bytes = src.as_bytes();
+    let mut toks = Vec::new();
+    let mut i = 0;
+    let mut line = 1;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let rest = &src[i..];
+        if c == b'\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_ascii_whitespace() {
+            i += 1;
+        } else if rest.starts_with("//") {
+            i += rest.find('\n').unwrap_or(rest.len());
+        } else if rest.starts_with("/*") {
+            let end = rest
+                .find("*/")
+                .ok_or_else(|| Error::parse(line, "unterminated block comment"))?;
+            line += rest[..end].matches('\n').count();
+            i += end + 2;
+        } else if c == b'"' {
+            let end = rest[1..]
+                .find('"')
+                .ok_or_else(|| Error::parse(line, "unterminated string literal"))?;
+            line += rest[..end].matches('\n').count();
+            toks.push((Tok::Str, line));
+            i += end + 2;
+        } else if c.is_ascii_digit()
+            || (c == b'-' && rest[1..].starts_with(|d: char| d.is_ascii_digit()))
+        {
+            let len = rest[1..]
+                .find(|d: char| !d.is_ascii_alphanumeric())
+                .map_or(rest.len(), |n| n + 1);
+            let text = &rest[..len];
+            let (neg, digits) = match text.strip_prefix('-') {
+                Some(d) => (true, d),
+                None => (false, text),
+            };
+            let value = match digits
+                .strip_prefix("0x")
+                .or_else(|| digits.strip_prefix("0X"))
+            {
+                Some(hex) => i128::from_str_radix(hex, 16),
+                None => digits.parse(),
+            }
+            .map_err(|_| Error::parse(line, format!("invalid integer `{text}`")))?;
+            toks.push((Tok::Int(if neg { -value } else { value }), line));
+            i += len;
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            let len = rest
+                .find(|d: char| !(d.is_ascii_alphanumeric() || d == '_'))
+                .unwrap_or(rest.len());
+            toks.push((Tok::Ident(rest[..len].to_owned()), line));
+            i += len;
+        } else if let Some(p) = PUNCT.iter().find(|p| rest.starts_with(**p)) {
+            toks.push((Tok::Punct(p), line));
+            i += p.len();
+        } else {
+            let ch = rest.chars().next().unwrap_or_default();
+            return Err(Error::parse(line, format!("unexpected character `{ch}`")));
+        }
+    }
+    Ok(toks)
+}
+
+// -----------------------------------------------------------------------------
+// Parser
+// -----------------------------------------------------------------------------
+
+struct Parser {
+    toks: Vec<(Tok, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn peek_at(&self, n: usize) -> Option<&Tok> {
+        self.toks.get(self.pos + n).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        self.toks
+            .get(self.pos)
+            .or_else(|| self.toks.last())
+            .map_or(1, |&(_, line)| line)
+    }
+
+    fn err(&self, msg: impl Into<String>) -> Error {
+        Error::parse(self.line(), msg)
+    }
+
+    fn next(&mut self) -> Result<Tok, Error> {
+        let tok = self
+            .toks
+            .get(self.pos)
+            .map(|(t, _)| t.clone())
+            .ok_or_else(|| self.err("unexpected end of schema"))?;
+        self.pos += 1;
+        Ok(tok)
+    }
+
+    fn is_punct(&self, p: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Punct(q)) if *q == p)
+    }
+
+    fn eat_punct(&mut self, p: &str) -> bool {
+        let found = self.is_punct(p);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_punct(&mut self, p: &str) -> Result<(), Error> {
+        match self.next()? {
+            Tok::Punct(q) if q == p => Ok(()),
+            other => {
+                self.pos -= 1;
+                Err(self.err(format!("expected `{p}`, found {other}")))
+            }
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, Error> {
+        match self.next()? {
+            Tok::Ident(s) => Ok(s),
+            other => {
+                self.pos -= 1;
+                Err(self.err(format!("expected identifier, found {other}")))
+            }
+        }
+    }
+
+    fn int(&mut self) -> Result<i128, Error> {
+        match self.next()? {
+            Tok::Int(n) => Ok(n),
+            other => {
+                self.pos -= 1;
+                Err(self.err(format!("expected integer, found {other}")))
+            }
+        }
+    }
+
+    /// Skip `[name(args)]` attributes.
+    fn skip_attributes(&mut self) -> Result<(), Error> {
+        while self.is_punct("[") {
+            let mut depth = 0;
+            loop {
+                match self.next()? {
+                    Tok::Punct("[") => depth += 1,
+                    Tok::Punct("]") => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn schema(&mut self) -> Result<Vec<Definition>, Error> {
+        let mut defs = Vec::new();
+        while self.peek().is_some() {
+            self.skip_attributes()?;
+            let keyword = self.ident()?;
+            defs.push(match keyword.as_str() {
+                "enum" => Definition::Enum(self.enumeration()?),
+                "readonly" => {
+                    let kw = self.ident()?;
+                    if kw != "struct" {
+                        return Err(self.err("expected `struct` after `readonly`"));
+                    }
+                    Definition::Struct(self.structure()?)
+                }
+                "struct" => Definition::Struct(self.structure()?),
+                "message" => Definition::Message(self.message()?),
+                "union" | "const" | "import" => {
+                    return Err(self.err(format!("`{keyword}` is not supported")))
+                }
+                other => return Err(self.err(format!("expected a definition, found `{other}`"))),
+            });
+        }
+        Ok(defs)
+    }
+
+    fn enumeration(&mut self) -> Result<Enum, Error> {
+        let name = self.ident()?;
+        let base = if self.eat_punct(":") {
+            self.ty()?
+        } else {
+            Type::UInt32
+        };
+        self.expect_punct("{")?;
+        let mut variants = Vec::new();
+        while !self.eat_punct("}") {
+            self.skip_attributes()?;
+            let variant = self.ident()?;
+            self.expect_punct("=")?;
+            variants.push((variant, self.int()?));
+            if !self.eat_punct(";") {
+                self.eat_punct(",");
+            }
+        }
+        Ok(Enum {
+            name,
+            base,
+            variants,
+        })
+    }
+
+    fn structure(&mut self) -> Result<Struct, Error> {
+        let name = self.ident()?;
+        self.expect_punct("{")?;
+        let mut fields = Vec::new();
+        while !self.eat_punct("}") {
+            self.skip_attributes()?;
+            let (name, ty) = self.field()?;
+            self.eat_punct(";");
+            fields.push(Field { name, ty, index: 0 });
+        }
+        Ok(Struct { name, fields })
+    }
+
+    fn message(&mut self) -> Result<Message, Error> {
+        let name = self.ident()?;
+        self.expect_punct("{")?;
+        let mut fields = Vec::new();
+        let mut next_index = 1;
+        while !self.eat_punct("}") {
+            self.skip_attributes()?;
+            let index = if matches!(self.peek(), Some(Tok::Int(_))) {
+                let index = self.int()?;
+                self.expect_punct("->")?;
+                index
+            } else {
+                next_index
+            };
+            if !(1..=255).contains(&index) {
+                return Err(self.err(format!("field index {index} is outside 1..=255")));
+            }
+            let (name, ty) = self.field()?;
+            self.eat_punct(";");
+            fields.push(Field {
+                name,
+                ty,
+                index: index as u8,
+            });
+            next_index = index + 1;
+        }
+        Ok(Message { name, fields })
+    }
+
+    /// `name: type` or `type name`.
+    fn field(&mut self) -> Result<(String, Type), Error> {
+        let name_first = matches!(self.peek(), Some(Tok::Ident(_)))
+            && matches!(self.peek_at(1), Some(Tok::Punct(":")));
+        if name_first {
+            let name = self.ident()?;
+            self.expect_punct(":")?;
+            Ok((name, self.ty()?))
+        } else {
+            let ty = self.ty()?;
+            Ok((self.ident()?, ty))
+        }
+    }
+
+    fn ty(&mut self) -> Result<Type, Error> {
+        let name = self.ident()?;
+        let mut ty = match name.as_str() {
+            "map" => {
+                let close = if self.eat_punct("<") {
+                    ">"
+                } else {
+                    self.expect_punct("[")?;
+                    "]"
+                };
+                let key = self.ty()?;
+                self.expect_punct(",")?;
+                let value = self.ty()?;
+                self.expect_punct(close)?;
+                Type::Map(Box::new(key), Box::new(value))
+            }
+            "array" if self.is_punct("[") => {
+                self.expect_punct("[")?;
+                let item = self.ty()?;
+                self.expect_punct("]")?;
+                Type::Array(Box::new(item))
+            }
+            _ => Type::scalar(&name).unwrap_or(Type::Named(name)),
+        };
+        while self.is_punct("[") && matches!(self.peek_at(1), Some(Tok::Punct("]"))) {
+            self.pos += 2;
+            ty = Type::Array(Box::new(ty));
+        }
+        Ok(ty)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Validation
+// -----------------------------------------------------------------------------
+
+/// Parse and validate `src`.
+pub fn parse(src: &str) -> Result<Schema, Error> {
+    let mut parser = Parser {
+        toks: lex(src)?,
+        pos: 0,
+    };
+    let schema = Schema {
+        definitions: parser.schema()?,
+    };
+    validate(&schema)?;
+    Ok(schema)
+}
+
+fn validate(schema: &Schema) -> Result<(), Error> {
+    let mut kinds = HashMap::new();
+    for def in &schema.definitions {
+        if kinds.insert(def.name(), def).is_some() {
+            return Err(Error::schema(format!("`{}` is defined twice", def.name())));
+        }
+    }
+
+    let check_type = |owner: &str, ty: &Type| -> Result<(), Error> {
+        let mut stack = vec![ty];
+        while let Some(ty) = stack.pop() {
+            match ty {
+                Type::Array(item) => stack.push(item),
+                Type::Map(key, value) => {
+                    let key_ok = match &**key {
+                        Type::Named(name) => {
+                            matches!(kinds.get(name.as_str()), Some(Definition::Enum(_)))
+                        }
+                        Type::Float32 | Type::Float64 | Type::Array(_) | Type::Map(..) => false,
+                        _ => true,
+                    };
+                    if !key_ok {
+                        return Err(Error::schema(format!(
+                            "{owner}: map key `{key}` must be a scalar, string, guid or enum"
+                        )));
+                    }
+                    stack.push(key);
+                    stack.push(value);
+                }
+                Type::Named(name) if !kinds.contains_key(name.as_str()) => {
+                    return Err(Error::schema(format!("{owner}: unknown type `{name}`")));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    };
+
+    for def in &schema.definitions {
+        match def {
+            Definition::Enum(e) => {
+                let (min, max) = e.base.int_range().ok_or_else(|| {
+                    Error::schema(format!(
+                        "{}: enum base `{}` is not an integer",
+                        e.name, e.base
+                    ))
+                })?;
+                let mut seen = HashSet::new();
+                let mut values = HashSet::new();
+                for (variant, value) in &e.variants {
+                    if !(min..=max).contains(value) {
+                        return Err(Error::schema(format!(
+                            "{}.{variant}: {value} does not fit in {}",
+                            e.name, e.base
+                        )));
+                    }
+                    if !seen.insert(variant) {
+                        return Err(Error::schema(format!(
+                            "{}.{variant} is defined twice",
+                            e.name
+                        )));
+                    }
+                    if !values.insert(value) {
+                        return Err(Error::schema(format!(
+                            "{}.{variant}: another variant has the same value {value}",
+                            e.name
+                        )));
+                    }
+                }
+                if e.variants.is_empty() {
+                    return Err(Error::schema(format!("{}: enum has no variants", e.name)));
+                }
+            }
+            Definition::Struct(s) => {
+                unique_names(&s.name, &s.fields)?;
+                for f in &s.fields {
+                    check_type(&format!("{}.{}", s.name, f.name), &f.ty)?;
+                }
+            }
+            Definition::Message(m) => {
+                unique_names(&m.name, &m.fields)?;
+                let mut indices = HashSet::new();
+                for f in &m.fields {
+                    if !indices.insert(f.index) {
+                        return Err(Error::schema(format!(
+                            "{}.{}: field index {} is used twice",
+                            m.name, f.name, f.index
+                        )));
+                    }
+                    check_type(&format!("{}.{}", m.name, f.name), &f.ty)?;
+                }
+            }
+        }
+    }
+    type_cycles(schema, &kinds)
+}
+
+fn unique_names(owner: &str, fields: &[Field]) -> Result<(), Error> {
+    let mut seen = HashSet::new();
+    for f in fields {
+        if !seen.insert(&f.name) {
+            return Err(Error::schema(format!(
+                "{owner}.{} is defined twice",
+                f.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Generated types embed named fields directly, so a type containing itself
+/// other than through an array or map would need boxing, which the generator
+/// does not do. For structs such a cycle has no finite encoding either.
+fn type_cycles(schema: &Schema, kinds: &HashMap<&str, &Definition>) -> Result<(), Error> {
+    fn visit<'a>(
+        name: &'a str,
+        kinds: &HashMap<&str, &'a Definition>,
+        path: &mut Vec<&'a str>,
+    ) -> Result<(), Error> {
+        if path.contains(&name) {
+            path.push(name);
+            return Err(Error::schema(format!(
+                "recursive type {} is not supported",
+                path.join(" -> ")
+            )));
+        }
+        let fields = match kinds.get(name) {
+            Some(Definition::Struct(s)) => &s.fields,
+            Some(Definition::Message(m)) => &m.fields,
+            _ => return Ok(()),
+        };
+        path.push(name);
+        for f in fields {
+            if let Type::Named(inner) = &f.ty {
+                visit(inner, kinds, path)?;
+            }
+        }
+        path.pop();
+        Ok(())
+    }
+
+    for def in &schema.definitions {
+        visit(def.name(), kinds, &mut Vec::new())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SENSORS: &str = include_str!("../../../schemas/sensors.bop");
+
+    #[test]
+    fn parses_sensors_schema() {
+        let schema = parse(SENSORS).unwrap();
+        let names: Vec<_> = schema.definitions.iter().map(Definition::name).collect();
+        assert_eq!(names, ["SensorType", "SensorReading", "BatchReadings"]);
+
+        let Definition::Enum(e) = &schema.definitions[0] else {
+            panic!("SensorType is not an enum");
+        };
+        assert_eq!(e.base, Type::UInt16);
+        assert_eq!(e.variants[3], ("Vibration".to_owned(), 4));
+
+        let Definition::Message(m) = &schema.definitions[1] else {
+            panic!("SensorReading is not a message");
+        };
+        let fields: Vec<_> = m
+            .fields
+            .iter()
+            .map(|f| (f.index, f.name.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                (1, "timestamp"),
+                (2, "sensorId"),
+                (3, "sensorType"),
+                (4, "value"),
+                (5, "unit"),
+                (6, "location"),
+                (7, "metadata")
+            ]
+        );
+        assert_eq!(
+            m.fields[6].ty,
+            Type::Map(Box::new(Type::String), Box::new(Type::String))
+        );
+
+        let Definition::Message(b) = &schema.definitions[2] else {
+            panic!("BatchReadings is not a message");
+        };
+        assert_eq!(
+            b.fields[0].ty,
+            Type::Array(Box::new(Type::Named("SensorReading".into())))
+        );
+        assert_eq!(b.fields[1].ty, Type::Guid);
+    }
+
+    #[test]
+    fn parses_upstream_syntax() {
+        let schema = parse(
+            r#"
+            /* block */ [opcode("PING")]
+            readonly struct Point { float32 x; float32 y; }
+            message Shape {
+                1 -> Point[] points;
+                [deprecated("use tags")] 4 -> map[string, int32] labels;
+                tags: array[string];
+            }
+            enum Flags { A = 0x1, B = -0 }
+            "#,
+        )
+        .unwrap();
+        let Definition::Message(m) = &schema.definitions[1] else {
+            panic!("Shape is not a message");
+        };
+        let indices: Vec<_> = m.fields.iter().map(|f| f.index).collect();
+        assert_eq!(indices, [1, 4, 5]);
+        assert_eq!(m.fields[2].ty, Type::Array(Box::new(Type::String)));
+    }
+
+    #[test]
+    fn rejects_invalid_schemas() {
+        for (src, needle) in [
+            ("message M { a: Missing; }", "unknown type"),
+            ("message M { 1 -> int32 a; 1 -> int32 b; }", "used twice"),
+            ("message M { 0 -> int32 a; }", "outside 1..=255"),
+            ("enum E : uint8 { A = 256 }", "does not fit"),
+            ("enum E : string { A = 1 }", "not an integer"),
+            ("struct S { m: map<float64, int32>; }", "map key"),
+            (
+                "struct A { b: B; } struct B { a: A; }",
+                "recursive type A -> B -> A",
+            ),
+            ("message M { m: M; }", "recursive type M -> M"),
+            ("enum E { A = 1 B = 1 }", "same value"),
+            ("union U { }", "not supported"),
+            ("struct S { a: int32; a: int32; }", "defined twice"),
+            ("message M { a: int32 ", "unexpected end"),
+        ] {
+            let err = parse(src).unwrap_err().to_string();
+            assert!(err.contains(needle), "{src}: {err}");
+        }
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let err = parse("enum E {\n  A = 1\n  B ! 2\n}").unwrap_err();
+        assert_eq!(err.to_string(), "line 3: unexpected character `!`");
+    }
+}
@@ -0,0 +1,477 @@
+// @generated by bebop-v-codegen. Do not edit; regenerate from the .bop schema.
+
+pub mod sensors {
+    #![allow(dead_code, unused_imports, clippy::all)]
+
+    pub mod bebop_runtime {
+        // SPDX-License-Identifier: AGPL-3.0-or-later
+        // SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+        //
+        // runtime.rs - Wire support copied into every generated module
+        //
+        // The generator embeds this file verbatim as `bebop_runtime`, so generated
+        // code has no dependency on this crate. Encoding follows the Bebop-V wire
+        // format used by implementations/zig/src/bridge.zig: little-endian scalars,
+        // u32-length-prefixed strings and arrays, structs as their fields in order,
+        // and messages as (index byte, value) pairs closed by a 0 byte.
+
+        use std::collections::BTreeMap;
+        use std::error::Error;
+        use std::fmt;
+
+        /// 16 raw GUID bytes in wire order.
+        pub type Guid = [u8; 16];
+
+        /// Errors returned by generated `decode` functions.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum DecodeError {
+            UnexpectedEnd,
+            InvalidUtf8,
+            /// A message field index not defined by the schema.
+            InvalidFieldIndex(u8),
+            /// A value not defined by the named enum.
+            InvalidEnum {
+                name: &'static str,
+                value: u64,
+            },
+        }
+
+        impl fmt::Display for DecodeError {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    DecodeError::UnexpectedEnd => f.write_str("unexpected end of data"),
+                    DecodeError::InvalidUtf8 => f.write_str("invalid UTF-8 string"),
+                    DecodeError::InvalidFieldIndex(index) => write!(f, "invalid field index {index}"),
+                    DecodeError::InvalidEnum { name, value } => {
+                        write!(f, "{value} is not a valid {name}")
+                    }
+                }
+            }
+        }
+
+        impl Error for DecodeError {}
+
+        /// Cursor over an input buffer.
+        #[derive(Debug, Clone)]
+        pub struct Reader<'a> {
+            data: &'a [u8],
+            pos: usize,
+        }
+
+        impl<'a> Reader<'a> {
+            pub fn new(data: &'a [u8]) -> Reader<'a> {
+                Reader { data, pos: 0 }
+            }
+
+            /// Bytes consumed so far.
+            pub fn position(&self) -> usize {
+                self.pos
+            }
+
+            pub fn remaining(&self) -> usize {
+                self.data.len() - self.pos
+            }
+
+            pub fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+                if n > self.remaining() {
+                    return Err(DecodeError::UnexpectedEnd);
+                }
+                let bytes = &self.data[self.pos..self.pos + n];
+                self.pos += n;
+                Ok(bytes)
+            }
+
+            pub fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+                let mut out = [0u8; N];
+                out.copy_from_slice(self.take(N)?);
+                Ok(out)
+            }
+
+            /// A u32 element count, capped for preallocation by the bytes left.
+            pub fn count(&mut self) -> Result<(usize, usize), DecodeError> {
+                let count = u32::decode_from(self)? as usize;
+                Ok((count, count.min(self.remaining())))
+            }
+
+            /// Next message field index; `None` at the 0 terminator or end of input.
+            pub fn field_index(&mut self) -> Result<Option<u8>, DecodeError> {
+                if self.remaining() == 0 {
+                    return Ok(None);
+                }
+                match self.take(1)?[0] {
+                    0 => Ok(None),
+                    index => Ok(Some(index)),
+                }
+            }
+
+            /// A string borrowed from the input.
+            pub fn str(&mut self) -> Result<&'a str, DecodeError> {
+                let len = u32::decode_from(self)? as usize;
+                std::str::from_utf8(self.take(len)?).map_err(|_| DecodeError::InvalidUtf8)
+            }
+        }
+
+        /// Write a u32 length or count.
+        ///
+        /// # Panics
+        ///
+        /// If `len` does not fit in a u32, which the wire format cannot express.
+        pub fn encode_len(len: usize, out: &mut Vec<u8>) {
+            let len = u32::try_from(len).expect("Bebop lengths are limited to u32::MAX");
+            out.extend_from_slice(&len.to_le_bytes());
+        }
+
+        /// A type with a Bebop wire representation.
+        pub trait Wire: Sized {
+            fn encode_into(&self, out: &mut Vec<u8>);
+            fn decode_from(r: &mut Reader<'_>) -> Result<Self, DecodeError>;
+
+            fn encode(&self) -> Vec<u8> {
+                let mut out = Vec::new();
+                self.encode_into(&mut out);
+                out
+            }
+
+            fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+                Self::decode_from(&mut Reader::new(bytes))
+            }
+        }
+
+        macro_rules! scalar {
+            ($($ty:ty),*) => {$(
+                impl Wire for $ty {
+                    fn encode_into(&self, out: &mut Vec<u8>) {
+                        out.extend_from_slice(&self.to_le_bytes());
+                    }
+
+                    fn decode_from(r: &mut Reader<'_>) -> Result<$ty, DecodeError> {
+                        r.array().map(<$ty>::from_le_bytes)
+                    }
+                }
+            )*};
+        }
+
+        scalar!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+        impl Wire for bool {
+            fn encode_into(&self, out: &mut Vec<u8>) {
+                out.push(u8::from(*self));
+            }
+
+            fn decode_from(r: &mut Reader<'_>) -> Result<bool, DecodeError> {
+                Ok(r.take(1)?[0] != 0)
+            }
+        }
+
+        impl Wire for String {
+            fn encode_into(&self, out: &mut Vec<u8>) {
+                encode_len(self.len(), out);
+                out.extend_from_slice(self.as_bytes());
+            }
+
+            fn decode_from(r: &mut Reader<'_>) -> Result<String, DecodeError> {
+                r.str().map(str::to_owned)
+            }
+        }
+
+        impl Wire for Guid {
+            fn encode_into(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(self);
+            }
+
+            fn decode_from(r: &mut Reader<'_>) -> Result<Guid, DecodeError> {
+                r.array()
+            }
+        }
+
+        impl<T: Wire> Wire for Vec<T> {
+            fn encode_into(&self, out: &mut Vec<u8>) {
+                encode_len(self.len(), out);
+                for item in self {
+                    item.encode_into(out);
+                }
+            }
+
+            fn decode_from(r: &mut Reader<'_>) -> Result<Vec<T>, DecodeError> {
+                let (count, capacity) = r.count()?;
+                let mut items = Vec::with_capacity(capacity);
+                for _ in 0..count {
+                    items.push(T::decode_from(r)?);
+                }
+                Ok(items)
+            }
+        }
+
+        /// Maps are written in key order.
+        impl<K: Wire + Ord, V: Wire> Wire for BTreeMap<K, V> {
+            fn encode_into(&self, out: &mut Vec<u8>) {
+                encode_len(self.len(), out);
+                for (k, v) in self {
+                    k.encode_into(out);
+                    v.encode_into(out);
+                }
+            }
+
+            fn decode_from(r: &mut Reader<'_>) -> Result<BTreeMap<K, V>, DecodeError> {
+                let (count, _) = r.count()?;
+                let mut map = BTreeMap::new();
+                for _ in 0..count {
+                    let k = K::decode_from(r)?;
+                    map.insert(k, V::decode_from(r)?);
+                }
+                Ok(map)
+            }
+        }
+
+        /// Byte slice in FFI views; layout of `VBytes` in include/bebop_v_ffi.h.
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy)]
+        pub struct VBytes {
+            pub ptr: *const u8,
+            pub len: usize,
+        }
+
+        impl VBytes {
+            pub const fn empty() -> VBytes {
+                VBytes {
+                    ptr: std::ptr::null(),
+                    len: 0,
+                }
+            }
+
+            pub fn from_slice(slice: &[u8]) -> VBytes {
+                VBytes {
+                    ptr: if slice.is_empty() {
+                        std::ptr::null()
+                    } else {
+                        slice.as_ptr()
+                    },
+                    len: slice.len(),
+                }
+            }
+        }
+
+        impl Default for VBytes {
+            fn default() -> VBytes {
+                VBytes::empty()
+            }
+        }
+    }
+
+    use std::collections::BTreeMap;
+
+    use bebop_runtime::{DecodeError, Reader, Wire};
+
+    #[repr(u16)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+    pub enum SensorType {
+        #[default]
+        Temperature = 1,
+        Humidity = 2,
+        Pressure = 3,
+        Vibration = 4,
+    }
+
+    impl SensorType {
+        pub const fn from_raw(value: u16) -> Option<SensorType> {
+            match value {
+                1 => Some(SensorType::Temperature),
+                2 => Some(SensorType::Humidity),
+                3 => Some(SensorType::Pressure),
+                4 => Some(SensorType::Vibration),
+                _ => None,
+            }
+        }
+    }
+
+    impl Wire for SensorType {
+        fn encode_into(&self, out: &mut Vec<u8>) {
+            (*self as u16).encode_into(out);
+        }
+
+        fn decode_from(r: &mut Reader<'_>) -> Result<SensorType, DecodeError> {
+            let value = u16::decode_from(r)?;
+            SensorType::from_raw(value).ok_or(DecodeError::InvalidEnum { name: "SensorType", value: value as u64 })
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Default)]
+    pub struct SensorReading {
+        pub timestamp: Option<u64>,
+        pub sensor_id: Option<String>,
+        pub sensor_type: Option<SensorType>,
+        pub value: Option<f64>,
+        pub unit: Option<String>,
+        pub location: Option<String>,
+        pub metadata: Option<BTreeMap<String, String>>,
+    }
+
+    impl Wire for SensorReading {
+        fn encode_into(&self, out: &mut Vec<u8>) {
+            if let Some(value) = &self.timestamp {
+                out.push(1);
+                value.encode_into(out);
+            }
+            if let Some(value) = &self.sensor_id {
+                out.push(2);
+                value.encode_into(out);
+            }
+            if let Some(value) = &self.sensor_type {
+                out.push(3);
+                value.encode_into(out);
+            }
+            if let Some(value) = &self.value {
+                out.push(4);
+                value.encode_into(out);
+            }
+            if let Some(value) = &self.unit {
+                out.push(5);
+                value.encode_into(out);
+            }
+            if let Some(value) = &self.location {
+                out.push(6);
+                value.encode_into(out);
+            }
+            if let Some(value) = &self.metadata {
+                out.push(7);
+                value.encode_into(out);
+            }
+            out.push(0);
+        }
+
+        fn decode_from(r: &mut Reader<'_>) -> Result<SensorReading, DecodeError> {
+            let mut message = SensorReading::default();
+            while let Some(index) = r.field_index()? {
+                match index {
+                    1 => message.timestamp = Some(Wire::decode_from(r)?),
+                    2 => message.sensor_id = Some(Wire::decode_from(r)?),
+                    3 => message.sensor_type = Some(Wire::decode_from(r)?),
+                    4 => message.value = Some(Wire::decode_from(r)?),
+                    5 => message.unit = Some(Wire::decode_from(r)?),
+                    6 => message.location = Some(Wire::decode_from(r)?),
+                    7 => message.metadata = Some(Wire::decode_from(r)?),
+                    other => return Err(DecodeError::InvalidFieldIndex(other)),
+                }
+            }
+            Ok(message)
+        }
+    }
+
+    impl SensorReading {
+        pub fn encode(&self) -> Vec<u8> {
+            Wire::encode(self)
+        }
+
+        pub fn decode(bytes: &[u8]) -> Result<SensorReading, DecodeError> {
+            <Self as Wire>::decode(bytes)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Default)]
+    pub struct BatchReadings {
+        pub readings: Option<Vec<SensorReading>>,
+        pub batch_id: Option<bebop_runtime::Guid>,
+        pub compressed: Option<bool>,
+    }
+
+    impl Wire for BatchReadings {
+        fn encode_into(&self, out: &mut Vec<u8>) {
+            if let Some(value) = &self.readings {
+                out.push(1);
+                value.encode_into(out);
+            }
+            if let Some(value) = &self.batch_id {
+                out.push(2);
+                value.encode_into(out);
+            }
+            if let Some(value) = &self.compressed {
+                out.push(3);
+                value.encode_into(out);
+            }
+            out.push(0);
+        }
+
+        fn decode_from(r: &mut Reader<'_>) -> Result<BatchReadings, DecodeError> {
+            let mut message = BatchReadings::default();
+            while let Some(index) = r.field_index()? {
+                match index {
+                    1 => message.readings = Some(Wire::decode_from(r)?),
+                    2 => message.batch_id = Some(Wire::decode_from(r)?),
+                    3 => message.compressed = Some(Wire::decode_from(r)?),
+                    other => return Err(DecodeError::InvalidFieldIndex(other)),
+                }
+            }
+            Ok(message)
+        }
+    }
+
+    impl BatchReadings {
+        pub fn encode(&self) -> Vec<u8> {
+            Wire::encode(self)
+        }
+
+        pub fn decode(bytes: &[u8]) -> Result<BatchReadings, DecodeError> {
+            <Self as Wire>::decode(bytes)
+        }
+    }
+
+    /// C layout of [`SensorReading`] (see include/bebop_v_ffi.h).
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    pub struct VSensorReading {
+        pub timestamp: u64,
+        pub sensor_id: bebop_runtime::VBytes,
+        pub sensor_type: u16,
+        pub value: f64,
+        pub unit: bebop_runtime::VBytes,
+        pub location: bebop_runtime::VBytes,
+        pub metadata_count: usize,
+        pub metadata_keys: *mut bebop_runtime::VBytes,
+        pub metadata_values: *mut bebop_runtime::VBytes,
+        pub error_code: i32,
+        pub error_message: *const std::ffi::c_char,
+    }
+
+    impl VSensorReading {
+        pub const fn empty() -> VSensorReading {
+            VSensorReading {
+                timestamp: 0,
+                sensor_id: bebop_runtime::VBytes::empty(),
+                sensor_type: 0,
+                value: 0.0,
+                unit: bebop_runtime::VBytes::empty(),
+                location: bebop_runtime::VBytes::empty(),
+                metadata_count: 0,
+                metadata_keys: std::ptr::null_mut(),
+                metadata_values: std::ptr::null_mut(),
+                error_code: 0,
+                error_message: std::ptr::null(),
+            }
+        }
+    }
+
+    /// C layout of [`BatchReadings`] (see include/bebop_v_ffi.h).
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    pub struct VBatchReadings {
+        pub readings_count: usize,
+        pub readings: *mut VSensorReading,
+        pub batch_id: [u8; 16],
+        pub compressed: bool,
+        pub error_code: i32,
+        pub error_message: *const std::ffi::c_char,
+    }
+
+    impl VBatchReadings {
+        pub const fn empty() -> VBatchReadings {
+            VBatchReadings {
+                readings_count: 0,
+                readings: std::ptr::null_mut(),
+                batch_id: [0; 16],
+                compressed: false,
+                error_code: 0,
+                error_message: std::ptr::null(),
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+//
+// sensors.rs - Generated code for schemas/sensors.bop
+//
+// tests/generated/sensors.rs is the checked-in output for sensors.bop. It is
+// compiled here and exercised against the golden vector shared with the Zig
+// and C implementations. After an intended generator change, refresh it with
+//
+//   BEBOP_CODEGEN_BLESS=1 cargo test
+
+include!("generated/sensors.rs");
+
+use std::collections::BTreeMap;
+use std::mem::{offset_of, size_of};
+use std::path::Path;
+
+use sensors::{BatchReadings, SensorReading, SensorType, VBatchReadings, VSensorReading};
+
+const SCHEMA: &str = "../../schemas/sensors.bop";
+const SNAPSHOT: &str = "tests/generated/sensors.rs";
+
+/// `wire_bytes_hex` of test-vectors/sensor_reading_001.json.
+fn golden_bytes() -> Vec<u8> {
+    let json = std::fs::read_to_string("../../test-vectors/sensor_reading_001.json").unwrap();
+    let hex = json
+        .split("\"wire_bytes_hex\": \"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .expect("wire_bytes_hex in sensor_reading_001.json");
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn snapshot_is_current() {
+    let dir = std::env::temp_dir().join(format!("bebop-v-codegen-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let out = bebop_v_codegen::compile_to(Path::new(SCHEMA), &dir).unwrap();
+    let generated = std::fs::read_to_string(&out).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    if std::env::var_os("BEBOP_CODEGEN_BLESS").is_some() {
+        std::fs::write(SNAPSHOT, &generated).unwrap();
+    }
+    let snapshot = std::fs::read_to_string(SNAPSHOT).unwrap();
+    assert!(
+        generated == snapshot,
+        "{SNAPSHOT} is stale; rerun with BEBOP_CODEGEN_BLESS=1"
+    );
+}
+
+#[test]
+fn golden_vector_round_trips() {
+    let bytes = golden_bytes();
+    let reading = SensorReading::decode(&bytes).unwrap();
+    assert_eq!(reading.timestamp, Some(2_000_000_000));
+    assert_eq!(reading.sensor_id.as_deref(), Some("temp-001"));
+    assert_eq!(reading.sensor_type, Some(SensorType::Temperature));
+    assert_eq!(reading.value, Some(23.5));
+    assert_eq!(reading.unit.as_deref(), Some("C"));
+    assert_eq!(reading.location.as_deref(), Some("floor-1"));
+    let metadata = BTreeMap::from([("status".to_owned(), "ok".to_owned())]);
+    assert_eq!(reading.metadata, Some(metadata));
+    assert_eq!(reading.encode(), bytes);
+}
+
+#[test]
+fn absent_fields_are_omitted() {
+    let batch = BatchReadings {
+        readings: Some(vec![SensorReading {
+            sensor_type: Some(SensorType::Vibration),
+            ..Default::default()
+        }]),
+        compressed: Some(true),
+        ..Default::default()
+    };
+    let bytes = batch.encode();
+    assert_eq!(bytes, [1, 1, 0, 0, 0, 3, 4, 0, 0, 3, 1, 0]);
+    assert_eq!(BatchReadings::decode(&bytes).unwrap(), batch);
+}
+
+#[test]
+fn decode_errors() {
+    use sensors::bebop_runtime::DecodeError;
+
+    assert_eq!(
+        SensorReading::decode(&[9]),
+        Err(DecodeError::InvalidFieldIndex(9))
+    );
+    assert_eq!(
+        SensorReading::decode(&[3, 5, 0]),
+        Err(DecodeError::InvalidEnum {
+            name: "SensorType",
+            value: 5
+        })
+    );
+    assert_eq!(
+        SensorReading::decode(&[2, 8, 0, 0, 0]),
+        Err(DecodeError::UnexpectedEnd)
+    );
+}
+
+#[test]
+fn enum_values_match_abi() {
+    assert_eq!(SensorType::Temperature as u16, 1);
+    assert_eq!(SensorType::from_raw(4), Some(SensorType::Vibration));
+    assert_eq!(SensorType::from_raw(0), None);
+}
+
+// Offsets of VSensorReading in include/bebop_v_ffi.h on LP64 targets.
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn view_layout_matches_header() {
+    assert_eq!(size_of::<VSensorReading>(), 112);
+    assert_eq!(offset_of!(VSensorReading, sensor_id), 8);
+    assert_eq!(offset_of!(VSensorReading, sensor_type), 24);
+    assert_eq!(offset_of!(VSensorReading, value), 32);
+    assert_eq!(offset_of!(VSensorReading, unit), 40);
+    assert_eq!(offset_of!(VSensorReading, location), 56);
+    assert_eq!(offset_of!(VSensorReading, metadata_count), 72);
+    assert_eq!(offset_of!(VSensorReading, metadata_keys), 80);
+    assert_eq!(offset_of!(VSensorReading, metadata_values), 88);
+    assert_eq!(offset_of!(VSensorReading, error_code), 96);
+    assert_eq!(offset_of!(VSensorReading, error_message), 104);
+
+    let batch = VBatchReadings::empty();
+    assert_eq!(batch.readings_count, 0);
+    assert!(batch.readings.is_null());
+}
@@ -0,0 +1,148 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+//
+// abi.rs - Bebop-V-FFI ABI types (mirror of implementations/zig/src/abi.zig)
+//
+// Layouts are `#[repr(C)]` copies of the Zig `extern struct`s and must stay
+// field-for-field identical to include/bebop_v_ffi.h. The same stability rules
+// apply: fields are only ever appended, values are frozen.
+
+use std::ffi::c_char;
+use std::ptr;
+
+/// ABI version - must match include/bebop_v_ffi.h
+pub const ABI_VERSION_MAJOR: u32 = 1;
+pub const ABI_VERSION_MINOR: u32 = 0;
+pub const ABI_VERSION_PATCH: u32 = 0;
+pub const ABI_VERSION: u32 =
+    (ABI_VERSION_MAJOR << 16) | (ABI_VERSION_MINOR << 8) | ABI_VERSION_PATCH;
+
+// Error codes - must match include/bebop_v_ffi.h
+pub const BEBOP_OK: i32 = 0;
+pub const BEBOP_ERR_NULL_CTX: i32 = -1;
+pub const BEBOP_ERR_NULL_DATA: i32 = -2;
+pub const BEBOP_ERR_INVALID_LENGTH: i32 = -3;
+pub const BEBOP_ERR_DECODE_FAILED: i32 = -4;
+pub const BEBOP_ERR_ENCODE_FAILED: i32 = -5;
+pub const BEBOP_ERR_BUFFER_TOO_SMALL: i32 = -6;
+pub const BEBOP_ERR_NOT_IMPLEMENTED: i32 = -99;
+
+// SensorType values (matches sensors.bop)
+pub const SENSOR_TYPE_TEMPERATURE: u16 = 1;
+pub const SENSOR_TYPE_HUMIDITY: u16 = 2;
+pub const SENSOR_TYPE_PRESSURE: u16 = 3;
+pub const SENSOR_TYPE_VIBRATION: u16 = 4;
+
+/// Byte slice passed across FFI. Data is NOT NUL-terminated.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VBytes {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+impl VBytes {
+    pub const fn empty() -> VBytes {
+        VBytes {
+            ptr: ptr::null(),
+            len: 0,
+        }
+    }
+
+    pub fn from_slice(slice: &[u8]) -> VBytes {
+        VBytes {
+            ptr: if slice.is_empty() {
+                ptr::null()
+            } else {
+                slice.as_ptr()
+            },
+            len: slice.len(),
+        }
+    }
+
+    /// View as a slice; `None` if `ptr` is null while `len` is not zero.
+    ///
+    /// # Safety
+    ///
+    /// A non-null `ptr` must be valid for reads of `len` bytes for `'a`.
+    pub unsafe fn as_slice<'a>(&self) -> Option<&'a [u8]> {
+        match (self.ptr.is_null(), self.len) {
+            (_, 0) => Some(&[]),
+            (true, _) => None,
+            (false, len) => Some(std::slice::from_raw_parts(self.ptr, len)),
+        }
+    }
+}
+
+/// Flat, FFI-friendly representation of SensorReading.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VSensorReading {
+    pub timestamp: u64,
+    pub sensor_id: VBytes,
+    pub sensor_type: u16,
+    pub value: f64,
+    pub unit: VBytes,
+    pub location: VBytes,
+
+    pub metadata_count: usize,
+    pub metadata_keys: *mut VBytes,
+    pub metadata_values: *mut VBytes,
+
+    pub error_code: i32,
+    pub error_message: *const c_char,
+}
+
+impl VSensorReading {
+    pub const fn empty() -> VSensorReading {
+        VSensorReading {
+            timestamp: 0,
+            sensor_id: VBytes::empty(),
+            sensor_type: 0,
+            value: 0.0,
+            unit: VBytes::empty(),
+            location: VBytes::empty(),
+            metadata_count: 0,
+            metadata_keys: ptr::null_mut(),
+            metadata_values: ptr::null_mut(),
+            error_code: 0,
+            error_message: ptr::null(),
+        }
+    }
+}
+
+/// Callback invoked when a sensor reading is received.
+pub type ReadingCallback = unsafe extern "C" fn(reading: *const VSensorReading);
+
+/// Callback invoked on errors.
+pub type ErrorCallback = unsafe extern "C" fn(code: i32, message: *const c_char);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{align_of, offset_of, size_of};
+
+    // Offsets of the C header on LP64 targets.
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn layout_matches_header() {
+        assert_eq!(size_of::<VBytes>(), 16);
+        assert_eq!(size_of::<VSensorReading>(), 112);
+        assert_eq!(align_of::<VSensorReading>(), 8);
+        assert_eq!(offset_of!(VSensorReading, sensor_id), 8);
+        assert_eq!(offset_of!(VSensorReading, sensor_type), 24);
+        assert_eq!(offset_of!(VSensorReading, value), 32);
+        assert_eq!(offset_of!(VSensorReading, unit), 40);
+        assert_eq!(offset_of!(VSensorReading, location), 56);
+        assert_eq!(offset_of!(VSensorReading, metadata_count), 72);
+        assert_eq!(offset_of!(VSensorReading, metadata_keys), 80);
+        assert_eq!(offset_of!(VSensorReading, metadata_values), 88);
+        assert_eq!(offset_of!(VSensorReading, error_code), 96);
+        assert_eq!(offset_of!(VSensorReading, error_message), 104);
+    }
+
+    #[test]
+    fn version_format() {
+        assert_eq!(ABI_VERSION, 0x010000);
+    }
+}
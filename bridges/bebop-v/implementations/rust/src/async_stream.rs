@@ -0,0 +1,350 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+//
+// async_stream.rs - Tokio variants of the stream.rs readers (feature `async`)
+//
+// Same decoder state machine, framing and LZ4 handling as `BatchReader` and
+// `FrameReader`, driven by `AsyncRead` instead of `Read`.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use async_compression::tokio::bufread::Lz4Decoder;
+use tokio::io::{AsyncRead, AsyncReadExt, BufReader, ReadBuf};
+
+use crate::stream::{BatchCore, Step, StreamError, DEFAULT_MAX_READING_LEN};
+use crate::wire::{DecodeError, SensorReadingRef};
+
+enum Source<R> {
+    Plain(R),
+    Lz4(Box<Lz4Decoder<BufReader<R>>>),
+    /// Only observable if a panic interrupts the switch to `Lz4`.
+    Switching,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for Source<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Source::Plain(r) => Pin::new(r).poll_read(cx, buf),
+            Source::Lz4(r) => Pin::new(r).poll_read(cx, buf),
+            Source::Switching => Poll::Ready(Err(io::Error::other("source lost while switching"))),
+        }
+    }
+}
+
+/// Async counterpart of `fill_chunk` in stream.rs. Progress is recorded in
+/// the core after every read, so dropping the future loses nothing.
+async fn fill_chunk(
+    r: &mut (impl AsyncRead + Unpin),
+    core: &mut BatchCore,
+) -> Result<bool, StreamError> {
+    loop {
+        let started = core.chunk_started();
+        let buf = core.fill()?;
+        if buf.is_empty() {
+            return Ok(true);
+        }
+        match r.read(buf).await? {
+            0 if !started => return Ok(false),
+            0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            n => core.add_filled(n),
+        }
+    }
+}
+
+/// Streaming decoder for one BatchReadings message from an `AsyncRead`.
+pub struct AsyncBatchReader<R> {
+    source: Source<R>,
+    core: BatchCore,
+}
+
+impl<R: AsyncRead + Unpin> AsyncBatchReader<R> {
+    pub fn new(reader: R) -> AsyncBatchReader<R> {
+        AsyncBatchReader {
+            source: Source::Plain(reader),
+            core: BatchCore::new(),
+        }
+    }
+
+    /// See [`BatchReader::with_max_reading_len`](crate::BatchReader::with_max_reading_len).
+    pub fn with_max_reading_len(mut self, max: usize) -> AsyncBatchReader<R> {
+        self.core.set_max_reading_len(max);
+        self
+    }
+
+    /// The next reading, or `None` once the whole batch has been read.
+    ///
+    /// Cancel safe: if the future is dropped before it completes, as in
+    /// `tokio::select!` or `tokio::time::timeout`, the bytes it read are kept
+    /// and the next call continues from them.
+    pub async fn next_reading(&mut self) -> Result<Option<SensorReadingRef<'_>>, StreamError> {
+        loop {
+            if self.core.is_done() {
+                return Ok(None);
+            }
+            let at_boundary = self.core.at_boundary();
+            if !fill_chunk(&mut self.source, &mut self.core).await? {
+                if !at_boundary {
+                    return Err(DecodeError::UnexpectedEnd.into());
+                }
+                self.core.finish();
+                return Ok(None);
+            }
+            match self.core.advance()? {
+                Step::More => {}
+                Step::Reading => return Ok(Some(self.core.reading()?)),
+                Step::Decompress => {
+                    self.source = match std::mem::replace(&mut self.source, Source::Switching) {
+                        Source::Plain(r) => {
+                            Source::Lz4(Box::new(Lz4Decoder::new(BufReader::new(r))))
+                        }
+                        other => other,
+                    };
+                }
+                Step::End => return Ok(None),
+            }
+        }
+    }
+
+    pub fn batch_id(&self) -> Option<[u8; 16]> {
+        self.core.batch_id()
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.core.compressed()
+    }
+}
+
+/// One frame's payload; reads stop at the frame's end.
+pub struct AsyncFrame<'a, R> {
+    inner: &'a mut R,
+    left: &'a mut u64,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncFrame<'_, R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let max = buf
+            .remaining()
+            .min(usize::try_from(*this.left).unwrap_or(usize::MAX));
+        if max == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let mut limited = ReadBuf::new(&mut buf.initialize_unfilled()[..max]);
+        ready!(Pin::new(&mut *this.inner).poll_read(cx, &mut limited))?;
+        let n = limited.filled().len();
+        buf.advance(n);
+        *this.left -= n as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Length-prefixed batches from an async byte stream such as a Tokio
+/// `TcpStream` or `UnixStream`.
+pub struct AsyncFrameReader<R> {
+    inner: R,
+    left: u64,
+    /// Length prefix of the next frame, `header_len` bytes of it read.
+    header: [u8; 4],
+    header_len: usize,
+    max_frame_len: u32,
+    max_reading_len: usize,
+}
+
+impl<R: AsyncRead + Unpin> AsyncFrameReader<R> {
+    pub fn new(inner: R) -> AsyncFrameReader<R> {
+        AsyncFrameReader {
+            inner,
+            left: 0,
+            header: [0; 4],
+            header_len: 0,
+            max_frame_len: u32::MAX,
+            max_reading_len: DEFAULT_MAX_READING_LEN,
+        }
+    }
+
+    pub fn with_max_frame_len(mut self, max: u32) -> AsyncFrameReader<R> {
+        self.max_frame_len = max;
+        self
+    }
+
+    pub fn with_max_reading_len(mut self, max: usize) -> AsyncFrameReader<R> {
+        self.max_reading_len = max;
+        self
+    }
+
+    /// Decoder for the next frame, or `None` when the stream ends cleanly.
+    /// Any unread part of the previous frame is skipped first.
+    ///
+    /// Cancel safe, like [`AsyncBatchReader::next_reading`].
+    pub async fn next_batch(
+        &mut self,
+    ) -> Result<Option<AsyncBatchReader<AsyncFrame<'_, R>>>, StreamError> {
+        let mut rest = AsyncFrame {
+            inner: &mut self.inner,
+            left: &mut self.left,
+        };
+        tokio::io::copy(&mut rest, &mut tokio::io::sink()).await?;
+        if *rest.left > 0 {
+            return Err(DecodeError::UnexpectedEnd.into());
+        }
+
+        while self.header_len < 4 {
+            match self.inner.read(&mut self.header[self.header_len..]).await? {
+                0 if self.header_len == 0 => return Ok(None),
+                0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                n => self.header_len += n,
+            }
+        }
+        self.header_len = 0;
+        let len = u32::from_le_bytes(self.header);
+        if len > self.max_frame_len {
+            return Err(StreamError::FrameTooLarge(len));
+        }
+        self.left = u64::from(len);
+        let frame = AsyncFrame {
+            inner: &mut self.inner,
+            left: &mut self.left,
+        };
+        Ok(Some(
+            AsyncBatchReader::new(frame).with_max_reading_len(self.max_reading_len),
+        ))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::tests::batch;
+    use crate::stream::write_frame;
+
+    #[tokio::test]
+    async fn frames_over_tcp() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let writer = tokio::spawn(async move {
+            use tokio::io::AsyncWriteExt;
+            let (mut conn, _) = listener.accept().await.unwrap();
+            let mut wire = Vec::new();
+            write_frame(&mut wire, &batch(&["a", "b"], false)).unwrap();
+            write_frame(&mut wire, &batch(&["c"], true)).unwrap();
+            write_frame(&mut wire, &batch(&["d"], false)).unwrap();
+            conn.write_all(&wire).await.unwrap();
+        });
+
+        let conn = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let mut frames = AsyncFrameReader::new(conn);
+        let mut seen = Vec::new();
+        let mut compressed = Vec::new();
+        while let Some(mut batch) = frames.next_batch().await.unwrap() {
+            while let Some(r) = batch.next_reading().await.unwrap() {
+                seen.push(r.sensor_id.to_owned());
+                if r.sensor_id == "a" {
+                    break; // leave "b" unread
+                }
+            }
+            compressed.push(batch.is_compressed());
+        }
+        writer.await.unwrap();
+        assert_eq!(seen, ["a", "c", "d"]);
+        assert_eq!(compressed, [false, true, false]);
+    }
+
+    /// Hands out `data` three bytes at a time, returning `Pending` before
+    /// every read.
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        pending: bool,
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            this.pending = !this.pending;
+            if this.pending {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let n = buf.remaining().min(3).min(this.data.len() - this.pos);
+            buf.put_slice(&this.data[this.pos..this.pos + n]);
+            this.pos += n;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Poll `fut` at most `polls` times, then drop it, as a timeout would.
+    fn poll_briefly<F: std::future::Future>(fut: F, polls: usize) -> Option<F::Output> {
+        let mut fut = std::pin::pin!(fut);
+        let mut cx = Context::from_waker(std::task::Waker::noop());
+        (0..polls).find_map(|_| match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(out) => Some(out),
+            Poll::Pending => None,
+        })
+    }
+
+    #[test]
+    fn dropped_calls_lose_nothing() {
+        let mut wire = Vec::new();
+        write_frame(&mut wire, &batch(&["temp-001", "temp-002"], false)).unwrap();
+        write_frame(&mut wire, &batch(&["c"], true)).unwrap();
+        let mut frames = AsyncFrameReader::new(Trickle {
+            data: wire,
+            pos: 0,
+            pending: false,
+        });
+        let mut polls = [1, 4, 200].into_iter().cycle();
+        let mut seen = Vec::new();
+        loop {
+            let Some(batch) = poll_briefly(frames.next_batch(), polls.next().unwrap()) else {
+                continue;
+            };
+            let Some(mut batch) = batch.unwrap() else {
+                break;
+            };
+            loop {
+                match poll_briefly(batch.next_reading(), polls.next().unwrap()) {
+                    Some(Ok(Some(r))) => seen.push(r.sensor_id.to_owned()),
+                    Some(Ok(None)) => break,
+                    Some(Err(err)) => panic!("{err}"),
+                    None => {}
+                }
+            }
+        }
+        assert_eq!(seen, ["temp-001", "temp-002", "c"]);
+    }
+
+    #[tokio::test]
+    async fn truncated_compressed_batch() {
+        let data = batch(&["abc", "def"], true);
+        let mut reader = AsyncBatchReader::new(&data[..data.len() - 12]);
+        let err = loop {
+            match reader.next_reading().await {
+                Ok(Some(_)) => {}
+                Ok(None) => panic!("truncated batch decoded"),
+                Err(err) => break err,
+            }
+        };
+        assert!(matches!(
+            err,
+            StreamError::Decode(DecodeError::UnexpectedEnd) | StreamError::Io(_)
+        ));
+    }
+}
@@ -0,0 +1,405 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+//
+// lib.rs - Rust implementation of the Bebop-V-FFI C ABI
+//
+// Exports the functions of include/bebop_v_ffi.h with the same symbols and
+// struct layouts as implementations/zig/src/bridge.zig, so C and V consumers
+// can switch between the two libraries by relinking.
+//
+// All decode state lives in the caller's BebopCtx. The only process-wide
+// state is the two callback slots, because the header's registration
+// functions take no context.
+
+pub mod abi;
+#[cfg(feature = "async")]
+pub mod async_stream;
+pub mod stream;
+pub mod wire;
+
+use std::ffi::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+pub use abi::*;
+#[cfg(feature = "async")]
+pub use async_stream::{AsyncBatchReader, AsyncFrameReader};
+pub use stream::{BatchReader, FrameReader, StreamError};
+use wire::Arena;
+pub use wire::{DecodeError, MetadataIter, SensorReadingRef};
+
+// -----------------------------------------------------------------------------
+// Context (owns the metadata arrays of decoded readings)
+// -----------------------------------------------------------------------------
+
+pub struct BebopCtx {
+    arena: Arena,
+    error_buf: [u8; 256],
+    error_msg: *const c_char,
+}
+
+impl BebopCtx {
+    fn new() -> BebopCtx {
+        BebopCtx {
+            arena: Arena::default(),
+            error_buf: [0; 256],
+            error_msg: ptr::null(),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.arena.reset();
+        self.error_msg = ptr::null();
+    }
+
+    fn set_error(&mut self, msg: &str) {
+        let len = msg.len().min(self.error_buf.len() - 1);
+        self.error_buf[..len].copy_from_slice(&msg.as_bytes()[..len]);
+        self.error_buf[len] = 0;
+        self.error_msg = self.error_buf.as_ptr().cast();
+    }
+}
+
+/// Run an export body; a panic must not unwind into C, so it becomes
+/// `fallback`.
+fn guard<R>(fallback: R, body: impl FnOnce() -> R) -> R {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(fallback)
+}
+
+// -----------------------------------------------------------------------------
+// Exported C ABI functions
+// -----------------------------------------------------------------------------
+
+/// Return ABI version for runtime compatibility checks.
+#[no_mangle]
+pub extern "C" fn bebop_version() -> u32 {
+    ABI_VERSION
+}
+
+/// Create a new context. Returns null on allocation failure.
+#[no_mangle]
+pub extern "C" fn bebop_ctx_new() -> *mut BebopCtx {
+    guard(ptr::null_mut(), || Box::into_raw(Box::new(BebopCtx::new())))
+}
+
+/// Free a context and all its allocations. Safe to call with null.
+///
+/// # Safety
+///
+/// `ctx` must be null or a context from [`bebop_ctx_new`] not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn bebop_ctx_free(ctx: *mut BebopCtx) {
+    if !ctx.is_null() {
+        guard((), || drop(Box::from_raw(ctx)));
+    }
+}
+
+/// Reset context for reuse. Invalidates all previously decoded data.
+///
+/// # Safety
+///
+/// `ctx` must be null or a live context.
+#[no_mangle]
+pub unsafe extern "C" fn bebop_ctx_reset(ctx: *mut BebopCtx) {
+    if let Some(c) = ctx.as_mut() {
+        c.reset();
+    }
+}
+
+/// Decode a SensorReading from Bebop wire format.
+/// Returns 0 on success, negative error code on failure.
+///
+/// # Safety
+///
+/// `ctx` must be null or a live context, `data` null or valid for `len`
+/// bytes, and `out` null or valid for writes. Decoded strings point into
+/// `data`, which must outlive them.
+#[no_mangle]
+pub unsafe extern "C" fn bebop_decode_sensor_reading(
+    ctx: *mut BebopCtx,
+    data: *const u8,
+    len: usize,
+    out: *mut VSensorReading,
+) -> i32 {
+    let Some(c) = ctx.as_mut() else {
+        return BEBOP_ERR_NULL_CTX;
+    };
+    let Some(output) = out.as_mut() else {
+        return BEBOP_ERR_NULL_DATA;
+    };
+    if data.is_null() {
+        return BEBOP_ERR_NULL_DATA;
+    }
+    if len == 0 {
+        return BEBOP_ERR_INVALID_LENGTH;
+    }
+    let bytes = std::slice::from_raw_parts(data, len);
+
+    let decoded = guard(Err("decoder panicked"), || {
+        wire::decode_sensor_reading(bytes, &mut c.arena).map_err(|err| err.message())
+    });
+    match decoded {
+        Ok(reading) => {
+            *output = reading;
+            BEBOP_OK
+        }
+        Err(msg) => {
+            *output = VSensorReading::empty();
+            output.error_code = BEBOP_ERR_DECODE_FAILED;
+            c.set_error(msg);
+            output.error_message = c.error_msg;
+            BEBOP_ERR_DECODE_FAILED
+        }
+    }
+}
+
+/// Free per-reading allocations. Safe to call multiple times.
+///
+/// # Safety
+///
+/// `reading` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bebop_free_sensor_reading(
+    _ctx: *mut BebopCtx,
+    reading: *mut VSensorReading,
+) {
+    // Arrays are owned by the context and reclaimed on reset/free.
+    if let Some(r) = reading.as_mut() {
+        *r = VSensorReading::empty();
+    }
+}
+
+/// Encode a batch of readings into `out_buf`. Returns bytes written, 0 on
+/// failure (the reason is kept as the context's error message).
+///
+/// # Safety
+///
+/// `ctx` must be null or a live context, `readings` null or valid for `count`
+/// readings whose pointers are valid, and `out_buf` null or valid for writes
+/// of `out_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bebop_encode_batch_readings(
+    ctx: *mut BebopCtx,
+    readings: *const VSensorReading,
+    count: usize,
+    out_buf: *mut u8,
+    out_len: usize,
+) -> usize {
+    let Some(c) = ctx.as_mut() else {
+        return 0;
+    };
+    if (readings.is_null() && count > 0) || out_buf.is_null() {
+        c.set_error("null data pointer");
+        return 0;
+    }
+    let readings = if count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(readings, count)
+    };
+    let out = std::slice::from_raw_parts_mut(out_buf, out_len);
+
+    match guard(
+        Err(wire::EncodeError::InvalidReading("encoder panicked")),
+        || wire::encode_batch_readings(readings, out),
+    ) {
+        Ok(written) => written,
+        Err(err) => {
+            c.set_error(err.message());
+            0
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Callback Registration (Bidirectional FFI)
+// -----------------------------------------------------------------------------
+
+static READING_CALLBACK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+static ERROR_CALLBACK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Register callback for receiving sensor readings. Pass null to unregister.
+#[no_mangle]
+pub extern "C" fn bebop_register_reading_callback(callback: Option<ReadingCallback>) {
+    let raw = callback.map_or(ptr::null_mut(), |cb| cb as *mut ());
+    READING_CALLBACK.store(raw, Ordering::Release);
+}
+
+/// Register callback for error notifications. Pass null to unregister.
+#[no_mangle]
+pub extern "C" fn bebop_register_error_callback(callback: Option<ErrorCallback>) {
+    let raw = callback.map_or(ptr::null_mut(), |cb| cb as *mut ());
+    ERROR_CALLBACK.store(raw, Ordering::Release);
+}
+
+/// Invoke the registered reading callback, if any.
+///
+/// # Safety
+///
+/// `reading` must be null or point to a valid reading.
+#[no_mangle]
+pub unsafe extern "C" fn bebop_invoke_reading_callback(reading: *const VSensorReading) {
+    let raw = READING_CALLBACK.load(Ordering::Acquire);
+    if !raw.is_null() && !reading.is_null() {
+        let cb: ReadingCallback = std::mem::transmute::<*mut (), ReadingCallback>(raw);
+        cb(reading);
+    }
+}
+
+/// Invoke the registered error callback, if any. A null message is passed
+/// on as "unknown error".
+///
+/// # Safety
+///
+/// `message` must be null or NUL-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn bebop_invoke_error_callback(code: i32, message: *const c_char) {
+    let raw = ERROR_CALLBACK.load(Ordering::Acquire);
+    if !raw.is_null() {
+        let cb: ErrorCallback = std::mem::transmute::<*mut (), ErrorCallback>(raw);
+        let msg = if message.is_null() {
+            c"unknown error".as_ptr()
+        } else {
+            message
+        };
+        cb(code, msg);
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    struct Ctx(*mut BebopCtx);
+
+    impl Ctx {
+        fn new() -> Ctx {
+            let ctx = bebop_ctx_new();
+            assert!(!ctx.is_null());
+            Ctx(ctx)
+        }
+    }
+
+    impl Drop for Ctx {
+        fn drop(&mut self) {
+            unsafe { bebop_ctx_free(self.0) }
+        }
+    }
+
+    fn text(b: VBytes) -> &'static str {
+        std::str::from_utf8(unsafe { b.as_slice() }.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn decode_errors_match_zig() {
+        let ctx = Ctx::new();
+        let mut r = VSensorReading::empty();
+        let truncated = [0x02, 0x08, 0x00, 0x00, 0x00];
+        unsafe {
+            assert_eq!(
+                bebop_decode_sensor_reading(ptr::null_mut(), truncated.as_ptr(), 5, &mut r),
+                BEBOP_ERR_NULL_CTX
+            );
+            assert_eq!(
+                bebop_decode_sensor_reading(ctx.0, ptr::null(), 5, &mut r),
+                BEBOP_ERR_NULL_DATA
+            );
+            assert_eq!(
+                bebop_decode_sensor_reading(ctx.0, truncated.as_ptr(), 0, &mut r),
+                BEBOP_ERR_INVALID_LENGTH
+            );
+            assert_eq!(
+                bebop_decode_sensor_reading(ctx.0, truncated.as_ptr(), 5, &mut r),
+                BEBOP_ERR_DECODE_FAILED
+            );
+            assert_eq!(r.error_code, BEBOP_ERR_DECODE_FAILED);
+            assert_eq!(
+                CStr::from_ptr(r.error_message).to_str().unwrap(),
+                "unexpected end of data"
+            );
+        }
+    }
+
+    #[test]
+    fn encode_decode_round_trip_with_metadata() {
+        let ctx = Ctx::new();
+        let mut keys = [VBytes::from_slice(b"status"), VBytes::from_slice(b"fw")];
+        let mut values = [VBytes::from_slice(b"ok"), VBytes::from_slice(b"1.2")];
+        let reading = VSensorReading {
+            timestamp: 42,
+            sensor_id: VBytes::from_slice(b"vib-7"),
+            sensor_type: SENSOR_TYPE_VIBRATION,
+            value: -0.25,
+            location: VBytes::from_slice(b"line-3"),
+            metadata_count: 2,
+            metadata_keys: keys.as_mut_ptr(),
+            metadata_values: values.as_mut_ptr(),
+            ..VSensorReading::empty()
+        };
+        let mut buf = [0u8; 256];
+        let n =
+            unsafe { bebop_encode_batch_readings(ctx.0, &reading, 1, buf.as_mut_ptr(), buf.len()) };
+        assert!(n > 0);
+        // Strip the batch framing (5 bytes ahead, end marker behind).
+        let inner = &buf[5..n - 1];
+        let mut r = VSensorReading::empty();
+        let code =
+            unsafe { bebop_decode_sensor_reading(ctx.0, inner.as_ptr(), inner.len(), &mut r) };
+        assert_eq!(code, BEBOP_OK);
+        assert_eq!(r.timestamp, 42);
+        assert_eq!(text(r.sensor_id), "vib-7");
+        assert_eq!(r.sensor_type, SENSOR_TYPE_VIBRATION);
+        assert_eq!(r.value, -0.25);
+        assert_eq!(text(r.unit), "");
+        assert_eq!(r.metadata_count, 2);
+        let got_values = unsafe { std::slice::from_raw_parts(r.metadata_values, 2) };
+        assert_eq!(text(got_values[1]), "1.2");
+
+        let n = unsafe { bebop_encode_batch_readings(ctx.0, &reading, 1, buf.as_mut_ptr(), 8) };
+        assert_eq!(n, 0);
+    }
+
+    #[test]
+    fn empty_batch_and_null_pointers() {
+        let ctx = Ctx::new();
+        let mut buf = [0u8; 8];
+        unsafe {
+            assert_eq!(
+                bebop_encode_batch_readings(ctx.0, ptr::null(), 0, buf.as_mut_ptr(), 8),
+                6
+            );
+            assert_eq!(
+                bebop_encode_batch_readings(ctx.0, ptr::null(), 1, buf.as_mut_ptr(), 8),
+                0
+            );
+            assert_eq!(
+                bebop_encode_batch_readings(ptr::null_mut(), ptr::null(), 0, buf.as_mut_ptr(), 8),
+                0
+            );
+        }
+        assert_eq!(buf[..6], [1, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn callbacks_round_trip() {
+        use std::sync::atomic::AtomicI32;
+        static LAST_CODE: AtomicI32 = AtomicI32::new(0);
+        unsafe extern "C" fn on_error(code: i32, message: *const c_char) {
+            assert_eq!(CStr::from_ptr(message).to_str().unwrap(), "unknown error");
+            LAST_CODE.store(code, Ordering::SeqCst);
+        }
+
+        bebop_register_error_callback(Some(on_error));
+        unsafe { bebop_invoke_error_callback(BEBOP_ERR_ENCODE_FAILED, ptr::null()) };
+        assert_eq!(LAST_CODE.load(Ordering::SeqCst), BEBOP_ERR_ENCODE_FAILED);
+        bebop_register_error_callback(None);
+        unsafe { bebop_invoke_error_callback(BEBOP_ERR_DECODE_FAILED, ptr::null()) };
+        assert_eq!(LAST_CODE.load(Ordering::SeqCst), BEBOP_ERR_ENCODE_FAILED);
+    }
+}
@@ -0,0 +1,723 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+//
+// stream.rs - Streaming BatchReadings decoder
+//
+// Decodes a BatchReadings message from any `Read` one SensorReading at a
+// time, buffering only the reading being decoded. Readings are handed out as
+// `SensorReadingRef`s borrowing that buffer, which is reused.
+//
+// BatchReadings fields (sensors.bop):
+//
+//   1  readings    u32 count, then each reading up to its 0 terminator
+//   2  batchId     16 bytes
+//   3  compressed  bool
+//
+// Compression, as in docs/20-wire-and-framing.adoc: a writer that sets
+// `compressed = true` places it before `readings`, and every byte after that
+// field - the remaining fields and the message terminator - is an LZ4 frame.
+// The decoder switches to decompressing as soon as it reads the flag, so the
+// batch is never held whole in either form. A flag after `readings`, as plain
+// encoders such as the generated code write it, compresses nothing; the rest
+// of the message is read as it is.
+//
+// Framing: on TCP and Unix sockets each batch is preceded by its u32
+// little-endian length, as in docs/20-wire-and-framing.adoc. `FrameReader`
+// yields one `BatchReader` per frame and skips whatever the caller leaves
+// unread.
+//
+// The state machine in `BatchCore` does no I/O, so the Tokio readers in
+// async_stream.rs share it.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use lz4_flex::frame::FrameDecoder;
+
+use crate::wire::{DecodeError, SensorReadingRef};
+
+/// Default cap on a single encoded SensorReading.
+pub const DEFAULT_MAX_READING_LEN: usize = 64 * 1024;
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Decode(DecodeError),
+    /// A reading exceeds the configured maximum; the value is the limit.
+    ReadingTooLarge(usize),
+    /// A frame header announced more than the configured maximum.
+    FrameTooLarge(u32),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(err) => write!(f, "I/O error: {err}"),
+            StreamError::Decode(err) => err.fmt(f),
+            StreamError::ReadingTooLarge(max) => {
+                write!(f, "sensor reading larger than {max} bytes")
+            }
+            StreamError::FrameTooLarge(len) => write!(f, "frame of {len} bytes exceeds limit"),
+        }
+    }
+}
+
+impl Error for StreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StreamError::Io(err) => Some(err),
+            StreamError::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(err: io::Error) -> StreamError {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            StreamError::Decode(DecodeError::UnexpectedEnd)
+        } else {
+            StreamError::Io(err)
+        }
+    }
+}
+
+impl From<DecodeError> for StreamError {
+    fn from(err: DecodeError) -> StreamError {
+        StreamError::Decode(err)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Decoder state machine
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// BatchReadings field index.
+    Field,
+    ReadingCount,
+    BatchId,
+    Compressed,
+    /// SensorReading field index.
+    ReadingField,
+    /// Fixed-size SensorReading value.
+    Value,
+    /// u32 string length; `in_map` strings count down `strings_left`.
+    StrLen {
+        in_map: bool,
+    },
+    StrBody {
+        in_map: bool,
+    },
+    MapCount,
+    Done,
+}
+
+/// What the driver must do after [`BatchCore::advance`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Step {
+    /// Fill and advance again.
+    More,
+    /// A complete reading is in [`BatchCore::reading`].
+    Reading,
+    /// Wrap the source in an LZ4 frame decoder, then continue.
+    Decompress,
+    /// The batch is finished.
+    End,
+}
+
+/// I/O-free BatchReadings decoder: the driver reads into the room `fill()`
+/// returns and reports it with `add_filled()` until `fill()` comes back
+/// empty, then calls `advance()`. A chunk left half read, such as by a
+/// cancelled async read, resumes where it stopped.
+#[derive(Debug)]
+pub(crate) struct BatchCore {
+    state: State,
+    want: usize,
+    /// Bytes of the current reading, plus the pending chunk at `start..`.
+    buf: Vec<u8>,
+    start: usize,
+    /// Bytes of the pending chunk read so far; `None` before `fill()` starts
+    /// a chunk and after `advance()` consumes it.
+    filled: Option<usize>,
+    /// Clear `buf` before the next fill (a reading was handed out).
+    consumed: bool,
+    readings_left: u32,
+    strings_left: u64,
+    batch_id: Option<[u8; 16]>,
+    /// `readings` has started, so a `compressed` flag no longer applies.
+    seen_readings: bool,
+    compressed: bool,
+    max_reading_len: usize,
+}
+
+impl BatchCore {
+    pub(crate) fn new() -> BatchCore {
+        BatchCore {
+            state: State::Field,
+            want: 1,
+            buf: Vec::new(),
+            start: 0,
+            filled: None,
+            consumed: false,
+            readings_left: 0,
+            strings_left: 0,
+            batch_id: None,
+            seen_readings: false,
+            compressed: false,
+            max_reading_len: DEFAULT_MAX_READING_LEN,
+        }
+    }
+
+    pub(crate) fn set_max_reading_len(&mut self, max: usize) {
+        self.max_reading_len = max;
+    }
+
+    /// Between BatchReadings fields, where end of input ends the message.
+    pub(crate) fn at_boundary(&self) -> bool {
+        self.state == State::Field
+    }
+
+    pub(crate) fn batch_id(&self) -> Option<[u8; 16]> {
+        self.batch_id
+    }
+
+    pub(crate) fn compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// The unread part of the next chunk; empty once the chunk is complete.
+    pub(crate) fn fill(&mut self) -> Result<&mut [u8], StreamError> {
+        let filled = match self.filled {
+            Some(filled) => filled,
+            None => {
+                if self.consumed {
+                    self.buf.clear();
+                    self.consumed = false;
+                }
+                let in_reading = !matches!(
+                    self.state,
+                    State::Field | State::ReadingCount | State::BatchId | State::Compressed
+                );
+                if in_reading && self.buf.len() + self.want > self.max_reading_len {
+                    return Err(StreamError::ReadingTooLarge(self.max_reading_len));
+                }
+                self.start = self.buf.len();
+                self.buf.resize(self.start + self.want, 0);
+                *self.filled.insert(0)
+            }
+        };
+        Ok(&mut self.buf[self.start + filled..])
+    }
+
+    /// `n` bytes were read into the room `fill()` returned.
+    pub(crate) fn add_filled(&mut self, n: usize) {
+        if let Some(filled) = &mut self.filled {
+            *filled += n;
+        }
+    }
+
+    /// Part of the pending chunk has been read, so the input may not end.
+    pub(crate) fn chunk_started(&self) -> bool {
+        self.filled.is_some_and(|filled| filled > 0)
+    }
+
+    /// End of input at a boundary.
+    pub(crate) fn finish(&mut self) {
+        self.state = State::Done;
+    }
+
+    /// The reading completed by the last `Step::Reading`.
+    pub(crate) fn reading(&self) -> Result<SensorReadingRef<'_>, DecodeError> {
+        SensorReadingRef::decode(&self.buf)
+    }
+
+    fn chunk_u32(&self) -> u32 {
+        let bytes = self.buf[self.start..self.start + 4].try_into();
+        u32::from_le_bytes(bytes.expect("want was 4"))
+    }
+
+    fn next(&mut self, state: State, want: usize) -> Result<Step, StreamError> {
+        self.state = state;
+        self.want = want;
+        Ok(Step::More)
+    }
+
+    /// A string of the current reading ended.
+    fn after_string(&mut self, in_map: bool) -> Result<Step, StreamError> {
+        if in_map {
+            self.strings_left -= 1;
+            if self.strings_left > 0 {
+                return self.next(State::StrLen { in_map: true }, 4);
+            }
+        }
+        self.next(State::ReadingField, 1)
+    }
+
+    /// Consume the chunk just filled.
+    pub(crate) fn advance(&mut self) -> Result<Step, StreamError> {
+        self.filled = None;
+        match self.state {
+            State::Field => {
+                let index = self.buf[self.start];
+                self.buf.truncate(self.start);
+                match index {
+                    0 => {
+                        self.state = State::Done;
+                        Ok(Step::End)
+                    }
+                    1 => self.next(State::ReadingCount, 4),
+                    2 => self.next(State::BatchId, 16),
+                    3 => self.next(State::Compressed, 1),
+                    _ => Err(DecodeError::InvalidFieldIndex.into()),
+                }
+            }
+            State::ReadingCount => {
+                self.seen_readings = true;
+                self.readings_left = self.chunk_u32();
+                self.buf.truncate(self.start);
+                if self.readings_left == 0 {
+                    self.next(State::Field, 1)
+                } else {
+                    self.next(State::ReadingField, 1)
+                }
+            }
+            State::BatchId => {
+                let id = self.buf[self.start..].try_into().expect("want was 16");
+                self.batch_id = Some(id);
+                self.buf.truncate(self.start);
+                self.next(State::Field, 1)
+            }
+            State::Compressed => {
+                let flag = self.buf[self.start] != 0;
+                self.buf.truncate(self.start);
+                self.next(State::Field, 1)?;
+                if flag && !self.compressed && !self.seen_readings {
+                    self.compressed = true;
+                    return Ok(Step::Decompress);
+                }
+                Ok(Step::More)
+            }
+            State::ReadingField => match self.buf[self.start] {
+                0 => {
+                    self.readings_left -= 1;
+                    self.consumed = true;
+                    self.state = if self.readings_left == 0 {
+                        State::Field
+                    } else {
+                        State::ReadingField
+                    };
+                    self.want = 1;
+                    Ok(Step::Reading)
+                }
+                1 | 4 => self.next(State::Value, 8),
+                3 => self.next(State::Value, 2),
+                2 | 5 | 6 => self.next(State::StrLen { in_map: false }, 4),
+                7 => self.next(State::MapCount, 4),
+                _ => Err(DecodeError::InvalidFieldIndex.into()),
+            },
+            State::Value => self.next(State::ReadingField, 1),
+            State::StrLen { in_map } => match self.chunk_u32() {
+                0 => self.after_string(in_map),
+                len => self.next(State::StrBody { in_map }, len as usize),
+            },
+            State::StrBody { in_map } => self.after_string(in_map),
+            State::MapCount => {
+                self.strings_left = u64::from(self.chunk_u32()) * 2;
+                if self.strings_left == 0 {
+                    self.next(State::ReadingField, 1)
+                } else {
+                    self.next(State::StrLen { in_map: true }, 4)
+                }
+            }
+            State::Done => Ok(Step::End),
+        }
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Blocking readers
+// -----------------------------------------------------------------------------
+
+enum Source<R: Read> {
+    Plain(R),
+    Lz4(FrameDecoder<R>),
+    /// Only observable if a panic interrupts the switch to `Lz4`.
+    Switching,
+}
+
+impl<R: Read> Read for Source<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Source::Plain(r) => r.read(buf),
+            Source::Lz4(r) => r.read(buf),
+            Source::Switching => Err(io::Error::other("source lost while switching")),
+        }
+    }
+}
+
+/// Fill `buf` completely. `Ok(false)` if the input ended before the first
+/// byte; a later end is `UnexpectedEof`.
+fn fill_exact(r: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    let mut got = 0;
+    while got < buf.len() {
+        match r.read(&mut buf[got..]) {
+            Ok(0) if got == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => got += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(true)
+}
+
+/// [`fill_exact`] for the core's pending chunk, recording progress in the
+/// core so an interrupted fill can be resumed.
+fn fill_chunk(r: &mut impl Read, core: &mut BatchCore) -> Result<bool, StreamError> {
+    loop {
+        let started = core.chunk_started();
+        let buf = core.fill()?;
+        if buf.is_empty() {
+            return Ok(true);
+        }
+        match r.read(buf) {
+            Ok(0) if !started => return Ok(false),
+            Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            Ok(n) => core.add_filled(n),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Streaming decoder for one BatchReadings message.
+///
+/// ```no_run
+/// # fn main() -> Result<(), bebop_v_ffi::StreamError> {
+/// use bebop_v_ffi::BatchReader;
+///
+/// let file = std::fs::File::open("batch.bin")?;
+/// let mut batch = BatchReader::new(std::io::BufReader::new(file));
+/// while let Some(reading) = batch.next_reading()? {
+///     println!("{} = {}", reading.sensor_id, reading.value);
+/// }
+/// println!("batch id {:?}", batch.batch_id());
+/// # Ok(())
+/// # }
+/// ```
+pub struct BatchReader<R: Read> {
+    source: Source<R>,
+    core: BatchCore,
+}
+
+impl<R: Read> BatchReader<R> {
+    pub fn new(reader: R) -> BatchReader<R> {
+        BatchReader {
+            source: Source::Plain(reader),
+            core: BatchCore::new(),
+        }
+    }
+
+    /// Reject readings larger than `max` encoded bytes (default
+    /// [`DEFAULT_MAX_READING_LEN`]); this bounds memory use.
+    pub fn with_max_reading_len(mut self, max: usize) -> BatchReader<R> {
+        self.core.set_max_reading_len(max);
+        self
+    }
+
+    /// The next reading, or `None` once the whole batch has been read.
+    /// The reading borrows the decoder's buffer until the next call.
+    pub fn next_reading(&mut self) -> Result<Option<SensorReadingRef<'_>>, StreamError> {
+        loop {
+            if self.core.is_done() {
+                return Ok(None);
+            }
+            let at_boundary = self.core.at_boundary();
+            if !fill_chunk(&mut self.source, &mut self.core)? {
+                if !at_boundary {
+                    return Err(DecodeError::UnexpectedEnd.into());
+                }
+                self.core.finish();
+                return Ok(None);
+            }
+            match self.core.advance()? {
+                Step::More => {}
+                Step::Reading => return Ok(Some(self.core.reading()?)),
+                Step::Decompress => {
+                    self.source = match std::mem::replace(&mut self.source, Source::Switching) {
+                        Source::Plain(r) => Source::Lz4(FrameDecoder::new(r)),
+                        other => other,
+                    };
+                }
+                Step::End => return Ok(None),
+            }
+        }
+    }
+
+    /// `batchId`, once it has been read. Fields after `readings` are only
+    /// known when [`next_reading`](Self::next_reading) has returned `None`.
+    pub fn batch_id(&self) -> Option<[u8; 16]> {
+        self.core.batch_id()
+    }
+
+    /// Whether the batch is LZ4-compressed: `compressed = true` came before
+    /// `readings`.
+    pub fn is_compressed(&self) -> bool {
+        self.core.compressed()
+    }
+}
+
+/// One frame's payload; reads stop at the frame's end.
+pub struct Frame<'a, R> {
+    inner: &'a mut R,
+    left: &'a mut u64,
+}
+
+impl<R: Read> Read for Frame<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let max = buf
+            .len()
+            .min(usize::try_from(*self.left).unwrap_or(usize::MAX));
+        if max == 0 {
+            return Ok(0);
+        }
+        let n = self.inner.read(&mut buf[..max])?;
+        *self.left -= n as u64;
+        Ok(n)
+    }
+}
+
+/// Length-prefixed batches from a byte stream such as a `TcpStream` or
+/// `UnixStream`.
+///
+/// ```no_run
+/// # fn main() -> Result<(), bebop_v_ffi::StreamError> {
+/// use bebop_v_ffi::FrameReader;
+///
+/// let conn = std::net::TcpStream::connect("gateway:9000")?;
+/// let mut frames = FrameReader::new(std::io::BufReader::new(conn));
+/// while let Some(mut batch) = frames.next_batch()? {
+///     while let Some(reading) = batch.next_reading()? {
+///         println!("{} = {}", reading.sensor_id, reading.value);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct FrameReader<R> {
+    inner: R,
+    left: u64,
+    max_frame_len: u32,
+    max_reading_len: usize,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(inner: R) -> FrameReader<R> {
+        FrameReader {
+            inner,
+            left: 0,
+            max_frame_len: u32::MAX,
+            max_reading_len: DEFAULT_MAX_READING_LEN,
+        }
+    }
+
+    /// Reject frames announcing more than `max` bytes.
+    pub fn with_max_frame_len(mut self, max: u32) -> FrameReader<R> {
+        self.max_frame_len = max;
+        self
+    }
+
+    /// Passed on to each [`BatchReader`].
+    pub fn with_max_reading_len(mut self, max: usize) -> FrameReader<R> {
+        self.max_reading_len = max;
+        self
+    }
+
+    /// Decoder for the next frame, or `None` when the stream ends cleanly.
+    /// Any unread part of the previous frame is skipped first.
+    pub fn next_batch(&mut self) -> Result<Option<BatchReader<Frame<'_, R>>>, StreamError> {
+        let mut rest = Frame {
+            inner: &mut self.inner,
+            left: &mut self.left,
+        };
+        io::copy(&mut rest, &mut io::sink())?;
+        if *rest.left > 0 {
+            return Err(DecodeError::UnexpectedEnd.into());
+        }
+
+        let mut header = [0u8; 4];
+        if !fill_exact(&mut self.inner, &mut header)? {
+            return Ok(None);
+        }
+        let len = u32::from_le_bytes(header);
+        if len > self.max_frame_len {
+            return Err(StreamError::FrameTooLarge(len));
+        }
+        self.left = u64::from(len);
+        let frame = Frame {
+            inner: &mut self.inner,
+            left: &mut self.left,
+        };
+        Ok(Some(
+            BatchReader::new(frame).with_max_reading_len(self.max_reading_len),
+        ))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// Write `payload` as one length-prefixed frame.
+pub fn write_frame(w: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "frame exceeds u32::MAX"))?;
+    w.write_all(&len.to_le_bytes())?;
+    w.write_all(payload)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// SensorReading with sensorId, value and one metadata entry.
+    pub(crate) fn reading(id: &str, value: f64) -> Vec<u8> {
+        let mut out = vec![2];
+        out.extend_from_slice(&(id.len() as u32).to_le_bytes());
+        out.extend_from_slice(id.as_bytes());
+        out.push(4);
+        out.extend_from_slice(&value.to_le_bytes());
+        out.extend_from_slice(&[7, 1, 0, 0, 0, 1, 0, 0, 0, b'k', 1, 0, 0, 0, b'v']);
+        out.push(0);
+        out
+    }
+
+    /// BatchReadings with `ids`, batchId 0x11.., compressed if asked.
+    pub(crate) fn batch(ids: &[&str], compressed: bool) -> Vec<u8> {
+        let mut body = vec![1];
+        body.extend_from_slice(&(ids.len() as u32).to_le_bytes());
+        for (i, id) in ids.iter().enumerate() {
+            body.extend_from_slice(&reading(id, i as f64));
+        }
+        body.push(2);
+        body.extend_from_slice(&[0x11; 16]);
+        body.push(0);
+        if !compressed {
+            return body;
+        }
+        let mut out = vec![3, 1];
+        let mut enc = lz4_flex::frame::FrameEncoder::new(&mut out);
+        enc.write_all(&body).unwrap();
+        enc.finish().unwrap();
+        out
+    }
+
+    fn ids<R: Read>(batch: &mut BatchReader<R>) -> Vec<String> {
+        let mut out = Vec::new();
+        while let Some(r) = batch.next_reading().unwrap() {
+            assert_eq!(r.metadata_get("k"), Some("v"));
+            out.push(r.sensor_id.to_owned());
+        }
+        out
+    }
+
+    #[test]
+    fn streams_plain_and_compressed_batches() {
+        for compressed in [false, true] {
+            let data = batch(&["a", "bb", "ccc"], compressed);
+            let mut reader = BatchReader::new(&data[..]);
+            assert_eq!(ids(&mut reader), ["a", "bb", "ccc"]);
+            assert_eq!(reader.batch_id(), Some([0x11; 16]));
+            assert_eq!(reader.is_compressed(), compressed);
+            assert!(reader.next_reading().unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn matches_c_abi_encoder_output() {
+        let mut readings = [crate::VSensorReading::empty(); 2];
+        readings[0].sensor_id = crate::VBytes::from_slice(b"x");
+        readings[1].value = 2.5;
+        let mut buf = [0u8; 256];
+        let n = unsafe { crate::wire::encode_batch_readings(&readings, &mut buf) }.unwrap();
+        let mut reader = BatchReader::new(&buf[..n]);
+        assert_eq!(reader.next_reading().unwrap().unwrap().sensor_id, "x");
+        assert_eq!(reader.next_reading().unwrap().unwrap().value, 2.5);
+        assert!(reader.next_reading().unwrap().is_none());
+        assert_eq!(reader.batch_id(), None);
+    }
+
+    #[test]
+    fn errors() {
+        let data = batch(&["abc"], false);
+        let mut reader = BatchReader::new(&data[..20]);
+        assert!(matches!(
+            reader.next_reading(),
+            Err(StreamError::Decode(DecodeError::UnexpectedEnd))
+        ));
+
+        let mut reader = BatchReader::new(&data[..]).with_max_reading_len(8);
+        assert!(matches!(
+            reader.next_reading(),
+            Err(StreamError::ReadingTooLarge(8))
+        ));
+
+        let mut reader = BatchReader::new(&[9u8][..]);
+        assert!(matches!(
+            reader.next_reading(),
+            Err(StreamError::Decode(DecodeError::InvalidFieldIndex))
+        ));
+
+        // End of input between fields ends the message.
+        let mut reader = BatchReader::new(&data[..data.len() - 1]);
+        assert_eq!(ids(&mut reader), ["abc"]);
+    }
+
+    #[test]
+    fn frames_skip_unread_payload() {
+        let mut wire = Vec::new();
+        write_frame(&mut wire, &batch(&["a", "b"], false)).unwrap();
+        write_frame(&mut wire, &batch(&["c"], true)).unwrap();
+
+        let mut frames = FrameReader::new(&wire[..]);
+        let mut first = frames.next_batch().unwrap().unwrap();
+        assert_eq!(first.next_reading().unwrap().unwrap().sensor_id, "a");
+        let mut second = frames.next_batch().unwrap().unwrap();
+        assert_eq!(ids(&mut second), ["c"]);
+        assert!(frames.next_batch().unwrap().is_none());
+
+        let mut frames = FrameReader::new(&wire[..]).with_max_frame_len(8);
+        assert!(matches!(
+            frames.next_batch(),
+            Err(StreamError::FrameTooLarge(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn frames_over_unix_socket() {
+        use std::os::unix::net::UnixStream;
+
+        let (mut tx, rx) = UnixStream::pair().unwrap();
+        let writer = std::thread::spawn(move || {
+            for i in 0..3 {
+                let id = format!("s{i}");
+                write_frame(&mut tx, &batch(&[&id], i % 2 == 1)).unwrap();
+            }
+        });
+        let mut frames = FrameReader::new(rx);
+        let mut seen = Vec::new();
+        while let Some(mut batch) = frames.next_batch().unwrap() {
+            seen.extend(ids(&mut batch));
+        }
+        writer.join().unwrap();
+        assert_eq!(seen, ["s0", "s1", "s2"]);
+    }
+}
@@ -0,0 +1,468 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+//
+// wire.rs - Bebop wire format for SensorReading / BatchReadings
+//
+// Same message encoding as the Zig implementation: each field is a one-byte
+// index followed by its value, and a 0 byte ends the message. Integers and
+// floats are little-endian; strings are a u32 length plus UTF-8 bytes; a
+// map<string, string> is a u32 count plus key/value strings; arrays are a
+// u32 count plus their elements.
+//
+// Decoding is zero-copy: [`SensorReadingRef`] borrows its strings from the
+// input buffer and walks metadata lazily, so it allocates nothing. The C ABI
+// decoder builds on it and only allocates the metadata key/value arrays, in
+// the context's arena.
+
+use std::fmt;
+use std::iter::FusedIterator;
+
+use crate::abi::{VBytes, VSensorReading};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    UnexpectedEnd,
+    InvalidFieldIndex,
+    InvalidUtf8,
+}
+
+impl DecodeError {
+    /// Message stored in the context, identical to the Zig implementation.
+    pub fn message(self) -> &'static str {
+        match self {
+            DecodeError::UnexpectedEnd => "unexpected end of data",
+            DecodeError::InvalidFieldIndex => "invalid field index",
+            DecodeError::InvalidUtf8 => "invalid UTF-8 string",
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeError {
+    /// A reading has a null pointer with non-zero length, non-UTF-8 text,
+    /// or a length that does not fit in a u32.
+    InvalidReading(&'static str),
+    BufferTooSmall,
+}
+
+impl EncodeError {
+    pub fn message(self) -> &'static str {
+        match self {
+            EncodeError::InvalidReading(msg) => msg,
+            EncodeError::BufferTooSmall => "output buffer too small",
+        }
+    }
+}
+
+/// Owner of the metadata arrays handed out by [`decode_sensor_reading`].
+#[derive(Default)]
+pub struct Arena {
+    arrays: Vec<Box<[VBytes]>>,
+}
+
+impl Arena {
+    pub fn reset(&mut self) {
+        self.arrays.clear();
+    }
+
+    fn alloc(&mut self, items: Vec<VBytes>) -> *mut VBytes {
+        let mut array = items.into_boxed_slice();
+        let ptr = array.as_mut_ptr();
+        self.arrays.push(array);
+        ptr
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Decoder
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.data.len())
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.take(N)?.try_into().expect("take returns N bytes"))
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> Result<f64, DecodeError> {
+        self.array().map(f64::from_le_bytes)
+    }
+
+    fn str(&mut self) -> Result<&'a str, DecodeError> {
+        let len = self.u32()? as usize;
+        std::str::from_utf8(self.take(len)?).map_err(|_| DecodeError::InvalidUtf8)
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+}
+
+/// A SensorReading borrowing its strings from the wire buffer.
+///
+/// The Rust counterpart of the Zig decoder: nothing is copied or allocated,
+/// and the lifetime keeps the reading from outliving the buffer.
+///
+/// ```compile_fail
+/// use bebop_v_ffi::SensorReadingRef;
+///
+/// let reading = {
+///     let data = vec![2, 1, 0, 0, 0, b'x', 0];
+///     SensorReadingRef::decode(&data).unwrap()
+/// }; // `data` dropped here while still borrowed
+/// println!("{}", reading.sensor_id);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SensorReadingRef<'a> {
+    pub timestamp: u64,
+    pub sensor_id: &'a str,
+    pub sensor_type: u16,
+    pub value: f64,
+    pub unit: &'a str,
+    pub location: &'a str,
+    /// Encoded metadata entries (after the count), already validated.
+    metadata: &'a [u8],
+    metadata_count: usize,
+}
+
+impl<'a> SensorReadingRef<'a> {
+    /// Decode a SensorReading. Fields missing from the input keep their
+    /// default (0 or ""); a 0 byte or the end of `data` ends the message.
+    pub fn decode(data: &'a [u8]) -> Result<SensorReadingRef<'a>, DecodeError> {
+        let mut out = SensorReadingRef::default();
+        let mut r = Reader { data, pos: 0 };
+
+        while r.remaining() > 0 {
+            let field_index = r.take(1)?[0];
+            match field_index {
+                0 => break, // End of message
+                1 => out.timestamp = r.u64()?,
+                2 => out.sensor_id = r.str()?,
+                3 => out.sensor_type = r.u16()?,
+                4 => out.value = r.f64()?,
+                5 => out.unit = r.str()?,
+                6 => out.location = r.str()?,
+                7 => {
+                    let count = r.u32()? as usize;
+                    // Validate every entry now so iteration cannot fail.
+                    let start = r.pos;
+                    for _ in 0..count {
+                        r.str()?;
+                        r.str()?;
+                    }
+                    out.metadata = &data[start..r.pos];
+                    out.metadata_count = count;
+                }
+                _ => return Err(DecodeError::InvalidFieldIndex),
+            }
+        }
+        Ok(out)
+    }
+
+    /// Number of metadata entries.
+    pub fn metadata_len(&self) -> usize {
+        self.metadata_count
+    }
+
+    /// Metadata key/value pairs in wire order, decoded on demand.
+    pub fn metadata(&self) -> MetadataIter<'a> {
+        MetadataIter {
+            r: Reader {
+                data: self.metadata,
+                pos: 0,
+            },
+            remaining: self.metadata_count,
+        }
+    }
+
+    /// Value of the first metadata entry with key `key`.
+    pub fn metadata_get(&self, key: &str) -> Option<&'a str> {
+        self.metadata().find(|&(k, _)| k == key).map(|(_, v)| v)
+    }
+}
+
+/// Iterator over the metadata of a [`SensorReadingRef`]; mirrors
+/// `MetadataIterator` in implementations/zig/src/abi.zig.
+#[derive(Debug, Clone)]
+pub struct MetadataIter<'a> {
+    r: Reader<'a>,
+    remaining: usize,
+}
+
+impl<'a> Iterator for MetadataIter<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<(&'a str, &'a str)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        // Entries were validated by SensorReadingRef::decode.
+        let key = self.r.str().ok()?;
+        let value = self.r.str().ok()?;
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for MetadataIter<'_> {}
+
+impl FusedIterator for MetadataIter<'_> {}
+
+/// Decode a SensorReading into its C form. String fields borrow from `data`.
+pub fn decode_sensor_reading(
+    data: &[u8],
+    arena: &mut Arena,
+) -> Result<VSensorReading, DecodeError> {
+    let reading = SensorReadingRef::decode(data)?;
+    let mut out = VSensorReading {
+        timestamp: reading.timestamp,
+        sensor_id: VBytes::from_slice(reading.sensor_id.as_bytes()),
+        sensor_type: reading.sensor_type,
+        value: reading.value,
+        unit: VBytes::from_slice(reading.unit.as_bytes()),
+        location: VBytes::from_slice(reading.location.as_bytes()),
+        ..VSensorReading::empty()
+    };
+    if reading.metadata_len() > 0 {
+        let (keys, values) = reading
+            .metadata()
+            .map(|(k, v)| {
+                (
+                    VBytes::from_slice(k.as_bytes()),
+                    VBytes::from_slice(v.as_bytes()),
+                )
+            })
+            .unzip();
+        out.metadata_count = reading.metadata_len();
+        out.metadata_keys = arena.alloc(keys);
+        out.metadata_values = arena.alloc(values);
+    }
+    Ok(out)
+}
+
+// -----------------------------------------------------------------------------
+// Encoder
+// -----------------------------------------------------------------------------
+
+struct Writer<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl Writer<'_> {
+    fn put(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        let end = self.pos + bytes.len();
+        if end > self.buf.len() {
+            return Err(EncodeError::BufferTooSmall);
+        }
+        self.buf[self.pos..end].copy_from_slice(bytes);
+        self.pos = end;
+        Ok(())
+    }
+
+    fn len(&mut self, len: usize) -> Result<(), EncodeError> {
+        let len =
+            u32::try_from(len).map_err(|_| EncodeError::InvalidReading("length exceeds u32"))?;
+        self.put(&len.to_le_bytes())
+    }
+
+    fn string(&mut self, s: VBytes) -> Result<(), EncodeError> {
+        let bytes = unsafe { s.as_slice() }.ok_or(EncodeError::InvalidReading("null string"))?;
+        std::str::from_utf8(bytes)
+            .map_err(|_| EncodeError::InvalidReading("invalid UTF-8 string"))?;
+        self.len(bytes.len())?;
+        self.put(bytes)
+    }
+}
+
+/// Encode a SensorReading with all seven fields in index order.
+///
+/// # Safety
+///
+/// Every non-null pointer in `reading` must be valid for its length.
+unsafe fn encode_sensor_reading(
+    w: &mut Writer<'_>,
+    reading: &VSensorReading,
+) -> Result<(), EncodeError> {
+    w.put(&[1])?;
+    w.put(&reading.timestamp.to_le_bytes())?;
+    w.put(&[2])?;
+    w.string(reading.sensor_id)?;
+    w.put(&[3])?;
+    w.put(&reading.sensor_type.to_le_bytes())?;
+    w.put(&[4])?;
+    w.put(&reading.value.to_le_bytes())?;
+    w.put(&[5])?;
+    w.string(reading.unit)?;
+    w.put(&[6])?;
+    w.string(reading.location)?;
+
+    w.put(&[7])?;
+    let count = reading.metadata_count;
+    w.len(count)?;
+    if count > 0 {
+        if reading.metadata_keys.is_null() || reading.metadata_values.is_null() {
+            return Err(EncodeError::InvalidReading("null metadata arrays"));
+        }
+        let keys = std::slice::from_raw_parts(reading.metadata_keys, count);
+        let values = std::slice::from_raw_parts(reading.metadata_values, count);
+        for (&k, &v) in keys.iter().zip(values) {
+            w.string(k)?;
+            w.string(v)?;
+        }
+    }
+    w.put(&[0])
+}
+
+/// Encode a BatchReadings message holding `readings` (field 1). `batchId`
+/// and `compressed` are not part of the C ABI and are omitted.
+/// Returns the number of bytes written to `out`.
+///
+/// # Safety
+///
+/// Every non-null pointer in `readings` must be valid for its length.
+pub unsafe fn encode_batch_readings(
+    readings: &[VSensorReading],
+    out: &mut [u8],
+) -> Result<usize, EncodeError> {
+    let mut w = Writer { buf: out, pos: 0 };
+    w.put(&[1])?;
+    w.len(readings.len())?;
+    for reading in readings {
+        encode_sensor_reading(&mut w, reading)?;
+    }
+    w.put(&[0])?;
+    Ok(w.pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(data: &[u8]) -> Result<VSensorReading, DecodeError> {
+        decode_sensor_reading(data, &mut Arena::default())
+    }
+
+    #[test]
+    fn decode_rejects_bad_input() {
+        assert_eq!(
+            decode(&[2, 8, 0, 0, 0]).unwrap_err(),
+            DecodeError::UnexpectedEnd
+        );
+        assert_eq!(decode(&[9]).unwrap_err(), DecodeError::InvalidFieldIndex);
+        assert_eq!(
+            decode(&[5, 1, 0, 0, 0, 0xff, 0]).unwrap_err(),
+            DecodeError::InvalidUtf8
+        );
+        assert_eq!(
+            decode(&[7, 0xff, 0xff, 0xff, 0xff, 0]).unwrap_err(),
+            DecodeError::UnexpectedEnd
+        );
+    }
+
+    #[test]
+    fn decode_stops_at_end_marker() {
+        let r = decode(&[3, 4, 0, 0, 9, 9]).unwrap();
+        assert_eq!(r.sensor_type, 4);
+    }
+
+    #[test]
+    fn borrowed_reading_points_into_input() {
+        let data = [
+            2, 3, 0, 0, 0, b'a', b'b', b'c', // sensorId
+            7, 2, 0, 0, 0, // metadata count
+            1, 0, 0, 0, b'k', 2, 0, 0, 0, b'v', b'1', // k = v1
+            1, 0, 0, 0, b'z', 0, 0, 0, 0, // z = ""
+            0,
+        ];
+        let r = SensorReadingRef::decode(&data).unwrap();
+        assert_eq!(r.sensor_id, "abc");
+        assert_eq!(r.sensor_id.as_ptr(), data[5..].as_ptr());
+        assert_eq!(r.unit, "");
+
+        let mut it = r.metadata();
+        assert_eq!(it.len(), 2);
+        assert_eq!(it.next(), Some(("k", "v1")));
+        assert_eq!(it.next(), Some(("z", "")));
+        assert_eq!(it.next(), None);
+        assert_eq!(it.next(), None);
+        assert_eq!(r.metadata_get("z"), Some(""));
+        assert_eq!(r.metadata_get("missing"), None);
+    }
+
+    #[test]
+    fn borrowed_reading_validates_metadata_up_front() {
+        // Second entry's value is not UTF-8.
+        let data = [
+            7, 2, 0, 0, 0, 1, 0, 0, 0, b'k', 0, 0, 0, 0, 1, 0, 0, 0, b'x', 1, 0, 0, 0, 0xff,
+        ];
+        assert_eq!(
+            SensorReadingRef::decode(&data).unwrap_err(),
+            DecodeError::InvalidUtf8
+        );
+        assert_eq!(
+            SensorReadingRef::decode(&data[..12]).unwrap_err(),
+            DecodeError::UnexpectedEnd
+        );
+    }
+
+    #[test]
+    fn encode_reports_short_buffer() {
+        let reading = VSensorReading::empty();
+        let mut buf = [0u8; 16];
+        assert_eq!(
+            unsafe { encode_batch_readings(&[reading], &mut buf) },
+            Err(EncodeError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn encode_rejects_null_string_with_length() {
+        let mut reading = VSensorReading::empty();
+        reading.unit.len = 3;
+        let mut buf = [0u8; 256];
+        assert_eq!(
+            unsafe { encode_batch_readings(&[reading], &mut buf) },
+            Err(EncodeError::InvalidReading("null string"))
+        );
+    }
+}
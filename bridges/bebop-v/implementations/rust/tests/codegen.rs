@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+//
+// codegen.rs - Batches encoded by the generated code, read by BatchReader
+//
+// codegen/rust/tests/generated/sensors.rs is the checked-in output of
+// bebop-v-codegen for schemas/sensors.bop. It writes message fields in index
+// order, so `compressed` follows `readings`; the streaming decoder must read
+// such batches as plain (docs/20-wire-and-framing.adoc).
+
+include!("../../../codegen/rust/tests/generated/sensors.rs");
+
+use std::collections::BTreeMap;
+
+use bebop_v_ffi::BatchReader;
+use sensors::{BatchReadings, SensorReading, SensorType};
+
+fn reading(id: &str, value: f64) -> SensorReading {
+    SensorReading {
+        timestamp: Some(2_000_000_000),
+        sensor_id: Some(id.to_owned()),
+        sensor_type: Some(SensorType::Temperature),
+        value: Some(value),
+        unit: Some("C".to_owned()),
+        location: Some("floor-1".to_owned()),
+        metadata: Some(BTreeMap::from([("status".to_owned(), "ok".to_owned())])),
+    }
+}
+
+#[test]
+fn generated_batches_stream() {
+    for compressed in [None, Some(false), Some(true)] {
+        let batch = BatchReadings {
+            readings: Some(vec![reading("temp-001", 23.5), reading("temp-002", -4.0)]),
+            batch_id: Some([0x42; 16]),
+            compressed,
+        };
+        let bytes = batch.encode();
+
+        let mut reader = BatchReader::new(&bytes[..]);
+        let mut seen = Vec::new();
+        while let Some(r) = reader.next_reading().unwrap() {
+            assert_eq!(r.timestamp, 2_000_000_000);
+            assert_eq!(r.sensor_type, SensorType::Temperature as u16);
+            assert_eq!((r.unit, r.location), ("C", "floor-1"));
+            assert_eq!(r.metadata_get("status"), Some("ok"));
+            seen.push((r.sensor_id.to_owned(), r.value));
+        }
+        assert_eq!(
+            seen,
+            [("temp-001".to_owned(), 23.5), ("temp-002".to_owned(), -4.0)],
+            "compressed = {compressed:?}"
+        );
+        assert_eq!(reader.batch_id(), Some([0x42; 16]));
+        assert!(!reader.is_compressed());
+    }
+}
+
+#[test]
+fn compressed_flag_after_readings_is_plain() {
+    // The encoding pinned by codegen's `absent_fields_are_omitted` test.
+    let batch = BatchReadings {
+        readings: Some(vec![SensorReading {
+            sensor_type: Some(SensorType::Vibration),
+            ..Default::default()
+        }]),
+        compressed: Some(true),
+        ..Default::default()
+    };
+    let bytes = batch.encode();
+    assert_eq!(bytes, [1, 1, 0, 0, 0, 3, 4, 0, 0, 3, 1, 0]);
+
+    let mut reader = BatchReader::new(&bytes[..]);
+    let r = reader.next_reading().unwrap().expect("one reading");
+    assert_eq!(r.sensor_type, SensorType::Vibration as u16);
+    assert!(reader.next_reading().unwrap().is_none());
+    assert!(!reader.is_compressed());
+    assert_eq!(reader.batch_id(), None);
+}
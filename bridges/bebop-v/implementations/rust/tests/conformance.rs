@@ -0,0 +1,718 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// SPDX-FileCopyrightText: 2025 Hyperpolymath Contributors
+//
+// conformance.rs - Golden-vector harness for every Bebop-V implementation
+//
+// Runs each test-vectors/*.json through every available backend:
+//
+//   rust      this crate's bebop_* exports
+//   rust-ref  SensorReadingRef (decode only)
+//   zig       bebop_* from the Zig library, loaded at run time
+//
+// and checks decode against `expected_decode` (and `input.metadata`), and,
+// where `round_trip` is set, that encoding `input` gives `wire_bytes_hex`
+// inside the BatchReadings framing. All mismatches are collected and reported
+// per vector and backend before the test fails.
+//
+// The Zig library is taken from $BEBOP_ZIG_LIB, else from
+// ../zig/zig-out/lib after `zig build`; without it the zig backend is skipped
+// with a note, or the test fails if $BEBOP_REQUIRE_ZIG is set. Without Zig,
+// only the Rust backends run, so vectors the Rust encoder generated are not
+// checked against an independent implementation. The library is loaded with
+// dlopen rather than linked because both libraries export the same symbols.
+//
+// Generation mode: `BEBOP_VECTORS_GENERATE=1 cargo test --test conformance`
+// (re)writes the vectors defined in `corpus()` below using the Rust encoder,
+// marked `"generated_by": "rust"`, then checks them like any other vector.
+// No bebopc needed.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bebop_v_ffi::*;
+use libloading::Library;
+use serde_json::{json, Value};
+
+// -----------------------------------------------------------------------------
+// Rust-typed readings
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+struct Reading {
+    timestamp: u64,
+    sensor_id: String,
+    sensor_type: u16,
+    value: f64,
+    unit: String,
+    location: String,
+    metadata: Vec<(String, String)>,
+}
+
+impl Reading {
+    /// From a vector's `input` object (schema field names).
+    fn from_input(input: &Value) -> Result<Reading, String> {
+        let str_field = |key: &str| {
+            input[key]
+                .as_str()
+                .map(str::to_owned)
+                .ok_or_else(|| format!("input.{key} missing or not a string"))
+        };
+        let metadata = match &input["metadata"] {
+            Value::Null => Vec::new(),
+            Value::Object(map) => map
+                .iter()
+                .map(|(k, v)| match v.as_str() {
+                    Some(v) => Ok((k.clone(), v.to_owned())),
+                    None => Err(format!("input.metadata.{k} is not a string")),
+                })
+                .collect::<Result<_, _>>()?,
+            _ => return Err("input.metadata is not an object".into()),
+        };
+        Ok(Reading {
+            timestamp: input["timestamp"]
+                .as_u64()
+                .ok_or("input.timestamp missing or not a u64")?,
+            sensor_id: str_field("sensorId")?,
+            sensor_type: input["sensorType"]
+                .as_u64()
+                .and_then(|v| u16::try_from(v).ok())
+                .ok_or("input.sensorType missing or not a u16")?,
+            value: input["value"]
+                .as_f64()
+                .ok_or("input.value missing or not a number")?,
+            unit: str_field("unit")?,
+            location: str_field("location")?,
+            metadata,
+        })
+    }
+
+    fn to_input(&self) -> Value {
+        let metadata: serde_json::Map<String, Value> = self
+            .metadata
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::from(v.as_str())))
+            .collect();
+        json!({
+            "timestamp": self.timestamp,
+            "sensorId": self.sensor_id,
+            "sensorType": self.sensor_type,
+            "value": self.value,
+            "unit": self.unit,
+            "location": self.location,
+            "metadata": metadata,
+        })
+    }
+
+    /// `expected_decode`: VSensorReading field names.
+    fn expected_decode(&self) -> Value {
+        json!({
+            "timestamp": self.timestamp,
+            "sensor_id": self.sensor_id,
+            "sensor_type": self.sensor_type,
+            "value": self.value,
+            "unit": self.unit,
+            "location": self.location,
+            "metadata_count": self.metadata.len(),
+        })
+    }
+
+    fn field(&self, key: &str) -> Option<Value> {
+        Some(match key {
+            "timestamp" => self.timestamp.into(),
+            "sensor_id" => self.sensor_id.as_str().into(),
+            "sensor_type" => self.sensor_type.into(),
+            "value" => self.value.into(),
+            "unit" => self.unit.as_str().into(),
+            "location" => self.location.as_str().into(),
+            "metadata_count" => self.metadata.len().into(),
+            _ => return None,
+        })
+    }
+}
+
+/// Vectors produced by generation mode.
+fn corpus() -> Vec<(&'static str, &'static str, Reading)> {
+    let reading = |sensor_type, sensor_id: &str, value, unit: &str| Reading {
+        timestamp: 1_700_000_000_000,
+        sensor_id: sensor_id.into(),
+        sensor_type,
+        value,
+        unit: unit.into(),
+        location: "plant-2/line-4".into(),
+        metadata: Vec::new(),
+    };
+    vec![
+        (
+            "sensor_reading_002",
+            "Humidity reading with no metadata and an empty unit",
+            reading(SENSOR_TYPE_HUMIDITY, "hum-17", 48.25, ""),
+        ),
+        (
+            "sensor_reading_003",
+            "Pressure reading with several metadata entries and a negative value",
+            Reading {
+                metadata: vec![
+                    ("calibrated".into(), "2025-03-01".into()),
+                    ("fw".into(), "2.4.1".into()),
+                    ("status".into(), "degraded".into()),
+                ],
+                ..reading(SENSOR_TYPE_PRESSURE, "press-0003", -0.875, "kPa")
+            },
+        ),
+        (
+            "sensor_reading_004",
+            "Vibration reading with multi-byte UTF-8 strings and extreme timestamp",
+            Reading {
+                timestamp: u64::MAX,
+                location: "Halle Süd/Maschine №7".into(),
+                metadata: vec![("axis".into(), "z".into())],
+                ..reading(SENSOR_TYPE_VIBRATION, "vib-µ1", 1e-3, "m/s²")
+            },
+        ),
+    ]
+}
+
+// -----------------------------------------------------------------------------
+// Backends
+// -----------------------------------------------------------------------------
+
+/// The bebop_* functions the harness calls, from either library.
+struct Api {
+    version: unsafe extern "C" fn() -> u32,
+    ctx_new: unsafe extern "C" fn() -> *mut BebopCtx,
+    ctx_free: unsafe extern "C" fn(*mut BebopCtx),
+    decode: unsafe extern "C" fn(*mut BebopCtx, *const u8, usize, *mut VSensorReading) -> i32,
+    encode:
+        unsafe extern "C" fn(*mut BebopCtx, *const VSensorReading, usize, *mut u8, usize) -> usize,
+}
+
+trait Backend {
+    fn name(&self) -> &str;
+    fn decode(&self, wire: &[u8]) -> Result<Reading, String>;
+    /// `Some(bytes)` of a one-reading batch, `None` if the backend cannot
+    /// encode.
+    fn encode(&self, reading: &Reading) -> Option<Result<Vec<u8>, String>>;
+}
+
+struct CAbi {
+    name: &'static str,
+    api: Api,
+    can_encode: bool,
+    // Keeps the loaded library alive for the function pointers in `api`.
+    _lib: Option<Library>,
+}
+
+impl CAbi {
+    fn new(name: &'static str, api: Api, lib: Option<Library>) -> CAbi {
+        let mut backend = CAbi {
+            name,
+            api,
+            can_encode: true,
+            _lib: lib,
+        };
+        // Every encoder writes an empty batch as 6 bytes; 0 means it is
+        // still a stub.
+        let ctx = unsafe { (backend.api.ctx_new)() };
+        let mut buf = [0u8; 8];
+        let n = unsafe { (backend.api.encode)(ctx, std::ptr::null(), 0, buf.as_mut_ptr(), 8) };
+        unsafe { (backend.api.ctx_free)(ctx) };
+        if n == 0 {
+            eprintln!("conformance: {name}: encoder not implemented, encode checks skipped");
+            backend.can_encode = false;
+        }
+        backend
+    }
+
+    fn native() -> CAbi {
+        let api = Api {
+            version: bebop_version,
+            ctx_new: bebop_ctx_new,
+            ctx_free: bebop_ctx_free,
+            decode: bebop_decode_sensor_reading,
+            encode: bebop_encode_batch_readings,
+        };
+        CAbi::new("rust", api, None)
+    }
+
+    fn zig() -> Option<CAbi> {
+        let path = match std::env::var_os("BEBOP_ZIG_LIB") {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let default = Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("../zig/zig-out/lib")
+                    .join(libloading::library_filename("bebop_v_ffi"));
+                if !default.exists() {
+                    let note = format!(
+                        "conformance: zig: no library at {} (run `zig build` in \
+                         implementations/zig or set BEBOP_ZIG_LIB)",
+                        default.display()
+                    );
+                    if std::env::var_os("BEBOP_REQUIRE_ZIG").is_some() {
+                        panic!("{note}, and BEBOP_REQUIRE_ZIG is set");
+                    }
+                    eprintln!("{note}, skipped");
+                    return None;
+                }
+                default
+            }
+        };
+        let lib = unsafe { Library::new(&path) }
+            .unwrap_or_else(|err| panic!("cannot load {}: {err}", path.display()));
+        let api = unsafe {
+            Api {
+                version: *lib.get(b"bebop_version\0").unwrap(),
+                ctx_new: *lib.get(b"bebop_ctx_new\0").unwrap(),
+                ctx_free: *lib.get(b"bebop_ctx_free\0").unwrap(),
+                decode: *lib.get(b"bebop_decode_sensor_reading\0").unwrap(),
+                encode: *lib.get(b"bebop_encode_batch_readings\0").unwrap(),
+            }
+        };
+        Some(CAbi::new("zig", api, Some(lib)))
+    }
+}
+
+fn bytes_to_string(b: VBytes) -> Result<String, String> {
+    let bytes = unsafe { b.as_slice() }.ok_or("null string with non-zero length")?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| "string is not UTF-8".to_owned())
+}
+
+impl Backend for CAbi {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn decode(&self, wire: &[u8]) -> Result<Reading, String> {
+        let ctx = unsafe { (self.api.ctx_new)() };
+        let mut r = VSensorReading::empty();
+        let code = unsafe { (self.api.decode)(ctx, wire.as_ptr(), wire.len(), &mut r) };
+        let result = (|| {
+            if code != BEBOP_OK {
+                return Err(format!("decode returned {code}"));
+            }
+            let mut metadata = Vec::new();
+            if r.metadata_count > 0 {
+                let keys = unsafe { std::slice::from_raw_parts(r.metadata_keys, r.metadata_count) };
+                let values =
+                    unsafe { std::slice::from_raw_parts(r.metadata_values, r.metadata_count) };
+                for (&k, &v) in keys.iter().zip(values) {
+                    metadata.push((bytes_to_string(k)?, bytes_to_string(v)?));
+                }
+            }
+            Ok(Reading {
+                timestamp: r.timestamp,
+                sensor_id: bytes_to_string(r.sensor_id)?,
+                sensor_type: r.sensor_type,
+                value: r.value,
+                unit: bytes_to_string(r.unit)?,
+                location: bytes_to_string(r.location)?,
+                metadata,
+            })
+        })();
+        unsafe { (self.api.ctx_free)(ctx) };
+        result
+    }
+
+    fn encode(&self, reading: &Reading) -> Option<Result<Vec<u8>, String>> {
+        if !self.can_encode {
+            return None;
+        }
+        let keys: Vec<VBytes> = reading
+            .metadata
+            .iter()
+            .map(|(k, _)| VBytes::from_slice(k.as_bytes()))
+            .collect();
+        let values: Vec<VBytes> = reading
+            .metadata
+            .iter()
+            .map(|(_, v)| VBytes::from_slice(v.as_bytes()))
+            .collect();
+        let v = VSensorReading {
+            timestamp: reading.timestamp,
+            sensor_id: VBytes::from_slice(reading.sensor_id.as_bytes()),
+            sensor_type: reading.sensor_type,
+            value: reading.value,
+            unit: VBytes::from_slice(reading.unit.as_bytes()),
+            location: VBytes::from_slice(reading.location.as_bytes()),
+            metadata_count: keys.len(),
+            metadata_keys: keys.as_ptr().cast_mut(),
+            metadata_values: values.as_ptr().cast_mut(),
+            ..VSensorReading::empty()
+        };
+        let mut buf = vec![0u8; 4096];
+        let ctx = unsafe { (self.api.ctx_new)() };
+        let n = unsafe { (self.api.encode)(ctx, &v, 1, buf.as_mut_ptr(), buf.len()) };
+        unsafe { (self.api.ctx_free)(ctx) };
+        if n == 0 {
+            return Some(Err("encode returned 0".into()));
+        }
+        buf.truncate(n);
+        Some(Ok(buf))
+    }
+}
+
+/// The borrowed Rust decoder.
+struct Borrowed;
+
+impl Backend for Borrowed {
+    fn name(&self) -> &str {
+        "rust-ref"
+    }
+
+    fn decode(&self, wire: &[u8]) -> Result<Reading, String> {
+        let r = SensorReadingRef::decode(wire).map_err(|err| err.to_string())?;
+        Ok(Reading {
+            timestamp: r.timestamp,
+            sensor_id: r.sensor_id.into(),
+            sensor_type: r.sensor_type,
+            value: r.value,
+            unit: r.unit.into(),
+            location: r.location.into(),
+            metadata: r.metadata().map(|(k, v)| (k.into(), v.into())).collect(),
+        })
+    }
+
+    fn encode(&self, _: &Reading) -> Option<Result<Vec<u8>, String>> {
+        None
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Vectors
+// -----------------------------------------------------------------------------
+
+fn vectors_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../test-vectors")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(hex: &str) -> Result<Vec<u8>, String> {
+    if !hex.len().is_multiple_of(2) {
+        return Err("wire_bytes_hex has odd length".into());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| format!("wire_bytes_hex: bad digits at {i}"))
+        })
+        .collect()
+}
+
+/// BatchReadings holding one reading, as bebop_encode_batch_readings writes.
+fn batch_of_one(wire: &[u8]) -> Vec<u8> {
+    let mut batch = vec![1, 1, 0, 0, 0];
+    batch.extend_from_slice(wire);
+    batch.push(0);
+    batch
+}
+
+/// First difference between two byte strings, with context.
+fn byte_diff(expected: &[u8], got: &[u8]) -> String {
+    let at = expected
+        .iter()
+        .zip(got)
+        .position(|(a, b)| a != b)
+        .unwrap_or(expected.len().min(got.len()));
+    let window = |b: &[u8]| hex(&b[at.saturating_sub(4)..(at + 8).min(b.len())]);
+    format!(
+        "bytes differ at offset {at} (lengths {} vs {}): expected ..{}.., got ..{}..",
+        expected.len(),
+        got.len(),
+        window(expected),
+        window(got)
+    )
+}
+
+/// `wire_format_annotated` lines for a SensorReading.
+fn annotate(wire: &[u8]) -> Vec<String> {
+    fn line(out: &mut Vec<String>, bytes: &[u8], comment: &str) {
+        let hex: Vec<String> = bytes.iter().map(|b| format!("{b:02x}")).collect();
+        out.push(format!("{:<25} # {comment}", hex.join(" ")));
+    }
+    fn string(out: &mut Vec<String>, wire: &[u8], pos: &mut usize, what: &str) {
+        let len = u32::from_le_bytes(wire[*pos..*pos + 4].try_into().unwrap()) as usize;
+        line(
+            out,
+            &wire[*pos..*pos + 4],
+            &format!("{what} length = {len}"),
+        );
+        *pos += 4;
+        let text = String::from_utf8_lossy(&wire[*pos..*pos + len]);
+        for (i, chunk) in wire[*pos..*pos + len].chunks(8).enumerate() {
+            let comment = if i == 0 {
+                format!("'{text}'")
+            } else {
+                "(cont.)".into()
+            };
+            line(out, chunk, &comment);
+        }
+        *pos += len;
+    }
+
+    let names = [
+        "",
+        "timestamp",
+        "sensorId",
+        "sensorType",
+        "value",
+        "unit",
+        "location",
+        "metadata",
+    ];
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos < wire.len() {
+        let index = wire[pos] as usize;
+        if index == 0 {
+            line(&mut out, &wire[pos..pos + 1], "end of message");
+            break;
+        }
+        line(
+            &mut out,
+            &wire[pos..pos + 1],
+            &format!("field {index}: {}", names[index]),
+        );
+        pos += 1;
+        match index {
+            1 => {
+                let v = u64::from_le_bytes(wire[pos..pos + 8].try_into().unwrap());
+                line(
+                    &mut out,
+                    &wire[pos..pos + 8],
+                    &format!("timestamp = {v} (u64 LE)"),
+                );
+                pos += 8;
+            }
+            3 => {
+                let v = u16::from_le_bytes(wire[pos..pos + 2].try_into().unwrap());
+                line(
+                    &mut out,
+                    &wire[pos..pos + 2],
+                    &format!("sensorType = {v} (u16 LE)"),
+                );
+                pos += 2;
+            }
+            4 => {
+                let v = f64::from_le_bytes(wire[pos..pos + 8].try_into().unwrap());
+                line(
+                    &mut out,
+                    &wire[pos..pos + 8],
+                    &format!("value = {v} (f64 LE)"),
+                );
+                pos += 8;
+            }
+            7 => {
+                let n = u32::from_le_bytes(wire[pos..pos + 4].try_into().unwrap());
+                line(&mut out, &wire[pos..pos + 4], &format!("map count = {n}"));
+                pos += 4;
+                for _ in 0..n {
+                    string(&mut out, wire, &mut pos, "key");
+                    string(&mut out, wire, &mut pos, "value");
+                }
+            }
+            _ => string(&mut out, wire, &mut pos, "string"),
+        }
+    }
+    out
+}
+
+/// `generated_by` of the vectors [`generate`] writes: their bytes come from
+/// the Rust encoder, not from bebopc or by hand.
+const GENERATED_BY: &str = "rust";
+
+/// Write the `corpus()` vectors, encoding with the Rust implementation.
+fn generate(dir: &Path) {
+    let native = CAbi::native();
+    for (name, description, reading) in corpus() {
+        let batch = native.encode(&reading).unwrap().unwrap();
+        // Strip the BatchReadings framing around the single reading.
+        let wire = &batch[5..batch.len() - 1];
+        assert_eq!(batch_of_one(wire), batch);
+        let vector = json!({
+            "name": name,
+            "description": description,
+            "generated_by": GENERATED_BY,
+            "schema": "SensorReading",
+            "input": reading.to_input(),
+            "wire_bytes_hex": hex(wire),
+            "wire_format_annotated": annotate(wire),
+            "expected_decode": reading.expected_decode(),
+            "round_trip": true,
+        });
+        let path = dir.join(format!("{name}.json"));
+        let text = serde_json::to_string_pretty(&vector).unwrap() + "\n";
+        fs::write(&path, text).unwrap();
+        eprintln!("conformance: wrote {}", path.display());
+    }
+}
+
+/// All mismatches of one vector on one backend.
+fn check(backend: &dyn Backend, vector: &Value) -> Result<Vec<String>, String> {
+    let wire = unhex(
+        vector["wire_bytes_hex"]
+            .as_str()
+            .ok_or("no wire_bytes_hex")?,
+    )?;
+    let input = Reading::from_input(&vector["input"])?;
+    let expected = vector["expected_decode"]
+        .as_object()
+        .ok_or("no expected_decode object")?;
+    let mut diffs = Vec::new();
+
+    match backend.decode(&wire) {
+        Err(err) => diffs.push(format!("decode: {err}")),
+        Ok(got) => {
+            for (key, want) in expected {
+                match got.field(key) {
+                    None => diffs.push(format!("decode: unknown expected_decode key `{key}`")),
+                    Some(value) if numbers_equal(&value, want) => {}
+                    Some(value) => {
+                        diffs.push(format!("decode: {key}: expected {want}, got {value}"))
+                    }
+                }
+            }
+            if got.metadata != input.metadata {
+                diffs.push(format!(
+                    "decode: metadata: expected {:?}, got {:?}",
+                    input.metadata, got.metadata
+                ));
+            }
+        }
+    }
+
+    if vector["round_trip"] == true {
+        if let Some(encoded) = backend.encode(&input) {
+            match encoded {
+                Err(err) => diffs.push(format!("encode: {err}")),
+                Ok(got) => {
+                    let want = batch_of_one(&wire);
+                    if got != want {
+                        diffs.push(format!("encode: {}", byte_diff(&want, &got)));
+                    }
+                }
+            }
+        }
+    }
+    Ok(diffs)
+}
+
+/// JSON equality that treats 23.5 and 23.5f64 from u64/f64 sources alike.
+fn numbers_equal(a: &Value, b: &Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) if !(a.is_u64() && b.is_u64()) => x == y,
+        _ => a == b,
+    }
+}
+
+#[test]
+fn golden_vectors() {
+    let dir = vectors_dir();
+    if std::env::var_os("BEBOP_VECTORS_GENERATE").is_some() {
+        generate(&dir);
+    }
+
+    let mut backends: Vec<Box<dyn Backend>> = vec![Box::new(CAbi::native()), Box::new(Borrowed)];
+    if let Some(zig) = CAbi::zig() {
+        let version = unsafe { (zig.api.version)() };
+        assert_eq!(version, ABI_VERSION, "zig library ABI version {version:#x}");
+        backends.push(Box::new(zig));
+    }
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "json"))
+        .collect();
+    paths.sort();
+
+    let mut report = String::new();
+    let mut checked = 0;
+    for path in &paths {
+        let name = path.file_name().unwrap().to_string_lossy();
+        let vector: Value = match serde_json::from_str(&fs::read_to_string(path).unwrap()) {
+            Ok(vector) => vector,
+            Err(err) => {
+                writeln!(report, "{name}: invalid JSON: {err}").unwrap();
+                continue;
+            }
+        };
+        if vector["schema"] != "SensorReading" {
+            continue;
+        }
+        for backend in &backends {
+            match check(backend.as_ref(), &vector) {
+                Err(err) => writeln!(report, "{name}: malformed vector: {err}").unwrap(),
+                Ok(diffs) => {
+                    for diff in diffs {
+                        writeln!(report, "{name} [{}]: {diff}", backend.name()).unwrap();
+                    }
+                }
+            }
+        }
+        checked += 1;
+    }
+
+    assert!(checked > 0, "no SensorReading vectors in {}", dir.display());
+    assert!(report.is_empty(), "golden vector mismatches:\n{report}");
+}
+
+#[test]
+fn corpus_encodes_like_checked_in_vectors() {
+    // Catches a corpus() edit without regenerating.
+    let native = CAbi::native();
+    for (name, _, reading) in corpus() {
+        let path = vectors_dir().join(format!("{name}.json"));
+        let Ok(text) = fs::read_to_string(&path) else {
+            panic!(
+                "{} missing; run with BEBOP_VECTORS_GENERATE=1",
+                path.display()
+            );
+        };
+        let vector: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(vector["generated_by"], GENERATED_BY, "{name}");
+        assert_eq!(
+            Reading::from_input(&vector["input"]).unwrap(),
+            reading,
+            "{name} is stale; run with BEBOP_VECTORS_GENERATE=1"
+        );
+        let batch = native.encode(&reading).unwrap().unwrap();
+        assert_eq!(
+            hex(&batch[5..batch.len() - 1]),
+            vector["wire_bytes_hex"],
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn annotation_matches_hand_written_vector() {
+    let text = fs::read_to_string(vectors_dir().join("sensor_reading_001.json")).unwrap();
+    let vector: Value = serde_json::from_str(&text).unwrap();
+    let wire = unhex(vector["wire_bytes_hex"].as_str().unwrap()).unwrap();
+    let lines = annotate(&wire);
+    let hand: Vec<&str> = vector["wire_format_annotated"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|line| line.as_str().unwrap())
+        .collect();
+    assert_eq!(lines.len(), hand.len());
+    // Same bytes on every line; comments may be worded differently.
+    for (generated, hand) in lines.iter().zip(hand) {
+        let bytes = |line: &str| line.split('#').next().unwrap().trim().to_owned();
+        assert_eq!(bytes(generated), bytes(hand));
+    }
+}
+
+#[test]
+fn byte_diff_reports_offset() {
+    let diff = byte_diff(&[1, 2, 3, 4], &[1, 2, 9, 4]);
+    assert!(
+        diff.starts_with("bytes differ at offset 2 (lengths 4 vs 4)"),
+        "{diff}"
+    );
+}
@@ -1,8 +1,20 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 //! Zig FFI bindings for document text extraction (i18n workflows)
 //! Inspired by: hyperpolymath/polyglot-i18n
+//!
+//! `extract` splits a document into the segments a translator works on, each
+//! with the 1-based line and byte column it starts at and a context naming
+//! where it came from. README.adoc lists the rules per format.
+//!
+//! The Rust bridge compiles this file into its Zig core
+//! (`bridges/rust/build.rs`) and carries a port of the same rules for its
+//! `pure-rust` backend (`bridges/rust/src/backend/polyglot.rs`); the two are
+//! tested against each other, so change them together.
 
 const std = @import("std");
+const mem = std.mem;
+const ascii = std.ascii;
+const Allocator = mem.Allocator;
 
 pub const Error = error{
     ParseFailed,
@@ -10,7 +22,8 @@ pub const Error = error{
     AllocationFailed,
 };
 
-pub const Format = enum {
+/// The discriminant is the `format` argument of `polyglot_extract`.
+pub const Format = enum(u32) {
     plain_text,
     markdown,
     asciidoc,
@@ -21,23 +34,1237 @@ pub const Format = enum {
 
 pub const TextSegment = struct {
     text: []const u8,
+    /// 1-based line of the first byte
     line: usize,
+    /// 1-based byte column; for quoted JSON and YAML strings, the quote
     column: usize,
+    /// Block kind, HTML element (`tag` or `tag@attribute`), JSON pointer or
+    /// YAML path; null for plain text
     context: ?[]const u8,
 };
 
-/// Extract translatable text segments from a document
-pub fn extract(allocator: std.mem.Allocator, content: []const u8, format: Format) Error![]TextSegment {
-    _ = content;
-    _ = format;
-    _ = allocator;
-    // TODO: Implement extraction per format
-    return &[_]TextSegment{};
+/// Deepest JSON nesting accepted
+const max_depth = 128;
+
+const translatable_attributes = [_][]const u8{ "alt", "title", "placeholder", "aria-label" };
+const void_elements = [_][]const u8{
+    "area", "base",  "br",    "col",   "embed", "hr",  "img",
+    "input", "link", "meta",  "source", "track", "wbr", "param",
+};
+const admonitions = [_][]const u8{ "NOTE", "TIP", "IMPORTANT", "WARNING", "CAUTION" };
+const yaml_non_strings = [_][]const u8{
+    "null", "~",  "true", "false", "yes",   "no",
+    "on",   "off", ".inf", "-.inf", "+.inf", ".nan",
+};
+
+/// Extract translatable text segments from a document.
+///
+/// Every string in the result is allocated with `allocator`; pass an arena
+/// to free them together.
+pub fn extract(allocator: Allocator, content: []const u8, format: Format) Error![]TextSegment {
+    var out = Segments{ .allocator = allocator, .list = std.ArrayList(TextSegment).init(allocator) };
+    switch (format) {
+        .plain_text => try plainText(content, &out),
+        .markdown => try markdown(content, &out),
+        .asciidoc => try asciidoc(content, &out),
+        .html => try html(allocator, content, &out),
+        .json => {
+            var parser = Json{
+                .allocator = allocator,
+                .b = content,
+                .starts = try LineStarts.init(allocator, content),
+                .path = std.ArrayList([]const u8).init(allocator),
+                .out = &out,
+            };
+            try parser.document();
+        },
+        .yaml => {
+            var parser = Yaml{
+                .allocator = allocator,
+                .out = &out,
+                .stack = std.ArrayList(YamlEntry).init(allocator),
+            };
+            try parser.document(content);
+        },
+    }
+    return out.list.toOwnedSlice() catch return error.AllocationFailed;
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+const Segments = struct {
+    allocator: Allocator,
+    list: std.ArrayList(TextSegment),
+
+    fn push(self: *Segments, text: []const u8, line_no: usize, column: usize, context: ?[]const u8) Error!void {
+        const owned_context: ?[]const u8 = if (context) |c| try self.dupe(c) else null;
+        self.list.append(.{
+            .text = try self.dupe(text),
+            .line = line_no,
+            .column = column,
+            .context = owned_context,
+        }) catch return error.AllocationFailed;
+    }
+
+    /// Add `line[start..end]` with blanks trimmed, unless nothing is left.
+    fn span(self: *Segments, line_no: usize, line: []const u8, start: usize, end: usize, context: ?[]const u8) Error!void {
+        var s = start;
+        var e = end;
+        while (s < e and isBlank(line[s])) s += 1;
+        while (e > s and isBlank(line[e - 1])) e -= 1;
+        if (s < e) try self.push(line[s..e], line_no, s + 1, context);
+    }
+
+    fn dupe(self: *Segments, s: []const u8) Error![]const u8 {
+        return self.allocator.dupe(u8, s) catch return error.AllocationFailed;
+    }
+};
+
+fn isBlank(c: u8) bool {
+    return c == ' ' or c == '\t' or c == '\r';
+}
+
+fn isSpace(c: u8) bool {
+    return isBlank(c) or c == '\n';
+}
+
+/// Lines numbered from 1, without their `\n` or `\r\n`.
+const LineIterator = struct {
+    inner: mem.SplitIterator(u8, .scalar),
+    number: usize = 0,
+
+    fn init(content: []const u8) LineIterator {
+        return .{ .inner = mem.splitScalar(u8, content, '\n') };
+    }
+
+    fn next(self: *LineIterator) ?[]const u8 {
+        const raw = self.inner.next() orelse return null;
+        self.number += 1;
+        return if (mem.endsWith(u8, raw, "\r")) raw[0 .. raw.len - 1] else raw;
+    }
+};
+
+/// The line without leading and trailing blanks is `line[start..end]`.
+const Bounds = struct { start: usize, end: usize };
+
+fn trimBounds(line: []const u8) Bounds {
+    var start: usize = 0;
+    while (start < line.len and isBlank(line[start])) start += 1;
+    var end = line.len;
+    while (end > start and isBlank(line[end - 1])) end -= 1;
+    return .{ .start = start, .end = end };
+}
+
+/// Number of leading `c` bytes in `s`.
+fn leadingRun(s: []const u8, c: u8) usize {
+    var n: usize = 0;
+    while (n < s.len and s[n] == c) n += 1;
+    return n;
+}
+
+/// A run of `c` followed by a blank; returns the run length.
+fn markerLen(s: []const u8, c: u8) ?usize {
+    const n = leadingRun(s, c);
+    return if (n > 0 and n < s.len and isBlank(s[n])) n else null;
+}
+
+/// Table cells of `line[from..]`, split on `|` not preceded by `\`.
+fn tableCells(out: *Segments, line_no: usize, line: []const u8, from: usize, context: []const u8) Error!void {
+    var start = from;
+    for (from..line.len) |i| {
+        if (line[i] == '|' and (i == 0 or line[i - 1] != '\\')) {
+            try out.span(line_no, line, start, i, context);
+            start = i + 1;
+        }
+    }
+    try out.span(line_no, line, start, line.len, context);
+}
+
+fn containsAny(haystack: []const []const u8, needle: []const u8) bool {
+    for (haystack) |item| {
+        if (mem.eql(u8, item, needle)) return true;
+    }
+    return false;
+}
+
+// ============================================================================
+// Plain text, Markdown, AsciiDoc
+// ============================================================================
+
+fn plainText(content: []const u8, out: *Segments) Error!void {
+    var it = LineIterator.init(content);
+    while (it.next()) |line| try out.span(it.number, line, 0, line.len, null);
+}
+
+fn markdown(content: []const u8, out: *Segments) Error!void {
+    const Fence = struct { char: u8, len: usize };
+    var fence: ?Fence = null;
+    var front_matter = false;
+    var comment = false;
+    var it = LineIterator.init(content);
+    while (it.next()) |line| {
+        const no = it.number;
+        const bounds = trimBounds(line);
+        const t = line[bounds.start..bounds.end];
+        if (no == 1 and mem.eql(u8, t, "---")) {
+            front_matter = true;
+            continue;
+        }
+        if (front_matter) {
+            front_matter = !mem.eql(u8, t, "---");
+            continue;
+        }
+        if (fence) |f| {
+            const k = leadingRun(t, f.char);
+            if (k >= f.len and k == t.len) fence = null;
+            continue;
+        }
+        if (t.len > 0 and (t[0] == '`' or t[0] == '~')) {
+            const n = leadingRun(t, t[0]);
+            if (n >= 3) {
+                fence = .{ .char = t[0], .len = n };
+                continue;
+            }
+        }
+        if (comment) {
+            comment = mem.indexOf(u8, t, "-->") == null;
+            continue;
+        }
+        if (mem.startsWith(u8, t, "<!--")) {
+            comment = mem.indexOf(u8, t[4..], "-->") == null;
+            continue;
+        }
+        if (t.len == 0 or isThematicBreak(t)) continue;
+        if (t[0] == '|') {
+            if (!isTableSeparator(t)) try tableCells(out, no, line, bounds.start, "table_cell");
+            continue;
+        }
+
+        var start = bounds.start;
+        var context: []const u8 = "paragraph";
+        switch (t[0]) {
+            '#' => {
+                if (markerLen(t, '#')) |n| {
+                    if (n <= 6) {
+                        start += n;
+                        context = "heading";
+                    }
+                } else if (leadingRun(t, '#') == t.len and t.len <= 6) continue;
+            },
+            '>' => {
+                var k: usize = 0;
+                while (k < t.len and (t[k] == '>' or isBlank(t[k]))) k += 1;
+                start += k;
+                context = "blockquote";
+            },
+            '-', '*', '+' => if (t.len > 1 and isBlank(t[1])) {
+                start += 1;
+                context = "list_item";
+            },
+            '0'...'9' => if (orderedMarker(t)) |n| {
+                start += n;
+                context = "list_item";
+            },
+            else => {},
+        }
+        try out.span(no, line, start, bounds.end, context);
+    }
+}
+
+/// `1.` / `1)` list markers followed by a blank; returns the marker length.
+fn orderedMarker(t: []const u8) ?usize {
+    var digits: usize = 0;
+    while (digits < t.len and ascii.isDigit(t[digits])) digits += 1;
+    const ok = digits >= 1 and digits <= 9 and digits + 1 < t.len and
+        (t[digits] == '.' or t[digits] == ')') and isBlank(t[digits + 1]);
+    return if (ok) digits + 1 else null;
+}
+
+fn isThematicBreak(t: []const u8) bool {
+    const c = t[0];
+    if (c != '-' and c != '*' and c != '_') return false;
+    var count: usize = 0;
+    for (t) |b| {
+        if (b == c) {
+            count += 1;
+        } else if (!isBlank(b)) return false;
+    }
+    return count >= 3;
+}
+
+fn isTableSeparator(t: []const u8) bool {
+    for (t) |c| {
+        if (c != '|' and c != '-' and c != ':' and !isBlank(c)) return false;
+    }
+    return true;
+}
+
+fn asciidoc(content: []const u8, out: *Segments) Error!void {
+    var verbatim: ?[]const u8 = null;
+    var table = false;
+    var it = LineIterator.init(content);
+    while (it.next()) |line| {
+        const no = it.number;
+        const bounds = trimBounds(line);
+        const t = line[bounds.start..bounds.end];
+        if (verbatim) |delimiter| {
+            if (mem.eql(u8, t, delimiter)) verbatim = null;
+            continue;
+        }
+        if (isDelimiter(t, "-./+")) {
+            verbatim = t;
+            continue;
+        }
+        if (isDelimiter(t, "=*_")) continue;
+        if (mem.startsWith(u8, t, "|===")) {
+            table = !table;
+            continue;
+        }
+        if (t.len == 0 or
+            mem.startsWith(u8, t, "//") or
+            mem.eql(u8, t, "<<<") or
+            mem.eql(u8, t, "'''") or
+            isAttributeEntry(t) or
+            (t[0] == '[' and t[t.len - 1] == ']') or
+            isBlockMacro(t)) continue;
+        if (table and t[0] == '|') {
+            try tableCells(out, no, line, bounds.start, "table_cell");
+            continue;
+        }
+
+        var start = bounds.start;
+        var stop = bounds.end;
+        var context: []const u8 = "paragraph";
+        const title: ?usize = if (markerLen(t, '=')) |n| (if (n <= 6) n else null) else null;
+        if (title) |n| {
+            start += n;
+            context = "title";
+        } else if (t[0] == '.' and t.len > 1 and !isBlank(t[1]) and t[1] != '.') {
+            start += 1;
+            context = "block_title";
+        } else if (markerLen(t, '*') orelse markerLen(t, '.')) |n| {
+            start += n;
+            context = "list_item";
+        } else if (t.len > 1 and t[0] == '-' and isBlank(t[1])) {
+            start += 1;
+            context = "list_item";
+        } else if (admonitionLabel(t)) |n| {
+            start += n + 1;
+            context = "admonition";
+        } else if (t.len > 2 and mem.endsWith(u8, t, "::")) {
+            stop -= 2;
+            context = "term";
+        }
+        try out.span(no, line, start, stop, context);
+    }
+}
+
+/// Four or more of one of `chars` and nothing else.
+fn isDelimiter(t: []const u8, chars: []const u8) bool {
+    return t.len >= 4 and mem.indexOfScalar(u8, chars, t[0]) != null and leadingRun(t, t[0]) == t.len;
+}
+
+/// `:name:` or `:name: value`.
+fn isAttributeEntry(t: []const u8) bool {
+    if (t[0] != ':') return false;
+    const k = mem.indexOfScalar(u8, t[1..], ':') orelse return false;
+    if (k == 0) return false;
+    for (t[1 .. k + 1]) |c| {
+        if (isBlank(c)) return false;
+    }
+    return true;
+}
+
+/// `name::target[attributes]`, e.g. `image::` or `include::`.
+fn isBlockMacro(t: []const u8) bool {
+    const k = mem.indexOf(u8, t, "::") orelse return false;
+    if (k == 0 or t[t.len - 1] != ']') return false;
+    for (t[0..k]) |c| {
+        if (!ascii.isAlphanumeric(c) and c != '-' and c != '_') return false;
+    }
+    return true;
+}
+
+/// Length of a `NOTE:`-style label followed by `": "`.
+fn admonitionLabel(t: []const u8) ?usize {
+    for (admonitions) |label| {
+        if (mem.startsWith(u8, t, label) and mem.startsWith(u8, t[label.len..], ": ")) return label.len;
+    }
+    return null;
+}
+
+// ============================================================================
+// HTML
+// ============================================================================
+
+/// Start offset of every line, for turning offsets into line and column.
+const LineStarts = struct {
+    starts: []const usize,
+
+    const Location = struct { line: usize, start: usize };
+
+    fn init(allocator: Allocator, content: []const u8) Error!LineStarts {
+        var starts = std.ArrayList(usize).init(allocator);
+        starts.append(0) catch return error.AllocationFailed;
+        for (content, 0..) |c, i| {
+            if (c == '\n') starts.append(i + 1) catch return error.AllocationFailed;
+        }
+        return .{ .starts = starts.items };
+    }
+
+    /// 1-based line of `pos` and the offset that line starts at.
+    fn locate(self: LineStarts, pos: usize) Location {
+        var lo: usize = 0;
+        var hi: usize = self.starts.len;
+        while (lo < hi) {
+            const mid = lo + (hi - lo) / 2;
+            if (self.starts[mid] <= pos) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        return .{ .line = lo, .start = self.starts[lo - 1] };
+    }
+};
+
+/// Text in `content[from..to]`, one segment per line.
+fn textRun(out: *Segments, content: []const u8, starts: LineStarts, from: usize, to: usize, context: ?[]const u8) Error!void {
+    var start = from;
+    var i = from;
+    while (i <= to) : (i += 1) {
+        if (i == to or content[i] == '\n') {
+            const loc = starts.locate(start);
+            try out.span(loc.line, content[loc.start..], start - loc.start, i - loc.start, context);
+            start = i + 1;
+        }
+    }
+}
+
+fn html(allocator: Allocator, content: []const u8, out: *Segments) Error!void {
+    const b = content;
+    const n = b.len;
+    const starts = try LineStarts.init(allocator, content);
+    var stack = std.ArrayList([]const u8).init(allocator);
+    var i: usize = 0;
+    while (i < n) {
+        if (b[i] == '<' and i + 1 < n) {
+            const next = b[i + 1];
+            if (mem.startsWith(u8, b[i..], "<!--")) {
+                i = if (mem.indexOfPos(u8, b, i + 4, "-->")) |k| k + 3 else n;
+                continue;
+            }
+            if (next == '!' or next == '?') {
+                i = if (mem.indexOfScalarPos(u8, b, i, '>')) |k| k + 1 else n;
+                continue;
+            }
+            if (next == '/') {
+                const name = try tagName(allocator, b, i + 2);
+                var depth = stack.items.len;
+                while (depth > 0) {
+                    depth -= 1;
+                    if (mem.eql(u8, stack.items[depth], name)) {
+                        stack.shrinkRetainingCapacity(depth);
+                        break;
+                    }
+                }
+                i = if (mem.indexOfScalarPos(u8, b, i, '>')) |k| k + 1 else n;
+                continue;
+            }
+            if (ascii.isAlphabetic(next)) {
+                const name = try tagName(allocator, b, i + 1);
+                const tag = try attributes(allocator, out, content, starts, i + 1 + name.len, name);
+                i = tag.end;
+                if (mem.eql(u8, name, "script") or mem.eql(u8, name, "style")) {
+                    i = findCloseTag(b, i, name) orelse n;
+                } else if (!tag.self_closing and !containsAny(&void_elements, name)) {
+                    stack.append(name) catch return error.AllocationFailed;
+                }
+                continue;
+            }
+        }
+        const to = mem.indexOfScalarPos(u8, b, i + 1, '<') orelse n;
+        const context: ?[]const u8 = if (stack.items.len > 0) stack.items[stack.items.len - 1] else null;
+        try textRun(out, content, starts, i, to, context);
+        i = to;
+    }
+}
+
+fn tagName(allocator: Allocator, b: []const u8, from: usize) Error![]const u8 {
+    var end = from;
+    while (end < b.len and (ascii.isAlphanumeric(b[end]) or b[end] == '-')) end += 1;
+    return ascii.allocLowerString(allocator, b[from..end]) catch return error.AllocationFailed;
+}
+
+const TagEnd = struct { end: usize, self_closing: bool };
+
+/// Scan attributes from `from` to the end of the tag, adding translatable
+/// values.
+fn attributes(allocator: Allocator, out: *Segments, content: []const u8, starts: LineStarts, from: usize, tag: []const u8) Error!TagEnd {
+    const b = content;
+    const n = b.len;
+    var i = from;
+    while (true) {
+        while (i < n and isSpace(b[i])) i += 1;
+        if (i >= n) return .{ .end = n, .self_closing = false };
+        switch (b[i]) {
+            '>' => return .{ .end = i + 1, .self_closing = false },
+            '/' => {
+                if (i + 1 < n and b[i + 1] == '>') return .{ .end = i + 2, .self_closing = true };
+                i += 1;
+                continue;
+            },
+            else => {},
+        }
+        const name_start = i;
+        while (i < n and !isSpace(b[i]) and b[i] != '=' and b[i] != '>' and b[i] != '/') i += 1;
+        const name = b[name_start..i];
+        var j = i;
+        while (j < n and isSpace(b[j])) j += 1;
+        if (j >= n or b[j] != '=') continue;
+        j += 1;
+        while (j < n and isSpace(b[j])) j += 1;
+
+        var value_start = j;
+        var value_end = j;
+        var after = j;
+        if (j < n and (b[j] == '"' or b[j] == '\'')) {
+            const close = mem.indexOfScalarPos(u8, b, j + 1, b[j]) orelse n;
+            value_start = j + 1;
+            value_end = close;
+            after = @min(close + 1, n);
+        } else {
+            while (value_end < n and !isSpace(b[value_end]) and b[value_end] != '>') value_end += 1;
+            after = value_end;
+        }
+        for (translatable_attributes) |attribute| {
+            if (ascii.eqlIgnoreCase(name, attribute)) {
+                const context = std.fmt.allocPrint(allocator, "{s}@{s}", .{ tag, attribute }) catch
+                    return error.AllocationFailed;
+                try textRun(out, content, starts, value_start, value_end, context);
+            }
+        }
+        i = after;
+    }
+}
+
+/// Offset of `</name`, matched case-insensitively.
+fn findCloseTag(b: []const u8, from: usize, name: []const u8) ?usize {
+    var p = from;
+    while (p + 2 + name.len <= b.len) : (p += 1) {
+        if (b[p] == '<' and b[p + 1] == '/' and ascii.eqlIgnoreCase(b[p + 2 .. p + 2 + name.len], name)) return p;
+    }
+    return null;
+}
+
+// ============================================================================
+// JSON
+// ============================================================================
+
+const Json = struct {
+    allocator: Allocator,
+    b: []const u8,
+    pos: usize = 0,
+    starts: LineStarts,
+    /// Keys and array indices from the root to the current value
+    path: std.ArrayList([]const u8),
+    out: *Segments,
+
+    fn document(self: *Json) Error!void {
+        try self.value(0);
+        self.ws();
+        if (self.pos != self.b.len) return error.ParseFailed;
+    }
+
+    fn ws(self: *Json) void {
+        while (self.pos < self.b.len and isSpace(self.b[self.pos])) self.pos += 1;
+    }
+
+    fn at(self: *const Json, c: u8) bool {
+        return self.pos < self.b.len and self.b[self.pos] == c;
+    }
+
+    fn expect(self: *Json, c: u8) Error!void {
+        if (!self.at(c)) return error.ParseFailed;
+        self.pos += 1;
+    }
+
+    fn value(self: *Json, depth: usize) Error!void {
+        self.ws();
+        if (self.pos >= self.b.len) return error.ParseFailed;
+        switch (self.b[self.pos]) {
+            '{', '[' => |c| {
+                if (depth >= max_depth) return error.ParseFailed;
+                if (c == '{') try self.object(depth) else try self.array(depth);
+            },
+            '"' => {
+                const start = self.pos;
+                const text = try self.string();
+                if (mem.trim(u8, text, " \t\n\r").len != 0) {
+                    const loc = self.starts.locate(start);
+                    try self.out.push(text, loc.line, start - loc.start + 1, try self.pointer());
+                }
+            },
+            't' => try self.literal("true"),
+            'f' => try self.literal("false"),
+            'n' => try self.literal("null"),
+            '-', '0'...'9' => try self.number(),
+            else => return error.ParseFailed,
+        }
+    }
+
+    /// RFC 6901 pointer to the current value.
+    fn pointer(self: *Json) Error![]const u8 {
+        var p = std.ArrayList(u8).init(self.allocator);
+        for (self.path.items) |token| {
+            p.append('/') catch return error.AllocationFailed;
+            for (token, 0..) |c, k| {
+                const piece: []const u8 = switch (c) {
+                    '~' => "~0",
+                    '/' => "~1",
+                    else => token[k .. k + 1],
+                };
+                p.appendSlice(piece) catch return error.AllocationFailed;
+            }
+        }
+        return p.items;
+    }
+
+    fn object(self: *Json, depth: usize) Error!void {
+        self.pos += 1;
+        self.ws();
+        if (self.at('}')) {
+            self.pos += 1;
+            return;
+        }
+        while (true) {
+            self.ws();
+            if (!self.at('"')) return error.ParseFailed;
+            const key = try self.string();
+            self.ws();
+            try self.expect(':');
+            self.path.append(key) catch return error.AllocationFailed;
+            try self.value(depth + 1);
+            _ = self.path.pop();
+            self.ws();
+            if (self.at(',')) {
+                self.pos += 1;
+            } else if (self.at('}')) {
+                self.pos += 1;
+                return;
+            } else return error.ParseFailed;
+        }
+    }
+
+    fn array(self: *Json, depth: usize) Error!void {
+        self.pos += 1;
+        self.ws();
+        if (self.at(']')) {
+            self.pos += 1;
+            return;
+        }
+        var index: usize = 0;
+        while (true) : (index += 1) {
+            const token = std.fmt.allocPrint(self.allocator, "{d}", .{index}) catch
+                return error.AllocationFailed;
+            self.path.append(token) catch return error.AllocationFailed;
+            try self.value(depth + 1);
+            _ = self.path.pop();
+            self.ws();
+            if (self.at(',')) {
+                self.pos += 1;
+            } else if (self.at(']')) {
+                self.pos += 1;
+                return;
+            } else return error.ParseFailed;
+        }
+    }
+
+    fn literal(self: *Json, word: []const u8) Error!void {
+        if (!mem.startsWith(u8, self.b[self.pos..], word)) return error.ParseFailed;
+        self.pos += word.len;
+    }
+
+    fn digits(self: *Json) usize {
+        const start = self.pos;
+        while (self.pos < self.b.len and ascii.isDigit(self.b[self.pos])) self.pos += 1;
+        return self.pos - start;
+    }
+
+    fn number(self: *Json) Error!void {
+        if (self.at('-')) self.pos += 1;
+        if (self.at('0')) {
+            self.pos += 1;
+        } else if (self.digits() == 0) return error.ParseFailed;
+        if (self.at('.')) {
+            self.pos += 1;
+            if (self.digits() == 0) return error.ParseFailed;
+        }
+        if (self.at('e') or self.at('E')) {
+            self.pos += 1;
+            if (self.at('+') or self.at('-')) self.pos += 1;
+            if (self.digits() == 0) return error.ParseFailed;
+        }
+    }
+
+    fn hex4(self: *Json) Error!u21 {
+        if (self.pos + 4 > self.b.len) return error.ParseFailed;
+        var acc: u21 = 0;
+        for (self.b[self.pos .. self.pos + 4]) |c| {
+            acc = acc * 16 + (std.fmt.charToDigit(c, 16) catch return error.ParseFailed);
+        }
+        self.pos += 4;
+        return acc;
+    }
+
+    /// Decode the string starting at the opening quote under `pos`.
+    fn string(self: *Json) Error![]const u8 {
+        self.pos += 1;
+        var text = std.ArrayList(u8).init(self.allocator);
+        while (true) {
+            const start = self.pos;
+            while (self.pos < self.b.len) : (self.pos += 1) {
+                const c = self.b[self.pos];
+                if (c == '"' or c == '\\' or c < 0x20) break;
+            }
+            text.appendSlice(self.b[start..self.pos]) catch return error.AllocationFailed;
+            if (self.at('"')) {
+                self.pos += 1;
+                return text.items;
+            }
+            if (!self.at('\\')) return error.ParseFailed;
+            self.pos += 1;
+            if (self.pos >= self.b.len) return error.ParseFailed;
+            const escape = self.b[self.pos];
+            self.pos += 1;
+            const decoded: u8 = switch (escape) {
+                '"', '\\', '/' => escape,
+                'b' => 0x08,
+                'f' => 0x0c,
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => {
+                    var buf: [4]u8 = undefined;
+                    const len = std.unicode.utf8Encode(try self.unicodeEscape(), &buf) catch
+                        return error.ParseFailed;
+                    text.appendSlice(buf[0..len]) catch return error.AllocationFailed;
+                    continue;
+                },
+                else => return error.ParseFailed,
+            };
+            text.append(decoded) catch return error.AllocationFailed;
+        }
+    }
+
+    fn unicodeEscape(self: *Json) Error!u21 {
+        const high = try self.hex4();
+        switch (high) {
+            0xd800...0xdbff => {
+                if (!mem.startsWith(u8, self.b[self.pos..], "\\u")) return error.ParseFailed;
+                self.pos += 2;
+                const low = try self.hex4();
+                if (low < 0xdc00 or low > 0xdfff) return error.ParseFailed;
+                return 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+            },
+            0xdc00...0xdfff => return error.ParseFailed,
+            else => return high,
+        }
+    }
+};
+
+// ============================================================================
+// YAML
+// ============================================================================
+
+const YamlNode = union(enum) {
+    key: []const u8,
+    item: usize,
+};
+
+const YamlEntry = struct { indent: usize, node: YamlNode };
+
+const Yaml = struct {
+    allocator: Allocator,
+    out: *Segments,
+    /// Open mapping keys and sequence items with their indent
+    stack: std.ArrayList(YamlEntry),
+    /// Parent indent and path of the block scalar being read
+    block: ?Block = null,
+
+    const Block = struct { parent: usize, path: []const u8 };
+
+    fn document(self: *Yaml, content: []const u8) Error!void {
+        var it = LineIterator.init(content);
+        while (it.next()) |line| {
+            const no = it.number;
+            const bounds = trimBounds(line);
+            const t = line[bounds.start..bounds.end];
+            if (self.block) |blk| {
+                if (t.len == 0) continue;
+                if (bounds.start > blk.parent) {
+                    try self.out.span(no, line, bounds.start, bounds.end, blk.path);
+                    continue;
+                }
+                self.block = null;
+            }
+            if (t.len == 0 or
+                t[0] == '#' or
+                t[0] == '%' or
+                mem.eql(u8, t, "---") or
+                mem.eql(u8, t, "...") or
+                mem.startsWith(u8, t, "--- ")) continue;
+            try self.parseNode(no, line, bounds.start, bounds.end);
+        }
+    }
+
+    fn currentPath(self: *Yaml) Error![]const u8 {
+        var p = std.ArrayList(u8).init(self.allocator);
+        const w = p.writer();
+        for (self.stack.items) |entry| {
+            switch (entry.node) {
+                .key => |name| {
+                    if (p.items.len > 0) w.writeByte('.') catch return error.AllocationFailed;
+                    w.writeAll(name) catch return error.AllocationFailed;
+                },
+                .item => |index| w.print("[{d}]", .{index}) catch return error.AllocationFailed,
+            }
+        }
+        return p.items;
+    }
+
+    /// Pop entries indented deeper than `pos`, or as deep when `inclusive`.
+    fn popTo(self: *Yaml, pos: usize, inclusive: bool) void {
+        while (self.stack.items.len > 0) {
+            const indent = self.stack.items[self.stack.items.len - 1].indent;
+            if (indent < pos or (indent == pos and !inclusive)) break;
+            _ = self.stack.pop();
+        }
+    }
+
+    fn push(self: *Yaml, indent: usize, node: YamlNode) Error!void {
+        self.stack.append(.{ .indent = indent, .node = node }) catch return error.AllocationFailed;
+    }
+
+    fn parseNode(self: *Yaml, no: usize, line: []const u8, start: usize, end: usize) Error!void {
+        var pos = start;
+        while (true) {
+            if (isSequenceDash(line[pos..end])) {
+                var index: usize = 0;
+                self.popTo(pos, false);
+                if (self.stack.items.len > 0) {
+                    const top = self.stack.items[self.stack.items.len - 1];
+                    if (top.indent == pos) switch (top.node) {
+                        .item => |previous| {
+                            index = previous + 1;
+                            _ = self.stack.pop();
+                        },
+                        .key => {},
+                    };
+                }
+                try self.push(pos, .{ .item = index });
+                const item = pos;
+                pos += 1;
+                while (pos < end and isBlank(line[pos])) pos += 1;
+                if (pos == end) return;
+                if (!isSequenceDash(line[pos..end])) {
+                    if (mappingKey(line, pos, end)) |key| {
+                        try self.parseKey(no, line, pos, key, end);
+                    } else {
+                        try self.scalar(no, line, pos, end, item);
+                    }
+                    return;
+                }
+                continue;
+            }
+            if (mappingKey(line, pos, end)) |key| {
+                try self.parseKey(no, line, pos, key, end);
+            } else {
+                self.popTo(pos, true);
+                try self.scalar(no, line, pos, end, pos);
+            }
+            return;
+        }
+    }
+
+    fn parseKey(self: *Yaml, no: usize, line: []const u8, pos: usize, key: MappingKey, end: usize) Error!void {
+        self.popTo(pos, true);
+        try self.push(pos, .{ .key = key.name });
+        try self.scalar(no, line, key.value, end, pos);
+    }
+
+    /// The scalar in `line[start..end]`, owned by a node at `owner` indent.
+    fn scalar(self: *Yaml, no: usize, line: []const u8, start: usize, end: usize, owner: usize) Error!void {
+        var pos = start;
+        while (pos < end and isBlank(line[pos])) pos += 1;
+        // Anchors and tags before the value.
+        while (pos < end and (line[pos] == '&' or line[pos] == '!')) {
+            while (pos < end and !isBlank(line[pos])) pos += 1;
+            while (pos < end and isBlank(line[pos])) pos += 1;
+        }
+        if (pos == end) return;
+        switch (line[pos]) {
+            '*', '[', '{', '#' => {},
+            '|', '>' => self.block = .{ .parent = owner, .path = try self.currentPath() },
+            '"', '\'' => |quote| {
+                const decoded = if (quote == '"')
+                    try doubleQuoted(self.allocator, line[pos + 1 .. end])
+                else
+                    try singleQuoted(self.allocator, line[pos + 1 .. end]);
+                if (decoded) |text| {
+                    if (mem.trim(u8, text, " \t\n\r").len != 0) {
+                        try self.out.push(text, no, pos + 1, try self.currentPath());
+                    }
+                }
+            },
+            else => {
+                var stop = end;
+                var k = pos + 1;
+                while (k < end) : (k += 1) {
+                    if (line[k] == '#' and isBlank(line[k - 1])) {
+                        stop = k;
+                        break;
+                    }
+                }
+                const bounds = trimBounds(line[pos..stop]);
+                if (!isYamlNonString(line[pos + bounds.start .. pos + bounds.end])) {
+                    try self.out.span(no, line, pos, stop, try self.currentPath());
+                }
+            },
+        }
+    }
+};
+
+fn isSequenceDash(rest: []const u8) bool {
+    return mem.eql(u8, rest, "-") or mem.startsWith(u8, rest, "- ");
+}
+
+const MappingKey = struct { name: []const u8, value: usize };
+
+/// `key:` at `pos`: the unquoted key and the offset after the colon.
+fn mappingKey(line: []const u8, pos: usize, end: usize) ?MappingKey {
+    switch (line[pos]) {
+        '"', '\'' => {
+            const close = closingQuote(line[0..end], pos) orelse return null;
+            if (!isKeyColon(line, close + 1, end)) return null;
+            return .{ .name = line[pos + 1 .. close], .value = close + 2 };
+        },
+        '[', '{', '#', '&', '*', '!', '|', '>' => return null,
+        else => {
+            var colon = pos;
+            while (colon < end and !isKeyColon(line, colon, end)) colon += 1;
+            if (colon == end) return null;
+            var k = pos + 1;
+            while (k < colon) : (k += 1) {
+                if (line[k] == '#' and isBlank(line[k - 1])) return null;
+            }
+            const bounds = trimBounds(line[pos..colon]);
+            if (bounds.start == bounds.end) return null;
+            return .{ .name = line[pos + bounds.start .. pos + bounds.end], .value = colon + 1 };
+        },
+    }
+}
+
+/// The quote closing the scalar opened at `pos`, past `\"` in double quotes and
+/// `''` in single quotes.
+fn closingQuote(line: []const u8, pos: usize) ?usize {
+    const quote = line[pos];
+    var k = pos + 1;
+    while (k < line.len) {
+        const escaped = if (quote == '"')
+            line[k] == '\\'
+        else
+            line[k] == quote and k + 1 < line.len and line[k + 1] == quote;
+        if (escaped) {
+            k += 2;
+        } else if (line[k] == quote) {
+            return k;
+        } else {
+            k += 1;
+        }
+    }
+    return null;
+}
+
+fn isKeyColon(line: []const u8, k: usize, end: usize) bool {
+    return k < end and line[k] == ':' and (k + 1 == end or isBlank(line[k + 1]));
+}
+
+/// Body of a double-quoted scalar after its opening quote, decoded up to the
+/// closing quote; null if it is not closed on this line.
+fn doubleQuoted(allocator: Allocator, s: []const u8) Error!?[]const u8 {
+    var text = std.ArrayList(u8).init(allocator);
+    var i: usize = 0;
+    while (i < s.len) {
+        const c = s[i];
+        i += 1;
+        if (c == '"') return text.items;
+        if (c != '\\') {
+            text.append(c) catch return error.AllocationFailed;
+            continue;
+        }
+        if (i >= s.len) return null;
+        const escape = s[i];
+        i += 1;
+        const decoded: u8 = switch (escape) {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => 0,
+            'u' => {
+                var code: u21 = 0xfffd;
+                if (i + 4 <= s.len) {
+                    var acc: u21 = 0;
+                    for (s[i .. i + 4]) |h| {
+                        const d = std.fmt.charToDigit(h, 16) catch break;
+                        acc = acc * 16 + d;
+                    } else {
+                        if (acc < 0xd800 or acc > 0xdfff) code = acc;
+                        i += 4;
+                    }
+                }
+                var buf: [4]u8 = undefined;
+                const len = std.unicode.utf8Encode(code, &buf) catch unreachable;
+                text.appendSlice(buf[0..len]) catch return error.AllocationFailed;
+                continue;
+            },
+            else => escape,
+        };
+        text.append(decoded) catch return error.AllocationFailed;
+    }
+    return null;
 }
 
-// C FFI exports
-export fn polyglot_extract(content: [*:0]const u8, format: u32) ?[*]TextSegment {
-    _ = content;
-    _ = format;
+/// Body of a single-quoted scalar after its opening quote; `''` is a quote.
+fn singleQuoted(allocator: Allocator, s: []const u8) Error!?[]const u8 {
+    var text = std.ArrayList(u8).init(allocator);
+    var i: usize = 0;
+    while (i < s.len) : (i += 1) {
+        if (s[i] == '\'') {
+            if (i + 1 < s.len and s[i + 1] == '\'') {
+                text.append('\'') catch return error.AllocationFailed;
+                i += 1;
+                continue;
+            }
+            return text.items;
+        }
+        text.append(s[i]) catch return error.AllocationFailed;
+    }
     return null;
 }
+
+fn isYamlNonString(s: []const u8) bool {
+    for (yaml_non_strings) |word| {
+        if (ascii.eqlIgnoreCase(s, word)) return true;
+    }
+    return isYamlNumber(s);
+}
+
+fn isYamlNumber(s: []const u8) bool {
+    if (mem.startsWith(u8, s, "0x")) return allDigits(s[2..], 16);
+    if (mem.startsWith(u8, s, "0o")) return allDigits(s[2..], 8);
+    var i: usize = if (s.len > 0 and (s[0] == '+' or s[0] == '-')) 1 else 0;
+    var count: usize = 0;
+    while (i < s.len and (ascii.isDigit(s[i]) or s[i] == '_')) : (i += 1) {
+        if (s[i] != '_') count += 1;
+    }
+    if (i < s.len and s[i] == '.') {
+        i += 1;
+        while (i < s.len and ascii.isDigit(s[i])) : (i += 1) count += 1;
+    }
+    if (count == 0) return false;
+    if (i < s.len and (s[i] == 'e' or s[i] == 'E')) {
+        i += 1;
+        if (i < s.len and (s[i] == '+' or s[i] == '-')) i += 1;
+        const exponent = i;
+        while (i < s.len and ascii.isDigit(s[i])) i += 1;
+        if (i == exponent) return false;
+    }
+    return i == s.len;
+}
+
+fn allDigits(s: []const u8, base: u8) bool {
+    if (s.len == 0) return false;
+    for (s) |c| {
+        if (c == '_') continue;
+        _ = std.fmt.charToDigit(c, base) catch return false;
+    }
+    return true;
+}
+
+// ============================================================================
+// C FFI
+// ============================================================================
+
+pub const POLYGLOT_OK: i32 = 0;
+pub const POLYGLOT_ERR_NULL_PTR: i32 = -1;
+pub const POLYGLOT_ERR_UNSUPPORTED_FORMAT: i32 = -2;
+pub const POLYGLOT_ERR_PARSE_FAILED: i32 = -3;
+pub const POLYGLOT_ERR_ALLOC_FAILED: i32 = -4;
+
+/// C view of a `TextSegment`. Strings are not NUL-terminated; `context` is
+/// null when the segment has none.
+pub const PolyglotSegment = extern struct {
+    text: [*]const u8,
+    text_len: usize,
+    line: usize,
+    column: usize,
+    context: ?[*]const u8,
+    context_len: usize,
+};
+
+/// Result of `polyglot_extract`. `handle` owns the array and every string it
+/// points to; release all of it with `polyglot_free`.
+pub const PolyglotSegments = extern struct {
+    segments: ?[*]const PolyglotSegment,
+    len: usize,
+    handle: ?*anyopaque,
+};
+
+fn errorCode(err: Error) i32 {
+    return switch (err) {
+        error.ParseFailed => POLYGLOT_ERR_PARSE_FAILED,
+        error.UnsupportedFormat => POLYGLOT_ERR_UNSUPPORTED_FORMAT,
+        error.AllocationFailed => POLYGLOT_ERR_ALLOC_FAILED,
+    };
+}
+
+/// Extract segments from `content[0..len]` (UTF-8, no terminator needed) into
+/// `out`. On failure `out` is left empty and nothing needs freeing.
+export fn polyglot_extract(content: ?[*]const u8, len: usize, format: u32, out: ?*PolyglotSegments) callconv(.c) i32 {
+    const result = out orelse return POLYGLOT_ERR_NULL_PTR;
+    result.* = .{ .segments = null, .len = 0, .handle = null };
+    const bytes: []const u8 = if (len == 0) "" else (content orelse return POLYGLOT_ERR_NULL_PTR)[0..len];
+    const fmt = std.meta.intToEnum(Format, format) catch return POLYGLOT_ERR_UNSUPPORTED_FORMAT;
+
+    const arena = std.heap.page_allocator.create(std.heap.ArenaAllocator) catch return POLYGLOT_ERR_ALLOC_FAILED;
+    arena.* = std.heap.ArenaAllocator.init(std.heap.page_allocator);
+    const raw = extractRaw(arena.allocator(), bytes, fmt) catch |err| {
+        arena.deinit();
+        std.heap.page_allocator.destroy(arena);
+        return errorCode(err);
+    };
+    result.* = .{ .segments = raw.ptr, .len = raw.len, .handle = arena };
+    return POLYGLOT_OK;
+}
+
+fn extractRaw(allocator: Allocator, content: []const u8, format: Format) Error![]PolyglotSegment {
+    const segments = try extract(allocator, content, format);
+    const raw = allocator.alloc(PolyglotSegment, segments.len) catch return error.AllocationFailed;
+    for (segments, raw) |segment, *r| {
+        r.* = .{
+            .text = segment.text.ptr,
+            .text_len = segment.text.len,
+            .line = segment.line,
+            .column = segment.column,
+            .context = if (segment.context) |c| c.ptr else null,
+            .context_len = if (segment.context) |c| c.len else 0,
+        };
+    }
+    return raw;
+}
+
+/// Release what `polyglot_extract` returned and reset `segments`.
+export fn polyglot_free(segments: ?*PolyglotSegments) callconv(.c) void {
+    const s = segments orelse return;
+    if (s.handle) |handle| {
+        const arena: *std.heap.ArenaAllocator = @ptrCast(@alignCast(handle));
+        arena.deinit();
+        std.heap.page_allocator.destroy(arena);
+    }
+    s.* = .{ .segments = null, .len = 0, .handle = null };
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+fn expectSegments(content: []const u8, format: Format, expected: []const TextSegment) !void {
+    var arena = std.heap.ArenaAllocator.init(std.testing.allocator);
+    defer arena.deinit();
+    const got = try extract(arena.allocator(), content, format);
+    try std.testing.expectEqual(expected.len, got.len);
+    for (expected, got) |want, have| {
+        try std.testing.expectEqualStrings(want.text, have.text);
+        try std.testing.expectEqual(want.line, have.line);
+        try std.testing.expectEqual(want.column, have.column);
+        if (want.context) |c| {
+            try std.testing.expectEqualStrings(c, have.context.?);
+        } else try std.testing.expect(have.context == null);
+    }
+}
+
+fn seg(text: []const u8, line: usize, column: usize, context: ?[]const u8) TextSegment {
+    return .{ .text = text, .line = line, .column = column, .context = context };
+}
+
+test "plain text" {
+    try expectSegments("  Hello, world  \r\n\n\tsecond line", .plain_text, &.{
+        seg("Hello, world", 1, 3, null),
+        seg("second line", 3, 2, null),
+    });
+}
+
+test "markdown" {
+    try expectSegments("# Title\n\n```\ncode\n```\n- item\n| A | B |\n|---|---|\n", .markdown, &.{
+        seg("Title", 1, 3, "heading"),
+        seg("item", 6, 3, "list_item"),
+        seg("A", 7, 3, "table_cell"),
+        seg("B", 7, 7, "table_cell"),
+    });
+}
+
+test "asciidoc" {
+    try expectSegments("= Doc\n:toc:\n----\ncode\n----\nNOTE: Careful.\nTerm::\n", .asciidoc, &.{
+        seg("Doc", 1, 3, "title"),
+        seg("Careful.", 6, 7, "admonition"),
+        seg("Term", 7, 1, "term"),
+    });
+}
+
+test "html" {
+    try expectSegments("<p>Hi <b>there</b></p><script>x<y</script><img alt='A cat'>", .html, &.{
+        seg("Hi", 1, 4, "p"),
+        seg("there", 1, 10, "b"),
+        seg("A cat", 1, 53, "img@alt"),
+    });
+}
+
+test "json" {
+    try expectSegments("{\"a/b\": [1, \"x\"], \"c\": \"\\u00e9\"}", .json, &.{
+        seg("x", 1, 13, "/a~1b/1"),
+        seg("\u{e9}", 1, 24, "/c"),
+    });
+    var arena = std.heap.ArenaAllocator.init(std.testing.allocator);
+    defer arena.deinit();
+    try std.testing.expectError(error.ParseFailed, extract(arena.allocator(), "[1,]", .json));
+    try std.testing.expectError(error.ParseFailed, extract(arena.allocator(), "\"\\ud800\"", .json));
+}
+
+test "yaml" {
+    try expectSegments("title: Hello # c\ncount: 3\nmenu:\n  - label: \"Open\"\n  - 'It''s'\nhelp: |\n  Line\n", .yaml, &.{
+        seg("Hello", 1, 8, "title"),
+        seg("Open", 4, 12, "menu[0].label"),
+        seg("It's", 5, 5, "menu[1]"),
+        seg("Line", 7, 3, "help"),
+    });
+    try expectSegments("- \"\\\": x\"\n'a''b': c\n", .yaml, &.{
+        seg("\": x", 1, 3, "[0]"),
+        seg("c", 2, 9, "a''b"),
+    });
+}
+
+test "c abi" {
+    var out: PolyglotSegments = undefined;
+    const doc = "one\ntwo";
+    try std.testing.expectEqual(POLYGLOT_OK, polyglot_extract(doc.ptr, doc.len, @intFromEnum(Format.plain_text), &out));
+    try std.testing.expectEqual(@as(usize, 2), out.len);
+    try std.testing.expectEqualStrings("two", out.segments.?[1].text[0..out.segments.?[1].text_len]);
+    polyglot_free(&out);
+    try std.testing.expect(out.handle == null);
+    try std.testing.expectEqual(POLYGLOT_ERR_UNSUPPORTED_FORMAT, polyglot_extract(doc.ptr, doc.len, 99, &out));
+    try std.testing.expectEqual(POLYGLOT_ERR_PARSE_FAILED, polyglot_extract(doc.ptr, doc.len, @intFromEnum(Format.json), &out));
+    try std.testing.expectEqual(POLYGLOT_ERR_NULL_PTR, polyglot_extract(null, 3, 0, &out));
+}
@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Compiles `zig-lib/src/lib.zig` with `zig build-lib` and links it into the
+//! crate. The Swift bridge core (`../swift/src/lib.zig`) is passed in as Zig
+//! module `szf`, so its `szf_*` exports end up in the same library, the
+//! Idris 2 bridge allocator (`../idris2/src/memory.zig`) as module `idris2`,
+//! and the polyglot text extractor (`../polyglot/src/main.zig`) as module
+//! `polyglot`.
+//!
+//! Linkage follows the Cargo features: `static` (default) produces an
+//! archive, `dynamic` a shared library with an rpath into `OUT_DIR`. The Zig
+//! compiler is taken from `$ZIG`, falling back to `zig` on `PATH`. When no
+//! compiler is found the build fails, unless the `pure-rust` backend makes Zig
+//! optional; then the crate is left unlinked and the `zig_linked` cfg tells it
+//! whether the symbols exist.
+//!
+//! After a host build, `zig-lib/src/layout.zig` is run as well. It writes the
+//! layout of the Zig extern structs the crate mirrors to `OUT_DIR/layout.rs`,
+//! and the `zig_layout` cfg turns on the compile-time checks in
+//! `src/layout.rs`.
+
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const LIB_NAME: &str = "rust_zig_ffi";
+const ZIG_ROOT: &str = "zig-lib/src/lib.zig";
+const SZF_ROOT: &str = "../swift/src/lib.zig";
+const IDRIS2_MEMORY: &str = "../idris2/src/memory.zig";
+const POLYGLOT_ROOT: &str = "../polyglot/src/main.zig";
+const LAYOUT_ROOT: &str = "zig-lib/src/layout.zig";
+const BEBOP_ROOT: &str = "../bebop-v/implementations/zig/src/bridge.zig";
+const IDRIS_RTS: &str = "../idris2/src/idris_rts.zig";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Linkage {
+    Static,
+    Dynamic,
+}
+
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(zig_linked)");
+    println!("cargo::rustc-check-cfg=cfg(zig_layout)");
+    println!("cargo:rerun-if-changed=zig-lib/src");
+    println!("cargo:rerun-if-changed={SZF_ROOT}");
+    println!("cargo:rerun-if-changed=../idris2/src");
+    println!("cargo:rerun-if-changed={POLYGLOT_ROOT}");
+    println!("cargo:rerun-if-changed={BEBOP_ROOT}");
+    println!("cargo:rerun-if-env-changed=ZIG");
+
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo"));
+    let linkage = if env::var_os("CARGO_FEATURE_DYNAMIC").is_some() {
+        Linkage::Dynamic
+    } else {
+        Linkage::Static
+    };
+
+    let zig = env::var_os("ZIG").map_or_else(|| PathBuf::from("zig"), PathBuf::from);
+    let artifact = out_dir.join(artifact_name(linkage));
+
+    match compile(&zig, &artifact, linkage) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            if env::var_os("CARGO_FEATURE_PURE_RUST").is_some() {
+                return;
+            }
+            panic!(
+                "zig compiler `{}` not found; {LIB_NAME} cannot be built (set $ZIG, put zig \
+                 on PATH, or enable the `pure-rust` feature)",
+                zig.display()
+            );
+        }
+        Err(err) => panic!("failed to build {ZIG_ROOT}: {err}"),
+    }
+
+    println!("cargo:rustc-link-search=native={}", out_dir.display());
+    match linkage {
+        Linkage::Static => println!("cargo:rustc-link-lib=static={LIB_NAME}"),
+        Linkage::Dynamic => {
+            println!("cargo:rustc-link-lib=dylib={LIB_NAME}");
+            if env::var("CARGO_CFG_TARGET_FAMILY").as_deref() == Ok("unix") {
+                println!("cargo:rustc-link-arg=-Wl,-rpath,{}", out_dir.display());
+            }
+        }
+    }
+    println!("cargo:rustc-cfg=zig_linked");
+
+    // The manifest describes the host, so cross builds go without the checks.
+    if zig_target().is_none() {
+        let manifest = out_dir.join("layout.rs");
+        if let Err(err) = write_layout(&zig, &manifest) {
+            panic!("failed to run {LAYOUT_ROOT}: {err}");
+        }
+        println!("cargo:rustc-cfg=zig_layout");
+    }
+}
+
+/// Run `zig-lib/src/layout.zig`, writing the layout manifest to `manifest`.
+fn write_layout(zig: &Path, manifest: &Path) -> io::Result<()> {
+    let output = Command::new(zig)
+        .arg("run")
+        .args(["--dep", "szf", "--dep", "bebop", "--dep", "idris_rts"])
+        .arg(format!("-Mroot={LAYOUT_ROOT}"))
+        .arg(format!("-Mszf={SZF_ROOT}"))
+        .arg(format!("-Mbebop={BEBOP_ROOT}"))
+        .arg(format!("-Midris_rts={IDRIS_RTS}"))
+        .arg("--")
+        .arg(manifest)
+        .env("ZIG_LOCAL_CACHE_DIR", manifest.with_file_name("zig-cache"))
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "zig exited with {}\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(())
+}
+
+/// Run `zig build-lib`, emitting the library at `artifact`.
+fn compile(zig: &Path, artifact: &Path, linkage: Linkage) -> io::Result<()> {
+    let mut cmd = Command::new(zig);
+    cmd.arg("build-lib")
+        .args(["--dep", "szf", "--dep", "idris2", "--dep", "polyglot"])
+        .arg(format!("-Mroot={ZIG_ROOT}"))
+        .arg(format!("-Mszf={SZF_ROOT}"))
+        .arg(format!("-Midris2={IDRIS2_MEMORY}"))
+        .arg(format!("-Mpolyglot={POLYGLOT_ROOT}"))
+        .arg("--name")
+        .arg(LIB_NAME)
+        .arg(format!("-O{}", optimize_mode()))
+        .arg("-fPIC")
+        .arg(format!("-femit-bin={}", artifact.display()))
+        .env("ZIG_LOCAL_CACHE_DIR", artifact.with_file_name("zig-cache"));
+
+    if linkage == Linkage::Dynamic {
+        cmd.arg("-dynamic");
+    }
+    if let Some(target) = zig_target() {
+        cmd.arg("-target").arg(target);
+    }
+
+    let output = cmd.output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "zig exited with {}\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(())
+}
+
+/// Platform file name of the library for the given linkage.
+fn artifact_name(linkage: Linkage) -> String {
+    let os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let msvc = env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("msvc");
+    match (linkage, os.as_str()) {
+        (Linkage::Static, _) if msvc => format!("{LIB_NAME}.lib"),
+        (Linkage::Static, _) => format!("lib{LIB_NAME}.a"),
+        (Linkage::Dynamic, "windows") => format!("{LIB_NAME}.dll"),
+        (Linkage::Dynamic, "macos" | "ios") => format!("lib{LIB_NAME}.dylib"),
+        (Linkage::Dynamic, _) => format!("lib{LIB_NAME}.so"),
+    }
+}
+
+/// Map the cargo profile onto a Zig optimisation mode.
+fn optimize_mode() -> &'static str {
+    match env::var("PROFILE").as_deref() {
+        Ok("release") => "ReleaseSafe",
+        _ => "Debug",
+    }
+}
+
+/// Zig target triple when cross-compiling, `None` for host builds.
+fn zig_target() -> Option<String> {
+    if env::var("TARGET").ok() == env::var("HOST").ok() {
+        return None;
+    }
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").ok()?;
+    let os = env::var("CARGO_CFG_TARGET_OS").ok()?;
+    let abi = env::var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
+    Some(if abi.is_empty() {
+        format!("{arch}-{os}")
+    } else {
+        format!("{arch}-{os}-{abi}")
+    })
+}
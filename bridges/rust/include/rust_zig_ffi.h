@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//
+// rust_zig_ffi.h - C header for the Rust side of the bridge
+//
+// ┌──────────────────────────────────────────────────────────────────────────┐
+// │ AUTO-GENERATED FILE - DO NOT EDIT MANUALLY                               │
+// │                                                                          │
+// │ Source: src/**/*.rs (#[no_mangle] extern "C" items)                      │
+// │ Generator: src/header.rs                                                 │
+// │ Regenerate: cargo run --features gen-header --bin gen-header             │
+// └──────────────────────────────────────────────────────────────────────────┘
+//
+// These functions are defined in Rust and resolved when the rust-zig-ffi
+// crate is linked into the final artifact. Zig code can @import
+// rust_zig_ffi.zig instead of @cImport-ing rust_zig_ffi.h.
+//
+// ABI STABILITY GUARANTEE:
+// - Version 1.x.x: Backwards compatible (no breaking changes)
+// - Structs: Fields may be added at end only, never removed/reordered
+// - Functions: New functions may be added, existing signatures frozen
+// - Status codes: New codes may be added, existing values frozen
+
+#ifndef RUST_ZIG_FFI_H
+#define RUST_ZIG_FFI_H
+
+#include <stddef.h>
+#include <stdint.h>
+#include <stdbool.h>
+
+// ============================================================================
+// ABI Version
+// ============================================================================
+
+#define RZF_VERSION_MAJOR 1
+#define RZF_VERSION_MINOR 0
+#define RZF_VERSION_PATCH 0
+#define RZF_VERSION_STRING "1.0.0"
+
+// Combine version for runtime checks: (major << 16) | (minor << 8) | patch
+#define RZF_VERSION \
+    ((RZF_VERSION_MAJOR << 16) | (RZF_VERSION_MINOR << 8) | RZF_VERSION_PATCH)
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// ============================================================================
+// Status Codes
+// ============================================================================
+
+/// Status returned by exported functions that completed normally.
+#define RZF_OK        0
+/// Status returned by exported functions whose body panicked.
+#define RZF_ERR_PANIC -7
+
+// ============================================================================
+// Functions
+// ============================================================================
+
+/// Copy the last panic message caught on this thread into `buf` and clear
+/// it.
+///
+/// Returns the full message length, which may exceed `cap`; at most `cap`
+/// bytes are written and the copy is not NUL-terminated. Returns 0 if no
+/// panic is pending.
+///
+/// Safety:
+///
+/// `buf` must be null or valid for writes of `cap` bytes.
+size_t rzf_last_panic_message(uint8_t* buf, size_t cap);
+
+/// Deliver `data` from Zig to every closure registered with
+/// `callback::register`.
+///
+/// Returns `RZF_OK`, or `RZF_ERR_PANIC` if a closure panicked; the
+/// remaining closures are skipped and the message is available from
+/// `rzf_last_panic_message`.
+///
+/// Safety:
+///
+/// `data` must be null or valid for reads of `len` bytes.
+int32_t rust_callback(const uint8_t* data, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // RUST_ZIG_FFI_H
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//
+// rust_zig_ffi.zig - Zig declarations for the Rust side of the bridge
+//
+// ┌──────────────────────────────────────────────────────────────────────────┐
+// │ AUTO-GENERATED FILE - DO NOT EDIT MANUALLY                               │
+// │                                                                          │
+// │ Source: src/**/*.rs (#[no_mangle] extern "C" items)                      │
+// │ Generator: src/header.rs                                                 │
+// │ Regenerate: cargo run --features gen-header --bin gen-header             │
+// └──────────────────────────────────────────────────────────────────────────┘
+//
+// These functions are defined in Rust and resolved when the rust-zig-ffi
+// crate is linked into the final artifact. Zig code can @import
+// rust_zig_ffi.zig instead of @cImport-ing rust_zig_ffi.h.
+//
+// ABI STABILITY GUARANTEE:
+// - Version 1.x.x: Backwards compatible (no breaking changes)
+// - Structs: Fields may be added at end only, never removed/reordered
+// - Functions: New functions may be added, existing signatures frozen
+// - Status codes: New codes may be added, existing values frozen
+
+// ============================================================================
+// ABI Version
+// ============================================================================
+
+pub const RZF_VERSION_MAJOR: u32 = 1;
+pub const RZF_VERSION_MINOR: u32 = 0;
+pub const RZF_VERSION_PATCH: u32 = 0;
+pub const RZF_VERSION_STRING = "1.0.0";
+
+// Combine version for runtime checks: (major << 16) | (minor << 8) | patch
+pub const RZF_VERSION: u32 = (RZF_VERSION_MAJOR << 16) | (RZF_VERSION_MINOR << 8) | RZF_VERSION_PATCH;
+
+// ============================================================================
+// Status Codes
+// ============================================================================
+
+/// Status returned by exported functions that completed normally.
+pub const RZF_OK: i32 = 0;
+/// Status returned by exported functions whose body panicked.
+pub const RZF_ERR_PANIC: i32 = -7;
+
+// ============================================================================
+// Functions
+// ============================================================================
+
+/// Copy the last panic message caught on this thread into `buf` and clear
+/// it.
+///
+/// Returns the full message length, which may exceed `cap`; at most `cap`
+/// bytes are written and the copy is not NUL-terminated. Returns 0 if no
+/// panic is pending.
+///
+/// Safety:
+///
+/// `buf` must be null or valid for writes of `cap` bytes.
+pub extern fn rzf_last_panic_message(buf: [*c]u8, cap: usize) usize;
+
+/// Deliver `data` from Zig to every closure registered with
+/// `callback::register`.
+///
+/// Returns `RZF_OK`, or `RZF_ERR_PANIC` if a closure panicked; the
+/// remaining closures are skipped and the message is available from
+/// `rzf_last_panic_message`.
+///
+/// Safety:
+///
+/// `data` must be null or valid for reads of `len` bytes.
+pub extern fn rust_callback(data: [*c]const u8, len: usize) i32;
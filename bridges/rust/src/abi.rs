@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! ABI versions of the Zig cores.
+//!
+//! Every core reports the version of its C ABI from an export:
+//! `rzf_version` for this crate's own core, `szf_version` for the Swift core,
+//! `bebop_version` for Bebop-V and `get_version` for the template bridges
+//! (Ada, Gleam, OCaml). All pack it as
+//! `(major << 16) | (minor << 8) | patch`, and [`AbiVersion`] unpacks it.
+//!
+//! A library is compatible with the version this crate was built against
+//! if it has the same major version and at least the same minor version;
+//! minor versions only add to the ABI. Anything else fails with an
+//! [`AbiError`] naming both versions, which is the way to catch a stale shared
+//! library on the host before a call into it goes wrong.
+//!
+//! ```
+//! use rust_zig_ffi::abi::{self, AbiVersion};
+//!
+//! let found = AbiVersion::from_packed(0x01_02_07);
+//! assert_eq!(found.to_string(), "1.2.7");
+//! assert!(abi::check("szf", abi::SZF, found).is_ok());
+//!
+//! let err = abi::check("szf", abi::SZF, AbiVersion::new(2, 0, 0)).unwrap_err();
+//! assert_eq!(err.to_string(), "szf ABI 2.0.0 is not compatible with 1.0.0: different major version");
+//! ```
+
+use std::error::Error;
+use std::fmt;
+
+/// A C ABI version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AbiVersion {
+    pub major: u16,
+    pub minor: u8,
+    pub patch: u8,
+}
+
+/// `ABI_VERSION` from this crate's `zig-lib/src/lib.zig`.
+pub const RZF: AbiVersion = AbiVersion::new(1, 0, 0);
+
+/// `SZF_VERSION` from `bridges/swift/include/SwiftZigFFI.h`.
+pub const SZF: AbiVersion = AbiVersion::new(1, 0, 0);
+
+/// `BEBOP_V_FFI_VERSION` from `bridges/bebop-v/include/bebop_v_ffi.h`.
+pub const BEBOP: AbiVersion = AbiVersion::new(1, 0, 0);
+
+/// `get_version` of the template Zig libraries in `bridges/{ada,gleam,ocaml}/zig-lib`.
+pub const TEMPLATE: AbiVersion = AbiVersion::new(0, 1, 0);
+
+impl AbiVersion {
+    pub const fn new(major: u16, minor: u8, patch: u8) -> AbiVersion {
+        AbiVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Unpack `(major << 16) | (minor << 8) | patch`.
+    pub const fn from_packed(packed: u32) -> AbiVersion {
+        AbiVersion::new((packed >> 16) as u16, (packed >> 8) as u8, packed as u8)
+    }
+
+    pub const fn packed(self) -> u32 {
+        (self.major as u32) << 16 | (self.minor as u32) << 8 | self.patch as u32
+    }
+
+    /// Whether a library at version `found` can be used by code built
+    /// against `self`.
+    pub const fn accepts(self, found: AbiVersion) -> bool {
+        found.major == self.major && found.minor >= self.minor
+    }
+}
+
+impl fmt::Display for AbiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Fail unless `expected` [accepts](AbiVersion::accepts) `found`.
+/// `library` names the ABI in the error, such as `"szf"`.
+pub fn check(
+    library: &'static str,
+    expected: AbiVersion,
+    found: AbiVersion,
+) -> Result<(), AbiError> {
+    if expected.accepts(found) {
+        Ok(())
+    } else {
+        Err(AbiError {
+            library,
+            expected,
+            found,
+        })
+    }
+}
+
+/// Check the core linked into this crate: its own exports and the szf core
+/// compiled in with them.
+///
+/// With the `dynamic` feature the core is a shared library resolved when the
+/// program starts, so it may be a stale copy. The first
+/// [`szf::Context::new`](crate::szf::Context::new) runs this check and
+/// returns its error; call it at startup to check the `rzf_*` exports without
+/// creating a context.
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+pub fn check_linked() -> Result<(), AbiError> {
+    extern "C" {
+        fn rzf_version() -> u32;
+    }
+    check(
+        "rzf",
+        RZF,
+        AbiVersion::from_packed(unsafe { rzf_version() }),
+    )?;
+    check("szf", SZF, AbiVersion::from_packed(crate::szf::version()))
+}
+
+/// A library whose ABI version this crate cannot use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbiError {
+    pub library: &'static str,
+    /// The version this crate was built against.
+    pub expected: AbiVersion,
+    /// The version the library reported.
+    pub found: AbiVersion,
+}
+
+impl fmt::Display for AbiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = if self.found.major == self.expected.major {
+            "older minor version"
+        } else {
+            "different major version"
+        };
+        write!(
+            f,
+            "{} ABI {} is not compatible with {}: {reason}",
+            self.library, self.found, self.expected
+        )
+    }
+}
+
+impl Error for AbiError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `{prefix}MAJOR`, `MINOR` and `PATCH` definitions in `source`,
+    /// either `#define NAME 1` or `pub const NAME: u32 = 1;`.
+    fn defined(source: &str, prefix: &str) -> AbiVersion {
+        let part = |name: &str| -> u32 {
+            let name = format!("{prefix}{name}");
+            source
+                .lines()
+                .find_map(|line| {
+                    let rest = line
+                        .trim()
+                        .strip_prefix("#define ")
+                        .or_else(|| line.trim().strip_prefix("pub const "))?
+                        .strip_prefix(name.as_str())?;
+                    // `: u32 = 1;` or ` 1`: the value is the last number.
+                    if !rest.starts_with([' ', ':']) {
+                        return None;
+                    }
+                    rest.split(|c: char| !c.is_ascii_digit())
+                        .rfind(|digits| !digits.is_empty())?
+                        .parse()
+                        .ok()
+                })
+                .unwrap_or_else(|| panic!("{name} is not defined"))
+        };
+        AbiVersion::from_packed(part("MAJOR") << 16 | part("MINOR") << 8 | part("PATCH"))
+    }
+
+    #[test]
+    fn expected_versions_match_the_headers() {
+        let rzf = include_str!("../zig-lib/src/lib.zig");
+        assert_eq!(defined(rzf, "ABI_VERSION_"), RZF);
+        let szf = include_str!("../../swift/include/SwiftZigFFI.h");
+        assert_eq!(defined(szf, "SZF_VERSION_"), SZF);
+        let bebop = include_str!("../../bebop-v/include/bebop_v_ffi.h");
+        assert_eq!(defined(bebop, "BEBOP_V_FFI_VERSION_"), BEBOP);
+        for template in [
+            include_str!("../../ada/zig-lib/src/lib.zig"),
+            include_str!("../../gleam/zig-lib/src/lib.zig"),
+            include_str!("../../ocaml/zig-lib/src/lib.zig"),
+        ] {
+            assert_eq!(defined(template, "VERSION_"), TEMPLATE);
+        }
+    }
+
+    #[test]
+    fn packing_round_trips() {
+        let version = AbiVersion::new(258, 3, 4);
+        assert_eq!(version.packed(), 0x0102_0304);
+        assert_eq!(AbiVersion::from_packed(version.packed()), version);
+        assert!(AbiVersion::new(1, 9, 9) < AbiVersion::new(2, 0, 0));
+    }
+
+    #[test]
+    fn same_major_and_newer_minor_are_accepted() {
+        let expected = AbiVersion::new(1, 2, 5);
+        for found in [(1, 2, 0), (1, 2, 5), (1, 2, 9), (1, 3, 0), (1, 255, 0)] {
+            let found = AbiVersion::new(found.0, found.1, found.2);
+            assert_eq!(check("bebop", expected, found), Ok(()), "{found}");
+        }
+        for found in [(1, 1, 9), (0, 2, 5), (2, 2, 5), (2, 0, 0)] {
+            let found = AbiVersion::new(found.0, found.1, found.2);
+            assert_eq!(
+                check("bebop", expected, found),
+                Err(AbiError {
+                    library: "bebop",
+                    expected,
+                    found
+                }),
+                "{found}"
+            );
+        }
+    }
+
+    #[test]
+    fn errors_name_both_versions() {
+        let err = check("bebop", AbiVersion::new(1, 2, 0), AbiVersion::new(1, 1, 3)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "bebop ABI 1.1.3 is not compatible with 1.2.0: older minor version"
+        );
+    }
+
+    #[cfg(zig_linked)]
+    #[test]
+    fn linked_core_is_compatible() {
+        assert_eq!(check_linked(), Ok(()));
+    }
+}
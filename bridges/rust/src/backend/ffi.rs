@@ -0,0 +1,307 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Zig core backend (`zig-lib/src/lib.zig`).
+
+use std::ffi::c_void;
+use std::{ptr, slice};
+
+#[cfg(not(feature = "pure-rust"))]
+use crate::callback::{CallbackError, RawCallback};
+use crate::hkdf::{Algorithm, HkdfError};
+use crate::kdf::{Argon2Params, KdfError, Pbkdf2Params, ScryptParams};
+use crate::polyglot::{Format, PolyglotError, TextSegment, POLYGLOT_OK};
+
+const HKDF_OK: i32 = 0;
+const HKDF_ERR_INVALID_ALGORITHM: i32 = -1;
+const HKDF_ERR_INVALID_LENGTH: i32 = -2;
+const KDF_ERR_INVALID_PARAMS: i32 = -3;
+const KDF_ERR_ALLOC_FAILED: i32 = -4;
+#[cfg(not(feature = "pure-rust"))]
+const RZF_ERR_REGISTRY_FULL: i32 = -6;
+
+/// `PolyglotSegment` from `bridges/polyglot/src/main.zig`.
+#[repr(C)]
+struct PolyglotSegment {
+    text: *const u8,
+    text_len: usize,
+    line: usize,
+    column: usize,
+    context: *const u8,
+    context_len: usize,
+}
+
+/// `PolyglotSegments`; `handle` owns the array and every string in it.
+#[repr(C)]
+struct PolyglotSegments {
+    segments: *const PolyglotSegment,
+    len: usize,
+    handle: *mut c_void,
+}
+
+extern "C" {
+    fn hkdf_derive(
+        password: *const u8,
+        password_len: usize,
+        salt: *const u8,
+        salt_len: usize,
+        key: *mut u8,
+    );
+    fn hkdf_extract(
+        algorithm: u32,
+        salt: *const u8,
+        salt_len: usize,
+        ikm: *const u8,
+        ikm_len: usize,
+        prk: *mut u8,
+    ) -> i32;
+    fn hkdf_expand(
+        algorithm: u32,
+        prk: *const u8,
+        prk_len: usize,
+        info: *const u8,
+        info_len: usize,
+        okm: *mut u8,
+        okm_len: usize,
+    ) -> i32;
+    fn kdf_pbkdf2(
+        algorithm: u32,
+        password: *const u8,
+        password_len: usize,
+        salt: *const u8,
+        salt_len: usize,
+        rounds: u32,
+        out: *mut u8,
+        out_len: usize,
+    ) -> i32;
+    fn kdf_scrypt(
+        password: *const u8,
+        password_len: usize,
+        salt: *const u8,
+        salt_len: usize,
+        log_n: u8,
+        r: u32,
+        p: u32,
+        out: *mut u8,
+        out_len: usize,
+    ) -> i32;
+    fn kdf_argon2id(
+        password: *const u8,
+        password_len: usize,
+        salt: *const u8,
+        salt_len: usize,
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+        out: *mut u8,
+        out_len: usize,
+    ) -> i32;
+    #[cfg(not(feature = "pure-rust"))]
+    fn rzf_register_callback(callback: Option<RawCallback>, context: *mut c_void) -> i32;
+    #[cfg(not(feature = "pure-rust"))]
+    fn rzf_unregister_callback(context: *mut c_void);
+    #[cfg(all(test, zig_linked, not(feature = "pure-rust")))]
+    fn rzf_emit(data: *const u8, len: usize) -> usize;
+    fn polyglot_extract(
+        content: *const u8,
+        len: usize,
+        format: u32,
+        out: *mut PolyglotSegments,
+    ) -> i32;
+    fn polyglot_free(segments: *mut PolyglotSegments);
+}
+
+fn id(algorithm: Algorithm) -> u32 {
+    match algorithm {
+        Algorithm::Sha256 => 0,
+        Algorithm::Sha512 => 1,
+    }
+}
+
+pub(crate) fn derive(password: &[u8], salt: &[u8], key: &mut [u8]) {
+    assert_eq!(key.len(), 64);
+    unsafe {
+        hkdf_derive(
+            password.as_ptr(),
+            password.len(),
+            salt.as_ptr(),
+            salt.len(),
+            key.as_mut_ptr(),
+        );
+    }
+}
+
+pub(crate) fn extract(algorithm: Algorithm, salt: &[u8], ikm: &[u8], prk: &mut [u8]) {
+    assert_eq!(prk.len(), algorithm.hash_len());
+    let code = unsafe {
+        hkdf_extract(
+            id(algorithm),
+            salt.as_ptr(),
+            salt.len(),
+            ikm.as_ptr(),
+            ikm.len(),
+            prk.as_mut_ptr(),
+        )
+    };
+    debug_assert_eq!(code, HKDF_OK);
+}
+
+pub(crate) fn expand(
+    algorithm: Algorithm,
+    prk: &[u8],
+    info: &[u8],
+    okm: &mut [u8],
+) -> Result<(), HkdfError> {
+    let code = unsafe {
+        hkdf_expand(
+            id(algorithm),
+            prk.as_ptr(),
+            prk.len(),
+            info.as_ptr(),
+            info.len(),
+            okm.as_mut_ptr(),
+            okm.len(),
+        )
+    };
+    match code {
+        HKDF_OK => Ok(()),
+        HKDF_ERR_INVALID_LENGTH => Err(HkdfError::OutputTooLong {
+            requested: okm.len(),
+            max: algorithm.max_output_len(),
+        }),
+        HKDF_ERR_INVALID_ALGORITHM => unreachable!("Algorithm ids are fixed"),
+        other => Err(HkdfError::Foreign(other)),
+    }
+}
+
+pub(crate) fn pbkdf2(
+    params: &Pbkdf2Params,
+    password: &[u8],
+    salt: &[u8],
+    out: &mut [u8],
+) -> Result<(), KdfError> {
+    kdf_result(unsafe {
+        kdf_pbkdf2(
+            id(params.hash),
+            password.as_ptr(),
+            password.len(),
+            salt.as_ptr(),
+            salt.len(),
+            params.rounds,
+            out.as_mut_ptr(),
+            out.len(),
+        )
+    })
+}
+
+pub(crate) fn scrypt(
+    params: &ScryptParams,
+    password: &[u8],
+    salt: &[u8],
+    out: &mut [u8],
+) -> Result<(), KdfError> {
+    kdf_result(unsafe {
+        kdf_scrypt(
+            password.as_ptr(),
+            password.len(),
+            salt.as_ptr(),
+            salt.len(),
+            params.log_n,
+            params.r,
+            params.p,
+            out.as_mut_ptr(),
+            out.len(),
+        )
+    })
+}
+
+pub(crate) fn argon2id(
+    params: &Argon2Params,
+    password: &[u8],
+    salt: &[u8],
+    out: &mut [u8],
+) -> Result<(), KdfError> {
+    kdf_result(unsafe {
+        kdf_argon2id(
+            password.as_ptr(),
+            password.len(),
+            salt.as_ptr(),
+            salt.len(),
+            params.memory_kib,
+            params.iterations,
+            params.parallelism,
+            out.as_mut_ptr(),
+            out.len(),
+        )
+    })
+}
+
+fn kdf_result(code: i32) -> Result<(), KdfError> {
+    match code {
+        HKDF_OK => Ok(()),
+        KDF_ERR_INVALID_PARAMS => Err(KdfError::InvalidParams("rejected by the Zig core")),
+        KDF_ERR_ALLOC_FAILED => Err(KdfError::AllocFailed),
+        other => Err(KdfError::Foreign(other)),
+    }
+}
+
+#[cfg(not(feature = "pure-rust"))]
+pub(crate) fn register_callback(
+    callback: RawCallback,
+    context: *mut c_void,
+) -> Result<(), CallbackError> {
+    match unsafe { rzf_register_callback(Some(callback), context) } {
+        HKDF_OK => Ok(()),
+        RZF_ERR_REGISTRY_FULL => Err(CallbackError::RegistryFull),
+        other => Err(CallbackError::Foreign(other)),
+    }
+}
+
+#[cfg(not(feature = "pure-rust"))]
+pub(crate) fn unregister_callback(context: *mut c_void) {
+    unsafe { rzf_unregister_callback(context) }
+}
+
+#[cfg(all(test, zig_linked, not(feature = "pure-rust")))]
+pub(crate) fn emit(data: &[u8]) -> usize {
+    unsafe { rzf_emit(data.as_ptr(), data.len()) }
+}
+
+pub(crate) fn extract_text(
+    content: &str,
+    format: Format,
+) -> Result<Vec<TextSegment>, PolyglotError> {
+    let mut raw = PolyglotSegments {
+        segments: ptr::null(),
+        len: 0,
+        handle: ptr::null_mut(),
+    };
+    let code =
+        unsafe { polyglot_extract(content.as_ptr(), content.len(), format as u32, &mut raw) };
+    if code != POLYGLOT_OK {
+        return Err(PolyglotError::from_code(code));
+    }
+    let segments = if raw.len == 0 {
+        &[][..]
+    } else {
+        unsafe { slice::from_raw_parts(raw.segments, raw.len) }
+    };
+    let text = |ptr: *const u8, len: usize| {
+        let bytes = if len == 0 {
+            &[][..]
+        } else {
+            unsafe { slice::from_raw_parts(ptr, len) }
+        };
+        String::from_utf8_lossy(bytes).into_owned()
+    };
+    let out = segments
+        .iter()
+        .map(|segment| TextSegment {
+            text: text(segment.text, segment.text_len),
+            line: segment.line,
+            column: segment.column,
+            context: (!segment.context.is_null())
+                .then(|| text(segment.context, segment.context_len)),
+        })
+        .collect();
+    unsafe { polyglot_free(&mut raw) };
+    Ok(out)
+}
@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! HKDF and password-hashing primitives behind the public API.
+//!
+//! `ffi` calls the Zig core; `native` (feature `pure-rust`) computes the same
+//! outputs in Rust. `active` is whichever the public API uses. When both are
+//! compiled, the differential tests below check they agree byte for byte.
+//!
+//! Callers validate lengths before reaching a backend: `prk` is exactly one
+//! hash length, `okm` at most 255 of them, and `derive` writes 64 bytes.
+//! Password-hashing parameters are checked by `kdf::Params` beforehand, so
+//! both backends see only inputs they accept.
+//!
+//! Text extraction (`extract_text`) follows the same split: the polyglot
+//! bridge in Zig, or its port in `polyglot.rs`.
+
+#[cfg(any(not(feature = "pure-rust"), all(test, zig_linked)))]
+pub(crate) mod ffi;
+#[cfg(feature = "pure-rust")]
+pub(crate) mod native;
+#[cfg(feature = "pure-rust")]
+mod polyglot;
+
+#[cfg(not(feature = "pure-rust"))]
+pub(crate) use ffi as active;
+#[cfg(feature = "pure-rust")]
+pub(crate) use native as active;
+
+#[cfg(all(test, zig_linked, feature = "pure-rust"))]
+mod differential {
+    use super::{ffi, native};
+    use crate::hkdf::Algorithm;
+    use crate::kdf::{Argon2Params, Pbkdf2Params, ScryptParams};
+    use crate::polyglot::Format;
+    use proptest::prelude::*;
+
+    fn algorithm() -> impl Strategy<Value = Algorithm> {
+        prop_oneof![Just(Algorithm::Sha256), Just(Algorithm::Sha512)]
+    }
+
+    proptest! {
+        #[test]
+        fn derive_matches(
+            password in prop::collection::vec(any::<u8>(), 0..256),
+            salt in prop::collection::vec(any::<u8>(), 0..256),
+        ) {
+            let (mut a, mut b) = ([0u8; 64], [0u8; 64]);
+            ffi::derive(&password, &salt, &mut a);
+            native::derive(&password, &salt, &mut b);
+            prop_assert_eq!(a, b);
+        }
+
+        #[test]
+        fn extract_matches(
+            algorithm in algorithm(),
+            salt in prop::collection::vec(any::<u8>(), 0..256),
+            ikm in prop::collection::vec(any::<u8>(), 0..256),
+        ) {
+            let len = algorithm.hash_len();
+            let (mut a, mut b) = (vec![0u8; len], vec![0u8; len]);
+            ffi::extract(algorithm, &salt, &ikm, &mut a);
+            native::extract(algorithm, &salt, &ikm, &mut b);
+            prop_assert_eq!(a, b);
+        }
+
+        #[test]
+        fn expand_matches(
+            algorithm in algorithm(),
+            seed in any::<u8>(),
+            info in prop::collection::vec(any::<u8>(), 0..256),
+            len in 0usize..2048,
+        ) {
+            let prk = vec![seed; algorithm.hash_len()];
+            let (mut a, mut b) = (vec![0u8; len], vec![0u8; len]);
+            ffi::expand(algorithm, &prk, &info, &mut a).unwrap();
+            native::expand(algorithm, &prk, &info, &mut b).unwrap();
+            prop_assert_eq!(a, b);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn pbkdf2_matches(
+            hash in algorithm(),
+            rounds in 1u32..64,
+            password in prop::collection::vec(any::<u8>(), 0..64),
+            salt in prop::collection::vec(any::<u8>(), 0..64),
+            len in 1usize..200,
+        ) {
+            let params = Pbkdf2Params { hash, rounds };
+            let (mut a, mut b) = (vec![0u8; len], vec![0u8; len]);
+            ffi::pbkdf2(&params, &password, &salt, &mut a).unwrap();
+            native::pbkdf2(&params, &password, &salt, &mut b).unwrap();
+            prop_assert_eq!(a, b);
+        }
+
+        #[test]
+        fn scrypt_matches(
+            log_n in 1u8..8,
+            r in 1u32..4,
+            p in 1u32..3,
+            password in prop::collection::vec(any::<u8>(), 0..64),
+            salt in prop::collection::vec(any::<u8>(), 0..64),
+            len in 1usize..100,
+        ) {
+            let params = ScryptParams { log_n, r, p };
+            let (mut a, mut b) = (vec![0u8; len], vec![0u8; len]);
+            ffi::scrypt(&params, &password, &salt, &mut a).unwrap();
+            native::scrypt(&params, &password, &salt, &mut b).unwrap();
+            prop_assert_eq!(a, b);
+        }
+
+        #[test]
+        fn argon2id_matches(
+            parallelism in 1u32..3,
+            extra_kib in 0u32..64,
+            iterations in 1u32..3,
+            password in prop::collection::vec(any::<u8>(), 0..64),
+            salt in prop::collection::vec(any::<u8>(), 8..64),
+            len in 4usize..100,
+        ) {
+            let params = Argon2Params { memory_kib: 8 * parallelism + extra_kib, iterations, parallelism };
+            let (mut a, mut b) = (vec![0u8; len], vec![0u8; len]);
+            ffi::argon2id(&params, &password, &salt, &mut a).unwrap();
+            native::argon2id(&params, &password, &salt, &mut b).unwrap();
+            prop_assert_eq!(a, b);
+        }
+    }
+
+    /// Documents made of the characters the extractors give meaning to.
+    fn document() -> impl Strategy<Value = String> {
+        "([-#>|*=.:_`~<>/!?\"'\\{}\\[\\],&a-cé0-9 \t\r\n]|<p>|</p>|<!--|-->|\\u00e9){0,200}"
+    }
+
+    fn format() -> impl Strategy<Value = Format> {
+        prop::sample::select(Format::ALL.to_vec())
+    }
+
+    proptest! {
+        #[test]
+        fn extract_text_matches(format in format(), content in document()) {
+            prop_assert_eq!(
+                ffi::extract_text(&content, format),
+                native::extract_text(&content, format)
+            );
+        }
+    }
+}
@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Pure-Rust backend (feature `pure-rust`), output-compatible with the Zig
+//! core.
+
+use std::ffi::c_void;
+
+use ::hkdf::Hkdf;
+use argon2::Argon2;
+use sha2::{Sha256, Sha512};
+
+use crate::callback::{CallbackError, RawCallback};
+use crate::hkdf::{Algorithm, HkdfError};
+use crate::kdf::{Argon2Params, KdfError, Pbkdf2Params, ScryptParams};
+
+pub(crate) use super::polyglot::extract as extract_text;
+
+pub(crate) fn derive(password: &[u8], salt: &[u8], key: &mut [u8]) {
+    assert_eq!(key.len(), 64);
+    Hkdf::<Sha512>::new(Some(salt), password)
+        .expand(&[], key)
+        .expect("64 bytes is within the HKDF-SHA512 limit");
+}
+
+pub(crate) fn extract(algorithm: Algorithm, salt: &[u8], ikm: &[u8], prk: &mut [u8]) {
+    assert_eq!(prk.len(), algorithm.hash_len());
+    match algorithm {
+        Algorithm::Sha256 => prk.copy_from_slice(&Hkdf::<Sha256>::extract(Some(salt), ikm).0),
+        Algorithm::Sha512 => prk.copy_from_slice(&Hkdf::<Sha512>::extract(Some(salt), ikm).0),
+    }
+}
+
+pub(crate) fn expand(
+    algorithm: Algorithm,
+    prk: &[u8],
+    info: &[u8],
+    okm: &mut [u8],
+) -> Result<(), HkdfError> {
+    const PRK_CHECKED: &str = "PRK length is checked by the caller";
+    let expanded = match algorithm {
+        Algorithm::Sha256 => Hkdf::<Sha256>::from_prk(prk)
+            .expect(PRK_CHECKED)
+            .expand(info, okm),
+        Algorithm::Sha512 => Hkdf::<Sha512>::from_prk(prk)
+            .expect(PRK_CHECKED)
+            .expand(info, okm),
+    };
+    expanded.map_err(|_| HkdfError::OutputTooLong {
+        requested: okm.len(),
+        max: algorithm.max_output_len(),
+    })
+}
+
+pub(crate) fn pbkdf2(
+    params: &Pbkdf2Params,
+    password: &[u8],
+    salt: &[u8],
+    out: &mut [u8],
+) -> Result<(), KdfError> {
+    match params.hash {
+        Algorithm::Sha256 => ::pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, params.rounds, out),
+        Algorithm::Sha512 => ::pbkdf2::pbkdf2_hmac::<Sha512>(password, salt, params.rounds, out),
+    }
+    Ok(())
+}
+
+pub(crate) fn scrypt(
+    params: &ScryptParams,
+    password: &[u8],
+    salt: &[u8],
+    out: &mut [u8],
+) -> Result<(), KdfError> {
+    let rejected = |_| KdfError::InvalidParams("rejected by the scrypt crate");
+    let params = ::scrypt::Params::new(
+        params.log_n,
+        params.r,
+        params.p,
+        ::scrypt::Params::RECOMMENDED_LEN,
+    )
+    .map_err(rejected)?;
+    ::scrypt::scrypt(password, salt, &params, out)
+        .map_err(|_| KdfError::InvalidParams("rejected by the scrypt crate"))
+}
+
+pub(crate) fn argon2id(
+    params: &Argon2Params,
+    password: &[u8],
+    salt: &[u8],
+    out: &mut [u8],
+) -> Result<(), KdfError> {
+    let rejected = |_| KdfError::InvalidParams("rejected by the argon2 crate");
+    let params = argon2::Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        None,
+    )
+    .map_err(rejected)?;
+    Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+        .hash_password_into(password, salt, out)
+        .map_err(rejected)
+}
+
+/// Without a Zig core nothing emits events, so there is nothing to subscribe
+/// to; closures are still reachable through `rust_callback`.
+pub(crate) fn register_callback(
+    _callback: RawCallback,
+    _context: *mut c_void,
+) -> Result<(), CallbackError> {
+    Ok(())
+}
+
+pub(crate) fn unregister_callback(_context: *mut c_void) {}
@@ -0,0 +1,1091 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Native port of `extract` from `bridges/polyglot/src/main.zig`.
+//!
+//! Both follow the rules in the polyglot bridge README and must stay in
+//! step: the differential tests in `backend/mod.rs` compare them on random
+//! documents. Offsets are bytes; every span starts and ends next to an ASCII
+//! byte, so slicing the `&str` never splits a character.
+
+use crate::polyglot::{Format, PolyglotError, TextSegment};
+
+/// Deepest JSON nesting accepted, as in `main.zig`.
+const MAX_DEPTH: usize = 128;
+
+const TRANSLATABLE_ATTRIBUTES: [&str; 4] = ["alt", "title", "placeholder", "aria-label"];
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr", "param",
+];
+const ADMONITIONS: [&str; 5] = ["NOTE", "TIP", "IMPORTANT", "WARNING", "CAUTION"];
+const YAML_NON_STRINGS: [&str; 12] = [
+    "null", "~", "true", "false", "yes", "no", "on", "off", ".inf", "-.inf", "+.inf", ".nan",
+];
+
+pub(crate) fn extract(content: &str, format: Format) -> Result<Vec<TextSegment>, PolyglotError> {
+    let mut out = Segments(Vec::new());
+    match format {
+        Format::PlainText => plain_text(content, &mut out),
+        Format::Markdown => markdown(content, &mut out),
+        Format::Asciidoc => asciidoc(content, &mut out),
+        Format::Html => html(content, &mut out),
+        Format::Json => Json::new(content, &mut out).document()?,
+        Format::Yaml => Yaml::default().document(content, &mut out),
+    }
+    Ok(out.0)
+}
+
+struct Segments(Vec<TextSegment>);
+
+impl Segments {
+    /// Add `line[start..end]` with blanks trimmed, unless nothing is left.
+    fn span(
+        &mut self,
+        line_no: usize,
+        line: &str,
+        start: usize,
+        end: usize,
+        context: Option<&str>,
+    ) {
+        let b = line.as_bytes();
+        let (mut s, mut e) = (start, end);
+        while s < e && is_blank(b[s]) {
+            s += 1;
+        }
+        while e > s && is_blank(b[e - 1]) {
+            e -= 1;
+        }
+        if s < e {
+            self.push(line[s..e].to_owned(), line_no, s + 1, context);
+        }
+    }
+
+    fn push(&mut self, text: String, line: usize, column: usize, context: Option<&str>) {
+        self.0.push(TextSegment {
+            text,
+            line,
+            column,
+            context: context.map(str::to_owned),
+        });
+    }
+}
+
+fn is_blank(c: u8) -> bool {
+    matches!(c, b' ' | b'\t' | b'\r')
+}
+
+/// Lines numbered from 1, without their `\n` or `\r\n`.
+fn lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    content
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.strip_suffix('\r').unwrap_or(line)))
+}
+
+/// `(indent, end)`: the line without leading and trailing blanks is
+/// `line[indent..end]`.
+fn bounds(line: &str) -> (usize, usize) {
+    let b = line.as_bytes();
+    let mut start = 0;
+    while start < b.len() && is_blank(b[start]) {
+        start += 1;
+    }
+    let mut end = b.len();
+    while end > start && is_blank(b[end - 1]) {
+        end -= 1;
+    }
+    (start, end)
+}
+
+/// Number of leading `c` bytes in `s`.
+fn run(s: &[u8], c: u8) -> usize {
+    s.iter().take_while(|&&b| b == c).count()
+}
+
+/// A run of `c` followed by a blank; returns the run length.
+fn marker(s: &[u8], c: u8) -> Option<usize> {
+    let n = run(s, c);
+    (n > 0 && n < s.len() && is_blank(s[n])).then_some(n)
+}
+
+/// Table cells of `line[from..]`, split on `|` not preceded by `\`.
+fn cells(out: &mut Segments, line_no: usize, line: &str, from: usize, context: &str) {
+    let b = line.as_bytes();
+    let mut start = from;
+    for i in from..b.len() {
+        if b[i] == b'|' && (i == 0 || b[i - 1] != b'\\') {
+            out.span(line_no, line, start, i, Some(context));
+            start = i + 1;
+        }
+    }
+    out.span(line_no, line, start, b.len(), Some(context));
+}
+
+// ----------------------------------------------------------------------------
+// Plain text, Markdown, AsciiDoc
+// ----------------------------------------------------------------------------
+
+fn plain_text(content: &str, out: &mut Segments) {
+    for (no, line) in lines(content) {
+        out.span(no, line, 0, line.len(), None);
+    }
+}
+
+fn markdown(content: &str, out: &mut Segments) {
+    let mut fence: Option<(u8, usize)> = None;
+    let mut front_matter = false;
+    let mut comment = false;
+    for (no, line) in lines(content) {
+        let (indent, end) = bounds(line);
+        let t = &line.as_bytes()[indent..end];
+        if no == 1 && t == b"---" {
+            front_matter = true;
+            continue;
+        }
+        if front_matter {
+            front_matter = t != b"---";
+            continue;
+        }
+        if let Some((c, n)) = fence {
+            let k = run(t, c);
+            if k >= n && k == t.len() {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(&c @ (b'`' | b'~')) = t.first() {
+            let n = run(t, c);
+            if n >= 3 {
+                fence = Some((c, n));
+                continue;
+            }
+        }
+        if comment {
+            comment = !contains(t, b"-->");
+            continue;
+        }
+        if t.starts_with(b"<!--") {
+            comment = !contains(&t[4..], b"-->");
+            continue;
+        }
+        if t.is_empty() || is_thematic_break(t) {
+            continue;
+        }
+        if t[0] == b'|' {
+            if !t
+                .iter()
+                .all(|&c| matches!(c, b'|' | b'-' | b':') || is_blank(c))
+            {
+                cells(out, no, line, indent, "table_cell");
+            }
+            continue;
+        }
+
+        let (start, context) = match t[0] {
+            b'#' => match marker(t, b'#') {
+                Some(n) if n <= 6 => (indent + n, "heading"),
+                _ if run(t, b'#') == t.len() && t.len() <= 6 => continue,
+                _ => (indent, "paragraph"),
+            },
+            b'>' => {
+                let k = t.iter().take_while(|&&c| c == b'>' || is_blank(c)).count();
+                (indent + k, "blockquote")
+            }
+            b'-' | b'*' | b'+' if t.len() > 1 && is_blank(t[1]) => (indent + 1, "list_item"),
+            b'0'..=b'9' => match ordered_marker(t) {
+                Some(n) => (indent + n, "list_item"),
+                None => (indent, "paragraph"),
+            },
+            _ => (indent, "paragraph"),
+        };
+        out.span(no, line, start, end, Some(context));
+    }
+}
+
+/// `1.` / `1)` list markers followed by a blank; returns the marker length.
+fn ordered_marker(t: &[u8]) -> Option<usize> {
+    let digits = t.iter().take_while(|c| c.is_ascii_digit()).count();
+    let ok = (1..=9).contains(&digits)
+        && digits + 1 < t.len()
+        && matches!(t[digits], b'.' | b')')
+        && is_blank(t[digits + 1]);
+    ok.then_some(digits + 1)
+}
+
+fn is_thematic_break(t: &[u8]) -> bool {
+    let c = t[0];
+    matches!(c, b'-' | b'*' | b'_')
+        && t.iter().filter(|&&b| b == c).count() >= 3
+        && t.iter().all(|&b| b == c || is_blank(b))
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, 0, needle).is_some()
+}
+
+fn find(haystack: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    if from > haystack.len() {
+        return None;
+    }
+    haystack[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|i| from + i)
+}
+
+fn asciidoc(content: &str, out: &mut Segments) {
+    let mut verbatim: Option<&[u8]> = None;
+    let mut table = false;
+    for (no, line) in lines(content) {
+        let (indent, end) = bounds(line);
+        let t = &line.as_bytes()[indent..end];
+        if let Some(delimiter) = verbatim {
+            if t == delimiter {
+                verbatim = None;
+            }
+            continue;
+        }
+        if is_delimiter(t, b"-./+") {
+            verbatim = Some(t);
+            continue;
+        }
+        if is_delimiter(t, b"=*_") {
+            continue;
+        }
+        if t.starts_with(b"|===") {
+            table = !table;
+            continue;
+        }
+        if t.is_empty()
+            || t.starts_with(b"//")
+            || t == b"<<<"
+            || t == b"'''"
+            || is_attribute_entry(t)
+            || (t[0] == b'[' && t[t.len() - 1] == b']')
+            || is_block_macro(t)
+        {
+            continue;
+        }
+        if table && t[0] == b'|' {
+            cells(out, no, line, indent, "table_cell");
+            continue;
+        }
+
+        let (start, stop, context) = if let Some(n) = marker(t, b'=').filter(|&n| n <= 6) {
+            (indent + n, end, "title")
+        } else if t[0] == b'.' && t.len() > 1 && !is_blank(t[1]) && t[1] != b'.' {
+            (indent + 1, end, "block_title")
+        } else if let Some(n) = marker(t, b'*').or(marker(t, b'.')) {
+            (indent + n, end, "list_item")
+        } else if t.len() > 1 && t[0] == b'-' && is_blank(t[1]) {
+            (indent + 1, end, "list_item")
+        } else if let Some(label) = ADMONITIONS
+            .iter()
+            .find(|label| t.starts_with(label.as_bytes()) && t[label.len()..].starts_with(b": "))
+        {
+            (indent + label.len() + 1, end, "admonition")
+        } else if t.len() > 2 && t.ends_with(b"::") {
+            (indent, end - 2, "term")
+        } else {
+            (indent, end, "paragraph")
+        };
+        out.span(no, line, start, stop, Some(context));
+    }
+}
+
+/// Four or more of one of `chars` and nothing else.
+fn is_delimiter(t: &[u8], chars: &[u8]) -> bool {
+    t.len() >= 4 && chars.contains(&t[0]) && run(t, t[0]) == t.len()
+}
+
+/// `:name:` or `:name: value`.
+fn is_attribute_entry(t: &[u8]) -> bool {
+    t[0] == b':'
+        && t[1..]
+            .iter()
+            .position(|&c| c == b':')
+            .is_some_and(|k| k > 0 && !t[1..=k].iter().any(|&c| is_blank(c)))
+}
+
+/// `name::target[attributes]`, e.g. `image::` or `include::`.
+fn is_block_macro(t: &[u8]) -> bool {
+    match find(t, 0, b"::") {
+        Some(k) => {
+            k > 0
+                && t[..k]
+                    .iter()
+                    .all(|&c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+                && t[t.len() - 1] == b']'
+        }
+        None => false,
+    }
+}
+
+// ----------------------------------------------------------------------------
+// HTML
+// ----------------------------------------------------------------------------
+
+/// Start offset of every line, for turning offsets into line and column.
+struct Lines(Vec<usize>);
+
+impl Lines {
+    fn new(content: &str) -> Lines {
+        let mut starts = vec![0];
+        starts.extend(
+            content
+                .bytes()
+                .enumerate()
+                .filter(|&(_, c)| c == b'\n')
+                .map(|(i, _)| i + 1),
+        );
+        Lines(starts)
+    }
+
+    /// 1-based line and the offset its line starts at.
+    fn locate(&self, pos: usize) -> (usize, usize) {
+        let line = self.0.partition_point(|&start| start <= pos);
+        (line, self.0[line - 1])
+    }
+}
+
+/// Text in `content[from..to]`, one segment per line.
+fn text_run(
+    out: &mut Segments,
+    content: &str,
+    lines: &Lines,
+    from: usize,
+    to: usize,
+    context: Option<&str>,
+) {
+    let mut start = from;
+    for line_end in content[from..to]
+        .match_indices('\n')
+        .map(|(k, _)| from + k)
+        .chain([to])
+    {
+        let (no, line_start) = lines.locate(start);
+        let line = &content[line_start..];
+        out.span(no, line, start - line_start, line_end - line_start, context);
+        start = line_end + 1;
+    }
+}
+
+fn html(content: &str, out: &mut Segments) {
+    let b = content.as_bytes();
+    let n = b.len();
+    let lines = Lines::new(content);
+    let mut stack: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < n {
+        if b[i] == b'<' && i + 1 < n {
+            let next = b[i + 1];
+            if b[i..].starts_with(b"<!--") {
+                i = find(b, i + 4, b"-->").map_or(n, |k| k + 3);
+                continue;
+            }
+            if next == b'!' || next == b'?' {
+                i = find(b, i, b">").map_or(n, |k| k + 1);
+                continue;
+            }
+            if next == b'/' {
+                let name = tag_name(b, i + 2);
+                if let Some(k) = stack.iter().rposition(|open| *open == name) {
+                    stack.truncate(k);
+                }
+                i = find(b, i, b">").map_or(n, |k| k + 1);
+                continue;
+            }
+            if next.is_ascii_alphabetic() {
+                let name = tag_name(b, i + 1);
+                let (after, self_closing) =
+                    attributes(out, content, &lines, i + 1 + name.len(), &name);
+                i = after;
+                if name == "script" || name == "style" {
+                    let close = format!("</{name}");
+                    i = find_ignore_case(b, i, close.as_bytes()).unwrap_or(n);
+                } else if !self_closing && !VOID_ELEMENTS.contains(&name.as_str()) {
+                    stack.push(name);
+                }
+                continue;
+            }
+        }
+        let to = find(b, i + 1, b"<").unwrap_or(n);
+        text_run(
+            out,
+            content,
+            &lines,
+            i,
+            to,
+            stack.last().map(String::as_str),
+        );
+        i = to;
+    }
+}
+
+fn tag_name(b: &[u8], from: usize) -> String {
+    let len = b[from..]
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || **c == b'-')
+        .count();
+    String::from_utf8_lossy(&b[from..from + len]).to_ascii_lowercase()
+}
+
+/// Scan attributes from `i` to the end of the tag, adding translatable
+/// values. Returns the offset after `>` and whether the tag was `/>`.
+fn attributes(
+    out: &mut Segments,
+    content: &str,
+    lines: &Lines,
+    mut i: usize,
+    tag: &str,
+) -> (usize, bool) {
+    let b = content.as_bytes();
+    let n = b.len();
+    loop {
+        while i < n && (is_blank(b[i]) || b[i] == b'\n') {
+            i += 1;
+        }
+        if i >= n {
+            return (n, false);
+        }
+        match b[i] {
+            b'>' => return (i + 1, false),
+            b'/' if i + 1 < n && b[i + 1] == b'>' => return (i + 2, true),
+            b'/' => {
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        let name_start = i;
+        while i < n && !is_blank(b[i]) && !matches!(b[i], b'\n' | b'=' | b'>' | b'/') {
+            i += 1;
+        }
+        let name = content[name_start..i].to_ascii_lowercase();
+        let mut j = i;
+        while j < n && (is_blank(b[j]) || b[j] == b'\n') {
+            j += 1;
+        }
+        if j >= n || b[j] != b'=' {
+            continue;
+        }
+        j += 1;
+        while j < n && (is_blank(b[j]) || b[j] == b'\n') {
+            j += 1;
+        }
+        let (value_start, value_end, after) = match b.get(j) {
+            Some(&q @ (b'"' | b'\'')) => {
+                let close = b[j + 1..]
+                    .iter()
+                    .position(|&c| c == q)
+                    .map_or(n, |k| j + 1 + k);
+                (j + 1, close, (close + 1).min(n))
+            }
+            _ => {
+                let mut k = j;
+                while k < n && !is_blank(b[k]) && b[k] != b'\n' && b[k] != b'>' {
+                    k += 1;
+                }
+                (j, k, k)
+            }
+        };
+        if TRANSLATABLE_ATTRIBUTES.contains(&name.as_str()) {
+            let context = format!("{tag}@{name}");
+            text_run(out, content, lines, value_start, value_end, Some(&context));
+        }
+        i = after;
+    }
+}
+
+fn find_ignore_case(haystack: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    if from > haystack.len() {
+        return None;
+    }
+    haystack[from..]
+        .windows(needle.len())
+        .position(|w| w.eq_ignore_ascii_case(needle))
+        .map(|i| from + i)
+}
+
+// ----------------------------------------------------------------------------
+// JSON
+// ----------------------------------------------------------------------------
+
+struct Json<'a, 'o> {
+    content: &'a str,
+    b: &'a [u8],
+    pos: usize,
+    lines: Lines,
+    path: Vec<String>,
+    out: &'o mut Segments,
+}
+
+impl<'a, 'o> Json<'a, 'o> {
+    fn new(content: &'a str, out: &'o mut Segments) -> Json<'a, 'o> {
+        Json {
+            content,
+            b: content.as_bytes(),
+            pos: 0,
+            lines: Lines::new(content),
+            path: Vec::new(),
+            out,
+        }
+    }
+
+    fn document(mut self) -> Result<(), PolyglotError> {
+        self.value(0)?;
+        self.ws();
+        if self.pos != self.b.len() {
+            return Err(PolyglotError::ParseFailed);
+        }
+        Ok(())
+    }
+
+    fn ws(&mut self) {
+        while self.pos < self.b.len() && matches!(self.b[self.pos], b' ' | b'\t' | b'\n' | b'\r') {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.b.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), PolyglotError> {
+        if self.peek() != Some(c) {
+            return Err(PolyglotError::ParseFailed);
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<(), PolyglotError> {
+        self.ws();
+        match self.peek().ok_or(PolyglotError::ParseFailed)? {
+            b'{' | b'[' if depth >= MAX_DEPTH => Err(PolyglotError::ParseFailed),
+            b'{' => self.object(depth),
+            b'[' => self.array(depth),
+            b'"' => {
+                let start = self.pos;
+                let text = self.string()?;
+                if !text
+                    .bytes()
+                    .all(|c| matches!(c, b' ' | b'\t' | b'\n' | b'\r'))
+                {
+                    let (line, line_start) = self.lines.locate(start);
+                    let pointer = self.pointer();
+                    self.out
+                        .push(text, line, start - line_start + 1, Some(&pointer));
+                }
+                Ok(())
+            }
+            b't' => self.literal(b"true"),
+            b'f' => self.literal(b"false"),
+            b'n' => self.literal(b"null"),
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => Err(PolyglotError::ParseFailed),
+        }
+    }
+
+    fn pointer(&self) -> String {
+        self.path
+            .iter()
+            .map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1")))
+            .collect()
+    }
+
+    fn object(&mut self, depth: usize) -> Result<(), PolyglotError> {
+        self.pos += 1;
+        self.ws();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            self.ws();
+            if self.peek() != Some(b'"') {
+                return Err(PolyglotError::ParseFailed);
+            }
+            let key = self.string()?;
+            self.ws();
+            self.expect(b':')?;
+            self.path.push(key);
+            self.value(depth + 1)?;
+            self.path.pop();
+            self.ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(PolyglotError::ParseFailed),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<(), PolyglotError> {
+        self.pos += 1;
+        self.ws();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(());
+        }
+        let mut index = 0usize;
+        loop {
+            self.path.push(index.to_string());
+            self.value(depth + 1)?;
+            self.path.pop();
+            self.ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(PolyglotError::ParseFailed),
+            }
+            index += 1;
+        }
+    }
+
+    fn literal(&mut self, word: &[u8]) -> Result<(), PolyglotError> {
+        if !self.b[self.pos..].starts_with(word) {
+            return Err(PolyglotError::ParseFailed);
+        }
+        self.pos += word.len();
+        Ok(())
+    }
+
+    fn digits(&mut self) -> usize {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn number(&mut self) -> Result<(), PolyglotError> {
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                self.digits();
+            }
+            _ => return Err(PolyglotError::ParseFailed),
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if self.digits() == 0 {
+                return Err(PolyglotError::ParseFailed);
+            }
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if self.digits() == 0 {
+                return Err(PolyglotError::ParseFailed);
+            }
+        }
+        Ok(())
+    }
+
+    fn hex4(&mut self) -> Result<u32, PolyglotError> {
+        let digits = self
+            .b
+            .get(self.pos..self.pos + 4)
+            .ok_or(PolyglotError::ParseFailed)?;
+        let mut value = 0;
+        for &c in digits {
+            let d = (c as char).to_digit(16).ok_or(PolyglotError::ParseFailed)?;
+            value = value * 16 + d;
+        }
+        self.pos += 4;
+        Ok(value)
+    }
+
+    /// Decode the string starting at the opening quote under `pos`.
+    fn string(&mut self) -> Result<String, PolyglotError> {
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            let start = self.pos;
+            while self
+                .peek()
+                .is_some_and(|c| c != b'"' && c != b'\\' && c >= 0x20)
+            {
+                self.pos += 1;
+            }
+            text.push_str(&self.content[start..self.pos]);
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escape = self.peek().ok_or(PolyglotError::ParseFailed)?;
+                    self.pos += 1;
+                    text.push(match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(PolyglotError::ParseFailed),
+                    });
+                }
+                _ => return Err(PolyglotError::ParseFailed),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, PolyglotError> {
+        let high = self.hex4()?;
+        let code = match high {
+            0xd800..=0xdbff => {
+                if !self.b[self.pos..].starts_with(b"\\u") {
+                    return Err(PolyglotError::ParseFailed);
+                }
+                self.pos += 2;
+                let low = self.hex4()?;
+                if !(0xdc00..=0xdfff).contains(&low) {
+                    return Err(PolyglotError::ParseFailed);
+                }
+                0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+            }
+            0xdc00..=0xdfff => return Err(PolyglotError::ParseFailed),
+            code => code,
+        };
+        char::from_u32(code).ok_or(PolyglotError::ParseFailed)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// YAML
+// ----------------------------------------------------------------------------
+
+enum Node {
+    Key(String),
+    Item(usize),
+}
+
+#[derive(Default)]
+struct Yaml {
+    /// Open mappings keys and sequence items with their indent.
+    stack: Vec<(usize, Node)>,
+    /// Parent indent and path of the block scalar being read.
+    block: Option<(usize, String)>,
+}
+
+impl Yaml {
+    fn document(mut self, content: &str, out: &mut Segments) {
+        for (no, line) in lines(content) {
+            let (indent, end) = bounds(line);
+            let t = &line.as_bytes()[indent..end];
+            if let Some((parent, path)) = &self.block {
+                if t.is_empty() {
+                    continue;
+                }
+                if indent > *parent {
+                    out.span(no, line, indent, end, Some(path));
+                    continue;
+                }
+                self.block = None;
+            }
+            if t.is_empty()
+                || t[0] == b'#'
+                || t[0] == b'%'
+                || t == b"---"
+                || t == b"..."
+                || t.starts_with(b"--- ")
+            {
+                continue;
+            }
+            self.node(out, no, line, indent, end);
+        }
+    }
+
+    fn path(&self) -> String {
+        let mut path = String::new();
+        for (_, node) in &self.stack {
+            match node {
+                Node::Key(name) if path.is_empty() => path.push_str(name),
+                Node::Key(name) => {
+                    path.push('.');
+                    path.push_str(name);
+                }
+                Node::Item(index) => path.push_str(&format!("[{index}]")),
+            }
+        }
+        path
+    }
+
+    fn node(&mut self, out: &mut Segments, no: usize, line: &str, mut pos: usize, end: usize) {
+        let b = line.as_bytes();
+        loop {
+            let rest = &b[pos..end];
+            if rest == b"-" || rest.starts_with(b"- ") {
+                let mut index = 0;
+                while self.stack.last().is_some_and(|(indent, _)| *indent > pos) {
+                    self.stack.pop();
+                }
+                if let Some((indent, Node::Item(previous))) = self.stack.last() {
+                    if *indent == pos {
+                        index = previous + 1;
+                        self.stack.pop();
+                    }
+                }
+                self.stack.push((pos, Node::Item(index)));
+                let item = pos;
+                pos += 1;
+                while pos < end && is_blank(b[pos]) {
+                    pos += 1;
+                }
+                if pos == end {
+                    return;
+                }
+                if !b[pos..end].starts_with(b"- ") && b[pos..end] != *b"-" {
+                    if let Some((key, value)) = mapping_key(line, pos, end) {
+                        self.key(out, no, line, pos, key, value, end);
+                    } else {
+                        self.scalar(out, no, line, pos, end, item);
+                    }
+                    return;
+                }
+                continue;
+            }
+            if let Some((key, value)) = mapping_key(line, pos, end) {
+                self.key(out, no, line, pos, key, value, end);
+            } else {
+                while self.stack.last().is_some_and(|(indent, _)| *indent >= pos) {
+                    self.stack.pop();
+                }
+                self.scalar(out, no, line, pos, end, pos);
+            }
+            return;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn key(
+        &mut self,
+        out: &mut Segments,
+        no: usize,
+        line: &str,
+        pos: usize,
+        key: String,
+        value: usize,
+        end: usize,
+    ) {
+        while self.stack.last().is_some_and(|(indent, _)| *indent >= pos) {
+            self.stack.pop();
+        }
+        self.stack.push((pos, Node::Key(key)));
+        self.scalar(out, no, line, value, end, pos);
+    }
+
+    /// The scalar in `line[pos..end]`, owned by a node at `owner` indent.
+    fn scalar(
+        &mut self,
+        out: &mut Segments,
+        no: usize,
+        line: &str,
+        mut pos: usize,
+        end: usize,
+        owner: usize,
+    ) {
+        let b = line.as_bytes();
+        while pos < end && is_blank(b[pos]) {
+            pos += 1;
+        }
+        // Anchors and tags before the value.
+        while pos < end && matches!(b[pos], b'&' | b'!') {
+            while pos < end && !is_blank(b[pos]) {
+                pos += 1;
+            }
+            while pos < end && is_blank(b[pos]) {
+                pos += 1;
+            }
+        }
+        if pos == end {
+            return;
+        }
+        match b[pos] {
+            b'*' | b'[' | b'{' | b'#' => {}
+            b'|' | b'>' => self.block = Some((owner, self.path())),
+            b'"' => {
+                if let Some(text) = double_quoted(&line[pos + 1..end]) {
+                    if !text.trim_matches([' ', '\t', '\n', '\r']).is_empty() {
+                        out.push(text, no, pos + 1, Some(&self.path()));
+                    }
+                }
+            }
+            b'\'' => {
+                if let Some(text) = single_quoted(&line[pos + 1..end]) {
+                    if !text.trim_matches([' ', '\t', '\n', '\r']).is_empty() {
+                        out.push(text, no, pos + 1, Some(&self.path()));
+                    }
+                }
+            }
+            _ => {
+                let mut stop = end;
+                if let Some(k) = (pos + 1..end).find(|&k| b[k] == b'#' && is_blank(b[k - 1])) {
+                    stop = k;
+                }
+                let (s, e) = bounds(&line[pos..stop]);
+                let plain = &line[pos + s..pos + e];
+                if !is_yaml_non_string(plain) {
+                    out.span(no, line, pos, stop, Some(&self.path()));
+                }
+            }
+        }
+    }
+}
+
+/// `key:` at `pos`: the unquoted key and the offset after the colon.
+fn mapping_key(line: &str, pos: usize, end: usize) -> Option<(String, usize)> {
+    let b = line.as_bytes();
+    let colon_ok = |k: usize| k < end && b[k] == b':' && (k + 1 == end || is_blank(b[k + 1]));
+    match b[pos] {
+        b'"' | b'\'' => {
+            let close = closing_quote(&b[..end], pos)?;
+            colon_ok(close + 1).then(|| (line[pos + 1..close].to_owned(), close + 2))
+        }
+        b'[' | b'{' | b'#' | b'&' | b'*' | b'!' | b'|' | b'>' => None,
+        _ => {
+            let colon = (pos..end).find(|&k| colon_ok(k))?;
+            if (pos + 1..colon).any(|k| b[k] == b'#' && is_blank(b[k - 1])) {
+                return None;
+            }
+            let (s, e) = bounds(&line[pos..colon]);
+            (s < e).then(|| (line[pos + s..pos + e].to_owned(), colon + 1))
+        }
+    }
+}
+
+/// The quote closing the scalar opened at `pos`, past `\"` in double quotes and
+/// `''` in single quotes.
+fn closing_quote(b: &[u8], pos: usize) -> Option<usize> {
+    let quote = b[pos];
+    let mut k = pos + 1;
+    while k < b.len() {
+        let escaped = match quote {
+            b'"' => b[k] == b'\\',
+            _ => b[k] == quote && b.get(k + 1) == Some(&quote),
+        };
+        if escaped {
+            k += 2;
+        } else if b[k] == quote {
+            return Some(k);
+        } else {
+            k += 1;
+        }
+    }
+    None
+}
+
+/// Body of a double-quoted scalar after its opening quote, decoded up to the
+/// closing quote; `None` if it is not closed on this line.
+fn double_quoted(s: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(text),
+            '\\' => match chars.next()? {
+                'n' => text.push('\n'),
+                't' => text.push('\t'),
+                'r' => text.push('\r'),
+                '0' => text.push('\0'),
+                'u' => {
+                    // Four hex digits, or U+FFFD without consuming them.
+                    let rest = chars.as_str();
+                    let code = rest
+                        .get(..4)
+                        .filter(|hex| hex.bytes().all(|c| c.is_ascii_hexdigit()))
+                        .map(|hex| {
+                            chars = rest[4..].chars();
+                            u32::from_str_radix(hex, 16).expect("four hex digits")
+                        });
+                    text.push(code.and_then(char::from_u32).unwrap_or('\u{fffd}'));
+                }
+                other => text.push(other),
+            },
+            c => text.push(c),
+        }
+    }
+    None
+}
+
+/// Body of a single-quoted scalar after its opening quote; `''` is a quote.
+fn single_quoted(s: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            if chars.peek() == Some(&'\'') {
+                chars.next();
+                text.push('\'');
+                continue;
+            }
+            return Some(text);
+        }
+        text.push(c);
+    }
+    None
+}
+
+fn is_yaml_non_string(s: &str) -> bool {
+    YAML_NON_STRINGS
+        .iter()
+        .any(|word| s.eq_ignore_ascii_case(word))
+        || is_yaml_number(s.as_bytes())
+}
+
+fn is_yaml_number(s: &[u8]) -> bool {
+    let digits_in = |s: &[u8], radix: u32| {
+        !s.is_empty() && s.iter().all(|&c| c == b'_' || (c as char).is_digit(radix))
+    };
+    if let Some(hex) = s.strip_prefix(b"0x") {
+        return digits_in(hex, 16);
+    }
+    if let Some(oct) = s.strip_prefix(b"0o") {
+        return digits_in(oct, 8);
+    }
+    let mut i = usize::from(matches!(s.first(), Some(b'+' | b'-')));
+    let mut digits = 0;
+    while i < s.len() && (s[i].is_ascii_digit() || s[i] == b'_') {
+        digits += usize::from(s[i] != b'_');
+        i += 1;
+    }
+    if i < s.len() && s[i] == b'.' {
+        i += 1;
+        while i < s.len() && s[i].is_ascii_digit() {
+            digits += 1;
+            i += 1;
+        }
+    }
+    if digits == 0 {
+        return false;
+    }
+    if i < s.len() && matches!(s[i], b'e' | b'E') {
+        i += 1;
+        if i < s.len() && matches!(s[i], b'+' | b'-') {
+            i += 1;
+        }
+        let exp = s[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+        if exp == 0 {
+            return false;
+        }
+        i += exp;
+    }
+    i == s.len()
+}
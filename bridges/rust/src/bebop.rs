@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Status codes and types of the Bebop-V bridge
+//! (`bridges/bebop-v/include/bebop_v_ffi.h`).
+//!
+//! Both the Zig core and the Rust implementation in
+//! `bridges/bebop-v/implementations/rust` return these from their
+//! `bebop_*` exports. Neither is linked into this crate; load one with the
+//! `dlopen` feature to call it.
+
+use std::error::Error;
+use std::ffi::c_char;
+use std::fmt;
+
+pub const BEBOP_OK: i32 = 0;
+pub const BEBOP_ERR_NULL_CTX: i32 = -1;
+pub const BEBOP_ERR_NULL_DATA: i32 = -2;
+pub const BEBOP_ERR_INVALID_LENGTH: i32 = -3;
+pub const BEBOP_ERR_DECODE_FAILED: i32 = -4;
+pub const BEBOP_ERR_ENCODE_FAILED: i32 = -5;
+pub const BEBOP_ERR_BUFFER_TOO_SMALL: i32 = -6;
+pub const BEBOP_ERR_NOT_IMPLEMENTED: i32 = -99;
+
+/// A non-zero `BEBOP_ERR_*` code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BebopError {
+    NullContext,
+    NullData,
+    InvalidLength,
+    DecodeFailed,
+    EncodeFailed,
+    BufferTooSmall,
+    NotImplemented,
+    /// A code this crate does not know about.
+    Foreign(i32),
+}
+
+impl BebopError {
+    /// Map a non-zero `BEBOP_ERR_*` code.
+    pub fn from_code(code: i32) -> BebopError {
+        match code {
+            BEBOP_ERR_NULL_CTX => BebopError::NullContext,
+            BEBOP_ERR_NULL_DATA => BebopError::NullData,
+            BEBOP_ERR_INVALID_LENGTH => BebopError::InvalidLength,
+            BEBOP_ERR_DECODE_FAILED => BebopError::DecodeFailed,
+            BEBOP_ERR_ENCODE_FAILED => BebopError::EncodeFailed,
+            BEBOP_ERR_BUFFER_TOO_SMALL => BebopError::BufferTooSmall,
+            BEBOP_ERR_NOT_IMPLEMENTED => BebopError::NotImplemented,
+            other => BebopError::Foreign(other),
+        }
+    }
+
+    /// The `BEBOP_ERR_*` code for this error.
+    pub fn code(self) -> i32 {
+        match self {
+            BebopError::NullContext => BEBOP_ERR_NULL_CTX,
+            BebopError::NullData => BEBOP_ERR_NULL_DATA,
+            BebopError::InvalidLength => BEBOP_ERR_INVALID_LENGTH,
+            BebopError::DecodeFailed => BEBOP_ERR_DECODE_FAILED,
+            BebopError::EncodeFailed => BEBOP_ERR_ENCODE_FAILED,
+            BebopError::BufferTooSmall => BEBOP_ERR_BUFFER_TOO_SMALL,
+            BebopError::NotImplemented => BEBOP_ERR_NOT_IMPLEMENTED,
+            BebopError::Foreign(code) => code,
+        }
+    }
+}
+
+/// `Ok(())` for `BEBOP_OK`, the mapped error otherwise.
+pub fn check(code: i32) -> Result<(), BebopError> {
+    match code {
+        BEBOP_OK => Ok(()),
+        other => Err(BebopError::from_code(other)),
+    }
+}
+
+impl fmt::Display for BebopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BebopError::NullContext => f.write_str("null Bebop context"),
+            BebopError::NullData => f.write_str("null data pointer"),
+            BebopError::InvalidLength => f.write_str("invalid length"),
+            BebopError::DecodeFailed => f.write_str("decode failed"),
+            BebopError::EncodeFailed => f.write_str("encode failed"),
+            BebopError::BufferTooSmall => f.write_str("buffer too small"),
+            BebopError::NotImplemented => f.write_str("not implemented by the Bebop core"),
+            BebopError::Foreign(code) => write!(f, "Bebop core returned error code {code}"),
+        }
+    }
+}
+
+impl Error for BebopError {}
+
+/// Opaque `BebopCtx`.
+#[repr(C)]
+pub struct RawBebopCtx {
+    _private: [u8; 0],
+}
+
+/// Byte slice passed across FFI. Data is not NUL-terminated.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VBytes {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+/// Flat representation of a `SensorReading`. Decoded readings borrow their
+/// bytes from the context that decoded them.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VSensorReading {
+    pub timestamp: u64,
+    pub sensor_id: VBytes,
+    pub sensor_type: u16,
+    pub value: f64,
+    pub unit: VBytes,
+    pub location: VBytes,
+    pub metadata_count: usize,
+    pub metadata_keys: *mut VBytes,
+    pub metadata_values: *mut VBytes,
+    pub error_code: i32,
+    /// NUL-terminated; owned by the context.
+    pub error_message: *const c_char,
+}
+
+pub type BebopReadingCallback = unsafe extern "C" fn(reading: *const VSensorReading);
+pub type BebopErrorCallback = unsafe extern "C" fn(code: i32, message: *const c_char);
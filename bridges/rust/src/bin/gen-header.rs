@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Regenerates `include/rust_zig_ffi.h` and `include/rust_zig_ffi.zig` from
+//! the crate's sources; see `rust_zig_ffi::header`.
+//!
+//! ```text
+//! cargo run --features gen-header --bin gen-header [-- HEADER [ZIG]]
+//! ```
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use rust_zig_ffi::header::Exports;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut args = env::args_os().skip(1).map(PathBuf::from);
+    let header = args
+        .next()
+        .unwrap_or_else(|| root.join("include/rust_zig_ffi.h"));
+    let zig = args
+        .next()
+        .unwrap_or_else(|| root.join("include/rust_zig_ffi.zig"));
+
+    let exports = Exports::scan(root.join("src"))?;
+    fs::write(&header, exports.c_header())?;
+    println!("Generated C header: {}", header.display());
+    fs::write(&zig, exports.zig_externs())?;
+    println!("Generated Zig declarations: {}", zig.display());
+    Ok(())
+}
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Generates Rust bindings from Idris 2 ABI definitions; see
+//! `rust_zig_ffi::idris::bindgen`.
+//!
+//! ```text
+//! cargo run --features idris-bindgen --bin idris-bindgen -- \
+//!     [--project NAME] OUTPUT FILE.idr...
+//! ```
+//!
+//! `--project` fills in the `{{project}}` placeholders of the bridges' ABI
+//! templates.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use rust_zig_ffi::idris::bindgen::{fill_template, BindgenError, Bindings};
+
+const USAGE: &str = "usage: idris-bindgen [--project NAME] OUTPUT FILE.idr...";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let project = match args.iter().position(|a| a == "--project") {
+        Some(i) if i + 1 < args.len() => {
+            let project = args.remove(i + 1);
+            args.remove(i);
+            Some(project)
+        }
+        Some(_) => return Err(USAGE.into()),
+        None => None,
+    };
+    let [output, inputs @ ..] = args.as_slice() else {
+        return Err(USAGE.into());
+    };
+    if inputs.is_empty() {
+        return Err(USAGE.into());
+    }
+
+    let mut sources = Vec::new();
+    for input in inputs {
+        let path = PathBuf::from(input);
+        let source = fs::read_to_string(&path).map_err(|source| BindgenError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        let source = match &project {
+            Some(project) => fill_template(&source, project),
+            None => source,
+        };
+        sources.push((path, source));
+    }
+    let bindings = Bindings::from_sources(sources.iter().map(|(p, s)| (p.as_path(), s.as_str())))?;
+
+    let output = Path::new(output);
+    fs::write(output, bindings.to_rust())?;
+    println!("Generated Rust bindings: {}", output.display());
+    Ok(())
+}
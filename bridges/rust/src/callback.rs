@@ -0,0 +1,256 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Zig → Rust callbacks.
+//!
+//! [`register`] stores a Rust closure under a [`CallbackHandle`] and hands
+//! the Zig core a single `extern "C"` trampoline with the handle as its
+//! opaque context. When Zig calls back, the trampoline looks the handle up
+//! and runs the closure. Because the context is a handle rather than a
+//! pointer, a Zig caller holding a stale context after [`unregister`] reaches
+//! nothing instead of freed memory.
+//!
+//! Zig code can deliver data either through the core's `rzf_emit`, which
+//! calls each registered trampoline, or by calling the exported
+//! [`rust_callback`](crate::rust_callback), which broadcasts to every
+//! registered closure.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::ffi::c_void;
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::backend::active as backend;
+
+/// A closure receiving bytes from Zig.
+pub type Callback = Box<dyn FnMut(&[u8]) + Send>;
+
+/// C signature of the trampoline handed to Zig.
+pub type RawCallback = unsafe extern "C" fn(context: *mut c_void, data: *const u8, len: usize);
+
+/// Identifies a registered closure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CallbackHandle(usize);
+
+impl CallbackHandle {
+    /// The opaque context pointer Zig passes back to [`trampoline`].
+    pub fn context(self) -> *mut c_void {
+        self.0 as *mut c_void
+    }
+
+    fn from_context(context: *mut c_void) -> CallbackHandle {
+        CallbackHandle(context as usize)
+    }
+}
+
+/// Errors returned by [`register`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallbackError {
+    /// The Zig core has no free callback slots.
+    RegistryFull,
+    /// The Zig core returned a code this crate does not know about.
+    Foreign(i32),
+}
+
+impl fmt::Display for CallbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallbackError::RegistryFull => f.write_str("Zig callback registry is full"),
+            CallbackError::Foreign(code) => write!(f, "Zig core returned error code {code}"),
+        }
+    }
+}
+
+impl Error for CallbackError {}
+
+type Slot = Arc<Mutex<Callback>>;
+
+struct Registry {
+    next: usize,
+    callbacks: BTreeMap<usize, Slot>,
+}
+
+// Handles start at 1 so a context pointer is never null.
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    next: 1,
+    callbacks: BTreeMap::new(),
+});
+
+fn registry() -> std::sync::MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Register `callback` and subscribe it to the Zig core.
+pub fn register<F>(callback: F) -> Result<CallbackHandle, CallbackError>
+where
+    F: FnMut(&[u8]) + Send + 'static,
+{
+    let handle = {
+        let mut registry = registry();
+        let handle = CallbackHandle(registry.next);
+        registry.next += 1;
+        let callback: Callback = Box::new(callback);
+        registry
+            .callbacks
+            .insert(handle.0, Arc::new(Mutex::new(callback)));
+        handle
+    };
+    if let Err(err) = backend::register_callback(trampoline, handle.context()) {
+        registry().callbacks.remove(&handle.0);
+        return Err(err);
+    }
+    Ok(handle)
+}
+
+/// Unsubscribe and drop the closure registered under `handle`.
+///
+/// Returns `false` if the handle was not registered. No new invocation starts
+/// once this returns; one already running on another thread completes.
+pub fn unregister(handle: CallbackHandle) -> bool {
+    backend::unregister_callback(handle.context());
+    let removed = registry().callbacks.remove(&handle.0);
+    removed.is_some()
+}
+
+/// Run the closure registered under `handle` with `data`.
+///
+/// Returns `false` if the handle is not registered. A closure must not
+/// dispatch to itself.
+pub fn dispatch(handle: CallbackHandle, data: &[u8]) -> bool {
+    let slot = registry().callbacks.get(&handle.0).cloned();
+    match slot {
+        Some(slot) => {
+            invoke(&slot, data);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Run every registered closure with `data`, in registration order.
+/// Returns how many ran.
+pub fn broadcast(data: &[u8]) -> usize {
+    let slots: Vec<Slot> = registry().callbacks.values().cloned().collect();
+    for slot in &slots {
+        invoke(slot, data);
+    }
+    slots.len()
+}
+
+fn invoke(slot: &Slot, data: &[u8]) {
+    let mut callback = slot.lock().unwrap_or_else(PoisonError::into_inner);
+    callback(data);
+}
+
+/// Trampoline handed to Zig; `context` is a [`CallbackHandle::context`].
+///
+/// A panicking closure is contained here; see [`crate::guard`].
+///
+/// # Safety
+///
+/// `data` must be null or valid for reads of `len` bytes.
+pub unsafe extern "C" fn trampoline(context: *mut c_void, data: *const u8, len: usize) {
+    crate::guard::contain(|| {
+        dispatch(CallbackHandle::from_context(context), unsafe {
+            bytes(data, len)
+        });
+    })
+}
+
+/// View a foreign `(ptr, len)` pair as a slice, tolerating null for empty.
+///
+/// # Safety
+///
+/// `data` must be null or valid for `len` bytes for the returned lifetime.
+pub(crate) unsafe fn bytes<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if data.is_null() || len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(data, len)
+    }
+}
+
+#[cfg(all(test, any(zig_linked, feature = "pure-rust")))]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    // The registry is process-wide and tests run in parallel, so each test
+    // observes only its own closures through channels.
+
+    #[test]
+    fn trampoline_routes_to_closure() {
+        let (tx, rx) = mpsc::channel();
+        let handle = register(move |data| tx.send(data.to_vec()).unwrap()).unwrap();
+        unsafe { trampoline(handle.context(), b"event".as_ptr(), 5) };
+        assert_eq!(rx.try_recv().unwrap(), b"event");
+        assert!(unregister(handle));
+    }
+
+    #[test]
+    fn stale_context_after_unregister_is_ignored() {
+        let (tx, rx) = mpsc::channel::<()>();
+        let handle = register(move |_| tx.send(()).unwrap()).unwrap();
+        assert!(unregister(handle));
+        assert!(!unregister(handle));
+        unsafe { trampoline(handle.context(), std::ptr::null(), 0) };
+        assert!(!dispatch(handle, b"late"));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn broadcast_reaches_every_closure() {
+        let (tx, rx) = mpsc::channel();
+        let tx2 = tx.clone();
+        let a = register(move |d| tx.send(("a", d.to_vec())).unwrap()).unwrap();
+        let b = register(move |d| tx2.send(("b", d.to_vec())).unwrap()).unwrap();
+        assert!(broadcast(b"x") >= 2);
+        let mut got: Vec<_> = rx.try_iter().collect();
+        got.sort();
+        assert_eq!(got, [("a", b"x".to_vec()), ("b", b"x".to_vec())]);
+        unregister(a);
+        unregister(b);
+    }
+
+    #[test]
+    fn closure_can_unregister_itself() {
+        let (tx, rx) = mpsc::channel();
+        let handle = Arc::new(Mutex::new(None::<CallbackHandle>));
+        let own = Arc::clone(&handle);
+        let h = register(move |_| {
+            let h = own.lock().unwrap().unwrap();
+            tx.send(unregister(h)).unwrap();
+        })
+        .unwrap();
+        *handle.lock().unwrap() = Some(h);
+        assert!(dispatch(h, b""));
+        assert!(rx.try_recv().unwrap());
+        assert!(!dispatch(h, b""));
+    }
+
+    #[test]
+    fn panicking_closure_does_not_unwind_into_caller() {
+        // Other tests broadcast concurrently; only panic on our own input.
+        let handle = register(|data| {
+            if data == b"boom" {
+                panic!("closure failed");
+            }
+        })
+        .unwrap();
+        unsafe { trampoline(handle.context(), b"boom".as_ptr(), 4) };
+        assert_eq!(
+            crate::guard::take_last_panic().as_deref(),
+            Some("closure failed")
+        );
+        unregister(handle);
+    }
+
+    #[cfg(all(zig_linked, not(feature = "pure-rust")))]
+    #[test]
+    fn zig_emit_reaches_closure() {
+        let (tx, rx) = mpsc::channel();
+        let handle = register(move |data| tx.send(data.to_vec()).unwrap()).unwrap();
+        crate::backend::ffi::emit(b"from zig");
+        assert!(rx.try_iter().any(|d| d == b"from zig"));
+        unregister(handle);
+    }
+}
@@ -0,0 +1,394 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Bridge libraries opened at run time (feature `dlopen`).
+//!
+//! [`Library::open`] loads a shared library by path. [`Library::hkdf`],
+//! [`Library::szf`] and [`Library::bebop`] resolve every symbol of one bridge
+//! into a table of typed function pointers and check the bridge's ABI version
+//! against the one this crate was built for, so a missing symbol or a stale
+//! library is an error at load time instead of a link failure or a crash.
+//!
+//! Tables keep their library loaded. It is unloaded when the [`Library`] and
+//! every table taken from it are dropped, so several libraries, or several
+//! copies of one, can be open side by side. That is how a host picks the Zig
+//! or the Rust Bebop-V implementation from its configuration:
+//!
+//! ```no_run
+//! use rust_zig_ffi::dlopen::Library;
+//!
+//! let path = std::env::var("BEBOP_LIBRARY").unwrap_or("libbebop_v_ffi.so".into());
+//! // Safety: the library is a Bebop-V bridge whose initialisers are sound.
+//! let bebop = unsafe { Library::open(&path) }?.bebop()?;
+//! let ctx = unsafe { (bebop.bebop_ctx_new)() };
+//! // ... decode with bebop.bebop_decode_sensor_reading ...
+//! unsafe { (bebop.bebop_ctx_free)(ctx) };
+//! # Ok::<(), rust_zig_ffi::dlopen::LoadError>(())
+//! ```
+//!
+//! This is independent of the crate's own linkage; build with `pure-rust` as
+//! well to link no Zig code at all.
+
+use std::error::Error;
+use std::ffi::c_char;
+use std::ffi::c_void;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::abi::{self, AbiError, AbiVersion};
+use crate::bebop::{BebopErrorCallback, BebopReadingCallback, RawBebopCtx, VSensorReading};
+use crate::secret::{Password, SecretKey};
+use crate::szf::{RawContext, SzfBytes, SzfProgressCallback, SzfResultCallback};
+
+/// A loaded shared library.
+#[derive(Debug, Clone)]
+pub struct Library {
+    inner: Arc<Loaded>,
+}
+
+#[derive(Debug)]
+struct Loaded {
+    path: PathBuf,
+    library: libloading::Library,
+}
+
+impl Library {
+    /// Load the library at `path`.
+    ///
+    /// # Safety
+    ///
+    /// Loading runs the library's initialisers, and unloading its
+    /// finalisers; both must be sound to run at this point.
+    pub unsafe fn open(path: impl AsRef<Path>) -> Result<Library, LoadError> {
+        let path = path.as_ref();
+        match libloading::Library::new(path) {
+            Ok(library) => Ok(Library {
+                inner: Arc::new(Loaded {
+                    path: path.to_owned(),
+                    library,
+                }),
+            }),
+            Err(source) => Err(LoadError::Open {
+                path: path.to_owned(),
+                source,
+            }),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+
+    /// The HKDF exports of a Rust bridge core (`zig-lib/src/lib.zig`),
+    /// checked against [`abi::RZF`].
+    pub fn hkdf(&self) -> Result<HkdfApi, LoadError> {
+        let api = HkdfApi::load(self)?;
+        self.check("rzf", abi::RZF, unsafe { (api.rzf_version)() })?;
+        Ok(api)
+    }
+
+    /// The `szf_*` context API, checked against [`abi::SZF`].
+    pub fn szf(&self) -> Result<SzfApi, LoadError> {
+        let api = SzfApi::load(self)?;
+        self.check("szf", abi::SZF, unsafe { (api.szf_version)() })?;
+        Ok(api)
+    }
+
+    /// The `bebop_*` API of either Bebop-V implementation, checked against
+    /// [`abi::BEBOP`].
+    pub fn bebop(&self) -> Result<BebopApi, LoadError> {
+        let api = BebopApi::load(self)?;
+        self.check("bebop", abi::BEBOP, unsafe { (api.bebop_version)() })?;
+        Ok(api)
+    }
+
+    fn check(
+        &self,
+        library: &'static str,
+        expected: AbiVersion,
+        found: u32,
+    ) -> Result<(), LoadError> {
+        abi::check(library, expected, AbiVersion::from_packed(found)).map_err(|source| {
+            LoadError::Abi {
+                path: self.inner.path.clone(),
+                source,
+            }
+        })
+    }
+
+    /// Resolve `symbol`, which must be NUL-terminated, as a `T`.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the type of the symbol.
+    unsafe fn symbol<T: Copy>(&self, symbol: &'static str) -> Result<T, LoadError> {
+        match self.inner.library.get::<T>(symbol.as_bytes()) {
+            Ok(value) => Ok(*value),
+            Err(source) => Err(LoadError::MissingSymbol {
+                path: self.inner.path.clone(),
+                symbol: symbol.trim_end_matches('\0'),
+                source,
+            }),
+        }
+    }
+}
+
+/// A struct of function pointers named after the symbols they are resolved
+/// from, plus the library that keeps them valid.
+macro_rules! function_table {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $($symbol:ident: $ty:ty,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone)]
+        pub struct $name {
+            $(pub $symbol: $ty,)*
+            _library: Arc<Loaded>,
+        }
+
+        impl $name {
+            fn load(library: &Library) -> Result<$name, LoadError> {
+                unsafe {
+                    Ok($name {
+                        $($symbol: library.symbol(concat!(stringify!($symbol), "\0"))?,)*
+                        _library: Arc::clone(&library.inner),
+                    })
+                }
+            }
+        }
+    };
+}
+
+function_table! {
+    /// `hkdf_*` from a Rust bridge core; see `zig-lib/src/lib.zig` for the
+    /// contracts.
+    pub struct HkdfApi {
+        rzf_version: unsafe extern "C" fn() -> u32,
+        hkdf_derive: unsafe extern "C" fn(
+            password: *const u8,
+            password_len: usize,
+            salt: *const u8,
+            salt_len: usize,
+            key: *mut u8,
+        ),
+        hkdf_extract: unsafe extern "C" fn(
+            algorithm: u32,
+            salt: *const u8,
+            salt_len: usize,
+            ikm: *const u8,
+            ikm_len: usize,
+            prk: *mut u8,
+        ) -> i32,
+        hkdf_expand: unsafe extern "C" fn(
+            algorithm: u32,
+            prk: *const u8,
+            prk_len: usize,
+            info: *const u8,
+            info_len: usize,
+            okm: *mut u8,
+            okm_len: usize,
+        ) -> i32,
+    }
+}
+
+impl HkdfApi {
+    /// [`derive_key`](crate::derive_key) through this library.
+    pub fn derive(&self, password: &Password, salt: &[u8]) -> SecretKey {
+        let password = password.expose_secret();
+        let mut key = SecretKey::zeroed(64);
+        unsafe {
+            (self.hkdf_derive)(
+                password.as_ptr(),
+                password.len(),
+                salt.as_ptr(),
+                salt.len(),
+                key.as_mut_bytes().as_mut_ptr(),
+            )
+        }
+        key
+    }
+}
+
+function_table! {
+    /// The `szf_*` context API; see [`crate::szf`].
+    pub struct SzfApi {
+        szf_version: unsafe extern "C" fn() -> u32,
+        szf_context_new: unsafe extern "C" fn() -> *mut RawContext,
+        szf_context_free: unsafe extern "C" fn(ctx: *mut RawContext),
+        szf_context_reset: unsafe extern "C" fn(ctx: *mut RawContext),
+        szf_context_get_error: unsafe extern "C" fn(ctx: *mut RawContext) -> *const c_char,
+        szf_process_data: unsafe extern "C" fn(
+            ctx: *mut RawContext,
+            input: SzfBytes,
+            progress_cb: Option<SzfProgressCallback>,
+            progress_ctx: *mut c_void,
+            result_cb: Option<SzfResultCallback>,
+            result_ctx: *mut c_void,
+        ) -> i32,
+        szf_transform_data: unsafe extern "C" fn(
+            ctx: *mut RawContext,
+            input: SzfBytes,
+            out: *mut SzfBytes,
+        ) -> i32,
+    }
+}
+
+function_table! {
+    /// The `bebop_*` API of `bridges/bebop-v/include/bebop_v_ffi.h`.
+    pub struct BebopApi {
+        bebop_version: unsafe extern "C" fn() -> u32,
+        bebop_ctx_new: unsafe extern "C" fn() -> *mut RawBebopCtx,
+        bebop_ctx_free: unsafe extern "C" fn(ctx: *mut RawBebopCtx),
+        bebop_ctx_reset: unsafe extern "C" fn(ctx: *mut RawBebopCtx),
+        bebop_decode_sensor_reading: unsafe extern "C" fn(
+            ctx: *mut RawBebopCtx,
+            data: *const u8,
+            len: usize,
+            out: *mut VSensorReading,
+        ) -> i32,
+        bebop_free_sensor_reading: unsafe extern "C" fn(
+            ctx: *mut RawBebopCtx,
+            reading: *mut VSensorReading,
+        ),
+        bebop_encode_batch_readings: unsafe extern "C" fn(
+            ctx: *mut RawBebopCtx,
+            readings: *const VSensorReading,
+            count: usize,
+            out_buf: *mut u8,
+            out_len: usize,
+        ) -> usize,
+        bebop_register_reading_callback: unsafe extern "C" fn(
+            callback: Option<BebopReadingCallback>,
+        ),
+        bebop_register_error_callback: unsafe extern "C" fn(callback: Option<BebopErrorCallback>),
+        bebop_invoke_reading_callback: unsafe extern "C" fn(reading: *const VSensorReading),
+        bebop_invoke_error_callback: unsafe extern "C" fn(code: i32, message: *const c_char),
+    }
+}
+
+/// Why a library or one of its function tables could not be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The dynamic loader refused the file.
+    Open {
+        path: PathBuf,
+        source: libloading::Error,
+    },
+    /// The library does not export `symbol`.
+    MissingSymbol {
+        path: PathBuf,
+        symbol: &'static str,
+        source: libloading::Error,
+    },
+    /// The library's ABI version is incompatible.
+    Abi { path: PathBuf, source: AbiError },
+}
+
+impl LoadError {
+    pub fn path(&self) -> &Path {
+        match self {
+            LoadError::Open { path, .. }
+            | LoadError::MissingSymbol { path, .. }
+            | LoadError::Abi { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Open { path, .. } => write!(f, "cannot load {}", path.display()),
+            LoadError::MissingSymbol { path, symbol, .. } => {
+                write!(f, "{} does not export `{symbol}`", path.display())
+            }
+            LoadError::Abi { path, source } => write!(f, "{}: {source}", path.display()),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Open { source, .. } | LoadError::MissingSymbol { source, .. } => {
+                Some(source)
+            }
+            LoadError::Abi { source, .. } => Some(source),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_files_fail_to_open() {
+        let err = unsafe { Library::open("/nonexistent/librust_zig_ffi.so") }.unwrap_err();
+        assert!(matches!(err, LoadError::Open { .. }));
+        assert_eq!(err.path(), Path::new("/nonexistent/librust_zig_ffi.so"));
+        assert_eq!(
+            err.to_string(),
+            "cannot load /nonexistent/librust_zig_ffi.so"
+        );
+        assert!(err.source().is_some());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn missing_symbols_are_named() {
+        let libc = unsafe { Library::open("libc.so.6") }.unwrap();
+        let err = libc.bebop().unwrap_err();
+        assert!(matches!(
+            err,
+            LoadError::MissingSymbol {
+                symbol: "bebop_version",
+                ..
+            }
+        ));
+        assert_eq!(err.to_string(), "libc.so.6 does not export `bebop_version`");
+        assert!(matches!(
+            libc.szf().unwrap_err(),
+            LoadError::MissingSymbol {
+                symbol: "szf_version",
+                ..
+            }
+        ));
+    }
+
+    /// The core this crate was built with, as a shared library.
+    #[cfg(all(zig_linked, feature = "dynamic", target_os = "linux"))]
+    fn core() -> Library {
+        unsafe { Library::open(concat!(env!("OUT_DIR"), "/librust_zig_ffi.so")) }.unwrap()
+    }
+
+    #[cfg(all(zig_linked, feature = "dynamic", target_os = "linux"))]
+    #[test]
+    fn tables_outlive_their_library() {
+        let hkdf = core().hkdf().unwrap();
+        let password = Password::from("password");
+        assert_eq!(
+            hkdf.derive(&password, b"salt"),
+            crate::derive_key(&password, b"salt")
+        );
+    }
+
+    #[cfg(all(zig_linked, feature = "dynamic", target_os = "linux"))]
+    #[test]
+    fn copies_coexist() {
+        let (a, b) = (core(), core());
+        let szf = a.szf().unwrap();
+        assert_eq!(
+            AbiVersion::from_packed(unsafe { (szf.szf_version)() }),
+            abi::SZF
+        );
+        let ctx = unsafe { (szf.szf_context_new)() };
+        assert!(!ctx.is_null());
+        drop(a);
+        let hkdf = b.hkdf().unwrap();
+        assert!(b.bebop().is_err());
+        assert_ne!(hkdf.derive(&Password::from(""), b""), SecretKey::zeroed(64));
+        unsafe { (szf.szf_context_free)(ctx) };
+    }
+}
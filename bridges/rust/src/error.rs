@@ -0,0 +1,318 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! One error type for every foreign bridge.
+//!
+//! The Zig cores report failure in their own vocabulary: the Idris 2 bridge
+//! with an `ErrorCode`, a message and an optional context
+//! (`bridges/idris2/src/errors.zig`), the Swift core with `SZF_ERR_*`, the
+//! Bebop-V core with `BEBOP_ERR_*` and the polyglot extractor with
+//! `POLYGLOT_ERR_*` return codes. [`BridgeError`] holds any of them with the
+//! foreign message, and keeps context as a chain of errors reachable through
+//! [`Error::source`], newest first.
+//!
+//! [`BridgeError::code`] is a stable number for logs: 10000 for Idris, 20000
+//! for szf, 30000 for Bebop or 40000 for polyglot, plus the magnitude of the
+//! foreign code. Idris `division_by_zero` (501) logs as `10501`,
+//! `SZF_ERR_INVALID_LENGTH` (-4) as `20004`. Foreign codes past 9999 all log
+//! as the bridge's `9999`.
+
+use std::error::Error;
+use std::fmt;
+use std::iter;
+
+use crate::bebop::BebopError;
+use crate::idris::{self, ConversionError};
+use crate::polyglot::PolyglotError;
+use crate::szf::SzfError;
+
+/// Which bridge failed, and its code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    Idris(idris::ErrorCode),
+    Szf(SzfError),
+    Bebop(BebopError),
+    Polyglot(PolyglotError),
+}
+
+impl ErrorKind {
+    /// See [`BridgeError::code`].
+    pub fn code(self) -> u32 {
+        let (base, code) = match self {
+            ErrorKind::Idris(code) => (10_000, code.code()),
+            ErrorKind::Szf(err) => (20_000, err.code().unsigned_abs()),
+            ErrorKind::Bebop(err) => (30_000, err.code().unsigned_abs()),
+            ErrorKind::Polyglot(err) => (40_000, err.code().unsigned_abs()),
+        };
+        base + code.min(9_999)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Idris(code) => write!(f, "Idris: {code}"),
+            ErrorKind::Szf(err) => write!(f, "szf: {err}"),
+            ErrorKind::Bebop(err) => write!(f, "Bebop: {err}"),
+            ErrorKind::Polyglot(err) => write!(f, "polyglot: {err}"),
+        }
+    }
+}
+
+/// A failure reported by one of the Zig bridges.
+///
+/// ```
+/// use rust_zig_ffi::idris::ErrorCode;
+/// use rust_zig_ffi::BridgeError;
+///
+/// let err = BridgeError::idris(ErrorCode::DivisionByZero, "b was 0", Some("safeDiv"))
+///     .context("computing the average");
+/// assert_eq!(err.to_string(), "Idris: division by zero: b was 0");
+/// assert_eq!(err.code(), 10501);
+/// assert_eq!(err.contexts().collect::<Vec<_>>(), ["computing the average", "safeDiv"]);
+/// ```
+#[derive(Debug)]
+pub struct BridgeError {
+    kind: ErrorKind,
+    message: Option<String>,
+    source: Option<Box<dyn Error + Send + Sync + 'static>>,
+}
+
+impl BridgeError {
+    pub fn new(kind: ErrorKind) -> BridgeError {
+        BridgeError {
+            kind,
+            message: None,
+            source: None,
+        }
+    }
+
+    /// The Rust form of the Idris bridge's `IdrisError`.
+    pub fn idris(
+        code: idris::ErrorCode,
+        message: impl Into<String>,
+        context: Option<&str>,
+    ) -> BridgeError {
+        let err = BridgeError::new(ErrorKind::Idris(code)).with_message(message);
+        match context {
+            Some(context) => err.context(context),
+            None => err,
+        }
+    }
+
+    /// Attach the message the foreign side gave for the failure.
+    pub fn with_message(mut self, message: impl Into<String>) -> BridgeError {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Add a layer of context in front of the existing chain.
+    pub fn context(mut self, context: impl Into<String>) -> BridgeError {
+        self.source = Some(Box::new(Context {
+            text: context.into(),
+            source: self.source.take(),
+        }));
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Stable numeric code for logging; see the [module docs](self).
+    pub fn code(&self) -> u32 {
+        self.kind.code()
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// The context layers, newest first.
+    pub fn contexts(&self) -> impl Iterator<Item = &str> {
+        let first = self.source.as_deref().and_then(downcast_context);
+        iter::successors(first, |context| {
+            context.source.as_deref().and_then(downcast_context)
+        })
+        .map(|context| context.text.as_str())
+    }
+}
+
+fn downcast_context<'a>(err: &'a (dyn Error + Send + Sync + 'static)) -> Option<&'a Context> {
+    err.downcast_ref::<Context>()
+}
+
+impl fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.kind, f)?;
+        match &self.message {
+            Some(message) => write!(f, ": {message}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Error for BridgeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|err| err as &(dyn Error + 'static))
+    }
+}
+
+/// One layer of a [`BridgeError`]'s context chain.
+#[derive(Debug)]
+struct Context {
+    text: String,
+    source: Option<Box<dyn Error + Send + Sync + 'static>>,
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl Error for Context {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|err| err as &(dyn Error + 'static))
+    }
+}
+
+impl From<ErrorKind> for BridgeError {
+    fn from(kind: ErrorKind) -> BridgeError {
+        BridgeError::new(kind)
+    }
+}
+
+impl From<idris::ErrorCode> for BridgeError {
+    fn from(code: idris::ErrorCode) -> BridgeError {
+        BridgeError::new(ErrorKind::Idris(code))
+    }
+}
+
+impl From<SzfError> for BridgeError {
+    fn from(err: SzfError) -> BridgeError {
+        BridgeError::new(ErrorKind::Szf(err))
+    }
+}
+
+impl From<BebopError> for BridgeError {
+    fn from(err: BebopError) -> BridgeError {
+        BridgeError::new(ErrorKind::Bebop(err))
+    }
+}
+
+impl From<PolyglotError> for BridgeError {
+    fn from(err: PolyglotError) -> BridgeError {
+        BridgeError::new(ErrorKind::Polyglot(err))
+    }
+}
+
+/// An Idris value of the wrong shape is a `type_mismatch`, with the details
+/// as the cause.
+impl From<ConversionError> for BridgeError {
+    fn from(err: ConversionError) -> BridgeError {
+        BridgeError {
+            source: Some(Box::new(err)),
+            ..BridgeError::new(ErrorKind::Idris(idris::ErrorCode::TypeMismatch))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{self, AbiVersion};
+    use crate::idris::{ErrorCode, IdrisEither, IdrisString};
+
+    fn chain(err: &(dyn Error + 'static)) -> Vec<String> {
+        iter::successors(Some(err), |&err| err.source())
+            .map(|err| err.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn codes_are_namespaced_by_bridge() {
+        assert_eq!(BridgeError::from(ErrorCode::Unknown).code(), 10000);
+        assert_eq!(
+            BridgeError::from(ErrorCode::TraversalDetected).code(),
+            10302
+        );
+        assert_eq!(BridgeError::from(ErrorCode::Foreign(123_456)).code(), 19999);
+        assert_eq!(BridgeError::from(BebopError::BufferTooSmall).code(), 30006);
+        assert_eq!(BridgeError::from(BebopError::NotImplemented).code(), 30099);
+        assert_eq!(
+            BridgeError::from(BebopError::Foreign(i32::MIN)).code(),
+            39999
+        );
+        assert_eq!(BridgeError::from(PolyglotError::ParseFailed).code(), 40003);
+    }
+
+    #[test]
+    fn szf_codes_map() {
+        let err = BridgeError::from(SzfError::from_code(crate::szf::SZF_ERR_INVALID_LENGTH));
+        assert_eq!(err.code(), 20004);
+        assert_eq!(err.to_string(), "szf: invalid length");
+
+        let stale = abi::check("szf", abi::SZF, AbiVersion::new(0, 9, 0));
+        let err = BridgeError::from(SzfError::Abi(stale.unwrap_err()));
+        assert_eq!(err.code(), 20099);
+        assert_eq!(
+            err.to_string(),
+            "szf: szf ABI 0.9.0 is not compatible with 1.0.0: different major version"
+        );
+    }
+
+    #[test]
+    fn idris_error_keeps_message_and_context() {
+        let err = BridgeError::idris(ErrorCode::OutOfRange, "port 70000", Some("parsePort"));
+        assert_eq!(err.kind(), ErrorKind::Idris(ErrorCode::OutOfRange));
+        assert_eq!(err.message(), Some("port 70000"));
+        assert_eq!(
+            chain(&err.context("loading config")),
+            [
+                "Idris: out of range: port 70000",
+                "loading config",
+                "parsePort"
+            ]
+        );
+    }
+
+    #[test]
+    fn conversion_errors_are_causes_below_context() {
+        let err = BridgeError::from(ConversionError::InvalidTag {
+            ty: "Maybe",
+            tag: 9,
+        })
+        .context("reading lookup result");
+        assert_eq!(
+            err.contexts().collect::<Vec<_>>(),
+            ["reading lookup result"]
+        );
+        assert_eq!(
+            chain(&err),
+            [
+                "Idris: type mismatch",
+                "reading lookup result",
+                "invalid Maybe tag 9"
+            ]
+        );
+    }
+
+    #[test]
+    fn idris_left_becomes_error() {
+        let ok: IdrisEither<IdrisString<'_>, i64> = IdrisEither::right(4);
+        assert_eq!(idris::from_either(ok).unwrap(), 4);
+        let left: IdrisEither<IdrisString<'_>, i64> = IdrisEither::left("bad digit".into());
+        let err = idris::from_either(left).unwrap_err();
+        assert_eq!(err.to_string(), "Idris: unknown error: bad digit");
+    }
+
+    #[test]
+    fn bridge_error_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<BridgeError>();
+    }
+}
@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Panic containment at the `extern "C"` boundary.
+//!
+//! A panic must never unwind into Zig or C frames. Every function this crate
+//! exports is declared through [`ffi_export!`], which runs the body inside
+//! [`contain`]: a panic is caught, its message is kept for the calling thread,
+//! and the function returns its [`PanicValue`] (`RZF_ERR_PANIC` for status
+//! codes). A process-wide [`PanicHook`] may instead ask for an abort.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{PoisonError, RwLock};
+
+/// Status returned by exported functions that completed normally.
+pub const RZF_OK: i32 = 0;
+/// Status returned by exported functions whose body panicked.
+pub const RZF_ERR_PANIC: i32 = -7;
+
+/// What to do after a panic has been caught at the boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicAction {
+    /// Return the function's error value and keep the message for
+    /// [`take_last_panic`] / `rzf_last_panic_message`.
+    Report,
+    /// Abort the process.
+    Abort,
+}
+
+/// Decides the [`PanicAction`] for a caught panic, given its message.
+pub type PanicHook = fn(message: &str) -> PanicAction;
+
+fn report(_message: &str) -> PanicAction {
+    PanicAction::Report
+}
+
+static HOOK: RwLock<PanicHook> = RwLock::new(report);
+
+thread_local! {
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Install the process-wide hook consulted on every caught panic.
+///
+/// The default hook always reports.
+pub fn set_panic_hook(hook: PanicHook) {
+    *HOOK.write().unwrap_or_else(PoisonError::into_inner) = hook;
+}
+
+/// Restore the default hook, which always reports.
+pub fn reset_panic_hook() {
+    set_panic_hook(report);
+}
+
+/// Take the message of the last panic caught on this thread, if any.
+pub fn take_last_panic() -> Option<String> {
+    LAST_PANIC.with(|last| last.borrow_mut().take())
+}
+
+/// Value an exported function returns when its body panicked.
+pub trait PanicValue {
+    fn on_panic() -> Self;
+}
+
+impl PanicValue for () {
+    fn on_panic() {}
+}
+
+/// `false`, so a panicking progress callback stops the work it reports on.
+impl PanicValue for bool {
+    fn on_panic() -> bool {
+        false
+    }
+}
+
+impl PanicValue for i32 {
+    fn on_panic() -> i32 {
+        RZF_ERR_PANIC
+    }
+}
+
+impl PanicValue for usize {
+    fn on_panic() -> usize {
+        0
+    }
+}
+
+impl<T> PanicValue for *const T {
+    fn on_panic() -> *const T {
+        std::ptr::null()
+    }
+}
+
+impl<T> PanicValue for *mut T {
+    fn on_panic() -> *mut T {
+        std::ptr::null_mut()
+    }
+}
+
+/// Run `body`, turning a panic into `R::on_panic()` or an abort.
+pub fn contain<R: PanicValue>(body: impl FnOnce() -> R) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(value) => value,
+        Err(payload) => {
+            let message = message(&*payload);
+            let hook = *HOOK.read().unwrap_or_else(PoisonError::into_inner);
+            if hook(&message) == PanicAction::Abort {
+                std::process::abort();
+            }
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(message));
+            R::on_panic()
+        }
+    }
+}
+
+fn message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        (*s).to_owned()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "panic with a non-string payload".to_owned()
+    }
+}
+
+/// Declare `#[no_mangle] extern "C"` functions whose bodies run inside
+/// [`contain`]. The return type must implement [`PanicValue`].
+macro_rules! ffi_export {
+    ($(
+        $(#[$attr:meta])*
+        pub unsafe extern "C" fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)? $body:block
+    )*) => {$(
+        $(#[$attr])*
+        #[no_mangle]
+        pub unsafe extern "C" fn $name($($arg: $ty),*) $(-> $ret)? {
+            $crate::guard::contain(move || $body)
+        }
+    )*};
+}
+
+pub(crate) use ffi_export;
+
+ffi_export! {
+    /// Copy the last panic message caught on this thread into `buf` and clear
+    /// it.
+    ///
+    /// Returns the full message length, which may exceed `cap`; at most `cap`
+    /// bytes are written and the copy is not NUL-terminated. Returns 0 if no
+    /// panic is pending.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must be null or valid for writes of `cap` bytes.
+    pub unsafe extern "C" fn rzf_last_panic_message(buf: *mut u8, cap: usize) -> usize {
+        let Some(message) = take_last_panic() else {
+            return 0;
+        };
+        if !buf.is_null() {
+            let n = message.len().min(cap);
+            std::ptr::copy_nonoverlapping(message.as_ptr(), buf, n);
+        }
+        message.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ffi_export! {
+        pub unsafe extern "C" fn rzf_test_status(fail: bool) -> i32 {
+            if fail {
+                panic!("status failed");
+            }
+            RZF_OK
+        }
+
+        pub unsafe extern "C" fn rzf_test_void(code: u32) {
+            panic!("void failed with {code}");
+        }
+    }
+
+    #[test]
+    fn panic_becomes_error_code_and_message() {
+        assert_eq!(unsafe { rzf_test_status(false) }, RZF_OK);
+        assert_eq!(take_last_panic(), None);
+        assert_eq!(unsafe { rzf_test_status(true) }, RZF_ERR_PANIC);
+        assert_eq!(take_last_panic().as_deref(), Some("status failed"));
+        assert_eq!(take_last_panic(), None);
+    }
+
+    #[test]
+    fn void_export_keeps_formatted_message() {
+        unsafe { rzf_test_void(3) };
+        let mut buf = [0u8; 8];
+        let len = unsafe { rzf_last_panic_message(buf.as_mut_ptr(), buf.len()) };
+        assert_eq!(len, "void failed with 3".len());
+        assert_eq!(&buf, b"void fai");
+        assert_eq!(unsafe { rzf_last_panic_message(buf.as_mut_ptr(), 8) }, 0);
+    }
+
+    #[test]
+    fn null_pointer_return_on_panic() {
+        let p: *const u8 = contain(|| panic!("no pointer"));
+        assert!(p.is_null());
+        assert_eq!(take_last_panic().as_deref(), Some("no pointer"));
+    }
+}
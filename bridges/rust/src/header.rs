@@ -0,0 +1,1252 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! C and Zig declarations for this crate's own exports (feature
+//! `gen-header`).
+//!
+//! The Swift and Bebop-V bridges generate their headers from the Zig
+//! definitions. The Rust side of this bridge exports functions of its own,
+//! such as `rust_callback`, so their declarations are generated from the Rust
+//! sources instead. [`Exports::scan`] parses every file under `src/` and
+//! collects
+//!
+//! - every `#[no_mangle] extern "C"` function, including those declared
+//!   through `ffi_export!`;
+//! - the integer `RZF_*` constants;
+//! - the `#[repr(C)]` structs and `extern "C"` function pointer aliases those
+//!   functions use, directly or through other structs.
+//!
+//! Test modules and other `#[cfg(test)]` items are skipped. `#[repr(C)]`
+//! types that no export uses are left out, since they mirror types the other
+//! bridges' headers already declare.
+//!
+//! [`Exports::c_header`] renders `include/rust_zig_ffi.h` with the same
+//! banner, version macros and ABI stability notes as those headers, and
+//! [`Exports::zig_externs`] renders `include/rust_zig_ffi.zig`, which Zig code
+//! can `@import` instead of going through `@cImport`. Both files are checked
+//! in; regenerate them with
+//!
+//! ```text
+//! cargo run --features gen-header --bin gen-header
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Expr, Fields, Item, ItemFn, Lit, Pat, ReturnType, Token, Type, UnOp};
+
+use crate::abi;
+
+/// Only constants with this prefix go into the header.
+const CONSTANT_PREFIX: &str = "RZF_";
+
+/// Rust primitive, C type, Zig type.
+const PRIMITIVES: &[(&str, &str, &str)] = &[
+    ("u8", "uint8_t", "u8"),
+    ("u16", "uint16_t", "u16"),
+    ("u32", "uint32_t", "u32"),
+    ("u64", "uint64_t", "u64"),
+    ("i8", "int8_t", "i8"),
+    ("i16", "int16_t", "i16"),
+    ("i32", "int32_t", "i32"),
+    ("i64", "int64_t", "i64"),
+    ("usize", "size_t", "usize"),
+    ("isize", "ptrdiff_t", "isize"),
+    ("bool", "bool", "bool"),
+    ("f32", "float", "f32"),
+    ("f64", "double", "f64"),
+    ("c_char", "char", "u8"),
+    ("c_int", "int", "c_int"),
+    ("c_uint", "unsigned int", "c_uint"),
+    ("c_long", "long", "c_long"),
+    ("c_ulong", "unsigned long", "c_ulong"),
+];
+
+const ZIG_KEYWORDS: &[&str] = &[
+    "addrspace",
+    "align",
+    "allowzero",
+    "and",
+    "anyframe",
+    "anytype",
+    "asm",
+    "async",
+    "await",
+    "break",
+    "callconv",
+    "catch",
+    "comptime",
+    "const",
+    "continue",
+    "defer",
+    "else",
+    "enum",
+    "errdefer",
+    "error",
+    "export",
+    "extern",
+    "fn",
+    "for",
+    "if",
+    "inline",
+    "linksection",
+    "noalias",
+    "noinline",
+    "nosuspend",
+    "opaque",
+    "or",
+    "orelse",
+    "packed",
+    "pub",
+    "resume",
+    "return",
+    "struct",
+    "suspend",
+    "switch",
+    "test",
+    "threadlocal",
+    "try",
+    "union",
+    "unreachable",
+    "usingnamespace",
+    "var",
+    "volatile",
+    "while",
+];
+
+const RULE: &str =
+    "// ============================================================================";
+
+/// Everything the Rust side of the bridge exports over the C ABI.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Exports {
+    constants: Vec<Constant>,
+    /// In dependency order: every type follows the types it uses.
+    types: Vec<TypeDef>,
+    functions: Vec<Function>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Constant {
+    docs: Vec<String>,
+    name: String,
+    ty: Ty,
+    value: i128,
+}
+
+/// An exported function, or the signature of a callback alias.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Function {
+    docs: Vec<String>,
+    name: String,
+    params: Vec<(String, Ty)>,
+    ret: Option<Ty>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TypeDef {
+    /// A `#[repr(C)]` struct with named fields.
+    Struct {
+        docs: Vec<String>,
+        name: String,
+        fields: Vec<Field>,
+    },
+    /// `type Name = unsafe extern "C" fn(..)`.
+    Callback(Function),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Field {
+    docs: Vec<String>,
+    name: String,
+    ty: Ty,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Ty {
+    Primitive {
+        c: &'static str,
+        zig: &'static str,
+    },
+    /// `c_void`, only valid behind a pointer.
+    Void,
+    Pointer {
+        mutable: bool,
+        pointee: Box<Ty>,
+    },
+    /// A `#[repr(C)]` struct or callback alias.
+    Named(String),
+    /// `Option<Callback>`: the same type in C, optional in Zig.
+    Nullable(String),
+}
+
+/// Items collected from the sources before their types are resolved.
+#[derive(Default)]
+struct Scan {
+    constants: Vec<Constant>,
+    functions: Vec<(Vec<String>, String, syn::Signature)>,
+    /// The first definition of each name wins.
+    types: HashMap<String, Item>,
+}
+
+/// A sequence of functions, as written inside `ffi_export! { .. }`.
+struct ExportedFns(Vec<ItemFn>);
+
+impl Parse for ExportedFns {
+    fn parse(input: ParseStream) -> syn::Result<ExportedFns> {
+        let mut fns = Vec::new();
+        while !input.is_empty() {
+            fns.push(input.parse()?);
+        }
+        Ok(ExportedFns(fns))
+    }
+}
+
+impl Exports {
+    /// Collect the exports of every `.rs` file under `dir`.
+    pub fn scan(dir: impl AsRef<Path>) -> Result<Exports, HeaderError> {
+        let mut paths = Vec::new();
+        rust_files(dir.as_ref(), &mut paths).map_err(|source| HeaderError::Io {
+            path: dir.as_ref().to_owned(),
+            source,
+        })?;
+        paths.sort();
+        let mut sources = Vec::new();
+        for path in paths {
+            match fs::read_to_string(&path) {
+                Ok(source) => sources.push((path, source)),
+                Err(source) => return Err(HeaderError::Io { path, source }),
+            }
+        }
+        Exports::from_sources(
+            sources
+                .iter()
+                .map(|(path, source)| (path.as_path(), source.as_str())),
+        )
+    }
+
+    /// Collect the exports of already-read source files. Paths are only used
+    /// in errors.
+    pub fn from_sources<'a>(
+        sources: impl IntoIterator<Item = (&'a Path, &'a str)>,
+    ) -> Result<Exports, HeaderError> {
+        let mut scan = Scan::default();
+        for (path, source) in sources {
+            let file = syn::parse_file(source).map_err(|source| HeaderError::Parse {
+                path: path.to_owned(),
+                source,
+            })?;
+            scan.items(path, file.items)?;
+        }
+        scan.resolve()
+    }
+
+    /// The C header, `include/rust_zig_ffi.h`.
+    pub fn c_header(&self) -> String {
+        let mut out = banner("rust_zig_ffi.h - C header for the Rust side of the bridge");
+        out += "\
+#ifndef RUST_ZIG_FFI_H
+#define RUST_ZIG_FFI_H
+
+#include <stddef.h>
+#include <stdint.h>
+#include <stdbool.h>
+
+";
+        section(&mut out, "ABI Version");
+        let version = abi::RZF;
+        out += &format!(
+            "\
+#define RZF_VERSION_MAJOR {}
+#define RZF_VERSION_MINOR {}
+#define RZF_VERSION_PATCH {}
+#define RZF_VERSION_STRING \"{version}\"
+
+// Combine version for runtime checks: (major << 16) | (minor << 8) | patch
+#define RZF_VERSION \\
+    ((RZF_VERSION_MAJOR << 16) | (RZF_VERSION_MINOR << 8) | RZF_VERSION_PATCH)
+
+#ifdef __cplusplus
+extern \"C\" {{
+#endif
+
+",
+            version.major, version.minor, version.patch
+        );
+
+        if !self.constants.is_empty() {
+            section(&mut out, "Status Codes");
+            let width = self
+                .constants
+                .iter()
+                .map(|c| c.name.len())
+                .max()
+                .unwrap_or(0);
+            for constant in &self.constants {
+                docs(&mut out, "", &constant.docs);
+                out += &format!("#define {:width$} {}\n", constant.name, constant.value);
+            }
+            out += "\n";
+        }
+
+        if !self.types.is_empty() {
+            section(&mut out, "Types");
+            for def in &self.types {
+                match def {
+                    TypeDef::Struct {
+                        docs: d,
+                        name,
+                        fields,
+                    } => {
+                        docs(&mut out, "", d);
+                        out += &format!("typedef struct {name} {{\n");
+                        for field in fields {
+                            docs(&mut out, "    ", &field.docs);
+                            out += &format!("    {};\n", field.ty.c_declaration(&field.name));
+                        }
+                        out += &format!("}} {name};\n\n");
+                    }
+                    TypeDef::Callback(callback) => {
+                        docs(&mut out, "", &callback.docs);
+                        out += &format!(
+                            "typedef {} (*{})({});\n\n",
+                            c_return(&callback.ret),
+                            callback.name,
+                            c_params(&callback.params)
+                        );
+                    }
+                }
+            }
+        }
+
+        if !self.functions.is_empty() {
+            section(&mut out, "Functions");
+            for function in &self.functions {
+                docs(&mut out, "", &function.docs);
+                let ret = c_return(&function.ret);
+                let line = format!("{ret} {}({});", function.name, c_params(&function.params));
+                if line.len() <= 80 {
+                    out += &line;
+                } else {
+                    out += &format!("{ret} {}(\n", function.name);
+                    let params: Vec<_> = function
+                        .params
+                        .iter()
+                        .map(|(name, ty)| format!("    {}", ty.c_declaration(name)))
+                        .collect();
+                    out += &params.join(",\n");
+                    out += "\n);";
+                }
+                out += "\n\n";
+            }
+        }
+
+        out += "\
+#ifdef __cplusplus
+}
+#endif
+
+#endif // RUST_ZIG_FFI_H
+";
+        out
+    }
+
+    /// Zig declarations of the same items, `include/rust_zig_ffi.zig`.
+    pub fn zig_externs(&self) -> String {
+        let mut out = banner("rust_zig_ffi.zig - Zig declarations for the Rust side of the bridge");
+        section(&mut out, "ABI Version");
+        let version = abi::RZF;
+        out += &format!(
+            "\
+pub const RZF_VERSION_MAJOR: u32 = {};
+pub const RZF_VERSION_MINOR: u32 = {};
+pub const RZF_VERSION_PATCH: u32 = {};
+pub const RZF_VERSION_STRING = \"{version}\";
+
+// Combine version for runtime checks: (major << 16) | (minor << 8) | patch
+pub const RZF_VERSION: u32 = (RZF_VERSION_MAJOR << 16) | (RZF_VERSION_MINOR << 8) | RZF_VERSION_PATCH;
+
+",
+            version.major, version.minor, version.patch
+        );
+
+        if !self.constants.is_empty() {
+            section(&mut out, "Status Codes");
+            for constant in &self.constants {
+                docs(&mut out, "", &constant.docs);
+                out += &format!(
+                    "pub const {}: {} = {};\n",
+                    constant.name,
+                    constant.ty.zig(),
+                    constant.value
+                );
+            }
+            out += "\n";
+        }
+
+        if !self.types.is_empty() {
+            section(&mut out, "Types");
+            for def in &self.types {
+                match def {
+                    TypeDef::Struct {
+                        docs: d,
+                        name,
+                        fields,
+                    } => {
+                        docs(&mut out, "", d);
+                        out += &format!("pub const {name} = extern struct {{\n");
+                        for field in fields {
+                            docs(&mut out, "    ", &field.docs);
+                            out += &format!("    {}: {},\n", zig_name(&field.name), field.ty.zig());
+                        }
+                        out += "};\n\n";
+                    }
+                    TypeDef::Callback(callback) => {
+                        docs(&mut out, "", &callback.docs);
+                        out += &format!(
+                            "pub const {} = *const fn ({}) callconv(.c) {};\n\n",
+                            callback.name,
+                            zig_params(&callback.params),
+                            zig_return(&callback.ret)
+                        );
+                    }
+                }
+            }
+        }
+
+        if !self.functions.is_empty() {
+            section(&mut out, "Functions");
+            for function in &self.functions {
+                docs(&mut out, "", &function.docs);
+                out += &format!(
+                    "pub extern fn {}({}) {};\n\n",
+                    function.name,
+                    zig_params(&function.params),
+                    zig_return(&function.ret)
+                );
+            }
+        }
+        out.truncate(out.trim_end().len());
+        out += "\n";
+        out
+    }
+}
+
+impl Scan {
+    fn items(&mut self, path: &Path, items: Vec<Item>) -> Result<(), HeaderError> {
+        for item in items {
+            if cfg_test(item_attrs(&item)) {
+                continue;
+            }
+            match item {
+                Item::Fn(item) => self.function(item, false),
+                Item::Macro(item)
+                    if item
+                        .mac
+                        .path
+                        .segments
+                        .last()
+                        .is_some_and(|last| last.ident == "ffi_export") =>
+                {
+                    let fns: ExportedFns =
+                        item.mac.parse_body().map_err(|source| HeaderError::Parse {
+                            path: path.to_owned(),
+                            source,
+                        })?;
+                    for item in fns.0 {
+                        self.function(item, true);
+                    }
+                }
+                Item::Mod(item) => {
+                    if let Some((_, items)) = item.content {
+                        self.items(path, items)?;
+                    }
+                }
+                Item::Const(item) => self.constant(item),
+                Item::Struct(ref def) if repr_c(&def.attrs) => {
+                    self.types.entry(def.ident.to_string()).or_insert(item);
+                }
+                Item::Type(ref def) if matches!(&*def.ty, Type::BareFn(f) if extern_c(&f.abi)) => {
+                    self.types.entry(def.ident.to_string()).or_insert(item);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn function(&mut self, item: ItemFn, exported: bool) {
+        let name = match export_name(&item.attrs) {
+            Some(name) => name,
+            None if exported => None,
+            None => return,
+        };
+        if !extern_c(&item.sig.abi) {
+            return;
+        }
+        let name = name.unwrap_or_else(|| item.sig.ident.to_string());
+        self.functions
+            .push((doc_lines(&item.attrs), name, item.sig));
+    }
+
+    fn constant(&mut self, item: syn::ItemConst) {
+        let name = item.ident.to_string();
+        if !matches!(item.vis, syn::Visibility::Public(_)) || !name.starts_with(CONSTANT_PREFIX) {
+            return;
+        }
+        let (Ok(ty @ Ty::Primitive { .. }), Some(value)) = (to_ty(&item.ty), integer(&item.expr))
+        else {
+            return;
+        };
+        self.constants.push(Constant {
+            docs: doc_lines(&item.attrs),
+            name,
+            ty,
+            value,
+        });
+    }
+
+    fn resolve(self) -> Result<Exports, HeaderError> {
+        let mut resolver = Resolver {
+            definitions: &self.types,
+            done: HashSet::new(),
+            pending: HashSet::new(),
+            types: Vec::new(),
+        };
+        let mut functions = Vec::new();
+        for (docs, name, sig) in &self.functions {
+            let function = signature(docs.clone(), name.clone(), sig.inputs.iter(), &sig.output)
+                .map_err(|ty| unsupported(name, ty))?;
+            resolver.function(name, &function)?;
+            functions.push(function);
+        }
+        Ok(Exports {
+            constants: self.constants,
+            types: resolver.types,
+            functions,
+        })
+    }
+}
+
+/// Emits the types reachable from the exports, each after its dependencies.
+struct Resolver<'a> {
+    definitions: &'a HashMap<String, Item>,
+    done: HashSet<String>,
+    pending: HashSet<String>,
+    types: Vec<TypeDef>,
+}
+
+impl Resolver<'_> {
+    fn function(&mut self, item: &str, function: &Function) -> Result<(), HeaderError> {
+        for (_, ty) in &function.params {
+            self.require(item, ty)?;
+        }
+        match &function.ret {
+            Some(ty) => self.require(item, ty),
+            None => Ok(()),
+        }
+    }
+
+    fn require(&mut self, item: &str, ty: &Ty) -> Result<(), HeaderError> {
+        let name = match ty {
+            Ty::Primitive { .. } => return Ok(()),
+            Ty::Void => return Err(unsupported(item, "c_void".into())),
+            Ty::Pointer { pointee, .. } if **pointee == Ty::Void => return Ok(()),
+            Ty::Pointer { pointee, .. } => return self.require(item, pointee),
+            Ty::Named(name) => name,
+            Ty::Nullable(name) => {
+                return match self.definitions.get(name) {
+                    Some(Item::Type(_)) => self.require(item, &Ty::Named(name.clone())),
+                    _ => Err(unsupported(item, format!("Option<{name}>"))),
+                };
+            }
+        };
+        if self.done.contains(name) {
+            return Ok(());
+        }
+        if !self.pending.insert(name.clone()) {
+            return Err(unsupported(item, format!("{name} (recursive)")));
+        }
+        let def = match self.definitions.get(name) {
+            Some(Item::Struct(def)) => {
+                let Fields::Named(named) = &def.fields else {
+                    return Err(unsupported(item, format!("{name} (tuple struct)")));
+                };
+                let mut fields = Vec::new();
+                for field in &named.named {
+                    let ty = to_ty(&field.ty).map_err(|ty| unsupported(name, ty))?;
+                    self.require(name, &ty)?;
+                    fields.push(Field {
+                        docs: doc_lines(&field.attrs),
+                        name: field.ident.as_ref().expect("named field").to_string(),
+                        ty,
+                    });
+                }
+                TypeDef::Struct {
+                    docs: doc_lines(&def.attrs),
+                    name: name.clone(),
+                    fields,
+                }
+            }
+            Some(Item::Type(def)) => {
+                let Type::BareFn(f) = &*def.ty else {
+                    unreachable!("only fn pointer aliases are collected")
+                };
+                let inputs = f.inputs.iter().enumerate().map(|(i, arg)| {
+                    let name = match &arg.name {
+                        Some((ident, _)) => ident.to_string(),
+                        None => format!("arg{i}"),
+                    };
+                    (name, &arg.ty)
+                });
+                let callback =
+                    bare_signature(doc_lines(&def.attrs), name.clone(), inputs, &f.output)
+                        .map_err(|ty| unsupported(name, ty))?;
+                self.function(name, &callback)?;
+                TypeDef::Callback(callback)
+            }
+            _ => return Err(unsupported(item, name.clone())),
+        };
+        self.pending.remove(name);
+        self.done.insert(name.clone());
+        self.types.push(def);
+        Ok(())
+    }
+}
+
+/// The symbol name of a `#[no_mangle]` or `#[export_name = ".."]` function:
+/// `Some(None)` for the former, which keeps its ident.
+fn export_name(attrs: &[Attribute]) -> Option<Option<String>> {
+    for attr in attrs {
+        // `#[unsafe(no_mangle)]` as well as `#[no_mangle]`.
+        let meta = if attr.path().is_ident("unsafe") {
+            match attr.parse_args::<syn::Meta>() {
+                Ok(meta) => meta,
+                Err(_) => continue,
+            }
+        } else {
+            attr.meta.clone()
+        };
+        match &meta {
+            syn::Meta::Path(path) if path.is_ident("no_mangle") => return Some(None),
+            syn::Meta::NameValue(value) if value.path.is_ident("export_name") => {
+                if let Expr::Lit(syn::ExprLit {
+                    lit: Lit::Str(name),
+                    ..
+                }) = &value.value
+                {
+                    return Some(Some(name.value()));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn signature<'a>(
+    docs: Vec<String>,
+    name: String,
+    inputs: impl Iterator<Item = &'a syn::FnArg>,
+    output: &ReturnType,
+) -> Result<Function, String> {
+    let mut typed = Vec::new();
+    for (i, input) in inputs.enumerate() {
+        let syn::FnArg::Typed(arg) = input else {
+            return Err("self".into());
+        };
+        let name = match &*arg.pat {
+            Pat::Ident(ident) => ident.ident.to_string(),
+            _ => format!("arg{i}"),
+        };
+        typed.push((name, &*arg.ty));
+    }
+    bare_signature(docs, name, typed.into_iter(), output)
+}
+
+fn bare_signature<'a>(
+    docs: Vec<String>,
+    name: String,
+    inputs: impl Iterator<Item = (String, &'a Type)>,
+    output: &ReturnType,
+) -> Result<Function, String> {
+    let params = inputs
+        .map(|(name, t)| Ok((name, to_ty(t)?)))
+        .collect::<Result<_, String>>()?;
+    let ret = match output {
+        ReturnType::Default => None,
+        ReturnType::Type(_, t) if matches!(&**t, Type::Tuple(tuple) if tuple.elems.is_empty()) => {
+            None
+        }
+        ReturnType::Type(_, t) => Some(to_ty(t)?),
+    };
+    Ok(Function {
+        docs,
+        name,
+        params,
+        ret,
+    })
+}
+
+/// The C type of a Rust type, or the Rust type as written if it has none.
+fn to_ty(t: &Type) -> Result<Ty, String> {
+    match t {
+        Type::Paren(inner) => return to_ty(&inner.elem),
+        Type::Group(inner) => return to_ty(&inner.elem),
+        Type::Ptr(ptr) => {
+            return Ok(Ty::Pointer {
+                mutable: ptr.mutability.is_some(),
+                pointee: Box::new(to_ty(&ptr.elem)?),
+            })
+        }
+        _ => {}
+    }
+    match last_segment(t) {
+        Some((name, None)) if name == "c_void" => Ok(Ty::Void),
+        Some((name, None)) => Ok(match primitive(&name) {
+            Some((_, c, zig)) => Ty::Primitive { c, zig },
+            None => Ty::Named(name),
+        }),
+        Some((option, Some(inner))) if option == "Option" => match last_segment(inner) {
+            Some((name, None)) if name != "c_void" && primitive(&name).is_none() => {
+                Ok(Ty::Nullable(name))
+            }
+            _ => Err(tokens(t)),
+        },
+        _ => Err(tokens(t)),
+    }
+}
+
+/// The last path segment of `t` and its single generic argument, if any.
+fn last_segment(t: &Type) -> Option<(String, Option<&Type>)> {
+    let Type::Path(path) = t else {
+        return None;
+    };
+    if path.qself.is_some() {
+        return None;
+    }
+    let segment = path.path.segments.last()?;
+    let name = segment.ident.to_string();
+    match &segment.arguments {
+        syn::PathArguments::None => Some((name, None)),
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            syn::GenericArgument::Type(arg) => Some((name, Some(arg))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn primitive(name: &str) -> Option<(&'static str, &'static str, &'static str)> {
+    PRIMITIVES.iter().copied().find(|p| p.0 == name)
+}
+
+/// An integer literal, possibly negated.
+fn integer(expr: &Expr) -> Option<i128> {
+    match expr {
+        Expr::Lit(syn::ExprLit {
+            lit: Lit::Int(int), ..
+        }) => int.base10_parse().ok(),
+        Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => integer(&unary.expr).map(|v| -v),
+        Expr::Paren(inner) => integer(&inner.expr),
+        _ => None,
+    }
+}
+
+fn tokens(t: &Type) -> String {
+    t.to_token_stream().to_string()
+}
+
+fn unsupported(item: &str, ty: String) -> HeaderError {
+    HeaderError::Unsupported {
+        item: item.to_owned(),
+        ty,
+    }
+}
+
+fn extern_c(abi: &Option<syn::Abi>) -> bool {
+    abi.as_ref()
+        .is_some_and(|abi| abi.name.as_ref().is_none_or(|name| name.value() == "C"))
+}
+
+fn repr_c(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|a| a.path().is_ident("repr"))
+        .any(|attr| {
+            let mut c = false;
+            let _ = attr.parse_nested_meta(|meta| {
+                c |= meta.path.is_ident("C");
+                Ok(())
+            });
+            c
+        })
+}
+
+/// `#[cfg(test)]` or `#[cfg(all(test, ..))]`: compiled only for tests.
+fn cfg_test(attrs: &[Attribute]) -> bool {
+    let test = |meta: &syn::Meta| meta.path().is_ident("test");
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg"))
+        .filter_map(|attr| attr.parse_args::<syn::Meta>().ok())
+        .any(|meta| match &meta {
+            syn::Meta::List(all) if all.path.is_ident("all") => all
+                .parse_args_with(Punctuated::<syn::Meta, Token![,]>::parse_terminated)
+                .is_ok_and(|metas| metas.iter().any(test)),
+            _ => test(&meta),
+        })
+}
+
+fn item_attrs(item: &Item) -> &[Attribute] {
+    match item {
+        Item::Const(item) => &item.attrs,
+        Item::Fn(item) => &item.attrs,
+        Item::Macro(item) => &item.attrs,
+        Item::Mod(item) => &item.attrs,
+        Item::Struct(item) => &item.attrs,
+        Item::Type(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+/// The `///` lines of an item, with rustdoc links reduced to their text.
+fn doc_lines(attrs: &[Attribute]) -> Vec<String> {
+    let mut lines: Vec<String> = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(value) if value.path.is_ident("doc") => match &value.value {
+                Expr::Lit(syn::ExprLit {
+                    lit: Lit::Str(doc), ..
+                }) => Some(doc.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .flat_map(|doc| doc.split('\n').map(str::to_owned).collect::<Vec<_>>())
+        .map(|line| plain(line.strip_prefix(' ').unwrap_or(&line)))
+        .collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Rustdoc markup that means nothing in C: `[`item`]` and `[text](target)`
+/// become their text, `# Heading` becomes `Heading:`.
+fn plain(line: &str) -> String {
+    if let Some(heading) = line.strip_prefix("# ") {
+        return format!("{heading}:");
+    }
+    let mut out = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find('[') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find(']') else {
+            break;
+        };
+        let text = &after[..end];
+        let tail = &after[end + 1..];
+        let target = tail
+            .strip_prefix('(')
+            .and_then(|t| Some(&t[t.find(')')? + 1..]));
+        let code = text.len() > 1 && text.starts_with('`') && text.ends_with('`');
+        match (target, code) {
+            (Some(tail), _) => {
+                out += &rest[..start];
+                out += text;
+                rest = tail;
+            }
+            (None, true) => {
+                out += &rest[..start];
+                out += text;
+                rest = tail;
+            }
+            (None, false) => {
+                out += &rest[..=start];
+                rest = after;
+            }
+        }
+    }
+    out += rest;
+    out
+}
+
+fn rust_files(dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            rust_files(&path, out)?;
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+impl Ty {
+    /// `name` declared with this type, as in a parameter or field.
+    fn c_declaration(&self, name: &str) -> String {
+        format!("{} {name}", self.c())
+    }
+
+    fn c(&self) -> String {
+        match self {
+            Ty::Primitive { c, .. } => (*c).to_owned(),
+            Ty::Void => "void".to_owned(),
+            Ty::Pointer { mutable, pointee } => match (&**pointee, mutable) {
+                (Ty::Pointer { .. }, true) => format!("{}*", pointee.c()),
+                (Ty::Pointer { .. }, false) => format!("{} const*", pointee.c()),
+                (_, true) => format!("{}*", pointee.c()),
+                (_, false) => format!("const {}*", pointee.c()),
+            },
+            Ty::Named(name) | Ty::Nullable(name) => name.clone(),
+        }
+    }
+
+    fn zig(&self) -> String {
+        match self {
+            Ty::Primitive { zig, .. } => (*zig).to_owned(),
+            Ty::Void => "anyopaque".to_owned(),
+            Ty::Pointer { mutable, pointee } => match (&**pointee, mutable) {
+                (Ty::Void, true) => "?*anyopaque".to_owned(),
+                (Ty::Void, false) => "?*const anyopaque".to_owned(),
+                (_, true) => format!("[*c]{}", pointee.zig()),
+                (_, false) => format!("[*c]const {}", pointee.zig()),
+            },
+            Ty::Named(name) => name.clone(),
+            Ty::Nullable(name) => format!("?{name}"),
+        }
+    }
+}
+
+fn c_return(ret: &Option<Ty>) -> String {
+    ret.as_ref().map_or_else(|| "void".to_owned(), Ty::c)
+}
+
+fn c_params(params: &[(String, Ty)]) -> String {
+    if params.is_empty() {
+        return "void".to_owned();
+    }
+    let params: Vec<_> = params
+        .iter()
+        .map(|(name, ty)| ty.c_declaration(name))
+        .collect();
+    params.join(", ")
+}
+
+fn zig_return(ret: &Option<Ty>) -> String {
+    ret.as_ref().map_or_else(|| "void".to_owned(), Ty::zig)
+}
+
+fn zig_params(params: &[(String, Ty)]) -> String {
+    let params: Vec<_> = params
+        .iter()
+        .map(|(name, ty)| format!("{}: {}", zig_name(name), ty.zig()))
+        .collect();
+    params.join(", ")
+}
+
+fn zig_name(name: &str) -> String {
+    if ZIG_KEYWORDS.contains(&name) {
+        format!("@\"{name}\"")
+    } else {
+        name.to_owned()
+    }
+}
+
+/// The licence line, the auto-generated box and the notes every generated
+/// file starts with.
+fn banner(title: &str) -> String {
+    let mut out = format!(
+        "// SPDX-License-Identifier: PMLP-1.0-or-later\n//\n// {title}\n//\n// ┌{}┐\n",
+        "─".repeat(74)
+    );
+    for line in [
+        "AUTO-GENERATED FILE - DO NOT EDIT MANUALLY",
+        "",
+        "Source: src/**/*.rs (#[no_mangle] extern \"C\" items)",
+        "Generator: src/header.rs",
+        "Regenerate: cargo run --features gen-header --bin gen-header",
+    ] {
+        out += &format!("// │ {line:<73}│\n");
+    }
+    out += &format!("// └{}┘\n", "─".repeat(74));
+    out += "\
+//
+// These functions are defined in Rust and resolved when the rust-zig-ffi
+// crate is linked into the final artifact. Zig code can @import
+// rust_zig_ffi.zig instead of @cImport-ing rust_zig_ffi.h.
+//
+// ABI STABILITY GUARANTEE:
+// - Version 1.x.x: Backwards compatible (no breaking changes)
+// - Structs: Fields may be added at end only, never removed/reordered
+// - Functions: New functions may be added, existing signatures frozen
+// - Status codes: New codes may be added, existing values frozen
+
+";
+    out
+}
+
+fn section(out: &mut String, title: &str) {
+    *out += &format!("{RULE}\n// {title}\n{RULE}\n\n");
+}
+
+fn docs(out: &mut String, indent: &str, lines: &[String]) {
+    for line in lines {
+        if line.is_empty() {
+            *out += &format!("{indent}///\n");
+        } else {
+            *out += &format!("{indent}/// {line}\n");
+        }
+    }
+}
+
+/// Sources that could not be turned into declarations.
+#[derive(Debug)]
+pub enum HeaderError {
+    /// A source file or directory could not be read.
+    Io { path: PathBuf, source: io::Error },
+    /// A source file is not valid Rust.
+    Parse { path: PathBuf, source: syn::Error },
+    /// `item` uses a type with no C equivalent, or a named type that is
+    /// neither a `#[repr(C)]` struct nor an `extern "C"` fn pointer alias.
+    Unsupported { item: String, ty: String },
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderError::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            HeaderError::Parse { path, source } => write!(f, "{}: {source}", path.display()),
+            HeaderError::Unsupported { item, ty } => {
+                write!(f, "{item}: `{ty}` has no C declaration")
+            }
+        }
+    }
+}
+
+impl Error for HeaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HeaderError::Io { source, .. } => Some(source),
+            HeaderError::Parse { source, .. } => Some(source),
+            HeaderError::Unsupported { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exports(source: &str) -> Result<Exports, HeaderError> {
+        Exports::from_sources([(Path::new("test.rs"), source)])
+    }
+
+    const SOURCE: &str = r#"
+        /// Sent with every sample.
+        pub const RZF_SAMPLE: u16 = 3;
+        pub const RZF_ERR_NEGATIVE: i32 = -(12);
+        pub const OTHER: i32 = 1;
+        const RZF_PRIVATE: i32 = 2;
+
+        /// A sample.
+        #[repr(C)]
+        pub struct Sample {
+            /// Bytes of the sample.
+            pub data: *const u8,
+            pub len: usize,
+            pub origin: Origin,
+        }
+
+        #[repr(C)]
+        #[derive(Clone, Copy)]
+        pub struct Origin {
+            pub names: *const *const c_char,
+            pub context: *mut std::ffi::c_void,
+        }
+
+        #[repr(C)]
+        pub struct Unused {
+            pub x: u8,
+        }
+
+        /// Receives samples.
+        pub type OnSample = unsafe extern "C" fn(sample: *const Sample, user: *mut c_void) -> bool;
+
+        #[no_mangle]
+        pub unsafe extern "C" fn rzf_subscribe(callback: Option<OnSample>, user: *mut c_void) -> i32 {
+            0
+        }
+
+        #[export_name = "rzf_renamed"]
+        pub extern "C" fn renamed(error: u32) {}
+
+        pub extern "C" fn not_exported() {}
+
+        #[no_mangle]
+        pub fn not_extern_c() {}
+
+        ffi_export! {
+            pub unsafe extern "C" fn rzf_from_macro() -> () {}
+        }
+
+        mod inner {
+            #[unsafe(no_mangle)]
+            pub extern "C" fn rzf_nested(sample: Sample) -> f64 { 0.0 }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            #[no_mangle]
+            pub extern "C" fn rzf_test_only() {}
+        }
+
+        #[cfg(all(test, unix))]
+        #[no_mangle]
+        pub extern "C" fn rzf_unix_test_only() {}
+    "#;
+
+    #[test]
+    fn checked_in_files_are_current() {
+        let exports = Exports::scan(concat!(env!("CARGO_MANIFEST_DIR"), "/src")).unwrap();
+        assert_eq!(
+            exports.c_header(),
+            include_str!("../include/rust_zig_ffi.h"),
+            "include/rust_zig_ffi.h is stale; run `cargo run --features gen-header --bin gen-header`"
+        );
+        assert_eq!(
+            exports.zig_externs(),
+            include_str!("../include/rust_zig_ffi.zig"),
+            "include/rust_zig_ffi.zig is stale; run `cargo run --features gen-header --bin gen-header`"
+        );
+        let names: Vec<_> = exports.functions.iter().map(|f| f.name.as_str()).collect();
+        assert!(names.contains(&"rust_callback"), "{names:?}");
+    }
+
+    #[test]
+    fn collects_exports_and_the_types_they_use() {
+        let exports = exports(SOURCE).unwrap();
+        let names: Vec<_> = exports.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "rzf_subscribe",
+                "rzf_renamed",
+                "rzf_from_macro",
+                "rzf_nested"
+            ]
+        );
+        let constants: Vec<_> = exports
+            .constants
+            .iter()
+            .map(|c| (c.name.as_str(), c.value))
+            .collect();
+        assert_eq!(constants, [("RZF_SAMPLE", 3), ("RZF_ERR_NEGATIVE", -12)]);
+        let types: Vec<_> = exports
+            .types
+            .iter()
+            .map(|def| match def {
+                TypeDef::Struct { name, .. } => name.as_str(),
+                TypeDef::Callback(callback) => callback.name.as_str(),
+            })
+            .collect();
+        assert_eq!(types, ["Origin", "Sample", "OnSample"]);
+    }
+
+    #[test]
+    fn c_declarations() {
+        let header = exports(SOURCE).unwrap().c_header();
+        for expected in [
+            "#define RZF_SAMPLE       3\n",
+            "#define RZF_ERR_NEGATIVE -12\n",
+            "typedef struct Origin {\n    const char* const* names;\n    void* context;\n} Origin;\n",
+            "/// A sample.\ntypedef struct Sample {\n    /// Bytes of the sample.\n    const uint8_t* data;\n",
+            "/// Receives samples.\ntypedef bool (*OnSample)(const Sample* sample, void* user);\n",
+            "int32_t rzf_subscribe(OnSample callback, void* user);\n",
+            "void rzf_renamed(uint32_t error);\n",
+            "void rzf_from_macro(void);\n",
+            "double rzf_nested(Sample sample);\n",
+        ] {
+            assert!(header.contains(expected), "{expected}\nnot in\n{header}");
+        }
+        assert!(!header.contains("Unused"));
+        assert!(!header.contains("test_only"));
+    }
+
+    #[test]
+    fn zig_declarations() {
+        let zig = exports(SOURCE).unwrap().zig_externs();
+        for expected in [
+            "pub const RZF_SAMPLE: u16 = 3;\n",
+            "pub const Origin = extern struct {\n    names: [*c]const [*c]const u8,\n    context: ?*anyopaque,\n};\n",
+            "pub const OnSample = *const fn (sample: [*c]const Sample, user: ?*anyopaque) callconv(.c) bool;\n",
+            "pub extern fn rzf_subscribe(callback: ?OnSample, user: ?*anyopaque) i32;\n",
+            "pub extern fn rzf_renamed(@\"error\": u32) void;\n",
+        ] {
+            assert!(zig.contains(expected), "{expected}\nnot in\n{zig}");
+        }
+    }
+
+    #[test]
+    fn long_signatures_are_wrapped() {
+        let header = exports(
+            "#[no_mangle] pub extern \"C\" fn rzf_long(first_argument: *const u8, second_argument: usize, third: *mut u8) {}",
+        )
+        .unwrap()
+        .c_header();
+        assert!(header.contains(
+            "void rzf_long(\n    const uint8_t* first_argument,\n    size_t second_argument,\n    uint8_t* third\n);\n"
+        ));
+    }
+
+    #[test]
+    fn types_without_a_c_declaration_are_rejected() {
+        for (source, ty) in [
+            ("#[no_mangle] pub extern \"C\" fn f(s: &[u8]) {}", "& [u8]"),
+            (
+                "#[no_mangle] pub extern \"C\" fn f() -> String { todo!() }",
+                "String",
+            ),
+            ("#[no_mangle] pub extern \"C\" fn f(v: c_void) {}", "c_void"),
+            (
+                "#[no_mangle] pub extern \"C\" fn f(x: Option<u32>) {}",
+                "Option < u32 >",
+            ),
+        ] {
+            match exports(source) {
+                Err(HeaderError::Unsupported { item, ty: found }) => {
+                    assert_eq!((item.as_str(), found.as_str()), ("f", ty))
+                }
+                other => panic!("{source}: {other:?}"),
+            }
+        }
+        let err = exports(
+            "#[repr(C)] pub struct Node { next: Box<Node> }
+             #[no_mangle] pub extern \"C\" fn f(node: *mut Node) {}",
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Node: `Box < Node >` has no C declaration");
+        assert!(matches!(
+            exports("fn broken("),
+            Err(HeaderError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn rustdoc_markup_is_plain_text() {
+        assert_eq!(
+            plain("See [`Hkdf`] and [`f`](crate::f)."),
+            "See `Hkdf` and `f`."
+        );
+        assert_eq!(plain("[the docs](https://example.com) say"), "the docs say");
+        assert_eq!(plain("buf[0] and [x]"), "buf[0] and [x]");
+        assert_eq!(plain("# Safety"), "Safety:");
+    }
+}
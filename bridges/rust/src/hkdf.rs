@@ -0,0 +1,265 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! HKDF (RFC 5869) over the Zig core or, with feature `pure-rust`, a native
+//! implementation.
+//!
+//! [`Hkdf::extract`] turns input keying material into a pseudorandom key;
+//! [`Hkdf::expand`] derives any number of independent subkeys from it, each
+//! bound to its own `info` string. Keying material goes in and comes out as
+//! [`Secret`] types.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::backend::active as backend;
+use crate::secret::{Secret, SecretKey};
+
+/// Hash function underlying HKDF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    Sha256,
+    Sha512,
+}
+
+impl Algorithm {
+    /// Digest length in bytes; also the PRK length.
+    pub const fn hash_len(self) -> usize {
+        match self {
+            Algorithm::Sha256 => 32,
+            Algorithm::Sha512 => 64,
+        }
+    }
+
+    /// Longest output `expand` can produce (255 × hash length).
+    pub const fn max_output_len(self) -> usize {
+        255 * self.hash_len()
+    }
+}
+
+/// Errors returned by [`Hkdf`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HkdfError {
+    /// Requested output is longer than 255 × hash length.
+    OutputTooLong { requested: usize, max: usize },
+    /// A PRK passed to [`Hkdf::from_prk`] is not exactly one hash length.
+    InvalidPrkLength { expected: usize, actual: usize },
+    /// The Zig core returned a code this crate does not know about.
+    Foreign(i32),
+}
+
+impl fmt::Display for HkdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HkdfError::OutputTooLong { requested, max } => {
+                write!(
+                    f,
+                    "requested {requested} bytes of HKDF output, maximum is {max}"
+                )
+            }
+            HkdfError::InvalidPrkLength { expected, actual } => {
+                write!(f, "PRK must be {expected} bytes, got {actual}")
+            }
+            HkdfError::Foreign(code) => write!(f, "HKDF core returned error code {code}"),
+        }
+    }
+}
+
+impl Error for HkdfError {}
+
+/// An extracted pseudorandom key, ready to be expanded into subkeys.
+#[derive(Clone)]
+pub struct Hkdf {
+    algorithm: Algorithm,
+    prk: SecretKey,
+}
+
+impl Hkdf {
+    /// HKDF-Extract: derive a PRK from `ikm` and an optional `salt`.
+    ///
+    /// An empty salt is treated as a string of zeros, as in the RFC.
+    pub fn extract(algorithm: Algorithm, salt: &[u8], ikm: &impl Secret) -> Hkdf {
+        let mut prk = SecretKey::zeroed(algorithm.hash_len());
+        backend::extract(algorithm, salt, ikm.expose_secret(), prk.as_mut_bytes());
+        Hkdf { algorithm, prk }
+    }
+
+    /// Skip extraction and expand from an existing PRK.
+    pub fn from_prk(algorithm: Algorithm, prk: SecretKey) -> Result<Hkdf, HkdfError> {
+        let expected = algorithm.hash_len();
+        if prk.len() != expected {
+            return Err(HkdfError::InvalidPrkLength {
+                expected,
+                actual: prk.len(),
+            });
+        }
+        Ok(Hkdf { algorithm, prk })
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// The pseudorandom key produced by extraction.
+    pub fn prk(&self) -> &SecretKey {
+        &self.prk
+    }
+
+    /// HKDF-Expand: derive `len` bytes of output keying material bound to
+    /// `info`.
+    pub fn expand(&self, info: &[u8], len: usize) -> Result<SecretKey, HkdfError> {
+        let max = self.algorithm.max_output_len();
+        if len > max {
+            return Err(HkdfError::OutputTooLong {
+                requested: len,
+                max,
+            });
+        }
+        let mut okm = SecretKey::zeroed(len);
+        backend::expand(
+            self.algorithm,
+            self.prk.expose_secret(),
+            info,
+            okm.as_mut_bytes(),
+        )?;
+        Ok(okm)
+    }
+}
+
+impl fmt::Debug for Hkdf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hkdf")
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, any(zig_linked, feature = "pure-rust")))]
+mod tests {
+    use super::*;
+    use crate::secret::Password;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn range(start: u8, end: u16) -> Vec<u8> {
+        (u16::from(start)..end).map(|b| b as u8).collect()
+    }
+
+    // RFC 5869, Appendix A.1 - A.3
+    #[test]
+    fn rfc5869_case_1() {
+        let hkdf = Hkdf::extract(
+            Algorithm::Sha256,
+            &range(0x00, 0x0d),
+            &SecretKey::from_slice(&[0x0b; 22]),
+        );
+        assert_eq!(
+            hex(hkdf.prk().expose_secret()),
+            "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"
+        );
+        let okm = hkdf.expand(&range(0xf0, 0xfa), 42).unwrap();
+        assert_eq!(
+            hex(okm.expose_secret()),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+    }
+
+    #[test]
+    fn rfc5869_case_2() {
+        let hkdf = Hkdf::extract(
+            Algorithm::Sha256,
+            &range(0x60, 0xb0),
+            &SecretKey::new(range(0x00, 0x50)),
+        );
+        assert_eq!(
+            hex(hkdf.prk().expose_secret()),
+            "06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244"
+        );
+        let okm = hkdf.expand(&range(0xb0, 0x100), 82).unwrap();
+        assert_eq!(
+            hex(okm.expose_secret()),
+            "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c\
+             59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71\
+             cc30c58179ec3e87c14c01d5c1f3434f1d87"
+        );
+    }
+
+    #[test]
+    fn rfc5869_case_3() {
+        let hkdf = Hkdf::extract(Algorithm::Sha256, &[], &SecretKey::from_slice(&[0x0b; 22]));
+        assert_eq!(
+            hex(hkdf.prk().expose_secret()),
+            "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04"
+        );
+        let okm = hkdf.expand(&[], 42).unwrap();
+        assert_eq!(
+            hex(okm.expose_secret()),
+            "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8"
+        );
+    }
+
+    #[test]
+    fn sha512_case_1_inputs() {
+        let hkdf = Hkdf::extract(
+            Algorithm::Sha512,
+            &range(0x00, 0x0d),
+            &SecretKey::from_slice(&[0x0b; 22]),
+        );
+        let okm = hkdf.expand(&range(0xf0, 0xfa), 42).unwrap();
+        assert_eq!(
+            hex(okm.expose_secret()),
+            "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c1481579338da362cb8d9f925d7cb"
+        );
+    }
+
+    #[test]
+    fn from_prk_round_trips() {
+        let hkdf = Hkdf::extract(Algorithm::Sha512, b"salt", &Password::from("password"));
+        let again = Hkdf::from_prk(Algorithm::Sha512, hkdf.prk().clone()).unwrap();
+        assert_eq!(
+            hkdf.expand(b"ctx", 64).unwrap(),
+            again.expand(b"ctx", 64).unwrap()
+        );
+        assert_eq!(
+            Hkdf::from_prk(Algorithm::Sha256, hkdf.prk().clone()).unwrap_err(),
+            HkdfError::InvalidPrkLength {
+                expected: 32,
+                actual: 64
+            }
+        );
+    }
+
+    #[test]
+    fn output_length_is_bounded() {
+        let hkdf = Hkdf::extract(Algorithm::Sha256, b"salt", &SecretKey::from_slice(b"ikm"));
+        assert_eq!(hkdf.expand(&[], 255 * 32).unwrap().len(), 255 * 32);
+        assert_eq!(
+            hkdf.expand(&[], 255 * 32 + 1).unwrap_err(),
+            HkdfError::OutputTooLong {
+                requested: 255 * 32 + 1,
+                max: 255 * 32
+            }
+        );
+    }
+
+    #[test]
+    fn distinct_info_gives_distinct_subkeys() {
+        let hkdf = Hkdf::extract(
+            Algorithm::Sha256,
+            b"salt",
+            &SecretKey::from_slice(b"master secret"),
+        );
+        let enc = hkdf.expand(b"encryption", 32).unwrap();
+        let mac = hkdf.expand(b"authentication", 32).unwrap();
+        assert_ne!(enc, mac);
+    }
+
+    #[test]
+    fn derive_key_is_sha512_with_empty_info() {
+        let hkdf = Hkdf::extract(Algorithm::Sha512, b"salt", &Password::from("password"));
+        assert_eq!(
+            hkdf.expand(&[], 64).unwrap(),
+            crate::derive_key(&Password::from("password"), b"salt")
+        );
+    }
+}
@@ -0,0 +1,1435 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Rust bindings generated from Idris 2 ABI definitions (feature
+//! `idris-bindgen`).
+//!
+//! The C and Bebop-V bridges describe their ABIs in `src/abi/Types.idr` and
+//! `src/abi/Foreign.idr`. [`Bindings::scan`] reads those files and
+//! [`Bindings::to_rust`] renders
+//!
+//! - a `#[repr(C)]` struct per `record` and per single-constructor `data`
+//!   type wrapping one integer (a `NonZero` integer if the constructor carries
+//!   a `So (x /= 0)` proof);
+//! - an enum per `data` type of nullary constructors, with the discriminants
+//!   and `#[repr]` of a `Name -> BitsN` function mapping every constructor to
+//!   a literal, such as `resultToInt`;
+//! - an `extern "C" fn` alias per function type alias, such as `Callback`;
+//! - a `pub mod ffi` with an `unsafe extern "C"` block per library, declaring every
+//!   `%foreign "C:symbol, library"` function;
+//! - a safe wrapper stub per Idris function that calls one of those, with the
+//!   Idris wrapper's documentation and signature (`Maybe` becomes `Option`,
+//!   `Either e a` becomes `Result<a, e>`) and a `todo!()` body naming the
+//!   foreign functions it calls.
+//!
+//! Foreign functions without a `C:` specification, such as the Idris
+//! runtime's `support:` functions, and declarations of other shapes (proofs,
+//! platform-indexed types, interface implementations) are skipped.
+//!
+//! The generated code spells out `::std::result::Result` and
+//! `::std::option::Option`, since ABIs commonly declare a `Result` type of
+//! their own. Include it in a module of its own:
+//!
+//! ```ignore
+//! mod abi {
+//!     include!(concat!(env!("OUT_DIR"), "/abi.rs"));
+//! }
+//! ```
+//!
+//! The ABI files in the bridges are templates: fill in `{{project}}` first,
+//! as the `idris-bindgen` binary does with `--project`.
+//!
+//! ```text
+//! cargo run --features idris-bindgen --bin idris-bindgen -- \
+//!     --project example abi.rs ../c/src/abi/Types.idr ../c/src/abi/Foreign.idr
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Idris integer type, Rust type, `NonZero` Rust type.
+const INTEGERS: &[(&str, &str, &str)] = &[
+    ("Bits8", "u8", "NonZeroU8"),
+    ("Bits16", "u16", "NonZeroU16"),
+    ("Bits32", "u32", "NonZeroU32"),
+    ("Bits64", "u64", "NonZeroU64"),
+    ("Int8", "i8", "NonZeroI8"),
+    ("Int16", "i16", "NonZeroI16"),
+    ("Int32", "i32", "NonZeroI32"),
+    ("Int64", "i64", "NonZeroI64"),
+    ("Int", "i64", "NonZeroI64"),
+];
+
+const C_VOID_PTR: &str = "*mut ::std::ffi::c_void";
+
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// The declarations of a set of Idris 2 ABI files.
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    sources: Vec<String>,
+    types: Vec<TypeDef>,
+    foreign: Vec<ForeignFn>,
+    wrappers: Vec<Wrapper>,
+}
+
+#[derive(Debug, Clone)]
+enum TypeDef {
+    Enum {
+        name: String,
+        docs: Vec<String>,
+        repr: Option<&'static str>,
+        variants: Vec<(String, Vec<String>, Option<String>)>,
+    },
+    Newtype {
+        name: String,
+        docs: Vec<String>,
+        field: String,
+    },
+    Struct {
+        name: String,
+        docs: Vec<String>,
+        fields: Vec<(String, Vec<String>, String)>,
+    },
+    Alias {
+        name: String,
+        docs: Vec<String>,
+        ty: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct ForeignFn {
+    symbol: String,
+    library: Option<String>,
+    docs: Vec<String>,
+    params: Vec<String>,
+    ret: String,
+}
+
+#[derive(Debug, Clone)]
+struct Wrapper {
+    name: String,
+    docs: Vec<String>,
+    params: Vec<(String, String)>,
+    ret: String,
+    calls: Vec<String>,
+}
+
+impl Bindings {
+    /// Read and parse the given `.idr` files.
+    pub fn scan<P: AsRef<Path>>(
+        paths: impl IntoIterator<Item = P>,
+    ) -> Result<Bindings, BindgenError> {
+        let mut sources = Vec::new();
+        for path in paths {
+            let path = path.as_ref();
+            let source = fs::read_to_string(path).map_err(|source| BindgenError::Io {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            sources.push((path.to_path_buf(), source));
+        }
+        Bindings::from_sources(sources.iter().map(|(path, s)| (path.as_path(), s.as_str())))
+    }
+
+    /// Parse already-read sources; the paths are only used in errors and in
+    /// the generated file's banner.
+    pub fn from_sources<'a>(
+        sources: impl IntoIterator<Item = (&'a Path, &'a str)>,
+    ) -> Result<Bindings, BindgenError> {
+        let mut names = Vec::new();
+        let mut decls = Vec::new();
+        for (path, source) in sources {
+            names.push(
+                path.file_name()
+                    .unwrap_or(path.as_os_str())
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+            decls.extend(parse_source(path, source)?);
+        }
+        let mut bindings = resolve(&decls)?;
+        bindings.sources = names;
+        Ok(bindings)
+    }
+
+    /// Render the bindings as a Rust source file.
+    pub fn to_rust(&self) -> String {
+        let mut out = format!(
+            "// Generated by idris-bindgen from {}; do not edit.\n",
+            self.sources.join(", ")
+        );
+
+        for def in &self.types {
+            out.push('\n');
+            match def {
+                TypeDef::Enum {
+                    name,
+                    docs,
+                    repr,
+                    variants,
+                } => {
+                    write_docs(&mut out, "", docs);
+                    if let Some(repr) = repr {
+                        let _ = writeln!(out, "#[repr({repr})]");
+                    }
+                    out.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n");
+                    let _ = writeln!(out, "pub enum {name} {{");
+                    for (variant, docs, value) in variants {
+                        write_docs(&mut out, "    ", docs);
+                        match value {
+                            Some(value) => {
+                                let _ = writeln!(out, "    {variant} = {value},");
+                            }
+                            None => {
+                                let _ = writeln!(out, "    {variant},");
+                            }
+                        }
+                    }
+                    out.push_str("}\n");
+                    if let Some(repr) = repr {
+                        let _ = writeln!(out, "\nimpl {name} {{");
+                        let _ = writeln!(
+                            out,
+                            "    /// The variant with discriminant `code`, if any.\n    \
+                             pub const fn from_code(code: {repr}) -> ::std::option::Option<{name}> {{\n        \
+                             match code {{"
+                        );
+                        for (variant, _, value) in variants {
+                            let value = value.as_deref().unwrap_or_default();
+                            let _ = writeln!(
+                                out,
+                                "            {value} => ::std::option::Option::Some({name}::{variant}),"
+                            );
+                        }
+                        out.push_str(
+                            "            _ => ::std::option::Option::None,\n        }\n    }\n}\n",
+                        );
+                    }
+                }
+                TypeDef::Newtype { name, docs, field } => {
+                    write_docs(&mut out, "", docs);
+                    out.push_str("#[repr(transparent)]\n");
+                    out.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n");
+                    let _ = writeln!(out, "pub struct {name}(pub {field});");
+                }
+                TypeDef::Struct { name, docs, fields } => {
+                    write_docs(&mut out, "", docs);
+                    out.push_str("#[repr(C)]\n");
+                    out.push_str("#[derive(Debug, Clone, Copy, PartialEq)]\n");
+                    let _ = writeln!(out, "pub struct {name} {{");
+                    for (field, docs, ty) in fields {
+                        write_docs(&mut out, "    ", docs);
+                        let _ = writeln!(out, "    pub {field}: {ty},");
+                    }
+                    out.push_str("}\n");
+                }
+                TypeDef::Alias { name, docs, ty } => {
+                    write_docs(&mut out, "", docs);
+                    let _ = writeln!(out, "pub type {name} = {ty};");
+                }
+            }
+        }
+
+        if !self.foreign.is_empty() {
+            out.push_str("\n/// The `%foreign \"C:...\"` functions.\npub mod ffi {\n");
+            out.push_str("    #[allow(unused_imports)]\n    use super::*;\n");
+            let mut libraries: Vec<Option<&str>> = Vec::new();
+            for f in &self.foreign {
+                if !libraries.contains(&f.library.as_deref()) {
+                    libraries.push(f.library.as_deref());
+                }
+            }
+            for library in libraries {
+                out.push('\n');
+                if let Some(library) = library {
+                    let _ = writeln!(out, "    #[link(name = \"{library}\")]");
+                }
+                out.push_str("    unsafe extern \"C\" {\n");
+                let mut first = true;
+                for f in self
+                    .foreign
+                    .iter()
+                    .filter(|f| f.library.as_deref() == library)
+                {
+                    if !first {
+                        out.push('\n');
+                    }
+                    first = false;
+                    write_docs(&mut out, "        ", &f.docs);
+                    let params: Vec<String> = f
+                        .params
+                        .iter()
+                        .enumerate()
+                        .map(|(i, ty)| format!("arg{i}: {ty}"))
+                        .collect();
+                    let _ = writeln!(
+                        out,
+                        "        pub fn {}({}){};",
+                        f.symbol,
+                        params.join(", "),
+                        returns(&f.ret)
+                    );
+                }
+                out.push_str("    }\n");
+            }
+            out.push_str("}\n");
+        }
+
+        for w in &self.wrappers {
+            out.push('\n');
+            write_docs(&mut out, "", &w.docs);
+            if !w.docs.is_empty() {
+                out.push_str("///\n");
+            }
+            let calls: Vec<String> = w.calls.iter().map(|c| format!("[`ffi::{c}`]")).collect();
+            let _ = writeln!(out, "/// Calls {}.", calls.join(", "));
+            if !w.params.is_empty() {
+                out.push_str("#[allow(unused_variables)]\n");
+            }
+            let params: Vec<String> = w
+                .params
+                .iter()
+                .map(|(name, ty)| format!("{name}: {ty}"))
+                .collect();
+            let _ = writeln!(
+                out,
+                "pub fn {}({}){} {{\n    todo!(\"port `{}` from the Idris 2 ABI\")\n}}",
+                rust_ident(&snake_case(&w.name)),
+                params.join(", "),
+                returns(&w.ret),
+                w.name
+            );
+        }
+        out
+    }
+}
+
+fn write_docs(out: &mut String, indent: &str, docs: &[String]) {
+    for line in docs {
+        if line.is_empty() {
+            let _ = writeln!(out, "{indent}///");
+        } else {
+            let _ = writeln!(out, "{indent}/// {line}");
+        }
+    }
+}
+
+fn returns(ty: &str) -> String {
+    if ty == "()" {
+        String::new()
+    } else {
+        format!(" -> {ty}")
+    }
+}
+
+/// Replace the `{{project}}` and `{{PROJECT}}` placeholders of the bridges'
+/// ABI templates with `project`.
+pub fn fill_template(source: &str, project: &str) -> String {
+    source
+        .replace("{{project}}", project)
+        .replace("{{PROJECT}}", project)
+}
+
+// ---------------------------------------------------------------------------
+// Declarations
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+enum Decl {
+    Data {
+        name: String,
+        docs: Vec<String>,
+        constructors: Vec<(String, Vec<String>, Signature)>,
+    },
+    Record {
+        name: String,
+        docs: Vec<String>,
+        fields: Vec<(String, Vec<String>, Ty)>,
+    },
+    Alias {
+        name: String,
+        docs: Vec<String>,
+        ty: Ty,
+    },
+    Function {
+        name: String,
+        docs: Vec<String>,
+        link: Link,
+        sig: Signature,
+        clauses: Vec<String>,
+    },
+}
+
+/// How a function is implemented.
+#[derive(Debug, Clone, PartialEq)]
+enum Link {
+    /// In Idris.
+    Idris,
+    /// `%foreign "C:symbol, library"`.
+    C {
+        symbol: String,
+        library: Option<String>,
+    },
+    /// `%foreign` without a `C:` specification.
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Ty {
+    Unit,
+    App(String, Vec<Ty>),
+    Fn(Vec<Ty>, Box<Ty>),
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::Unit => f.write_str("()"),
+            Ty::App(head, args) => {
+                f.write_str(head)?;
+                for arg in args {
+                    match arg {
+                        Ty::App(_, a) if !a.is_empty() => write!(f, " ({arg})")?,
+                        Ty::Fn(..) => write!(f, " ({arg})")?,
+                        _ => write!(f, " {arg}")?,
+                    }
+                }
+                Ok(())
+            }
+            Ty::Fn(params, ret) => {
+                for param in params {
+                    match param {
+                        Ty::Fn(..) => write!(f, "({param}) -> ")?,
+                        _ => write!(f, "{param} -> ")?,
+                    }
+                }
+                write!(f, "{ret}")
+            }
+        }
+    }
+}
+
+/// A type signature: explicit parameters with their binder names, the raw
+/// text of the implicit ones, and the result.
+#[derive(Debug, Clone, PartialEq)]
+struct Signature {
+    params: Vec<(Option<String>, Ty)>,
+    implicits: Vec<String>,
+    ret: Ty,
+}
+
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+fn parse_source(path: &Path, source: &str) -> Result<Vec<Decl>, BindgenError> {
+    let lines: Vec<Line<'_>> = source
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let text = line.trim_start();
+            (!text.is_empty() && !text.starts_with("--")).then(|| Line {
+                number: i + 1,
+                indent: line.len() - text.len(),
+                text: text.trim_end(),
+            })
+        })
+        .collect();
+    let syntax = |line: &Line<'_>, reason| BindgenError::Syntax {
+        path: path.to_path_buf(),
+        line: line.number,
+        reason,
+    };
+
+    let mut decls = Vec::new();
+    let mut docs = Vec::new();
+    let mut link = Link::Idris;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        let text = line.text;
+        i += 1;
+
+        if let Some(doc) = text.strip_prefix("|||") {
+            docs.push(doc.strip_prefix(' ').unwrap_or(doc).to_string());
+            continue;
+        }
+        if let Some(specs) = text.strip_prefix("%foreign") {
+            link = foreign_link(specs).map_err(|reason| syntax(line, reason))?;
+            continue;
+        }
+        if text.starts_with("module ") || text.starts_with("namespace ") {
+            docs.clear();
+            continue;
+        }
+        if is_modifier(text) {
+            continue;
+        }
+
+        // The declaration runs over the more indented lines that follow, and
+        // over the clauses of a function after its signature.
+        let head = first_word(text);
+        let start = i;
+        while i < lines.len()
+            && (lines[i].indent > line.indent
+                || (lines[i].indent == line.indent
+                    && first_word(lines[i].text) == head
+                    && !lines[i].text.starts_with("|||")))
+        {
+            i += 1;
+        }
+        let body = &lines[start..i];
+        let docs = std::mem::take(&mut docs);
+        let link = std::mem::replace(&mut link, Link::Idris);
+
+        let decl = match head {
+            "data" => parse_data(text, body, docs).map_err(|reason| syntax(line, reason))?,
+            "record" => parse_record(text, body, docs).map_err(|reason| syntax(line, reason))?,
+            _ => match text.split_once(" : ") {
+                Some((name, ty)) if is_ident(name) => {
+                    let mut sig = ty.to_string();
+                    let mut clauses = body.iter();
+                    let mut rest = clauses.clone();
+                    while let Some(next) = rest.next() {
+                        if !next.text.starts_with("->") && !sig.ends_with("->") {
+                            break;
+                        }
+                        sig.push(' ');
+                        sig.push_str(next.text);
+                        clauses = rest.clone();
+                    }
+                    let clauses: Vec<String> = clauses.map(|l| l.text.to_string()).collect();
+                    if sig.trim() == "Type" {
+                        let rhs = clauses
+                            .first()
+                            .and_then(|c| c.strip_prefix(name))
+                            .and_then(|c| c.trim_start().strip_prefix('='));
+                        match rhs {
+                            Some(rhs) => Some(Decl::Alias {
+                                name: name.to_string(),
+                                docs,
+                                ty: parse_type(rhs).map_err(|reason| syntax(line, reason))?,
+                            }),
+                            None => None,
+                        }
+                    } else {
+                        // Only foreign functions must have a signature this
+                        // parser understands; other functions are skipped.
+                        match parse_signature(&sig) {
+                            Ok(sig) => Some(Decl::Function {
+                                name: name.to_string(),
+                                docs,
+                                link,
+                                sig,
+                                clauses,
+                            }),
+                            Err(reason) if matches!(link, Link::C { .. }) => {
+                                return Err(syntax(line, reason));
+                            }
+                            Err(_) => None,
+                        }
+                    }
+                }
+                _ => None,
+            },
+        };
+        decls.extend(decl);
+    }
+    Ok(decls)
+}
+
+fn is_modifier(text: &str) -> bool {
+    matches!(
+        text,
+        "export" | "public export" | "private" | "total" | "partial" | "covering"
+    ) || text.starts_with("module ")
+        || text.starts_with("import ")
+        || text.starts_with('%')
+}
+
+fn first_word(text: &str) -> &str {
+    text.split(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .unwrap_or_default()
+}
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '\'')
+}
+
+/// The `C:` specification of a `%foreign` directive, or [`Link::Other`].
+fn foreign_link(specs: &str) -> Result<Link, &'static str> {
+    let mut rest = specs.trim();
+    let mut link = Link::Other;
+    while !rest.is_empty() {
+        let (spec, tail) = rest
+            .strip_prefix('"')
+            .and_then(|r| r.split_once('"'))
+            .ok_or("expected quoted `%foreign` specifications")?;
+        rest = tail.trim_start();
+        if link != Link::Other {
+            continue;
+        }
+        if let Some(spec) = spec.strip_prefix("C:") {
+            let mut parts = spec.split(',').map(str::trim);
+            let symbol = parts
+                .next()
+                .filter(|s| is_ident(s))
+                .ok_or("`C:` symbol is not a C identifier (unfilled `{{project}}`?)")?
+                .to_string();
+            let library = parts.next().filter(|l| !l.is_empty()).map(link_name);
+            link = Link::C { symbol, library };
+        }
+    }
+    Ok(link)
+}
+
+/// `libexample.so` links as `example`.
+fn link_name(library: &str) -> String {
+    let name = library.strip_prefix("lib").unwrap_or(library);
+    name.split('.').next().unwrap_or(name).to_string()
+}
+
+/// `data Name = A | B`, or `data Name : Type where` with one constructor
+/// signature per line. Indexed families are skipped.
+fn parse_data(
+    head: &str,
+    body: &[Line<'_>],
+    docs: Vec<String>,
+) -> Result<Option<Decl>, &'static str> {
+    let head = head.strip_prefix("data ").unwrap_or(head);
+    if let Some((name, rhs)) = head.split_once('=') {
+        let mut rhs = rhs.to_string();
+        for line in body {
+            rhs.push(' ');
+            rhs.push_str(line.text);
+        }
+        let constructors = rhs
+            .split('|')
+            .map(|c| {
+                let c = c.trim();
+                if !is_ident(c) {
+                    return None;
+                }
+                let sig = Signature {
+                    params: Vec::new(),
+                    implicits: Vec::new(),
+                    ret: Ty::App(name.trim().to_string(), Vec::new()),
+                };
+                Some((c.to_string(), Vec::new(), sig))
+            })
+            .collect::<Option<Vec<_>>>();
+        return Ok(constructors.map(|constructors| Decl::Data {
+            name: name.trim().to_string(),
+            docs,
+            constructors,
+        }));
+    }
+
+    let Some((name, kind)) = head
+        .strip_suffix(" where")
+        .and_then(|h| h.split_once(" : "))
+    else {
+        return Err("expected `data Name = ...` or `data Name : Type where`");
+    };
+    if kind.trim() != "Type" || !is_ident(name.trim()) {
+        return Ok(None);
+    }
+    let mut constructors = Vec::new();
+    let mut con_docs = Vec::new();
+    for line in body {
+        if let Some(doc) = line.text.strip_prefix("|||") {
+            con_docs.push(doc.strip_prefix(' ').unwrap_or(doc).to_string());
+        } else if let Some((con, sig)) = line.text.split_once(" : ") {
+            let Ok(sig) = parse_signature(sig) else {
+                return Ok(None);
+            };
+            constructors.push((con.trim().to_string(), std::mem::take(&mut con_docs), sig));
+        }
+    }
+    Ok(Some(Decl::Data {
+        name: name.trim().to_string(),
+        docs,
+        constructors,
+    }))
+}
+
+/// `record Name where` with a `constructor` line and one field per line.
+/// Parameterised records are skipped.
+fn parse_record(
+    head: &str,
+    body: &[Line<'_>],
+    docs: Vec<String>,
+) -> Result<Option<Decl>, &'static str> {
+    let Some(name) = head
+        .strip_prefix("record ")
+        .and_then(|h| h.strip_suffix(" where"))
+        .map(str::trim)
+    else {
+        return Err("expected `record Name where`");
+    };
+    if !is_ident(name) {
+        return Ok(None);
+    }
+    let mut fields = Vec::new();
+    let mut field_docs = Vec::new();
+    for line in body {
+        if let Some(doc) = line.text.strip_prefix("|||") {
+            field_docs.push(doc.strip_prefix(' ').unwrap_or(doc).to_string());
+        } else if let Some((field, ty)) = line.text.split_once(" : ") {
+            fields.push((
+                field.trim().to_string(),
+                std::mem::take(&mut field_docs),
+                parse_type(ty)?,
+            ));
+        }
+    }
+    Ok(Some(Decl::Record {
+        name: name.to_string(),
+        docs,
+        fields,
+    }))
+}
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Arrow,
+    Colon,
+    Open,
+    Close,
+    /// The text of a `{...}` binder.
+    Implicit(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            ':' => tokens.push(Token::Colon),
+            '-' if chars.next_if(|&(_, c)| c == '>').is_some() => tokens.push(Token::Arrow),
+            '{' => {
+                let mut depth = 1;
+                let mut end = None;
+                for (j, c) in chars.by_ref() {
+                    match c {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        end = Some(j);
+                        break;
+                    }
+                }
+                let end = end.ok_or("unclosed `{`")?;
+                tokens.push(Token::Implicit(text[i + 1..end].trim().to_string()));
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut end = i + c.len_utf8();
+                while let Some((j, c)) =
+                    chars.next_if(|&(_, c)| c.is_ascii_alphanumeric() || "_'.".contains(c))
+                {
+                    end = j + c.len_utf8();
+                }
+                tokens.push(Token::Ident(text[i..end].to_string()));
+            }
+            _ => return Err("unexpected character in type"),
+        }
+    }
+    Ok(tokens)
+}
+
+/// One element of an arrow chain.
+enum Part {
+    Explicit(Option<String>, Ty),
+    Implicit(String),
+}
+
+struct TypeParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl TypeParser {
+    fn peek(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn chain(&mut self) -> Result<Vec<Part>, &'static str> {
+        let mut parts = vec![self.part()?];
+        while self.peek(0) == Some(&Token::Arrow) {
+            self.pos += 1;
+            parts.push(self.part()?);
+        }
+        Ok(parts)
+    }
+
+    fn part(&mut self) -> Result<Part, &'static str> {
+        if let Some(Token::Implicit(text)) = self.peek(0) {
+            let text = text.clone();
+            self.pos += 1;
+            return Ok(Part::Implicit(text));
+        }
+        if let (Some(Token::Open), Some(Token::Ident(name)), Some(Token::Colon)) =
+            (self.peek(0), self.peek(1), self.peek(2))
+        {
+            let name = name.clone();
+            self.pos += 3;
+            let ty = chain_type(self.chain()?)?;
+            self.expect_close()?;
+            return Ok(Part::Explicit(Some(name), ty));
+        }
+        Ok(Part::Explicit(None, self.application()?))
+    }
+
+    fn application(&mut self) -> Result<Ty, &'static str> {
+        let head = self.atom()?;
+        let mut args = Vec::new();
+        while matches!(self.peek(0), Some(Token::Ident(_) | Token::Open)) {
+            args.push(self.atom()?);
+        }
+        match head {
+            _ if args.is_empty() => Ok(head),
+            Ty::App(name, none) if none.is_empty() => Ok(Ty::App(name, args)),
+            _ => Err("unsupported type application"),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Ty, &'static str> {
+        match self.peek(0).cloned() {
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                Ok(Ty::App(name, Vec::new()))
+            }
+            Some(Token::Open) => {
+                self.pos += 1;
+                if self.peek(0) == Some(&Token::Close) {
+                    self.pos += 1;
+                    return Ok(Ty::Unit);
+                }
+                let ty = chain_type(self.chain()?)?;
+                self.expect_close()?;
+                Ok(ty)
+            }
+            _ => Err("expected a type"),
+        }
+    }
+
+    fn expect_close(&mut self) -> Result<(), &'static str> {
+        if self.peek(0) != Some(&Token::Close) {
+            return Err("expected `)`");
+        }
+        self.pos += 1;
+        Ok(())
+    }
+}
+
+fn chain_type(parts: Vec<Part>) -> Result<Ty, &'static str> {
+    let mut types: Vec<Ty> = parts
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Explicit(_, ty) => Some(ty),
+            Part::Implicit(_) => None,
+        })
+        .collect();
+    let ret = types.pop().ok_or("expected a type")?;
+    Ok(if types.is_empty() {
+        ret
+    } else {
+        Ty::Fn(types, Box::new(ret))
+    })
+}
+
+fn parse_chain(text: &str) -> Result<Vec<Part>, &'static str> {
+    let mut parser = TypeParser {
+        tokens: tokenize(text)?,
+        pos: 0,
+    };
+    let parts = parser.chain()?;
+    if parser.pos != parser.tokens.len() {
+        return Err("unexpected tokens after type");
+    }
+    Ok(parts)
+}
+
+fn parse_type(text: &str) -> Result<Ty, &'static str> {
+    chain_type(parse_chain(text)?)
+}
+
+fn parse_signature(text: &str) -> Result<Signature, &'static str> {
+    let mut params = Vec::new();
+    let mut implicits = Vec::new();
+    for part in parse_chain(text)? {
+        match part {
+            Part::Explicit(name, ty) => params.push((name, ty)),
+            Part::Implicit(text) => implicits.push(text),
+        }
+    }
+    let (_, ret) = params.pop().ok_or("expected a type")?;
+    Ok(Signature {
+        params,
+        implicits,
+        ret,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Rust types
+// ---------------------------------------------------------------------------
+
+/// What a declared Idris type name stands for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    /// A C-compatible type: a struct, a `#[repr]` enum, a newtype or an alias.
+    C,
+    /// An enum without discriminants, usable by the safe wrappers only.
+    Enum,
+}
+
+struct Resolver {
+    kinds: HashMap<String, Kind>,
+}
+
+impl Resolver {
+    /// The Rust type of `ty` across the C ABI.
+    fn c_type(&self, ty: &Ty) -> Option<String> {
+        match ty {
+            Ty::Unit => Some("()".to_string()),
+            Ty::App(name, args) if args.is_empty() => match name.as_str() {
+                "Double" => Some("f64".to_string()),
+                "AnyPtr" | "GCAnyPtr" => Some(C_VOID_PTR.to_string()),
+                "String" => Some("*const ::std::ffi::c_char".to_string()),
+                _ => integer(name)
+                    .map(|(rust, _)| rust.to_string())
+                    .or_else(|| (self.kinds.get(name) == Some(&Kind::C)).then(|| name.clone())),
+            },
+            Ty::App(name, args) if args.len() == 1 && (name == "Ptr" || name == "GCPtr") => {
+                Some(C_VOID_PTR.to_string())
+            }
+            Ty::Fn(params, ret) => {
+                let params = params
+                    .iter()
+                    .map(|p| self.c_param(p))
+                    .collect::<Option<Vec<_>>>()?;
+                let ret = self.c_type(ret)?;
+                Some(format!(
+                    "unsafe extern \"C\" fn({}){}",
+                    params.join(", "),
+                    returns(&ret)
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    fn c_param(&self, ty: &Ty) -> Option<String> {
+        self.c_type(ty).filter(|ty| ty != "()")
+    }
+
+    /// The Rust type of `ty` in a safe wrapper's signature.
+    fn safe_type(&self, ty: &Ty, param: bool) -> Option<String> {
+        let Ty::App(name, args) = ty else {
+            return self.c_type(ty).filter(|_| !matches!(ty, Ty::Fn(..)));
+        };
+        match (name.as_str(), args.as_slice()) {
+            ("IO", [a]) if !param => self.safe_type(a, false),
+            ("Bool", []) => Some("bool".to_string()),
+            ("String", []) => Some(if param { "&str" } else { "String" }.to_string()),
+            ("Maybe", [a]) => Some(format!(
+                "::std::option::Option<{}>",
+                self.safe_type(a, param)?
+            )),
+            ("Either", [e, a]) => Some(format!(
+                "::std::result::Result<{}, {}>",
+                self.safe_type(a, param)?,
+                self.safe_type(e, param)?
+            )),
+            ("List", [a]) if param => Some(format!("&[{}]", self.safe_type(a, false)?)),
+            ("List", [a]) => Some(format!("Vec<{}>", self.safe_type(a, false)?)),
+            (name, []) if self.kinds.contains_key(name) => Some(name.to_string()),
+            _ => self.c_type(ty),
+        }
+    }
+}
+
+fn integer(name: &str) -> Option<(&'static str, &'static str)> {
+    INTEGERS
+        .iter()
+        .find(|(idris, _, _)| *idris == name)
+        .map(|&(_, rust, nonzero)| (rust, nonzero))
+}
+
+fn resolve(decls: &[Decl]) -> Result<Bindings, BindgenError> {
+    let mut kinds = HashMap::new();
+    for decl in decls {
+        match decl {
+            Decl::Data {
+                name, constructors, ..
+            } => {
+                if constructors.iter().all(|(_, _, sig)| sig.params.is_empty()) {
+                    let kind = if discriminants(decls, name, constructors).is_some() {
+                        Kind::C
+                    } else {
+                        Kind::Enum
+                    };
+                    kinds.insert(name.clone(), kind);
+                } else if newtype_field(constructors).is_some() {
+                    kinds.insert(name.clone(), Kind::C);
+                }
+            }
+            Decl::Record { name, .. } | Decl::Alias { name, .. } => {
+                kinds.insert(name.clone(), Kind::C);
+            }
+            Decl::Function { .. } => {}
+        }
+    }
+    let resolver = Resolver { kinds };
+    let unsupported = |item: &str, ty: &Ty| BindgenError::Unsupported {
+        item: item.to_string(),
+        ty: ty.to_string(),
+    };
+
+    let mut types = Vec::new();
+    let mut foreign = Vec::new();
+    let mut symbols = HashMap::new();
+    for decl in decls {
+        match decl {
+            Decl::Data {
+                name,
+                docs,
+                constructors,
+            } => {
+                if constructors.iter().all(|(_, _, sig)| sig.params.is_empty()) {
+                    let values = discriminants(decls, name, constructors);
+                    let variants = constructors
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (con, docs, _))| {
+                            let value = values.as_ref().map(|(_, v)| v[i].clone());
+                            (con.clone(), docs.clone(), value)
+                        })
+                        .collect();
+                    types.push(TypeDef::Enum {
+                        name: name.clone(),
+                        docs: docs.clone(),
+                        repr: values.map(|(repr, _)| repr),
+                        variants,
+                    });
+                } else if let Some(field) = newtype_field(constructors) {
+                    types.push(TypeDef::Newtype {
+                        name: name.clone(),
+                        docs: docs.clone(),
+                        field,
+                    });
+                }
+            }
+            Decl::Record { name, docs, fields } => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, docs, ty)| {
+                        let rust = resolver
+                            .c_param(ty)
+                            .ok_or_else(|| unsupported(&format!("{name}.{field}"), ty))?;
+                        Ok((rust_ident(&snake_case(field)), docs.clone(), rust))
+                    })
+                    .collect::<Result<_, BindgenError>>()?;
+                types.push(TypeDef::Struct {
+                    name: name.clone(),
+                    docs: docs.clone(),
+                    fields,
+                });
+            }
+            Decl::Alias { name, docs, ty } => {
+                types.push(TypeDef::Alias {
+                    name: name.clone(),
+                    docs: docs.clone(),
+                    ty: resolver.c_param(ty).ok_or_else(|| unsupported(name, ty))?,
+                });
+            }
+            Decl::Function {
+                name,
+                docs,
+                link: Link::C { symbol, library },
+                sig,
+                ..
+            } => {
+                let params = sig
+                    .params
+                    .iter()
+                    .map(|(_, ty)| resolver.c_param(ty).ok_or_else(|| unsupported(name, ty)))
+                    .collect::<Result<_, _>>()?;
+                let ret = match &sig.ret {
+                    Ty::App(io, args) if io == "PrimIO" && args.len() == 1 => &args[0],
+                    ret => ret,
+                };
+                let ret = resolver.c_type(ret).ok_or_else(|| unsupported(name, ret))?;
+                symbols.insert(name.as_str(), symbol.as_str());
+                foreign.push(ForeignFn {
+                    symbol: symbol.clone(),
+                    library: library.clone(),
+                    docs: docs.clone(),
+                    params,
+                    ret,
+                });
+            }
+            Decl::Function { .. } => {}
+        }
+    }
+
+    let mut wrappers = Vec::new();
+    for decl in decls {
+        let Decl::Function {
+            name,
+            docs,
+            link: Link::Idris,
+            sig,
+            clauses,
+        } = decl
+        else {
+            continue;
+        };
+        let mut calls = Vec::new();
+        for clause in clauses {
+            let mut rest = clause.as_str();
+            while let Some(at) = rest.find("prim__") {
+                let ident: String = rest[at..]
+                    .chars()
+                    .take_while(|&c| c.is_ascii_alphanumeric() || c == '_' || c == '\'')
+                    .collect();
+                rest = &rest[at + ident.len()..];
+                if let Some(symbol) = symbols.get(ident.as_str()) {
+                    if !calls.iter().any(|c| c == symbol) {
+                        calls.push(symbol.to_string());
+                    }
+                }
+            }
+        }
+        if calls.is_empty() {
+            continue;
+        }
+
+        let names = clause_params(name, clauses, sig.params.len());
+        let params = sig
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, (binder, ty))| {
+                let param = names
+                    .as_ref()
+                    .map(|names| names[i].clone())
+                    .or_else(|| binder.clone())
+                    .unwrap_or_else(|| format!("arg{i}"));
+                let rust = resolver
+                    .safe_type(ty, true)
+                    .ok_or_else(|| unsupported(name, ty))?;
+                Ok((rust_ident(&snake_case(&param)), rust))
+            })
+            .collect::<Result<_, BindgenError>>()?;
+        let ret = resolver
+            .safe_type(&sig.ret, false)
+            .ok_or_else(|| unsupported(name, &sig.ret))?;
+        wrappers.push(Wrapper {
+            name: name.clone(),
+            docs: docs.clone(),
+            params,
+            ret,
+            calls,
+        });
+    }
+
+    Ok(Bindings {
+        sources: Vec::new(),
+        types,
+        foreign,
+        wrappers,
+    })
+}
+
+/// The `#[repr]` and discriminants of the enum `name`: from a function of
+/// type `name -> BitsN` with one clause mapping each constructor to a
+/// literal, such as `resultToInt`.
+fn discriminants(
+    decls: &[Decl],
+    name: &str,
+    constructors: &[(String, Vec<String>, Signature)],
+) -> Option<(&'static str, Vec<String>)> {
+    decls.iter().find_map(|decl| {
+        let Decl::Function {
+            name: function,
+            link: Link::Idris,
+            sig,
+            clauses,
+            ..
+        } = decl
+        else {
+            return None;
+        };
+        let [(_, Ty::App(param, none))] = sig.params.as_slice() else {
+            return None;
+        };
+        let Ty::App(ret, args) = &sig.ret else {
+            return None;
+        };
+        if param != name || !none.is_empty() || !args.is_empty() {
+            return None;
+        }
+        let (repr, _) = integer(ret)?;
+        let mut values: HashMap<&str, String> = HashMap::new();
+        for clause in clauses {
+            let rest = clause.strip_prefix(function.as_str())?;
+            let (con, value) = rest.split_once('=')?;
+            let value = value.trim();
+            let digits = value.strip_prefix('-').unwrap_or(value);
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            values.insert(con.trim(), value.to_string());
+        }
+        let values = constructors
+            .iter()
+            .map(|(con, _, _)| values.get(con.as_str()).cloned())
+            .collect::<Option<Vec<_>>>()?;
+        Some((repr, values))
+    })
+}
+
+/// The field type of a single-constructor type wrapping one integer, such as
+/// `MkHandle : (ptr : Bits64) -> {auto 0 nonNull : So (ptr /= 0)} -> Handle`.
+fn newtype_field(constructors: &[(String, Vec<String>, Signature)]) -> Option<String> {
+    let [(_, _, sig)] = constructors else {
+        return None;
+    };
+    let [(binder, Ty::App(ty, args))] = sig.params.as_slice() else {
+        return None;
+    };
+    let (rust, nonzero) = integer(ty).filter(|_| args.is_empty())?;
+    let proof = binder.as_ref().map(|b| {
+        let proof: String = format!("So ({b} /= 0)").split_whitespace().collect();
+        proof
+    });
+    let non_null = sig.implicits.iter().any(|implicit| {
+        let implicit: String = implicit.split_whitespace().collect();
+        proof
+            .as_ref()
+            .is_some_and(|p| implicit.ends_with(p.as_str()))
+    });
+    Some(if non_null {
+        format!("::std::num::{nonzero}")
+    } else {
+        rust.to_string()
+    })
+}
+
+/// The parameter names of a function's single clause, such as `h buf len` in
+/// `processArray h buf len = do`, if they are all plain variables.
+fn clause_params(name: &str, clauses: &[String], arity: usize) -> Option<Vec<String>> {
+    let clauses: Vec<&String> = clauses.iter().filter(|c| first_word(c) == name).collect();
+    let [clause] = clauses.as_slice() else {
+        return None;
+    };
+    let (lhs, _) = clause.split_once('=')?;
+    let names: Vec<String> = lhs.split_whitespace().skip(1).map(str::to_string).collect();
+    let plain = names
+        .iter()
+        .all(|n| is_ident(n) && n.starts_with(|c: char| c.is_ascii_lowercase()));
+    (plain && names.len() == arity).then_some(names)
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c == '\'' {
+            out.push('_');
+            continue;
+        }
+        if c.is_ascii_uppercase() && prev_lower {
+            out.push('_');
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+fn rust_ident(name: &str) -> String {
+    if matches!(name, "self" | "super" | "crate") {
+        format!("{name}_")
+    } else if RUST_KEYWORDS.contains(&name) {
+        format!("r#{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+/// An error from [`Bindings::scan`] or [`Bindings::from_sources`].
+#[derive(Debug)]
+pub enum BindgenError {
+    /// A source file could not be read.
+    Io { path: PathBuf, source: io::Error },
+    /// A declaration this generator reads is malformed.
+    Syntax {
+        path: PathBuf,
+        line: usize,
+        reason: &'static str,
+    },
+    /// `item` uses an Idris type with no Rust equivalent.
+    Unsupported { item: String, ty: String },
+}
+
+impl fmt::Display for BindgenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindgenError::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            BindgenError::Syntax { path, line, reason } => {
+                write!(f, "{}:{line}: {reason}", path.display())
+            }
+            BindgenError::Unsupported { item, ty } => {
+                write!(f, "{item}: `{ty}` has no Rust equivalent")
+            }
+        }
+    }
+}
+
+impl Error for BindgenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BindgenError::Io { source, .. } => Some(source),
+            BindgenError::Syntax { .. } | BindgenError::Unsupported { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const C_TYPES: &str = include_str!("../../../c/src/abi/Types.idr");
+    const C_FOREIGN: &str = include_str!("../../../c/src/abi/Foreign.idr");
+
+    fn bindings(sources: &[&str]) -> Result<Bindings, BindgenError> {
+        Bindings::from_sources(sources.iter().map(|s| (Path::new("Test.idr"), *s)))
+    }
+
+    fn template(types: &str, foreign: &str) -> String {
+        let types = fill_template(types, "example");
+        let foreign = fill_template(foreign, "example");
+        bindings(&[&types, &foreign]).unwrap().to_rust()
+    }
+
+    #[test]
+    fn c_bridge_abi_template() {
+        let rust = template(C_TYPES, C_FOREIGN);
+        for expected in [
+            "#[repr(u32)]\n#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub enum Result {",
+            "    /// Null pointer encountered\n    NullPointer = 4,\n",
+            "pub struct Handle(pub ::std::num::NonZeroU64);",
+            "#[repr(C)]\n#[derive(Debug, Clone, Copy, PartialEq)]\npub struct ExampleStruct {\n    \
+             pub field1: u32,\n    pub field2: u64,\n    pub field3: f64,\n}",
+            "pub type Callback = unsafe extern \"C\" fn(u64, u32) -> u32;",
+            "    #[link(name = \"example\")]\n    unsafe extern \"C\" {",
+            "        /// Initialize the library\n        /// Returns a handle to the library \
+             instance, or Nothing on failure\n        pub fn example_init() -> u64;",
+            "pub fn example_register_callback(arg0: u64, arg1: *mut ::std::ffi::c_void) -> u32;",
+            "/// Calls [`ffi::example_get_string`], [`ffi::example_free_string`].",
+            "pub fn process_array(h: Handle, buf: u64, len: u32) -> \
+             ::std::result::Result<(), Result> {",
+            "pub fn init() -> ::std::option::Option<Handle> {\n    \
+             todo!(\"port `init` from the Idris 2 ABI\")\n}",
+        ] {
+            assert!(rust.contains(expected), "missing {expected:?} in\n{rust}");
+        }
+        // The module documentation and the Idris runtime's `support:`
+        // functions are not part of the ABI.
+        assert!(!rust.contains("ABI Type Definitions Template"));
+        assert!(!rust.contains("Foreign Function Interface Declarations"));
+        assert!(!rust.contains("idris2_getString"));
+        assert!(!rust.contains("fn error_description"));
+    }
+
+    #[test]
+    fn bridges_share_one_abi() {
+        let bebop = template(
+            include_str!("../../../bebop-v/src/abi/Types.idr"),
+            include_str!("../../../bebop-v/src/abi/Foreign.idr"),
+        );
+        assert_eq!(bebop, template(C_TYPES, C_FOREIGN));
+    }
+
+    #[test]
+    fn discriminants_need_every_constructor() {
+        let source = "data Mode = Read | Write\n\
+                      \n\
+                      modeToInt : Mode -> Bits8\n\
+                      modeToInt Read = 1\n";
+        let rust = bindings(&[source]).unwrap().to_rust();
+        assert!(rust.contains("pub enum Mode {\n    Read,\n    Write,\n}"));
+        assert!(!rust.contains("repr"));
+
+        let source = format!("{source}modeToInt Write = 2\n");
+        let rust = bindings(&[&source]).unwrap().to_rust();
+        assert!(rust.contains("#[repr(u8)]"));
+        assert!(rust.contains("    Write = 2,\n"));
+        assert!(rust.contains("pub const fn from_code(code: u8)"));
+    }
+
+    #[test]
+    fn wrapper_names_come_from_clauses_then_binders() {
+        let source = "%foreign \"C:lib_send, libnet\"\n\
+                      prim__send : Bits64 -> String -> PrimIO Int32\n\
+                      \n\
+                      sendTo : (sockFd : Bits64) -> (type : String) -> IO Int32\n\
+                      sendTo fd = primIO . prim__send fd\n";
+        let rust = bindings(&[source]).unwrap().to_rust();
+        assert!(
+            rust.contains("pub fn lib_send(arg0: u64, arg1: *const ::std::ffi::c_char) -> i32;")
+        );
+        assert!(rust.contains("pub fn send_to(sock_fd: u64, r#type: &str) -> i32 {"));
+    }
+
+    #[test]
+    fn unsupported_foreign_types_are_errors() {
+        let source = "%foreign \"C:count, libcount\"\nprim__count : Nat -> PrimIO ()\n";
+        match bindings(&[source]) {
+            Err(BindgenError::Unsupported { item, ty }) => {
+                assert_eq!((item.as_str(), ty.as_str()), ("prim__count", "Nat"));
+            }
+            other => panic!("expected Unsupported, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn unfilled_templates_are_errors() {
+        match bindings(&[C_FOREIGN]) {
+            Err(err @ BindgenError::Syntax { line: 23, .. }) => {
+                assert!(err.to_string().starts_with("Test.idr:23: `C:` symbol"));
+            }
+            other => panic!("expected a syntax error, got {other:?}"),
+        }
+    }
+}
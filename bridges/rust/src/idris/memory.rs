@@ -0,0 +1,469 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Owning handles for memory from the Idris 2 bridge allocator.
+//!
+//! `bridges/idris2/src/memory.zig` allocates Idris values from one global
+//! allocator (`memory.alloc`, `Pool`, `toIdrisString`) or from an `Arena`.
+//! The Zig core exports `idris_*` functions that release through the same
+//! allocator, and the types here call them on drop:
+//!
+//! - [`IdrisBox<T>`] owns one `T` and frees it with `idris_free`.
+//! - [`IdrisStringBuf`] owns the data of an [`IdrisString`] and frees it with
+//!   `idris_string_free`, like `freeIdrisString`.
+//! - [`IdrisArena`] owns an `Arena`. Its allocations are borrows of the arena,
+//!   so they cannot outlive [`reset`](IdrisArena::reset) or the arena itself,
+//!   and there is no way to free one on its own.
+//! - [`Managed<T>`] is `Managed(T)`: a value and the cleanup function the
+//!   Idris side handed over with it.
+//!
+//! Each kind of allocation has its own type and none converts into another or
+//! into a `Box`, so memory can only be released through the function that
+//! matches where it came from:
+//!
+//! ```compile_fail
+//! use rust_zig_ffi::idris::IdrisArena;
+//!
+//! let mut arena = IdrisArena::new();
+//! let n = arena.alloc(1u64);
+//! arena.reset(); // `n` still borrows the arena
+//! *n += 1;
+//! ```
+//!
+//! ```compile_fail
+//! use rust_zig_ffi::idris::{IdrisArena, IdrisString};
+//!
+//! let name: IdrisString<'_> = {
+//!     let arena = IdrisArena::new();
+//!     arena.alloc_str("dropped with the arena")
+//! };
+//! ```
+
+use std::alloc::{handle_alloc_error, Layout};
+use std::ffi::c_void;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::ptr::{self, NonNull};
+use std::slice;
+
+use super::IdrisString;
+
+extern "C" {
+    fn idris_alloc(size: usize, alignment: usize) -> *mut c_void;
+    fn idris_free(ptr: *mut c_void, size: usize, alignment: usize);
+    fn idris_string_alloc(data: *const u8, len: usize) -> *mut u8;
+    fn idris_string_free(data: *mut u8, len: usize);
+    fn idris_arena_new() -> *mut c_void;
+    fn idris_arena_alloc(arena: *mut c_void, size: usize, alignment: usize) -> *mut c_void;
+    fn idris_arena_reset(arena: *mut c_void);
+    fn idris_arena_free(arena: *mut c_void);
+}
+
+/// A properly aligned pointer for `layout`, from `alloc` unless the layout
+/// is zero-sized.
+fn allocate<T>(layout: Layout, alloc: impl FnOnce(usize, usize) -> *mut c_void) -> NonNull<T> {
+    if layout.size() == 0 {
+        return NonNull::dangling();
+    }
+    NonNull::new(alloc(layout.size(), layout.align()).cast())
+        .unwrap_or_else(|| handle_alloc_error(layout))
+}
+
+// ----------------------------------------------------------------------------
+// IdrisBox
+// ----------------------------------------------------------------------------
+
+/// A `T` on the Idris 2 bridge heap, freed with `idris_free` on drop.
+pub struct IdrisBox<T> {
+    ptr: NonNull<T>,
+    _owns: PhantomData<T>,
+}
+
+// SAFETY: `IdrisBox` owns its `T` like `Box`; the Zig allocator is thread-safe.
+unsafe impl<T: Send> Send for IdrisBox<T> {}
+unsafe impl<T: Sync> Sync for IdrisBox<T> {}
+
+impl<T> IdrisBox<T> {
+    pub fn new(value: T) -> IdrisBox<T> {
+        // SAFETY: `idris_alloc` has no preconditions.
+        let ptr = allocate::<T>(Layout::new::<T>(), |size, align| unsafe {
+            idris_alloc(size, align)
+        });
+        // SAFETY: `ptr` is valid for writes of a `T`.
+        unsafe { ptr.as_ptr().write(value) };
+        IdrisBox {
+            ptr,
+            _owns: PhantomData,
+        }
+    }
+
+    /// Take ownership of a `T` allocated on the Idris side, or `None` if
+    /// `ptr` is null (how allocating Zig functions report failure).
+    ///
+    /// # Safety
+    ///
+    /// A non-null `ptr` must come from `memory.alloc`, `Pool.create` or
+    /// [`into_raw`](IdrisBox::into_raw) with the size and alignment of `T`,
+    /// point to a valid `T`, and not be freed by anyone else.
+    pub unsafe fn from_raw(ptr: *mut T) -> Option<IdrisBox<T>> {
+        NonNull::new(ptr).map(|ptr| IdrisBox {
+            ptr,
+            _owns: PhantomData,
+        })
+    }
+
+    /// Give up ownership, e.g. to hand the value to Idris code that frees it.
+    pub fn into_raw(this: IdrisBox<T>) -> *mut T {
+        ManuallyDrop::new(this).ptr.as_ptr()
+    }
+
+    pub fn as_ptr(this: &IdrisBox<T>) -> *mut T {
+        this.ptr.as_ptr()
+    }
+}
+
+impl<T> Deref for IdrisBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: `ptr` points to a valid, owned `T`.
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> DerefMut for IdrisBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: as in `deref`, and `&mut self` makes the access unique.
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T> Drop for IdrisBox<T> {
+    fn drop(&mut self) {
+        let layout = Layout::new::<T>();
+        // SAFETY: the `T` is valid and owned; a non-zero-sized one was
+        // allocated by the Idris allocator with this layout.
+        unsafe {
+            ptr::drop_in_place(self.ptr.as_ptr());
+            if layout.size() != 0 {
+                idris_free(self.ptr.as_ptr().cast(), layout.size(), layout.align());
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for IdrisBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// IdrisStringBuf
+// ----------------------------------------------------------------------------
+
+/// String data on the Idris 2 bridge heap, freed with `idris_string_free`.
+pub struct IdrisStringBuf {
+    data: *mut u8,
+    len: usize,
+}
+
+// SAFETY: the buffer is uniquely owned and only read through `&self`.
+unsafe impl Send for IdrisStringBuf {}
+unsafe impl Sync for IdrisStringBuf {}
+
+impl IdrisStringBuf {
+    /// Copy `bytes` to the Idris heap, as `toIdrisString` does.
+    pub fn new(bytes: impl AsRef<[u8]>) -> IdrisStringBuf {
+        let bytes = bytes.as_ref();
+        // SAFETY: `bytes` is valid for `len` reads.
+        let data = unsafe { idris_string_alloc(bytes.as_ptr(), bytes.len()) };
+        if data.is_null() && !bytes.is_empty() {
+            handle_alloc_error(Layout::for_value(bytes));
+        }
+        IdrisStringBuf {
+            data,
+            len: bytes.len(),
+        }
+    }
+
+    /// Take ownership of a string returned by Idris code.
+    ///
+    /// # Safety
+    ///
+    /// `s` must come from `toIdrisString` or [`into_raw`](Self::into_raw)
+    /// and not be freed by anyone else.
+    pub unsafe fn from_raw(s: IdrisString<'_>) -> IdrisStringBuf {
+        IdrisStringBuf {
+            data: s.data.cast_mut(),
+            len: s.len,
+        }
+    }
+
+    /// Give up ownership; the result must eventually be freed by the Idris
+    /// side or through [`from_raw`](Self::from_raw).
+    pub fn into_raw(self) -> IdrisString<'static> {
+        let this = ManuallyDrop::new(self);
+        // SAFETY: the buffer stays allocated until someone frees it.
+        unsafe { IdrisString::from_raw_parts(this.data, this.len) }
+    }
+
+    /// Borrow the string to pass it to Idris code.
+    pub fn as_idris_string(&self) -> IdrisString<'_> {
+        // SAFETY: `data` is null or holds `len` bytes for as long as `self`.
+        unsafe { IdrisString::from_raw_parts(self.data, self.len) }
+    }
+}
+
+impl Deref for IdrisStringBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_idris_string().as_bytes()
+    }
+}
+
+impl Drop for IdrisStringBuf {
+    fn drop(&mut self) {
+        // SAFETY: the buffer was allocated by the Idris allocator with `len`
+        // bytes and is owned by `self`.
+        unsafe { idris_string_free(self.data, self.len) }
+    }
+}
+
+impl fmt::Debug for IdrisStringBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.as_idris_string(), f)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// IdrisArena
+// ----------------------------------------------------------------------------
+
+/// An Idris bridge `Arena`; everything allocated from it is released at once
+/// by [`reset`](IdrisArena::reset) or on drop.
+///
+/// Only `Copy` values are accepted because the arena never runs destructors.
+pub struct IdrisArena {
+    raw: NonNull<c_void>,
+}
+
+// SAFETY: the arena is only used from one thread at a time: allocation
+// takes `&self` but `IdrisArena` is not `Sync`.
+unsafe impl Send for IdrisArena {}
+
+impl IdrisArena {
+    pub fn new() -> IdrisArena {
+        // SAFETY: `idris_arena_new` has no preconditions.
+        let raw = unsafe { idris_arena_new() };
+        IdrisArena {
+            raw: NonNull::new(raw).expect("idris_arena_new failed to allocate"),
+        }
+    }
+
+    fn allocate<T>(&self, layout: Layout) -> NonNull<T> {
+        // SAFETY: `raw` is a live arena.
+        allocate(layout, |size, align| unsafe {
+            idris_arena_alloc(self.raw.as_ptr(), size, align)
+        })
+    }
+
+    // Handing out `&mut` from `&self` is the point of an arena: every call
+    // returns a fresh, disjoint allocation.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc<T: Copy>(&self, value: T) -> &mut T {
+        let ptr = self.allocate::<T>(Layout::new::<T>());
+        // SAFETY: `ptr` is valid for a `T`, unaliased, and lives until the
+        // arena is reset or dropped, both of which need `self` unborrowed.
+        unsafe {
+            ptr.as_ptr().write(value);
+            &mut *ptr.as_ptr()
+        }
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice<T: Copy>(&self, values: &[T]) -> &mut [T] {
+        let layout = Layout::for_value(values);
+        let ptr = self.allocate::<T>(layout);
+        // SAFETY: as in `alloc`, for `values.len()` elements.
+        unsafe {
+            ptr::copy_nonoverlapping(values.as_ptr(), ptr.as_ptr(), values.len());
+            slice::from_raw_parts_mut(ptr.as_ptr(), values.len())
+        }
+    }
+
+    /// Copy `s` into the arena as an [`IdrisString`] to pass to Idris code.
+    pub fn alloc_str(&self, s: &str) -> IdrisString<'_> {
+        IdrisString::from_bytes(self.alloc_slice(s.as_bytes()))
+    }
+
+    /// Release every allocation, keeping the arena's capacity.
+    pub fn reset(&mut self) {
+        // SAFETY: `raw` is a live arena and `&mut self` proves nothing
+        // allocated from it is still borrowed.
+        unsafe { idris_arena_reset(self.raw.as_ptr()) }
+    }
+
+    /// The `Arena` pointer, for Zig functions that allocate into it.
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.raw.as_ptr()
+    }
+}
+
+impl Default for IdrisArena {
+    fn default() -> IdrisArena {
+        IdrisArena::new()
+    }
+}
+
+impl Drop for IdrisArena {
+    fn drop(&mut self) {
+        // SAFETY: `raw` came from `idris_arena_new` and is freed only here.
+        unsafe { idris_arena_free(self.raw.as_ptr()) }
+    }
+}
+
+impl fmt::Debug for IdrisArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdrisArena")
+            .field("raw", &self.raw)
+            .finish()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Managed
+// ----------------------------------------------------------------------------
+
+/// Cleanup function for a [`Managed`] value.
+pub type Cleanup<T> = unsafe extern "C" fn(value: *mut T);
+
+/// A value with the function that releases it, run once on drop, like Zig's
+/// `Managed(T).deinit`.
+pub struct Managed<T> {
+    value: T,
+    cleanup: Option<Cleanup<T>>,
+}
+
+impl<T> Managed<T> {
+    /// # Safety
+    ///
+    /// `cleanup` must be sound to call once with a pointer to `value`.
+    pub unsafe fn new(value: T, cleanup: Option<Cleanup<T>>) -> Managed<T> {
+        Managed { value, cleanup }
+    }
+
+    /// Take the value back without running the cleanup.
+    pub fn into_inner(self) -> T {
+        let this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never used or dropped again.
+        unsafe { ptr::read(&this.value) }
+    }
+}
+
+impl<T> Deref for Managed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Managed<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T> Drop for Managed<T> {
+    fn drop(&mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            // SAFETY: promised by the caller of `new`.
+            unsafe { cleanup(&mut self.value) }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Managed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Managed")
+            .field("value", &self.value)
+            .field("cleanup", &self.cleanup.is_some())
+            .finish()
+    }
+}
+
+#[cfg(all(test, zig_linked))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn box_round_trips_through_raw() {
+        let mut b = IdrisBox::new([1u64, 2, 3]);
+        b[1] = 20;
+        let raw = IdrisBox::into_raw(b);
+        let b = unsafe { IdrisBox::from_raw(raw) }.unwrap();
+        assert_eq!(*b, [1, 20, 3]);
+        assert!(unsafe { IdrisBox::<u8>::from_raw(ptr::null_mut()) }.is_none());
+    }
+
+    #[test]
+    fn box_adopts_idris_allocations() {
+        let layout = Layout::new::<u32>();
+        let raw = unsafe { idris_alloc(layout.size(), layout.align()) }.cast::<u32>();
+        unsafe { raw.write(7) };
+        let b = unsafe { IdrisBox::from_raw(raw) }.unwrap();
+        assert_eq!(*b, 7);
+    }
+
+    #[test]
+    fn box_runs_destructors_and_handles_zsts() {
+        let b = IdrisBox::new(String::from("dropped in place"));
+        assert_eq!(b.len(), 16);
+        drop(b);
+        let unit = IdrisBox::new(());
+        assert_eq!(*unit, ());
+    }
+
+    #[test]
+    fn string_buf_copies_and_frees() {
+        let s = IdrisStringBuf::new("Hello, Idris!");
+        assert_eq!(s.as_idris_string().to_str(), Ok("Hello, Idris!"));
+        let raw = s.into_raw();
+        let s = unsafe { IdrisStringBuf::from_raw(raw) };
+        assert_eq!(&*s, b"Hello, Idris!");
+        assert!(IdrisStringBuf::new("").is_empty());
+    }
+
+    #[test]
+    fn arena_allocations_live_until_reset() {
+        let mut arena = IdrisArena::new();
+        let a = arena.alloc(1u64);
+        let b = arena.alloc_slice(&[2u16, 3, 4]);
+        let s = arena.alloc_str("arena");
+        *a += 1;
+        b[0] = 5;
+        assert_eq!((*a, &*b, s.to_str()), (2, &[5, 3, 4][..], Ok("arena")));
+        arena.reset();
+        assert_eq!(*arena.alloc(9u8), 9);
+        assert_eq!(arena.alloc_slice::<u32>(&[]), &[]);
+    }
+
+    static CLEANED: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe extern "C" fn count_cleanup(value: *mut u32) {
+        CLEANED.fetch_add(*value as usize, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn managed_runs_cleanup_once() {
+        let m = unsafe { Managed::new(5u32, Some(count_cleanup)) };
+        assert_eq!(*m, 5);
+        drop(m);
+        let kept = unsafe { Managed::new(100u32, Some(count_cleanup)) }.into_inner();
+        assert_eq!(kept, 100);
+        assert_eq!(CLEANED.load(Ordering::SeqCst), 5);
+    }
+}
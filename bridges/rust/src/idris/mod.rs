@@ -0,0 +1,1014 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Rust mirrors of the Idris 2 runtime values in
+//! `bridges/idris2/src/idris_rts.zig`.
+//!
+//! The `#[repr(C)]` types here have the field order of their Zig
+//! counterparts, so Idris 2 functions exported through the Zig layer can be
+//! declared in an `extern "C"` block and their results consumed directly.
+//! The generic Zig types (`IdrisMaybe(T)`, `IdrisEither(L, R)`,
+//! `IdrisList(T)`, `IdrisPair(A, B)`) are plain structs; an export passing one
+//! across the C ABI declares it `extern`, which is the layout mirrored here.
+//!
+//! Conversions follow `types.zig`: [`IdrisMaybe`] ↔ `Option`,
+//! [`IdrisEither`] ↔ `Result` (Idris `Right` is `Ok`), [`IdrisList`] → `Vec`
+//! and [`IdrisPair`] ↔ tuples. Conversions out of a foreign value are
+//! `TryFrom` because the Zig side can hand over a tag this crate does not
+//! know, a string that is not UTF-8 or a list whose nodes disagree with its
+//! length.
+//!
+//! ```
+//! use rust_zig_ffi::idris::{IdrisEither, IdrisMaybe};
+//!
+//! let div: IdrisMaybe<i64> = Some(7).into();
+//! assert_eq!(Option::try_from(div), Ok(Some(7)));
+//!
+//! let parsed: IdrisEither<u32, f64> = IdrisEither::left(3);
+//! assert_eq!(Result::try_from(parsed), Ok(Err(3)));
+//! ```
+//!
+//! Borrowed values ([`IdrisString`], [`IdrisList`], [`IdrisValue`]) carry a
+//! lifetime for the memory they point into. For values returned by Zig that
+//! is whatever the export promises; the `extern` declaration chooses it.
+//! Values the Rust side owns are held by the handles in `memory`
+//! ([`IdrisBox`], [`IdrisStringBuf`], [`IdrisArena`], [`Managed`]), which
+//! need the Zig core.
+
+use std::error::Error;
+use std::ffi::c_void;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::slice;
+use std::str::{self, Utf8Error};
+
+use crate::error::BridgeError;
+
+#[cfg(feature = "idris-bindgen")]
+pub mod bindgen;
+// Frees through the Zig core, so pure-rust builds only have it with Zig.
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+mod memory;
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+pub use memory::{Cleanup, IdrisArena, IdrisBox, IdrisStringBuf, Managed};
+
+/// Why a foreign Idris value could not be converted to a Rust one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionError {
+    /// A `MaybeTag` or `EitherTag` outside the Zig enum.
+    InvalidTag { ty: &'static str, tag: u8 },
+    /// An [`IdrisString`] whose bytes are not UTF-8.
+    InvalidUtf8(Utf8Error),
+    /// An [`IdrisList`] whose node chain does not match its `len`. Counting
+    /// stops one node past `expected`, so cycles are reported too.
+    LengthMismatch { expected: usize, found: usize },
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::InvalidTag { ty, tag } => write!(f, "invalid {ty} tag {tag}"),
+            ConversionError::InvalidUtf8(err) => write!(f, "Idris string is not UTF-8: {err}"),
+            ConversionError::LengthMismatch { expected, found } if found > expected => {
+                write!(f, "Idris list has more than its length of {expected} nodes")
+            }
+            ConversionError::LengthMismatch { expected, found } => {
+                write!(f, "Idris list has {found} of its {expected} nodes")
+            }
+        }
+    }
+}
+
+impl Error for ConversionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConversionError::InvalidUtf8(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// `MaybeTag` from `idris_rts.zig`.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MaybeTag {
+    Nothing = 0,
+    Just = 1,
+}
+
+impl TryFrom<u8> for MaybeTag {
+    type Error = ConversionError;
+
+    fn try_from(tag: u8) -> Result<MaybeTag, ConversionError> {
+        match tag {
+            0 => Ok(MaybeTag::Nothing),
+            1 => Ok(MaybeTag::Just),
+            tag => Err(ConversionError::InvalidTag { ty: "Maybe", tag }),
+        }
+    }
+}
+
+/// `EitherTag` from `idris_rts.zig`.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EitherTag {
+    Left = 0,
+    Right = 1,
+}
+
+impl TryFrom<u8> for EitherTag {
+    type Error = ConversionError;
+
+    fn try_from(tag: u8) -> Result<EitherTag, ConversionError> {
+        match tag {
+            0 => Ok(EitherTag::Left),
+            1 => Ok(EitherTag::Right),
+            tag => Err(ConversionError::InvalidTag { ty: "Either", tag }),
+        }
+    }
+}
+
+/// `ErrorCode` from `bridges/idris2/src/errors.zig`. The hundreds digit is
+/// the category: parsing, validation, security, resource, math.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    Unknown,
+    ParseError,
+    InvalidInput,
+    UnexpectedEof,
+    ValidationError,
+    OutOfRange,
+    TypeMismatch,
+    SecurityError,
+    InjectionDetected,
+    TraversalDetected,
+    ResourceError,
+    NotFound,
+    AccessDenied,
+    MathError,
+    DivisionByZero,
+    Overflow,
+    Underflow,
+    /// A code this crate does not know about.
+    Foreign(u32),
+}
+
+impl ErrorCode {
+    pub fn from_code(code: u32) -> ErrorCode {
+        match code {
+            0 => ErrorCode::Unknown,
+            100 => ErrorCode::ParseError,
+            101 => ErrorCode::InvalidInput,
+            102 => ErrorCode::UnexpectedEof,
+            200 => ErrorCode::ValidationError,
+            201 => ErrorCode::OutOfRange,
+            202 => ErrorCode::TypeMismatch,
+            300 => ErrorCode::SecurityError,
+            301 => ErrorCode::InjectionDetected,
+            302 => ErrorCode::TraversalDetected,
+            400 => ErrorCode::ResourceError,
+            401 => ErrorCode::NotFound,
+            402 => ErrorCode::AccessDenied,
+            500 => ErrorCode::MathError,
+            501 => ErrorCode::DivisionByZero,
+            502 => ErrorCode::Overflow,
+            503 => ErrorCode::Underflow,
+            other => ErrorCode::Foreign(other),
+        }
+    }
+
+    pub fn code(self) -> u32 {
+        match self {
+            ErrorCode::Unknown => 0,
+            ErrorCode::ParseError => 100,
+            ErrorCode::InvalidInput => 101,
+            ErrorCode::UnexpectedEof => 102,
+            ErrorCode::ValidationError => 200,
+            ErrorCode::OutOfRange => 201,
+            ErrorCode::TypeMismatch => 202,
+            ErrorCode::SecurityError => 300,
+            ErrorCode::InjectionDetected => 301,
+            ErrorCode::TraversalDetected => 302,
+            ErrorCode::ResourceError => 400,
+            ErrorCode::NotFound => 401,
+            ErrorCode::AccessDenied => 402,
+            ErrorCode::MathError => 500,
+            ErrorCode::DivisionByZero => 501,
+            ErrorCode::Overflow => 502,
+            ErrorCode::Underflow => 503,
+            ErrorCode::Foreign(code) => code,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorCode::Unknown => "unknown error",
+            ErrorCode::ParseError => "parse error",
+            ErrorCode::InvalidInput => "invalid input",
+            ErrorCode::UnexpectedEof => "unexpected end of input",
+            ErrorCode::ValidationError => "validation error",
+            ErrorCode::OutOfRange => "out of range",
+            ErrorCode::TypeMismatch => "type mismatch",
+            ErrorCode::SecurityError => "security error",
+            ErrorCode::InjectionDetected => "injection detected",
+            ErrorCode::TraversalDetected => "path traversal detected",
+            ErrorCode::ResourceError => "resource error",
+            ErrorCode::NotFound => "not found",
+            ErrorCode::AccessDenied => "access denied",
+            ErrorCode::MathError => "math error",
+            ErrorCode::DivisionByZero => "division by zero",
+            ErrorCode::Overflow => "overflow",
+            ErrorCode::Underflow => "underflow",
+            ErrorCode::Foreign(code) => return write!(f, "Idris error code {code}"),
+        })
+    }
+}
+
+/// The Rust side of `fromIdrisEither`: a `Left` message becomes an
+/// [`ErrorCode::Unknown`] error carrying it, as `parseError` does.
+pub fn from_either<T>(either: IdrisEither<IdrisString<'_>, T>) -> Result<T, BridgeError> {
+    match Result::try_from(either)? {
+        Ok(value) => Ok(value),
+        Err(message) => Err(BridgeError::idris(
+            ErrorCode::Unknown,
+            String::from_utf8_lossy(message.as_bytes()),
+            None,
+        )),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Strings and untyped values
+// ----------------------------------------------------------------------------
+
+/// `IdrisString`: a byte pointer and length, not NUL-terminated.
+///
+/// A null `data` reads as the empty string, as in `fromIdrisString`. Idris
+/// strings are immutable, so the bytes are only ever read.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct IdrisString<'a> {
+    data: *const u8,
+    len: usize,
+    _bytes: PhantomData<&'a [u8]>,
+}
+
+impl<'a> IdrisString<'a> {
+    pub const fn empty() -> IdrisString<'static> {
+        IdrisString {
+            data: ptr::null(),
+            len: 0,
+            _bytes: PhantomData,
+        }
+    }
+
+    /// Borrow `bytes` for as long as the Idris side may read them.
+    pub const fn from_bytes(bytes: &'a [u8]) -> IdrisString<'a> {
+        IdrisString {
+            data: bytes.as_ptr(),
+            len: bytes.len(),
+            _bytes: PhantomData,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `data` must be null or valid for reads of `len` bytes for `'a`.
+    pub const unsafe fn from_raw_parts(data: *const u8, len: usize) -> IdrisString<'a> {
+        IdrisString {
+            data,
+            len,
+            _bytes: PhantomData,
+        }
+    }
+
+    pub const fn as_ptr(&self) -> *const u8 {
+        self.data
+    }
+
+    pub fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        if self.data.is_null() {
+            return &[];
+        }
+        // SAFETY: non-null `data` is valid for `len` bytes for `'a`, by the
+        // constructors' contracts.
+        unsafe { slice::from_raw_parts(self.data, self.len) }
+    }
+
+    pub fn to_str(&self) -> Result<&'a str, ConversionError> {
+        str::from_utf8(self.as_bytes()).map_err(ConversionError::InvalidUtf8)
+    }
+}
+
+impl fmt::Debug for IdrisString<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&String::from_utf8_lossy(self.as_bytes()), f)
+    }
+}
+
+impl<'a> From<&'a str> for IdrisString<'a> {
+    fn from(s: &'a str) -> IdrisString<'a> {
+        IdrisString::from_bytes(s.as_bytes())
+    }
+}
+
+impl<'a> From<&'a [u8]> for IdrisString<'a> {
+    fn from(bytes: &'a [u8]) -> IdrisString<'a> {
+        IdrisString::from_bytes(bytes)
+    }
+}
+
+impl<'a> TryFrom<IdrisString<'a>> for &'a str {
+    type Error = ConversionError;
+
+    fn try_from(s: IdrisString<'a>) -> Result<&'a str, ConversionError> {
+        s.to_str()
+    }
+}
+
+impl TryFrom<IdrisString<'_>> for String {
+    type Error = ConversionError;
+
+    fn try_from(s: IdrisString<'_>) -> Result<String, ConversionError> {
+        s.to_str().map(str::to_owned)
+    }
+}
+
+/// `IdrisValue`: the untagged value passed to and returned from `callRaw*`.
+///
+/// Which field is live is part of the called function's type; reading a
+/// field is `unsafe` as for any Rust union.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union IdrisValue<'a> {
+    pub int: i64,
+    pub float: f64,
+    pub string: IdrisString<'a>,
+    pub ptr: *mut c_void,
+}
+
+impl From<i64> for IdrisValue<'_> {
+    fn from(int: i64) -> Self {
+        IdrisValue { int }
+    }
+}
+
+impl From<f64> for IdrisValue<'_> {
+    fn from(float: f64) -> Self {
+        IdrisValue { float }
+    }
+}
+
+/// `Bool` travels as an `int` of 1 or 0, as in `toIdris`.
+impl From<bool> for IdrisValue<'_> {
+    fn from(b: bool) -> Self {
+        IdrisValue { int: i64::from(b) }
+    }
+}
+
+impl<'a> From<IdrisString<'a>> for IdrisValue<'a> {
+    fn from(string: IdrisString<'a>) -> IdrisValue<'a> {
+        IdrisValue { string }
+    }
+}
+
+impl<'a> From<&'a str> for IdrisValue<'a> {
+    fn from(s: &'a str) -> IdrisValue<'a> {
+        IdrisValue { string: s.into() }
+    }
+}
+
+impl From<*mut c_void> for IdrisValue<'_> {
+    fn from(ptr: *mut c_void) -> Self {
+        IdrisValue { ptr }
+    }
+}
+
+/// `IdrisConstructor`: an algebraic data type value with `arity` arguments.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IdrisConstructor<'a> {
+    pub tag: u32,
+    pub arity: u32,
+    pub args: *mut IdrisValue<'a>,
+}
+
+impl<'a> IdrisConstructor<'a> {
+    /// # Safety
+    ///
+    /// `args` must point to `arity` values that stay valid for `'a`.
+    pub unsafe fn args(&self) -> &'a [IdrisValue<'a>] {
+        if self.arity == 0 {
+            return &[];
+        }
+        slice::from_raw_parts(self.args, self.arity as usize)
+    }
+}
+
+/// `IdrisMaybeValue`: a `Maybe` whose payload stays behind a pointer.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IdrisMaybeValue {
+    pub tag: u8,
+    pub value_ptr: *mut c_void,
+}
+
+impl From<Option<*mut c_void>> for IdrisMaybeValue {
+    fn from(value: Option<*mut c_void>) -> IdrisMaybeValue {
+        match value {
+            Some(value_ptr) => IdrisMaybeValue {
+                tag: MaybeTag::Just as u8,
+                value_ptr,
+            },
+            None => IdrisMaybeValue {
+                tag: MaybeTag::Nothing as u8,
+                value_ptr: ptr::null_mut(),
+            },
+        }
+    }
+}
+
+impl TryFrom<IdrisMaybeValue> for Option<*mut c_void> {
+    type Error = ConversionError;
+
+    fn try_from(maybe: IdrisMaybeValue) -> Result<Option<*mut c_void>, ConversionError> {
+        Ok(match MaybeTag::try_from(maybe.tag)? {
+            MaybeTag::Just => Some(maybe.value_ptr),
+            MaybeTag::Nothing => None,
+        })
+    }
+}
+
+/// `IdrisEitherValue`: an `Either` whose payloads stay behind pointers.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IdrisEitherValue {
+    pub tag: u8,
+    pub left_ptr: *mut c_void,
+    pub right_ptr: *mut c_void,
+}
+
+impl From<Result<*mut c_void, *mut c_void>> for IdrisEitherValue {
+    fn from(value: Result<*mut c_void, *mut c_void>) -> IdrisEitherValue {
+        match value {
+            Ok(right_ptr) => IdrisEitherValue {
+                tag: EitherTag::Right as u8,
+                left_ptr: ptr::null_mut(),
+                right_ptr,
+            },
+            Err(left_ptr) => IdrisEitherValue {
+                tag: EitherTag::Left as u8,
+                left_ptr,
+                right_ptr: ptr::null_mut(),
+            },
+        }
+    }
+}
+
+impl TryFrom<IdrisEitherValue> for Result<*mut c_void, *mut c_void> {
+    type Error = ConversionError;
+
+    fn try_from(either: IdrisEitherValue) -> Result<Self, ConversionError> {
+        Ok(match EitherTag::try_from(either.tag)? {
+            EitherTag::Right => Ok(either.right_ptr),
+            EitherTag::Left => Err(either.left_ptr),
+        })
+    }
+}
+
+/// `World`: the token threaded through Idris `IO` calls.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct World {
+    _marker: u8,
+}
+
+impl World {
+    pub const fn new() -> World {
+        World { _marker: 0 }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Typed Maybe, Either and Pair
+// ----------------------------------------------------------------------------
+
+/// `IdrisMaybe(T)`. The payload is uninitialised for `Nothing` and is never
+/// dropped in place; convert to `Option<T>` to take ownership of it.
+#[repr(C)]
+pub struct IdrisMaybe<T> {
+    tag: u8,
+    value: MaybeUninit<T>,
+}
+
+impl<T> IdrisMaybe<T> {
+    pub const fn nothing() -> IdrisMaybe<T> {
+        IdrisMaybe {
+            tag: MaybeTag::Nothing as u8,
+            value: MaybeUninit::uninit(),
+        }
+    }
+
+    pub const fn just(value: T) -> IdrisMaybe<T> {
+        IdrisMaybe {
+            tag: MaybeTag::Just as u8,
+            value: MaybeUninit::new(value),
+        }
+    }
+
+    pub fn tag(&self) -> Result<MaybeTag, ConversionError> {
+        MaybeTag::try_from(self.tag)
+    }
+
+    pub fn as_option(&self) -> Result<Option<&T>, ConversionError> {
+        Ok(match self.tag()? {
+            // SAFETY: `Just` values carry an initialised payload, whether
+            // built by `just` or handed over by Zig.
+            MaybeTag::Just => Some(unsafe { self.value.assume_init_ref() }),
+            MaybeTag::Nothing => None,
+        })
+    }
+}
+
+impl<T: Copy> Clone for IdrisMaybe<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Copy> Copy for IdrisMaybe<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for IdrisMaybe<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.as_option() {
+            Ok(Some(value)) => f.debug_tuple("Just").field(value).finish(),
+            Ok(None) => f.write_str("Nothing"),
+            Err(_) => write!(f, "IdrisMaybe {{ tag: {} }}", self.tag),
+        }
+    }
+}
+
+impl<T> From<Option<T>> for IdrisMaybe<T> {
+    fn from(value: Option<T>) -> IdrisMaybe<T> {
+        value.map_or_else(IdrisMaybe::nothing, IdrisMaybe::just)
+    }
+}
+
+impl<T> TryFrom<IdrisMaybe<T>> for Option<T> {
+    type Error = ConversionError;
+
+    fn try_from(maybe: IdrisMaybe<T>) -> Result<Option<T>, ConversionError> {
+        Ok(match maybe.tag()? {
+            // SAFETY: as in `as_option`.
+            MaybeTag::Just => Some(unsafe { maybe.value.assume_init() }),
+            MaybeTag::Nothing => None,
+        })
+    }
+}
+
+/// `IdrisEither(L, R)`. Only the side named by the tag is initialised, and
+/// neither is dropped in place; convert to `Result<R, L>` to take ownership.
+#[repr(C)]
+pub struct IdrisEither<L, R> {
+    tag: u8,
+    left: MaybeUninit<L>,
+    right: MaybeUninit<R>,
+}
+
+impl<L, R> IdrisEither<L, R> {
+    pub const fn left(value: L) -> IdrisEither<L, R> {
+        IdrisEither {
+            tag: EitherTag::Left as u8,
+            left: MaybeUninit::new(value),
+            right: MaybeUninit::uninit(),
+        }
+    }
+
+    pub const fn right(value: R) -> IdrisEither<L, R> {
+        IdrisEither {
+            tag: EitherTag::Right as u8,
+            left: MaybeUninit::uninit(),
+            right: MaybeUninit::new(value),
+        }
+    }
+
+    pub fn tag(&self) -> Result<EitherTag, ConversionError> {
+        EitherTag::try_from(self.tag)
+    }
+
+    pub fn as_result(&self) -> Result<Result<&R, &L>, ConversionError> {
+        // SAFETY: the side named by the tag is initialised, whether built by
+        // `left`/`right` or handed over by Zig.
+        Ok(match self.tag()? {
+            EitherTag::Right => Ok(unsafe { self.right.assume_init_ref() }),
+            EitherTag::Left => Err(unsafe { self.left.assume_init_ref() }),
+        })
+    }
+}
+
+impl<L: Copy, R: Copy> Clone for IdrisEither<L, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<L: Copy, R: Copy> Copy for IdrisEither<L, R> {}
+
+impl<L: fmt::Debug, R: fmt::Debug> fmt::Debug for IdrisEither<L, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.as_result() {
+            Ok(Ok(right)) => f.debug_tuple("Right").field(right).finish(),
+            Ok(Err(left)) => f.debug_tuple("Left").field(left).finish(),
+            Err(_) => write!(f, "IdrisEither {{ tag: {} }}", self.tag),
+        }
+    }
+}
+
+impl<L, R> From<Result<R, L>> for IdrisEither<L, R> {
+    fn from(value: Result<R, L>) -> IdrisEither<L, R> {
+        match value {
+            Ok(right) => IdrisEither::right(right),
+            Err(left) => IdrisEither::left(left),
+        }
+    }
+}
+
+impl<L, R> TryFrom<IdrisEither<L, R>> for Result<R, L> {
+    type Error = ConversionError;
+
+    fn try_from(either: IdrisEither<L, R>) -> Result<Result<R, L>, ConversionError> {
+        // SAFETY: as in `as_result`.
+        Ok(match either.tag()? {
+            EitherTag::Right => Ok(unsafe { either.right.assume_init() }),
+            EitherTag::Left => Err(unsafe { either.left.assume_init() }),
+        })
+    }
+}
+
+/// `IdrisPair(A, B)`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IdrisPair<A, B> {
+    pub fst: A,
+    pub snd: B,
+}
+
+impl<A, B> From<(A, B)> for IdrisPair<A, B> {
+    fn from((fst, snd): (A, B)) -> IdrisPair<A, B> {
+        IdrisPair { fst, snd }
+    }
+}
+
+impl<A, B> From<IdrisPair<A, B>> for (A, B) {
+    fn from(pair: IdrisPair<A, B>) -> (A, B) {
+        (pair.fst, pair.snd)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Lists
+// ----------------------------------------------------------------------------
+
+/// `IdrisListNode(T)`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct IdrisListNode<T> {
+    pub value: T,
+    pub next: *mut IdrisListNode<T>,
+}
+
+/// `IdrisList(T)`: a singly linked list with cached tail and length,
+/// borrowing nodes that live for `'a`.
+///
+/// Lists from Zig are read through [`iter`](IdrisList::iter) or copied out
+/// with `Vec::try_from`; lists for Zig are built with [`IdrisListBuf`].
+#[repr(C)]
+pub struct IdrisList<'a, T> {
+    head: *mut IdrisListNode<T>,
+    tail: *mut IdrisListNode<T>,
+    len: usize,
+    _nodes: PhantomData<&'a IdrisListNode<T>>,
+}
+
+impl<'a, T> IdrisList<'a, T> {
+    pub const fn empty() -> IdrisList<'a, T> {
+        IdrisList {
+            head: ptr::null_mut(),
+            tail: ptr::null_mut(),
+            len: 0,
+            _nodes: PhantomData,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Every node reachable from `head` must stay valid and unmodified for
+    /// `'a`.
+    pub const unsafe fn from_raw_parts(
+        head: *mut IdrisListNode<T>,
+        tail: *mut IdrisListNode<T>,
+        len: usize,
+    ) -> IdrisList<'a, T> {
+        IdrisList {
+            head,
+            tail,
+            len,
+            _nodes: PhantomData,
+        }
+    }
+
+    /// The length the list claims; `Vec::try_from` checks it.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The nodes' values, following `next` until it is null.
+    pub fn iter(&self) -> Iter<'a, T> {
+        Iter {
+            node: self.head,
+            _nodes: PhantomData,
+        }
+    }
+
+    pub fn last(&self) -> Option<&'a T> {
+        // SAFETY: `tail` is null or one of the list's nodes.
+        unsafe { self.tail.as_ref() }.map(|node| &node.value)
+    }
+}
+
+impl<T> Clone for IdrisList<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for IdrisList<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for IdrisList<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter().take(self.len)).finish()
+    }
+}
+
+impl<'a, T> IntoIterator for IdrisList<'a, T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Copies the values out, as `fromSlice` does.
+impl<T: Clone> TryFrom<IdrisList<'_, T>> for Vec<T> {
+    type Error = ConversionError;
+
+    fn try_from(list: IdrisList<'_, T>) -> Result<Vec<T>, ConversionError> {
+        let found = list.iter().take(list.len.saturating_add(1)).count();
+        if found != list.len {
+            return Err(ConversionError::LengthMismatch {
+                expected: list.len,
+                found,
+            });
+        }
+        Ok(list.iter().cloned().collect())
+    }
+}
+
+/// Iterator over the values of an [`IdrisList`].
+pub struct Iter<'a, T> {
+    node: *const IdrisListNode<T>,
+    _nodes: PhantomData<&'a IdrisListNode<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        // SAFETY: nodes reachable from the list's head are valid for `'a`.
+        let node = unsafe { self.node.as_ref() }?;
+        self.node = node.next;
+        Some(&node.value)
+    }
+}
+
+/// Rust-owned storage for an [`IdrisList`], the counterpart of `toSlice`.
+///
+/// ```
+/// use rust_zig_ffi::idris::IdrisListBuf;
+///
+/// let buf = IdrisListBuf::from(vec![1i64, 2, 3]);
+/// let list = buf.as_list(); // pass to an Idris export
+/// assert_eq!(Vec::try_from(list), Ok(vec![1, 2, 3]));
+/// ```
+pub struct IdrisListBuf<T> {
+    nodes: Vec<IdrisListNode<T>>,
+}
+
+impl<T> IdrisListBuf<T> {
+    pub fn as_list(&self) -> IdrisList<'_, T> {
+        let (head, tail) = match (self.nodes.first(), self.nodes.last()) {
+            (Some(head), Some(tail)) => (ptr::from_ref(head), ptr::from_ref(tail)),
+            _ => (ptr::null(), ptr::null()),
+        };
+        // SAFETY: the nodes are linked in order and are not touched again
+        // while `self` is borrowed.
+        unsafe { IdrisList::from_raw_parts(head.cast_mut(), tail.cast_mut(), self.nodes.len()) }
+    }
+}
+
+impl<T> From<Vec<T>> for IdrisListBuf<T> {
+    fn from(values: Vec<T>) -> IdrisListBuf<T> {
+        values.into_iter().collect()
+    }
+}
+
+impl<T> FromIterator<T> for IdrisListBuf<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> IdrisListBuf<T> {
+        let mut nodes: Vec<IdrisListNode<T>> = values
+            .into_iter()
+            .map(|value| IdrisListNode {
+                value,
+                next: ptr::null_mut(),
+            })
+            .collect();
+        // Link after collecting so the addresses are final.
+        let base = nodes.as_mut_ptr();
+        for i in 1..nodes.len() {
+            // SAFETY: `i` is in bounds, and `base` stays valid because
+            // `nodes` is not resized again.
+            unsafe { (*base.add(i - 1)).next = base.add(i) };
+        }
+        IdrisListBuf { nodes }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for IdrisListBuf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.as_list(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{align_of, offset_of, size_of};
+
+    #[test]
+    fn layouts_match_zig_extern_structs() {
+        assert_eq!(size_of::<IdrisString<'_>>(), 2 * size_of::<usize>());
+        assert_eq!(size_of::<IdrisValue<'_>>(), 2 * size_of::<usize>());
+        assert_eq!(align_of::<IdrisValue<'_>>(), 8);
+        assert_eq!(offset_of!(IdrisMaybeValue, value_ptr), size_of::<usize>());
+        assert_eq!(
+            offset_of!(IdrisEitherValue, right_ptr),
+            2 * size_of::<usize>()
+        );
+        assert_eq!(offset_of!(IdrisConstructor<'_>, args), 8);
+        assert_eq!(offset_of!(IdrisMaybe<f64>, value), 8);
+        assert_eq!(offset_of!(IdrisEither<u8, u32>, right), 4);
+        assert_eq!(offset_of!(IdrisList<'_, i64>, len), 2 * size_of::<usize>());
+        assert_eq!(size_of::<World>(), 1);
+    }
+
+    #[test]
+    fn maybe_round_trips() {
+        let just = IdrisMaybe::from(Some(42i32));
+        assert_eq!(just.tag(), Ok(MaybeTag::Just));
+        assert_eq!(just.as_option(), Ok(Some(&42)));
+        assert_eq!(Option::try_from(just), Ok(Some(42)));
+        assert_eq!(Option::<i32>::try_from(IdrisMaybe::nothing()), Ok(None));
+        assert_eq!(format!("{just:?}"), "Just(42)");
+    }
+
+    #[test]
+    fn either_maps_right_to_ok() {
+        let right: IdrisEither<&str, i32> = Ok(42).into();
+        let left: IdrisEither<&str, i32> = Err("error").into();
+        assert_eq!(Result::try_from(right), Ok(Ok(42)));
+        assert_eq!(Result::try_from(left), Ok(Err("error")));
+        assert_eq!(format!("{left:?}"), "Left(\"error\")");
+    }
+
+    #[test]
+    fn unknown_tags_are_rejected() {
+        let mut maybe = IdrisMaybe::just(1u8);
+        maybe.tag = 2;
+        assert_eq!(
+            Option::<u8>::try_from(maybe),
+            Err(ConversionError::InvalidTag {
+                ty: "Maybe",
+                tag: 2
+            })
+        );
+        let either = IdrisEitherValue {
+            tag: 7,
+            left_ptr: ptr::null_mut(),
+            right_ptr: ptr::null_mut(),
+        };
+        assert!(Result::try_from(either).is_err());
+    }
+
+    #[test]
+    fn strings_borrow_and_validate() {
+        let s = IdrisString::from("Hello, Idris!");
+        assert_eq!(<&str>::try_from(s), Ok("Hello, Idris!"));
+        assert_eq!(String::try_from(IdrisString::empty()).unwrap(), "");
+        // A null pointer reads as empty whatever the length says.
+        let null = unsafe { IdrisString::from_raw_parts(ptr::null(), 5) };
+        assert!(null.is_empty());
+        let bad = IdrisString::from(&[0xff, 0xfe][..]);
+        assert!(matches!(bad.to_str(), Err(ConversionError::InvalidUtf8(_))));
+    }
+
+    #[test]
+    fn values_follow_to_idris() {
+        assert_eq!(unsafe { IdrisValue::from(true).int }, 1);
+        assert_eq!(unsafe { IdrisValue::from(2.5).float }, 2.5);
+        let value = IdrisValue::from("x");
+        assert_eq!(unsafe { value.string }.to_str(), Ok("x"));
+    }
+
+    #[test]
+    fn pairs_convert_to_tuples() {
+        let pair = IdrisPair::from((1u8, "one"));
+        assert_eq!(pair.snd, "one");
+        assert_eq!(<(u8, &str)>::from(pair), (1, "one"));
+    }
+
+    #[test]
+    fn list_buf_links_nodes() {
+        let buf: IdrisListBuf<i64> = (1..=4).collect();
+        let list = buf.as_list();
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.last(), Some(&4));
+        assert_eq!(Vec::try_from(list), Ok(vec![1, 2, 3, 4]));
+
+        let empty = IdrisListBuf::<i64>::from(Vec::new());
+        assert!(empty.as_list().iter().next().is_none());
+        assert_eq!(Vec::try_from(empty.as_list()), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn list_length_is_checked() {
+        let buf = IdrisListBuf::from(vec![1u8, 2, 3]);
+        let list = buf.as_list();
+        let short = unsafe { IdrisList::from_raw_parts(list.head, list.tail, 2) };
+        assert_eq!(
+            Vec::try_from(short),
+            Err(ConversionError::LengthMismatch {
+                expected: 2,
+                found: 3
+            })
+        );
+        let long = unsafe { IdrisList::from_raw_parts(list.head, list.tail, 5) };
+        assert_eq!(
+            Vec::try_from(long),
+            Err(ConversionError::LengthMismatch {
+                expected: 5,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn cyclic_list_terminates() {
+        let mut nodes = [
+            IdrisListNode {
+                value: 1u8,
+                next: ptr::null_mut(),
+            },
+            IdrisListNode {
+                value: 2u8,
+                next: ptr::null_mut(),
+            },
+        ];
+        let base = nodes.as_mut_ptr();
+        unsafe {
+            (*base).next = base.add(1);
+            (*base.add(1)).next = base;
+        }
+        let list = unsafe { IdrisList::from_raw_parts(base, base.add(1), 2) };
+        assert_eq!(
+            Vec::try_from(list),
+            Err(ConversionError::LengthMismatch {
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+}
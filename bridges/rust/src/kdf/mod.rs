@@ -0,0 +1,422 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Password hashing: Argon2id, scrypt and PBKDF2-HMAC.
+//!
+//! Unlike [`Hkdf`](crate::Hkdf), these functions are deliberately slow and
+//! memory-hard so that low-entropy passwords resist brute force. Use
+//! [`hash_password`] to produce a storable [`PasswordHash`] (PHC string
+//! format) and [`verify`] to check a login attempt against it.
+
+mod phc;
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use subtle::ConstantTimeEq;
+
+use crate::backend::active as backend;
+use crate::hkdf::Algorithm;
+use crate::secret::{Password, SecretKey};
+
+/// Salt length used by [`hash_password`].
+pub const DEFAULT_SALT_LEN: usize = 16;
+/// Hash length used by [`hash_password`].
+pub const DEFAULT_HASH_LEN: usize = 32;
+
+/// Argon2id cost parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Params {
+    /// Memory in KiB; at least 8 × `parallelism`.
+    pub memory_kib: u32,
+    /// Number of passes over memory.
+    pub iterations: u32,
+    /// Degree of parallelism (lanes).
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Params {
+    /// OWASP minimum: 19 MiB, 2 passes, 1 lane.
+    fn default() -> Argon2Params {
+        Argon2Params {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// scrypt cost parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScryptParams {
+    /// CPU/memory cost as a power of two (N = 2^log_n).
+    pub log_n: u8,
+    /// Block size.
+    pub r: u32,
+    /// Parallelisation.
+    pub p: u32,
+}
+
+impl Default for ScryptParams {
+    /// OWASP minimum: N = 2^17, r = 8, p = 1.
+    fn default() -> ScryptParams {
+        ScryptParams {
+            log_n: 17,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+/// PBKDF2-HMAC cost parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pbkdf2Params {
+    pub hash: Algorithm,
+    pub rounds: u32,
+}
+
+impl Default for Pbkdf2Params {
+    /// OWASP minimum for HMAC-SHA256: 600 000 rounds.
+    fn default() -> Pbkdf2Params {
+        Pbkdf2Params {
+            hash: Algorithm::Sha256,
+            rounds: 600_000,
+        }
+    }
+}
+
+/// A password-hashing algorithm together with its cost parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Params {
+    Argon2id(Argon2Params),
+    Scrypt(ScryptParams),
+    Pbkdf2(Pbkdf2Params),
+}
+
+impl Default for Params {
+    fn default() -> Params {
+        Params::Argon2id(Argon2Params::default())
+    }
+}
+
+impl Params {
+    /// Check the parameters, salt and output length before any hashing.
+    fn validate(&self, salt_len: usize, out_len: usize) -> Result<(), KdfError> {
+        let invalid = |reason| Err(KdfError::InvalidParams(reason));
+        if out_len == 0 {
+            return invalid("output length must be non-zero");
+        }
+        match *self {
+            Params::Argon2id(Argon2Params {
+                memory_kib,
+                iterations,
+                parallelism,
+            }) => {
+                if iterations == 0 {
+                    return invalid("argon2 iterations must be at least 1");
+                }
+                if parallelism == 0 || parallelism > 0x00ff_ffff {
+                    return invalid("argon2 parallelism must be between 1 and 2^24 - 1");
+                }
+                if u64::from(memory_kib) < 8 * u64::from(parallelism) {
+                    return invalid("argon2 memory must be at least 8 KiB per lane");
+                }
+                if salt_len < 8 {
+                    return invalid("argon2 salt must be at least 8 bytes");
+                }
+                if out_len < 4 {
+                    return invalid("argon2 output must be at least 4 bytes");
+                }
+            }
+            Params::Scrypt(ScryptParams { log_n, r, p }) => {
+                if log_n == 0 || log_n > 63 {
+                    return invalid("scrypt log_n must be between 1 and 63");
+                }
+                if r == 0 || p == 0 {
+                    return invalid("scrypt r and p must be at least 1");
+                }
+                if u64::from(r) * u64::from(p) >= 1 << 30 {
+                    return invalid("scrypt r × p must be below 2^30");
+                }
+                if u64::from(log_n) >= 16 * u64::from(r) {
+                    return invalid("scrypt log_n must be below 16 × r");
+                }
+            }
+            Params::Pbkdf2(Pbkdf2Params { rounds, .. }) => {
+                if rounds == 0 {
+                    return invalid("pbkdf2 rounds must be at least 1");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Errors returned by the password-hashing functions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KdfError {
+    /// Cost parameters, salt or output length are out of range.
+    InvalidParams(&'static str),
+    /// A PHC string could not be parsed.
+    InvalidPhc(&'static str),
+    /// A PHC string names an algorithm this crate does not implement.
+    UnsupportedAlgorithm(String),
+    /// The backend could not allocate working memory.
+    AllocFailed,
+    /// The operating system random source failed.
+    Rng,
+    /// The Zig core returned a code this crate does not know about.
+    Foreign(i32),
+}
+
+impl fmt::Display for KdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdfError::InvalidParams(reason) => write!(f, "invalid KDF parameters: {reason}"),
+            KdfError::InvalidPhc(reason) => write!(f, "malformed PHC string: {reason}"),
+            KdfError::UnsupportedAlgorithm(id) => write!(f, "unsupported algorithm `{id}`"),
+            KdfError::AllocFailed => f.write_str("KDF could not allocate working memory"),
+            KdfError::Rng => f.write_str("random number generator failed"),
+            KdfError::Foreign(code) => write!(f, "KDF core returned error code {code}"),
+        }
+    }
+}
+
+impl Error for KdfError {}
+
+/// Derive `len` bytes from `password` and `salt` with the given algorithm.
+pub fn derive(
+    params: &Params,
+    password: &Password,
+    salt: &[u8],
+    len: usize,
+) -> Result<SecretKey, KdfError> {
+    params.validate(salt.len(), len)?;
+    let mut out = SecretKey::zeroed(len);
+    let password = password.expose_secret();
+    match params {
+        Params::Argon2id(p) => backend::argon2id(p, password, salt, out.as_mut_bytes())?,
+        Params::Scrypt(p) => backend::scrypt(p, password, salt, out.as_mut_bytes())?,
+        Params::Pbkdf2(p) => backend::pbkdf2(p, password, salt, out.as_mut_bytes())?,
+    }
+    Ok(out)
+}
+
+/// Hash `password` with a fresh random salt for storage.
+pub fn hash_password(password: &Password, params: &Params) -> Result<PasswordHash, KdfError> {
+    let mut salt = vec![0u8; DEFAULT_SALT_LEN];
+    getrandom::getrandom(&mut salt).map_err(|_| KdfError::Rng)?;
+    PasswordHash::new(*params, password, salt, DEFAULT_HASH_LEN)
+}
+
+/// Check `password` against a PHC string produced by [`hash_password`].
+///
+/// Returns `Ok(false)` on a mismatch and an error only if `phc` is malformed
+/// or uses unsupported parameters.
+pub fn verify(password: &Password, phc: &str) -> Result<bool, KdfError> {
+    phc.parse::<PasswordHash>()?.verify(password)
+}
+
+/// A stored password hash: algorithm, parameters, salt and output.
+///
+/// Displays as, and parses from, a PHC string such as
+/// `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordHash {
+    params: Params,
+    salt: Vec<u8>,
+    hash: Vec<u8>,
+}
+
+impl PasswordHash {
+    /// Hash `password` with an explicit salt and output length.
+    pub fn new(
+        params: Params,
+        password: &Password,
+        salt: Vec<u8>,
+        len: usize,
+    ) -> Result<PasswordHash, KdfError> {
+        let hash = derive(&params, password, &salt, len)?;
+        Ok(PasswordHash {
+            params,
+            salt,
+            hash: hash.expose_secret().to_vec(),
+        })
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    pub fn hash(&self) -> &[u8] {
+        &self.hash
+    }
+
+    /// Recompute the hash for `password` and compare in constant time.
+    pub fn verify(&self, password: &Password) -> Result<bool, KdfError> {
+        let candidate = derive(&self.params, password, &self.salt, self.hash.len())?;
+        Ok(candidate.expose_secret().ct_eq(&self.hash).into())
+    }
+}
+
+impl fmt::Display for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&phc::encode(&self.params, &self.salt, &self.hash))
+    }
+}
+
+impl FromStr for PasswordHash {
+    type Err = KdfError;
+
+    fn from_str(s: &str) -> Result<PasswordHash, KdfError> {
+        let (params, salt, hash) = phc::decode(s)?;
+        params.validate(salt.len(), hash.len())?;
+        Ok(PasswordHash { params, salt, hash })
+    }
+}
+
+#[cfg(all(test, any(zig_linked, feature = "pure-rust")))]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    const CHEAP_ARGON2: Params = Params::Argon2id(Argon2Params {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    });
+    const CHEAP_SCRYPT: Params = Params::Scrypt(ScryptParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    });
+    const CHEAP_PBKDF2: Params = Params::Pbkdf2(Pbkdf2Params {
+        hash: Algorithm::Sha512,
+        rounds: 10,
+    });
+
+    // RFC 7914, section 11
+    #[test]
+    fn pbkdf2_sha256_vector() {
+        let params = Params::Pbkdf2(Pbkdf2Params {
+            hash: Algorithm::Sha256,
+            rounds: 1,
+        });
+        let key = derive(&params, &Password::from("passwd"), b"salt", 64).unwrap();
+        assert_eq!(
+            hex(key.expose_secret()),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+             49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+        );
+    }
+
+    // RFC 7914, section 12
+    #[test]
+    fn scrypt_vector() {
+        let params = Params::Scrypt(ScryptParams {
+            log_n: 10,
+            r: 8,
+            p: 16,
+        });
+        let key = derive(&params, &Password::from("password"), b"NaCl", 64).unwrap();
+        assert_eq!(
+            hex(key.expose_secret()),
+            "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+             2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"
+        );
+    }
+
+    // Reference implementation (libargon2) output
+    #[test]
+    fn argon2id_vector() {
+        let key = derive(
+            &Params::default(),
+            &Password::from("password"),
+            b"somesaltsomesalt",
+            32,
+        )
+        .unwrap();
+        assert_eq!(
+            hex(key.expose_secret()),
+            "2b5dc4054886ec957ef59c73b661c54dd6fb274590b278f657c6d96aac8fa6d1"
+        );
+    }
+
+    #[test]
+    fn argon2id_phc_string() {
+        let hash = PasswordHash::new(
+            Params::default(),
+            &Password::from("password"),
+            b"somesaltsomesalt".to_vec(),
+            32,
+        )
+        .unwrap();
+        let phc = "$argon2id$v=19$m=19456,t=2,p=1$c29tZXNhbHRzb21lc2FsdA\
+                   $K13EBUiG7JV+9ZxztmHFTdb7J0WQsnj2V8bZaqyPptE";
+        assert_eq!(hash.to_string(), phc);
+        assert_eq!(phc.parse::<PasswordHash>().unwrap(), hash);
+        assert!(verify(&Password::from("password"), phc).unwrap());
+    }
+
+    #[test]
+    fn hash_and_verify_each_algorithm() {
+        for params in [CHEAP_ARGON2, CHEAP_SCRYPT, CHEAP_PBKDF2] {
+            let stored = hash_password(&Password::from("correct horse"), &params)
+                .unwrap()
+                .to_string();
+            assert!(
+                verify(&Password::from("correct horse"), &stored).unwrap(),
+                "{stored}"
+            );
+            assert!(
+                !verify(&Password::from("battery staple"), &stored).unwrap(),
+                "{stored}"
+            );
+        }
+    }
+
+    #[test]
+    fn salts_are_random() {
+        let password = Password::from("pw");
+        let a = hash_password(&password, &CHEAP_PBKDF2).unwrap();
+        let b = hash_password(&password, &CHEAP_PBKDF2).unwrap();
+        assert_ne!(a.salt(), b.salt());
+        assert_ne!(a.hash(), b.hash());
+    }
+
+    #[test]
+    fn invalid_params_are_rejected() {
+        let password = Password::from("pw");
+        let weak_memory = Params::Argon2id(Argon2Params {
+            memory_kib: 7,
+            iterations: 1,
+            parallelism: 1,
+        });
+        assert!(matches!(
+            derive(&weak_memory, &password, b"saltsalt", 32),
+            Err(KdfError::InvalidParams(_))
+        ));
+        assert!(matches!(
+            derive(&CHEAP_ARGON2, &password, b"short", 32),
+            Err(KdfError::InvalidParams(_))
+        ));
+        let no_rounds = Params::Pbkdf2(Pbkdf2Params {
+            hash: Algorithm::Sha256,
+            rounds: 0,
+        });
+        assert!(matches!(
+            derive(&no_rounds, &password, b"salt", 32),
+            Err(KdfError::InvalidParams(_))
+        ));
+        assert!(matches!(
+            derive(&CHEAP_SCRYPT, &password, b"salt", 0),
+            Err(KdfError::InvalidParams(_))
+        ));
+    }
+}
@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! PHC string format: `$<id>[$v=<version>]$<k>=<v>,...$<salt>$<hash>`.
+//!
+//! Salt and hash use the unpadded standard Base64 alphabet, as in the PHC
+//! specification and the reference Argon2 encoder.
+
+use super::{Argon2Params, KdfError, Params, Pbkdf2Params, ScryptParams};
+use crate::hkdf::Algorithm;
+
+const ARGON2_VERSION: u32 = 0x13;
+
+const B64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(super) fn encode(params: &Params, salt: &[u8], hash: &[u8]) -> String {
+    let head = match params {
+        Params::Argon2id(p) => format!(
+            "$argon2id$v={ARGON2_VERSION}$m={},t={},p={}",
+            p.memory_kib, p.iterations, p.parallelism
+        ),
+        Params::Scrypt(p) => format!("$scrypt$ln={},r={},p={}", p.log_n, p.r, p.p),
+        Params::Pbkdf2(p) => format!("${}$i={}", pbkdf2_id(p.hash), p.rounds),
+    };
+    format!("{head}${}${}", b64_encode(salt), b64_encode(hash))
+}
+
+pub(super) fn decode(s: &str) -> Result<(Params, Vec<u8>, Vec<u8>), KdfError> {
+    let mut fields = s
+        .strip_prefix('$')
+        .ok_or(KdfError::InvalidPhc("missing leading `$`"))?
+        .split('$');
+    let id = fields.next().unwrap_or_default();
+    let mut rest: Vec<&str> = fields.collect();
+
+    let version = match rest.first().and_then(|f| f.strip_prefix("v=")) {
+        Some(v) => {
+            let v = parse_u32(v)?;
+            rest.remove(0);
+            Some(v)
+        }
+        None => None,
+    };
+    let [params, salt, hash] = rest[..] else {
+        return Err(KdfError::InvalidPhc("expected parameters, salt and hash"));
+    };
+    let mut kv = KeyValues::parse(params)?;
+    let salt = b64_decode(salt)?;
+    let hash = b64_decode(hash)?;
+
+    let params = match id {
+        "argon2id" => {
+            if version != Some(ARGON2_VERSION) {
+                return Err(KdfError::InvalidPhc("argon2id requires v=19"));
+            }
+            Params::Argon2id(Argon2Params {
+                memory_kib: kv.take("m")?,
+                iterations: kv.take("t")?,
+                parallelism: kv.take("p")?,
+            })
+        }
+        "scrypt" => {
+            let log_n: u32 = kv.take("ln")?;
+            Params::Scrypt(ScryptParams {
+                log_n: u8::try_from(log_n).map_err(|_| KdfError::InvalidPhc("ln out of range"))?,
+                r: kv.take("r")?,
+                p: kv.take("p")?,
+            })
+        }
+        "pbkdf2-sha256" | "pbkdf2-sha512" => {
+            let hash_alg = if id == "pbkdf2-sha256" {
+                Algorithm::Sha256
+            } else {
+                Algorithm::Sha512
+            };
+            if let Some(len) = kv.take_opt("l")? {
+                if len as usize != hash.len() {
+                    return Err(KdfError::InvalidPhc("l does not match hash length"));
+                }
+            }
+            Params::Pbkdf2(Pbkdf2Params {
+                hash: hash_alg,
+                rounds: kv.take("i")?,
+            })
+        }
+        other => return Err(KdfError::UnsupportedAlgorithm(other.to_owned())),
+    };
+    if version.is_some() && !matches!(params, Params::Argon2id(_)) {
+        return Err(KdfError::InvalidPhc("unexpected version field"));
+    }
+    kv.finish()?;
+    Ok((params, salt, hash))
+}
+
+fn pbkdf2_id(hash: Algorithm) -> &'static str {
+    match hash {
+        Algorithm::Sha256 => "pbkdf2-sha256",
+        Algorithm::Sha512 => "pbkdf2-sha512",
+    }
+}
+
+fn parse_u32(s: &str) -> Result<u32, KdfError> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(KdfError::InvalidPhc("parameter is not a decimal integer"));
+    }
+    s.parse()
+        .map_err(|_| KdfError::InvalidPhc("parameter out of range"))
+}
+
+/// The `k=v,k=v` parameter field, consumed one key at a time.
+struct KeyValues<'a>(Vec<(&'a str, &'a str)>);
+
+impl<'a> KeyValues<'a> {
+    fn parse(field: &'a str) -> Result<KeyValues<'a>, KdfError> {
+        let mut pairs = Vec::new();
+        for pair in field.split(',') {
+            let (k, v) = pair
+                .split_once('=')
+                .ok_or(KdfError::InvalidPhc("parameter without `=`"))?;
+            if pairs.iter().any(|&(seen, _)| seen == k) {
+                return Err(KdfError::InvalidPhc("duplicate parameter"));
+            }
+            pairs.push((k, v));
+        }
+        Ok(KeyValues(pairs))
+    }
+
+    fn take_opt(&mut self, key: &str) -> Result<Option<u32>, KdfError> {
+        match self.0.iter().position(|&(k, _)| k == key) {
+            Some(i) => parse_u32(self.0.remove(i).1).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn take(&mut self, key: &str) -> Result<u32, KdfError> {
+        self.take_opt(key)?
+            .ok_or(KdfError::InvalidPhc("missing parameter"))
+    }
+
+    fn finish(self) -> Result<(), KdfError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(KdfError::InvalidPhc("unknown parameter"))
+        }
+    }
+}
+
+fn b64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(B64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+fn b64_decode(s: &str) -> Result<Vec<u8>, KdfError> {
+    if s.len() % 4 == 1 {
+        return Err(KdfError::InvalidPhc("invalid Base64 length"));
+    }
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let v = B64
+                .iter()
+                .position(|&b| b == c)
+                .ok_or(KdfError::InvalidPhc("invalid Base64 character"))?;
+            n |= (v as u32) << (18 - 6 * i);
+        }
+        let bytes = n.to_be_bytes();
+        let len = chunk.len() - 1;
+        if bytes[1 + len..].iter().any(|&b| b != 0) {
+            return Err(KdfError::InvalidPhc("non-canonical Base64 padding bits"));
+        }
+        out.extend_from_slice(&bytes[1..1 + len]);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_round_trip() {
+        for len in 0..10 {
+            let bytes: Vec<u8> = (0..len).map(|i| (i * 37 + 5) as u8).collect();
+            assert_eq!(b64_decode(&b64_encode(&bytes)).unwrap(), bytes);
+        }
+        assert_eq!(b64_encode(b"somesalt"), "c29tZXNhbHQ");
+        assert!(b64_decode("c29tZXNhbHR").is_err());
+        assert!(b64_decode("c29tZXNhbHQ=").is_err());
+    }
+
+    #[test]
+    fn round_trip_each_algorithm() {
+        let all = [
+            Params::Argon2id(Argon2Params {
+                memory_kib: 65536,
+                iterations: 3,
+                parallelism: 4,
+            }),
+            Params::Scrypt(ScryptParams {
+                log_n: 15,
+                r: 8,
+                p: 2,
+            }),
+            Params::Pbkdf2(Pbkdf2Params {
+                hash: Algorithm::Sha512,
+                rounds: 210_000,
+            }),
+        ];
+        for params in all {
+            let s = encode(&params, b"0123456789abcdef", &[7; 32]);
+            assert_eq!(
+                decode(&s).unwrap(),
+                (params, b"0123456789abcdef".to_vec(), vec![7; 32])
+            );
+        }
+    }
+
+    #[test]
+    fn accepts_reordered_parameters_and_pbkdf2_length() {
+        let (params, _, hash) = decode("$pbkdf2-sha256$l=4,i=1000$c2FsdA$AAECAw").unwrap();
+        assert_eq!(
+            params,
+            Params::Pbkdf2(Pbkdf2Params {
+                hash: Algorithm::Sha256,
+                rounds: 1000
+            })
+        );
+        assert_eq!(hash, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn rejects_malformed_strings() {
+        for bad in [
+            "argon2id$v=19$m=8,t=1,p=1$c2FsdA$AAAA",
+            "$argon2id$m=8,t=1,p=1$c2FsdA$AAAA",
+            "$argon2id$v=16$m=8,t=1,p=1$c2FsdA$AAAA",
+            "$argon2id$v=19$m=8,t=1$c2FsdA$AAAA",
+            "$argon2id$v=19$m=8,t=1,p=1,x=2$c2FsdA$AAAA",
+            "$argon2id$v=19$m=8,m=8,t=1,p=1$c2FsdA$AAAA",
+            "$argon2id$v=19$m=-8,t=1,p=1$c2FsdA$AAAA",
+            "$scrypt$v=19$ln=4,r=8,p=1$c2FsdA$AAAA",
+            "$scrypt$ln=4,r=8,p=1$c2FsdA",
+            "$pbkdf2-sha256$i=1000,l=5$c2FsdA$AAECAw",
+        ] {
+            assert!(matches!(decode(bad), Err(KdfError::InvalidPhc(_))), "{bad}");
+        }
+        assert_eq!(
+            decode("$bcrypt$c=10$c2FsdA$AAAA"),
+            Err(KdfError::UnsupportedAlgorithm("bcrypt".into()))
+        );
+    }
+}
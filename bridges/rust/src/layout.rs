@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Compile-time layout checks of the `#[repr(C)]` mirrors against the Zig
+//! extern structs.
+//!
+//! A mirror can drift from its Zig definition without either compiler
+//! noticing: a field added on one side, a `u8` widened to `u32`, or padding
+//! that differs from what was assumed (`VSensorReading` has six bytes of it
+//! after `sensor_type: u16`). `build.rs` therefore runs
+//! `zig-lib/src/layout.zig`, which writes the size, alignment and every
+//! field's offset and size of the mirrored structs, as Zig lays them out,
+//! into a manifest in `OUT_DIR`. [`assert_layout!`] compares each mirror with
+//! its manifest entry in a `const` item, so a difference on either side fails
+//! the build naming the struct and field.
+//!
+//! This is the Rust counterpart of the `Layout.idr` proofs in the Idris 2 ABI
+//! definitions. The module only exists when the manifest does (the `zig_layout`
+//! cfg): builds without Zig, or cross builds, skip the checks.
+
+use std::mem::size_of;
+
+/// The layout of a C struct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Layout {
+    pub size: usize,
+    pub align: usize,
+    pub fields: &'static [Field],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Field {
+    pub name: &'static str,
+    pub offset: usize,
+    pub size: usize,
+}
+
+impl Layout {
+    /// The field called `name`.
+    pub const fn field(&self, name: &str) -> Option<&Field> {
+        let mut i = 0;
+        while i < self.fields.len() {
+            if str_eq(self.fields[i].name, name) {
+                return Some(&self.fields[i]);
+            }
+            i += 1;
+        }
+        None
+    }
+}
+
+/// The manifest written by `zig-lib/src/layout.zig`: one [`Layout`] per
+/// mirrored struct, named after it, and `ALL`.
+#[allow(dead_code, non_upper_case_globals)]
+pub(crate) mod zig {
+    use super::{Field, Layout};
+
+    include!(concat!(env!("OUT_DIR"), "/layout.rs"));
+}
+
+/// The size of the field `field` selects, which is how a `const` item gets at
+/// a field's type.
+pub(crate) const fn field_size<T, F>(_field: fn(&T) -> &F) -> usize {
+    size_of::<F>()
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Assert at compile time that each Rust type has the layout of the
+/// [`zig`] manifest entry named after `as`: the same size and alignment, the
+/// same number of fields, and each listed field at the same offset with the
+/// same size.
+///
+/// ```ignore
+/// assert_layout! {
+///     crate::szf::SzfBytes as SzfBytes { ptr, len, cap, owned },
+/// }
+/// ```
+///
+/// Also defines `CHECKED`, the manifest names in the order given.
+macro_rules! assert_layout {
+    ($($rust:ty as $zig:ident { $($field:ident),+ $(,)? }),+ $(,)?) => {
+        $(
+            const _: () = {
+                use ::std::mem::{align_of, offset_of, size_of};
+
+                let zig = &$crate::layout::zig::$zig;
+                assert!(
+                    size_of::<$rust>() == zig.size,
+                    concat!("size of ", stringify!($rust), " differs from Zig ", stringify!($zig))
+                );
+                assert!(
+                    align_of::<$rust>() == zig.align,
+                    concat!("alignment of ", stringify!($rust), " differs from Zig ", stringify!($zig))
+                );
+                assert!(
+                    zig.fields.len() == [$(stringify!($field)),+].len(),
+                    concat!("the fields listed for ", stringify!($rust), " are not those of Zig ", stringify!($zig))
+                );
+                $(
+                    let Some(field) = zig.field(stringify!($field)) else {
+                        panic!(concat!("Zig ", stringify!($zig), " has no field ", stringify!($field)));
+                    };
+                    assert!(
+                        offset_of!($rust, $field) == field.offset,
+                        concat!("offset of ", stringify!($rust), ".", stringify!($field), " differs from Zig")
+                    );
+                    assert!(
+                        $crate::layout::field_size(|value: &$rust| &value.$field) == field.size,
+                        concat!("size of ", stringify!($rust), ".", stringify!($field), " differs from Zig")
+                    );
+                )+
+            };
+        )+
+
+        #[cfg(test)]
+        const CHECKED: &[&str] = &[$(stringify!($zig)),+];
+    };
+}
+
+assert_layout! {
+    crate::szf::SzfBytes as SzfBytes { ptr, len, cap, owned },
+    crate::szf::SzfString as SzfString { ptr, len },
+    crate::szf::SzfResult as SzfResult { code, message, data },
+    crate::bebop::VBytes as VBytes { ptr, len },
+    crate::bebop::VSensorReading as VSensorReading {
+        timestamp,
+        sensor_id,
+        sensor_type,
+        value,
+        unit,
+        location,
+        metadata_count,
+        metadata_keys,
+        metadata_values,
+        error_code,
+        error_message,
+    },
+    crate::idris::IdrisMaybeValue as IdrisMaybeValue { tag, value_ptr },
+    crate::idris::IdrisEitherValue as IdrisEitherValue { tag, left_ptr, right_ptr },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_zig_struct_is_checked() {
+        let manifest: Vec<_> = zig::ALL.iter().map(|(name, _)| *name).collect();
+        assert_eq!(manifest, CHECKED);
+    }
+
+    #[test]
+    fn fields_are_found_by_name() {
+        let bytes = zig::SzfBytes;
+        assert_eq!(bytes.field("ptr").map(|f| f.offset), Some(0));
+        assert_eq!(bytes.field("owned").map(|f| f.size), Some(1));
+        assert_eq!(bytes.field("own"), None);
+        assert_eq!(bytes.field("owned_"), None);
+    }
+
+    #[test]
+    fn padding_after_sensor_type_is_in_the_manifest() {
+        let reading = zig::VSensorReading;
+        let sensor_type = reading.field("sensor_type").unwrap();
+        let value = reading.field("value").unwrap();
+        assert_eq!(sensor_type.size, 2);
+        assert_eq!(value.offset % std::mem::align_of::<f64>(), 0);
+        assert!(value.offset > sensor_type.offset + sensor_type.size);
+    }
+}
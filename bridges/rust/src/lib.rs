@@ -1,17 +1,77 @@
 // SPDX-License-Identifier: PMLP-1.0-or-later
-extern "C" {
-    fn hkdf_derive(password: *const u8, password_len: usize, salt: *const u8, salt_len: usize, key: *mut u8);
+pub mod abi;
+mod backend;
+pub mod bebop;
+pub mod callback;
+#[cfg(feature = "dlopen")]
+pub mod dlopen;
+pub mod error;
+pub mod guard;
+#[cfg(feature = "gen-header")]
+pub mod header;
+pub mod hkdf;
+pub mod idris;
+pub mod kdf;
+#[cfg(zig_layout)]
+mod layout;
+pub mod polyglot;
+pub mod secret;
+pub mod szf;
+
+pub use self::hkdf::{Algorithm, Hkdf, HkdfError};
+pub use abi::{AbiError, AbiVersion};
+pub use callback::{Callback, CallbackHandle};
+pub use error::{BridgeError, ErrorKind};
+pub use guard::{PanicAction, PanicHook, RZF_ERR_PANIC, RZF_OK};
+pub use kdf::{hash_password, verify, KdfError, PasswordHash};
+pub use secret::{Password, Secret, SecretKey};
+
+guard::ffi_export! {
+    /// Deliver `data` from Zig to every closure registered with
+    /// [`callback::register`].
+    ///
+    /// Returns [`RZF_OK`], or [`RZF_ERR_PANIC`] if a closure panicked; the
+    /// remaining closures are skipped and the message is available from
+    /// `rzf_last_panic_message`.
+    ///
+    /// # Safety
+    ///
+    /// `data` must be null or valid for reads of `len` bytes.
+    pub unsafe extern "C" fn rust_callback(data: *const u8, len: usize) -> i32 {
+        callback::broadcast(callback::bytes(data, len));
+        RZF_OK
+    }
 }
 
-#[no_mangle]
-pub extern "C" fn rust_callback(data: *const u8, len: usize) {
-    let _ = (data, len);
+/// Derive a 64-byte key with HKDF-SHA512 and an empty `info`.
+///
+/// Shorthand for `Hkdf::extract(Algorithm::Sha512, salt, password)` expanded
+/// to 64 bytes; use [`Hkdf`] directly for other lengths, hashes or contexts.
+pub fn derive_key(password: &Password, salt: &[u8]) -> SecretKey {
+    let mut key = SecretKey::zeroed(64);
+    backend::active::derive(password.expose_secret(), salt, key.as_mut_bytes());
+    key
 }
 
-pub fn derive_key(password: &[u8], salt: &[u8]) -> [64; u8] {
-    let mut key = [0u8; 64];
-    unsafe {
-        hkdf_derive(password.as_ptr(), password.len(), salt.as_ptr(), salt.len(), key.as_mut_ptr());
+#[cfg(all(test, any(zig_linked, feature = "pure-rust")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derive_key_matches_hkdf_sha512() {
+        let key = derive_key(&Password::from("password"), b"salt");
+        let expected = "dd1893e26644afff749b0e665ac7fda3b67ac4ebd9383de5f915f3df0cb8d39f\
+                        9f07f79ea98dde92f997089a6fa6501ccbd29ccea55da02e9ce70774fe73018e";
+        let hex: String = key
+            .expose_secret()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        assert_eq!(hex, expected);
+    }
+
+    #[test]
+    fn derive_key_accepts_empty_inputs() {
+        assert_ne!(derive_key(&Password::from(""), b""), SecretKey::zeroed(64));
     }
-    key
 }
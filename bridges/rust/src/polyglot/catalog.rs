@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! What comes back from translators: [`Catalog`], shared by the [`po`] and
+//! [`xliff`] importers.
+//!
+//! [`po`]: super::po
+//! [`xliff`]: super::xliff
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use super::TextSegment;
+
+/// The translation of one segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Translation {
+    /// The translated text. A plural entry has one string per plural form,
+    /// in the order given by the catalog's `Plural-Forms`; any other entry
+    /// has exactly one.
+    pub forms: Vec<String>,
+    /// `msgid_plural` of a plural entry.
+    pub plural: Option<String>,
+    /// The translator has not confirmed the text: `#, fuzzy` in PO,
+    /// `state="initial"` in XLIFF. MO files never contain fuzzy entries.
+    pub fuzzy: bool,
+}
+
+impl Translation {
+    /// The first (for plural entries, singular) form, or `None` if `forms` is
+    /// empty.
+    pub fn text(&self) -> Option<&str> {
+        self.forms.first().map(String::as_str)
+    }
+}
+
+/// Translations read back from a PO, MO or XLIFF file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalog {
+    /// The target language: `Language` in the PO or MO header, `trgLang` in
+    /// XLIFF.
+    pub language: Option<String>,
+    /// The `Plural-Forms` header of a PO or MO file, as written.
+    pub plural_forms: Option<String>,
+    /// Every translated segment. Untranslated entries are left out.
+    pub entries: HashMap<TextSegment, Translation>,
+}
+
+impl Catalog {
+    /// The text of every translation that is not fuzzy, as [`inject`] takes
+    /// it. Entries without any form are left out.
+    ///
+    /// [`inject`]: super::inject
+    pub fn translations(&self) -> HashMap<TextSegment, String> {
+        self.entries
+            .iter()
+            .filter(|(_, translation)| !translation.fuzzy)
+            .filter_map(|(segment, translation)| {
+                Some((segment.clone(), translation.text()?.to_owned()))
+            })
+            .collect()
+    }
+}
+
+/// A PO, MO or XLIFF file that could not be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogError {
+    /// Malformed PO syntax on the 1-based `line`.
+    Po { line: usize, reason: &'static str },
+    /// Not a valid MO file.
+    Mo(&'static str),
+    /// Malformed XML, or not XLIFF 2.x, on the 1-based `line`.
+    Xliff { line: usize, reason: &'static str },
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::Po { line, reason } => write!(f, "PO line {line}: {reason}"),
+            CatalogError::Mo(reason) => write!(f, "MO: {reason}"),
+            CatalogError::Xliff { line, reason } => write!(f, "XLIFF line {line}: {reason}"),
+        }
+    }
+}
+
+impl Error for CatalogError {}
+
+/// `file:line:column` or `line:column`, as written by the exporters.
+pub(super) fn parse_location(location: &str) -> Option<(usize, usize)> {
+    let mut parts = location.rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    (line > 0 && column > 0).then_some((line, column))
+}
+
+/// Group `segments` by `(context, text)`, in order of first appearance.
+pub(super) fn group(segments: &[TextSegment]) -> Vec<Vec<&TextSegment>> {
+    let mut groups: Vec<Vec<&TextSegment>> = Vec::new();
+    let mut index: HashMap<_, usize> = HashMap::new();
+    for segment in segments {
+        let key = (segment.context.as_deref(), segment.text.as_str());
+        match index.get(&key) {
+            Some(&i) => groups[i].push(segment),
+            None => {
+                index.insert(key, groups.len());
+                groups.push(vec![segment]);
+            }
+        }
+    }
+    groups
+}
+
+/// Pick `Language` and `Plural-Forms` out of a PO or MO header.
+pub(super) fn read_header(catalog: &mut Catalog, header: &str) {
+    for field in header.lines() {
+        let Some((name, value)) = field.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let slot = match name.trim() {
+            "Language" => &mut catalog.language,
+            "Plural-Forms" => &mut catalog.plural_forms,
+            _ => continue,
+        };
+        if !value.is_empty() {
+            *slot = Some(value.to_owned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translation(forms: &[&str], fuzzy: bool) -> Translation {
+        Translation {
+            forms: forms.iter().map(|&form| form.to_owned()).collect(),
+            plural: None,
+            fuzzy,
+        }
+    }
+
+    #[test]
+    fn translations_skip_fuzzy_and_empty_entries() {
+        let segment = |text: &str| TextSegment {
+            text: text.to_owned(),
+            line: 1,
+            column: 1,
+            context: None,
+        };
+        let catalog = Catalog {
+            entries: HashMap::from([
+                (segment("file"), translation(&["Datei", "Dateien"], false)),
+                (segment("fuzzy"), translation(&["unscharf"], true)),
+                (segment("none"), translation(&[], false)),
+            ]),
+            ..Catalog::default()
+        };
+        assert_eq!(catalog.entries[&segment("file")].text(), Some("Datei"));
+        assert_eq!(catalog.entries[&segment("none")].text(), None);
+        assert_eq!(
+            catalog.translations(),
+            HashMap::from([(segment("file"), "Datei".to_owned())])
+        );
+    }
+}
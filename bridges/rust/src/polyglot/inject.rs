@@ -0,0 +1,702 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Writing translations back into the document they were extracted from.
+//!
+//! [`inject`] extracts the document again and, for every segment that has a
+//! translation, replaces the segment's source text with the translation.
+//! Everything between segments is copied unchanged, so markup, indentation
+//! and the shape of JSON and YAML documents stay as they were. The
+//! translation is escaped for where it lands:
+//!
+//! * Plain text, Markdown and AsciiDoc are line based: line breaks in a
+//!   translation become spaces. `|` in table cells is written as `\|`, and
+//!   Markdown text that would start a block (a heading, list item, thematic
+//!   break, quote or code fence) has its marker escaped with a backslash. Inline
+//!   markup such as `*bold*` is left as written.
+//! * HTML text has `<` written as `&lt;`; attribute values have quotes
+//!   written as `&quot;` and `&#39;`, and unquoted values are quoted when the
+//!   translation needs it. Other text is inserted as written, since extraction
+//!   leaves entities alone too.
+//! * JSON strings are re-encoded.
+//! * YAML scalars keep their quoting style when it can hold the translation.
+//!   Plain scalars that would read back as something else (a number, `yes`,
+//!   a nested mapping, ...) are double-quoted, and lines of a block scalar keep
+//!   their indentation across line breaks.
+//!
+//! A translation equal to the source text leaves the source untouched.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use super::{extract, Format, PolyglotError, TextSegment};
+
+/// Result of [`inject`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Injection {
+    /// The localised document.
+    pub document: String,
+    /// Keys of the translation map that match no segment of the document,
+    /// ordered by position.
+    pub unplaced: Vec<TextSegment>,
+}
+
+/// Write `translations` into `document`.
+///
+/// Keys are segments as returned by [`extract`] for the same document and
+/// format; segments without a translation are left as they are. Fails only if
+/// the document cannot be extracted.
+///
+/// ```
+/// # #[cfg(any(zig_linked, feature = "pure-rust"))] {
+/// use std::collections::HashMap;
+/// use rust_zig_ffi::polyglot::{self, Format};
+///
+/// let doc = "title: Hello\nitems:\n  - Open\n";
+/// let translations: HashMap<_, _> = polyglot::extract(doc, Format::Yaml)?
+///     .into_iter()
+///     .map(|segment| {
+///         let text = match segment.text.as_str() {
+///             "Hello" => "Hallo",
+///             _ => "Öffnen: Datei",
+///         };
+///         (segment, text.to_owned())
+///     })
+///     .collect();
+///
+/// let injected = polyglot::inject(doc, Format::Yaml, &translations)?;
+/// assert_eq!(injected.document, "title: Hallo\nitems:\n  - \"Öffnen: Datei\"\n");
+/// assert!(injected.unplaced.is_empty());
+/// # }
+/// # Ok::<(), rust_zig_ffi::polyglot::PolyglotError>(())
+/// ```
+pub fn inject(
+    document: &str,
+    format: Format,
+    translations: &HashMap<TextSegment, String>,
+) -> Result<Injection, PolyglotError> {
+    let segments = extract(document, format)?;
+    let line_starts: Vec<usize> = [0]
+        .into_iter()
+        .chain(document.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+
+    let mut output = String::with_capacity(document.len());
+    let mut copied = 0;
+    let mut placed = HashSet::new();
+    for segment in &segments {
+        let Some(translation) = translations.get(segment) else {
+            continue;
+        };
+        let start = line_starts[segment.line - 1] + segment.column - 1;
+        if start < copied {
+            continue;
+        }
+        if *translation == segment.text {
+            placed.insert(segment);
+            continue;
+        }
+        let (end, replacement) = match format {
+            Format::PlainText => (start + segment.text.len(), single_line(translation)),
+            Format::Markdown => (start + segment.text.len(), markdown(segment, translation)),
+            Format::Asciidoc => (start + segment.text.len(), asciidoc(segment, translation)),
+            Format::Html => (
+                start + segment.text.len(),
+                html(document, segment, start, translation),
+            ),
+            Format::Json => (double_quoted_end(document, start), json_string(translation)),
+            Format::Yaml => yaml(document, segment, start, translation),
+        };
+        output.push_str(&document[copied..start]);
+        output.push_str(&replacement);
+        copied = end;
+        placed.insert(segment);
+    }
+    output.push_str(&document[copied..]);
+
+    let mut unplaced: Vec<TextSegment> = translations
+        .keys()
+        .filter(|segment| !placed.contains(segment))
+        .cloned()
+        .collect();
+    unplaced.sort_by(|a, b| (a.line, a.column, &a.text).cmp(&(b.line, b.column, &b.text)));
+    Ok(Injection {
+        document: output,
+        unplaced,
+    })
+}
+
+/// Runs of line breaks become one space.
+fn single_line(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_break = false;
+    for c in text.chars() {
+        let is_break = c == '\n' || c == '\r';
+        if !is_break {
+            out.push(c);
+        } else if !in_break {
+            out.push(' ');
+        }
+        in_break = is_break;
+    }
+    out
+}
+
+/// `|` not already escaped becomes `\|`.
+fn escape_cell(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut previous = None;
+    for c in text.chars() {
+        if c == '|' && previous != Some('\\') {
+            out.push('\\');
+        }
+        out.push(c);
+        previous = Some(c);
+    }
+    out
+}
+
+fn is_table_cell(segment: &TextSegment) -> bool {
+    segment.context.as_deref() == Some("table_cell")
+}
+
+fn markdown(segment: &TextSegment, translation: &str) -> String {
+    let text = single_line(translation);
+    if is_table_cell(segment) {
+        return escape_cell(&text);
+    }
+    match block_marker(&text) {
+        Some(at) => format!("{}\\{}", &text[..at], &text[at..]),
+        None => text,
+    }
+}
+
+/// Where the marker is, if Markdown would read `text` at the start of a line
+/// as a heading, list item, thematic break, quote or code fence rather than
+/// as a paragraph.
+fn block_marker(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let run = |c: u8| bytes.iter().take_while(|&&b| b == c).count();
+    let ends_marker = |at: usize| matches!(bytes.get(at), None | Some(b' ' | b'\t'));
+    let is_break = |c: u8| {
+        bytes.iter().filter(|&&b| b == c).count() >= 3
+            && bytes.iter().all(|&b| matches!(b, b' ' | b'\t') || b == c)
+    };
+    match *bytes.first()? {
+        b'#' if run(b'#') <= 6 && ends_marker(run(b'#')) => Some(0),
+        b'-' | b'*' | b'+' if ends_marker(1) => Some(0),
+        c @ (b'-' | b'*' | b'_') if is_break(c) => Some(0),
+        b'>' => Some(0),
+        c @ (b'`' | b'~') if run(c) >= 3 => Some(0),
+        b'0'..=b'9' => {
+            let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+            (digits <= 9
+                && matches!(bytes.get(digits), Some(b'.' | b')'))
+                && ends_marker(digits + 1))
+            .then_some(digits)
+        }
+        _ => None,
+    }
+}
+
+fn asciidoc(segment: &TextSegment, translation: &str) -> String {
+    let text = single_line(translation);
+    if is_table_cell(segment) {
+        escape_cell(&text)
+    } else {
+        text
+    }
+}
+
+fn html(document: &str, segment: &TextSegment, start: usize, translation: &str) -> String {
+    let in_attribute = segment.context.as_deref().is_some_and(|c| c.contains('@'));
+    if !in_attribute {
+        return translation.replace('<', "&lt;");
+    }
+    let escaped = translation.replace('"', "&quot;").replace('\'', "&#39;");
+    let unquoted = document[..start]
+        .trim_end_matches([' ', '\t', '\r', '\n'])
+        .ends_with('=');
+    let needs_quotes = escaped.is_empty()
+        || escaped
+            .chars()
+            .any(|c| c.is_ascii_whitespace() || matches!(c, '>' | '=' | '`' | '<'));
+    if unquoted && needs_quotes {
+        format!("\"{escaped}\"")
+    } else {
+        escaped
+    }
+}
+
+/// Offset just past the closing quote of the `\`-escaped string at `start`.
+fn double_quoted_end(document: &str, start: usize) -> usize {
+    let b = document.as_bytes();
+    let mut i = start + 1;
+    while i < b.len() {
+        match b[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    b.len()
+}
+
+/// Offset just past the closing quote of the YAML single-quoted scalar at
+/// `start`.
+fn single_quoted_end(document: &str, start: usize) -> usize {
+    let b = document.as_bytes();
+    let mut i = start + 1;
+    while i < b.len() {
+        if b[i] == b'\'' {
+            if b.get(i + 1) != Some(&b'\'') {
+                return i + 1;
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    b.len()
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if c < ' ' => write!(out, "\\u{:04x}", c as u32).expect("writing to a String"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn yaml(document: &str, segment: &TextSegment, start: usize, translation: &str) -> (usize, String) {
+    match document.as_bytes()[start] {
+        b'"' => (
+            double_quoted_end(document, start),
+            yaml_double_quoted(translation),
+        ),
+        b'\'' => {
+            let text = if translation.chars().any(char::is_control) {
+                yaml_double_quoted(translation)
+            } else {
+                format!("'{}'", translation.replace('\'', "''"))
+            };
+            (single_quoted_end(document, start), text)
+        }
+        _ => {
+            let end = start + segment.text.len();
+            let line_start = document[..start].rfind('\n').map_or(0, |i| i + 1);
+            let indent = &document[line_start..start];
+            let text = if indent.bytes().all(|c| c == b' ' || c == b'\t') {
+                // A line of a block scalar (or of a multi-line plain one):
+                // further lines get the same indentation.
+                translation
+                    .lines()
+                    .collect::<Vec<_>>()
+                    .join(&format!("\n{indent}"))
+            } else if is_yaml_plain(translation) {
+                translation.to_owned()
+            } else {
+                yaml_double_quoted(translation)
+            };
+            (end, text)
+        }
+    }
+}
+
+/// Double-quoted with the escapes the extractor decodes.
+fn yaml_double_quoted(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).expect("writing to a String"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Whether `text` reads back unchanged as a plain scalar value, both here and
+/// in a full YAML parser.
+fn is_yaml_plain(text: &str) -> bool {
+    let starts_well = text
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || !c.is_ascii());
+    starts_well
+        && !text.chars().any(char::is_control)
+        && !text.ends_with([' ', ':'])
+        && !text.contains(": ")
+        && !text.contains(" #")
+        && extract(&format!("k: {text}"), Format::Yaml).is_ok_and(|segments| {
+            matches!(segments.as_slice(), [segment] if segment.text == text && segment.column == 4)
+        })
+}
+
+#[cfg(all(test, any(zig_linked, feature = "pure-rust")))]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Translate every segment with `f`.
+    fn translate(
+        doc: &str,
+        format: Format,
+        f: impl Fn(&str) -> String,
+    ) -> HashMap<TextSegment, String> {
+        extract(doc, format)
+            .unwrap()
+            .into_iter()
+            .map(|segment| {
+                let text = f(&segment.text);
+                (segment, text)
+            })
+            .collect()
+    }
+
+    fn inject_all(doc: &str, format: Format, f: impl Fn(&str) -> String) -> String {
+        let injection = inject(doc, format, &translate(doc, format, f)).unwrap();
+        assert_eq!(injection.unplaced, []);
+        injection.document
+    }
+
+    fn upper(text: &str) -> String {
+        text.to_uppercase()
+    }
+
+    #[test]
+    fn plain_text_keeps_indentation() {
+        assert_eq!(
+            inject_all("  one\r\n\n\ttwo  \n", Format::PlainText, upper),
+            "  ONE\r\n\n\tTWO  \n"
+        );
+        assert_eq!(
+            inject_all("a\nb", Format::PlainText, |_| "x\r\ny".into()),
+            "x y\nx y"
+        );
+    }
+
+    #[test]
+    fn markdown_keeps_markup_and_escapes_block_starts() {
+        let doc = "# Title\n\n- item\n\n| a | b |\n|---|---|\n\n```\ncode\n```\npara\n";
+        assert_eq!(
+            inject_all(doc, Format::Markdown, upper),
+            "# TITLE\n\n- ITEM\n\n| A | B |\n|---|---|\n\n```\ncode\n```\nPARA\n"
+        );
+        let escaped = inject_all(
+            "# t\n\n| c |\n\np\n\nq\n",
+            Format::Markdown,
+            |text| match text {
+                "t" => "# not a subheading".into(),
+                "c" => "x | y".into(),
+                "p" => "1. not a list".into(),
+                _ => "- not an item".into(),
+            },
+        );
+        assert_eq!(
+            escaped,
+            "# \\# not a subheading\n\n| x \\| y |\n\n1\\. not a list\n\n\\- not an item\n"
+        );
+    }
+
+    #[test]
+    fn markdown_leaves_inline_markup_alone() {
+        for text in [
+            "*Bold* text",
+            "_Italic_ text",
+            "`code` here",
+            "<b>tag</b>",
+            "~~gone~~",
+            "-1 degrees",
+            "#hashtag",
+            "2024.10 release",
+            "| a |",
+        ] {
+            assert_eq!(
+                inject_all("p\n", Format::Markdown, |_| text.into()),
+                format!("{text}\n")
+            );
+        }
+        for (text, escaped) in [
+            ("* item", "\\* item"),
+            ("+", "\\+"),
+            ("###### h6", "\\###### h6"),
+            ("12) step", "12\\) step"),
+            ("> quote", "\\> quote"),
+            ("```rust", "\\```rust"),
+            ("- - -", "\\- - -"),
+            ("***", "\\***"),
+        ] {
+            assert_eq!(
+                inject_all("p\n", Format::Markdown, |_| text.into()),
+                format!("{escaped}\n")
+            );
+        }
+    }
+
+    #[test]
+    fn identity_translations_leave_source_untouched() {
+        for (format, doc) in [
+            (Format::PlainText, "a\rb\n"),
+            (Format::Markdown, "- a\n"),
+            (Format::Markdown, "*Bold* text\r_Italic_\n"),
+            (Format::Markdown, "&\n"),
+            (Format::Html, "<p>a</p>\n"),
+            (Format::Json, "{\"a\": \"x\\u0041\", \"b\": [\"y\"]}"),
+            (Format::Yaml, "a: >\n  folded\n  text\n"),
+        ] {
+            assert_eq!(inject_all(doc, format, str::to_owned), doc, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn asciidoc_keeps_markup() {
+        let doc = "= Title\n:toc:\n\nNOTE: Be careful.\n\n|===\n| a | b\n|===\n\nTerm::\n";
+        assert_eq!(
+            inject_all(doc, Format::Asciidoc, upper),
+            "= TITLE\n:toc:\n\nNOTE: BE CAREFUL.\n\n|===\n| A | B\n|===\n\nTERM::\n"
+        );
+    }
+
+    #[test]
+    fn html_escapes_text_and_attributes() {
+        let doc = "<p class=x>Hello <b>world</b></p>\n<img alt=cat title=\"A cat\">";
+        let out = inject_all(doc, Format::Html, |text| match text {
+            "Hello" => "1 < 2".into(),
+            "world" => "all".into(),
+            "cat" => "a \"big\" cat".into(),
+            _ => "It's".into(),
+        });
+        assert_eq!(
+            out,
+            "<p class=x>1 &lt; 2 <b>all</b></p>\n\
+             <img alt=\"a &quot;big&quot; cat\" title=\"It&#39;s\">"
+        );
+    }
+
+    #[test]
+    fn json_strings_are_reencoded() {
+        let doc = "{\n  \"a\": \"caf\\u00e9\",\n  \"b\": [\"x\", 1]\n}\n";
+        let out = inject_all(doc, Format::Json, |text| format!("\"{text}\"\n\\"));
+        assert_eq!(
+            out,
+            "{\n  \"a\": \"\\\"café\\\"\\n\\\\\",\n  \"b\": [\"\\\"x\\\"\\n\\\\\", 1]\n}\n"
+        );
+    }
+
+    #[test]
+    fn yaml_keeps_quoting_style_when_possible() {
+        let doc =
+            "a: plain\nb: \"double\"\nc: 'single'\nd: |\n  block line\ne:\n  - item # comment\n";
+        let out = inject_all(doc, Format::Yaml, |text| match text {
+            "plain" => "yes".into(),
+            "double" => "tab\there".into(),
+            "single" => "it's".into(),
+            "block line" => "first\nsecond".into(),
+            _ => "translated".into(),
+        });
+        assert_eq!(
+            out,
+            "a: \"yes\"\nb: \"tab\\there\"\nc: 'it''s'\nd: |\n  first\n  second\ne:\n  - translated # comment\n"
+        );
+    }
+
+    #[test]
+    fn unmatched_translations_are_reported() {
+        let doc = "one\ntwo\n";
+        let mut translations = translate(doc, Format::PlainText, upper);
+        let stale = TextSegment {
+            text: "three".into(),
+            line: 3,
+            column: 1,
+            context: None,
+        };
+        let moved = TextSegment {
+            text: "one".into(),
+            line: 1,
+            column: 2,
+            context: None,
+        };
+        translations.insert(stale.clone(), "THREE".into());
+        translations.insert(moved.clone(), "ONE".into());
+        let injection = inject(doc, Format::PlainText, &translations).unwrap();
+        assert_eq!(injection.document, "ONE\nTWO\n");
+        assert_eq!(injection.unplaced, [moved, stale]);
+    }
+
+    #[test]
+    fn invalid_json_fails() {
+        assert_eq!(
+            inject("{", Format::Json, &HashMap::new()),
+            Err(PolyglotError::ParseFailed)
+        );
+    }
+
+    // Documents built from blocks of random words, per format.
+
+    fn words() -> impl Strategy<Value = String> {
+        prop::collection::vec("[a-z]{1,8}", 1..4).prop_map(|words| words.join(" "))
+    }
+
+    fn document(format: Format) -> BoxedStrategy<String> {
+        let block = match format {
+            Format::PlainText => prop_oneof![
+                words().prop_map(|w| format!("{w}\n")),
+                words().prop_map(|w| format!("  {w}  \n")),
+                Just("\n".to_owned()),
+            ]
+            .boxed(),
+            Format::Markdown => prop_oneof![
+                words().prop_map(|w| format!("## {w}\n\n")),
+                words().prop_map(|w| format!("{w}\n\n")),
+                words().prop_map(|w| format!("- {w}\n")),
+                words().prop_map(|w| format!("3. {w}\n")),
+                words().prop_map(|w| format!("> {w}\n\n")),
+                (words(), words()).prop_map(|(a, b)| format!("| {a} | {b} |\n|---|---|\n\n")),
+                words().prop_map(|w| format!("```\n{w}\n```\n")),
+                words().prop_map(|w| format!("*{w}* _{w}_ `{w}`\n\n")),
+            ]
+            .boxed(),
+            Format::Asciidoc => prop_oneof![
+                words().prop_map(|w| format!("== {w}\n\n")),
+                words().prop_map(|w| format!("{w}\n\n")),
+                words().prop_map(|w| format!(".{w}\n")),
+                words().prop_map(|w| format!("* {w}\n")),
+                words().prop_map(|w| format!("TIP: {w}\n\n")),
+                words().prop_map(|w| format!("{w}::\n")),
+                (words(), words()).prop_map(|(a, b)| format!("|===\n| {a} | {b}\n|===\n")),
+                words().prop_map(|w| format!("----\n{w}\n----\n")),
+            ]
+            .boxed(),
+            Format::Html => prop_oneof![
+                words().prop_map(|w| format!("<p>{w}</p>\n")),
+                (words(), words()).prop_map(|(a, b)| format!("<h2 title=\"{a}\">{b}</h2>\n")),
+                words().prop_map(|w| format!("<ul>\n  <li>{w}</li>\n</ul>\n")),
+                "[a-z]{1,8}".prop_map(|w| format!("<img alt={w}>\n")),
+                words().prop_map(|w| format!("<script>{w}</script>\n")),
+            ]
+            .boxed(),
+            Format::Json => prop_oneof![
+                words().prop_map(|w| format!("\"{w}\"")),
+                any::<u16>().prop_map(|n| n.to_string()),
+                (words(), words()).prop_map(|(a, b)| format!("[\"{a}\", {{\"k\": \"{b}\"}}]")),
+                Just("null".to_owned()),
+            ]
+            .boxed(),
+            Format::Yaml => prop_oneof![
+                words().prop_map(|w| format!("{w}\n")),
+                words().prop_map(|w| format!("\"{w}\"\n")),
+                words().prop_map(|w| format!("'{w}'\n")),
+                (words(), words()).prop_map(|(a, b)| format!("\n  - {a}\n  - {b}\n")),
+                words().prop_map(|w| format!("|\n  {w}\n")),
+                any::<u16>().prop_map(|n| format!("{n}\n")),
+            ]
+            .boxed(),
+        };
+        let blocks = prop::collection::vec(block, 0..8);
+        match format {
+            Format::Json => blocks
+                .prop_map(|values| {
+                    let members: Vec<String> = values
+                        .iter()
+                        .enumerate()
+                        .map(|(i, value)| format!("  \"k{i}\": {value}"))
+                        .collect();
+                    format!("{{\n{}\n}}\n", members.join(",\n"))
+                })
+                .boxed(),
+            Format::Yaml => blocks
+                .prop_map(|values| {
+                    values
+                        .iter()
+                        .enumerate()
+                        .map(|(i, value)| format!("k{i}: {value}"))
+                        .collect()
+                })
+                .boxed(),
+            _ => blocks.prop_map(|blocks| blocks.concat()).boxed(),
+        }
+    }
+
+    fn format_and_document() -> impl Strategy<Value = (Format, String)> {
+        prop::sample::select(Format::ALL.to_vec())
+            .prop_flat_map(|format| (Just(format), document(format)))
+    }
+
+    /// A letter-for-letter translation: same shape, different words, and
+    /// sometimes a YAML keyword (`ab` becomes `no`).
+    fn rot13(text: &str) -> String {
+        text.chars()
+            .map(|c| match c {
+                'a'..='m' | 'A'..='M' => (c as u8 + 13) as char,
+                'n'..='z' | 'N'..='Z' => (c as u8 - 13) as char,
+                c => c,
+            })
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn extract_translate_inject_round_trips((format, doc) in format_and_document()) {
+            let before = extract(&doc, format).unwrap();
+            let translations = translate(&doc, format, rot13);
+            let injection = inject(&doc, format, &translations).unwrap();
+            prop_assert!(injection.unplaced.is_empty());
+
+            let after = extract(&injection.document, format).unwrap();
+            prop_assert_eq!(after.len(), before.len());
+            for (old, new) in before.iter().zip(&after) {
+                prop_assert_eq!(&new.text, &rot13(&old.text));
+                prop_assert_eq!((new.line, new.column), (old.line, old.column));
+                prop_assert_eq!(&new.context, &old.context);
+            }
+            prop_assert_eq!(injection.document.lines().count(), doc.lines().count());
+        }
+
+        #[test]
+        fn untranslated_documents_are_unchanged(
+            (format, doc) in format_and_document(),
+            cr in "[a-z]{1,8}\r[a-z]{1,8}\n",
+        ) {
+            // A lone `\r` inside a line is text that a translation would lose.
+            let doc = match format {
+                Format::PlainText | Format::Markdown | Format::Asciidoc => format!("{cr}\n{doc}"),
+                _ => doc,
+            };
+            let identity = translate(&doc, format, str::to_owned);
+            prop_assert_eq!(inject(&doc, format, &identity).unwrap().document, doc.clone());
+            prop_assert_eq!(inject(&doc, format, &HashMap::new()).unwrap().document, doc);
+        }
+
+        #[test]
+        fn json_and_yaml_values_take_any_translation(
+            (format, doc) in prop::sample::select(vec![
+                (Format::Json, "{\"a\": \"x\", \"b\": [\"y\"]}"),
+                (Format::Yaml, "a: x\nb:\n  - y\nc: \"z\"\nd: 'w'\n"),
+            ]),
+            translation in "\\PC*[^\\s]\\PC*|[\"'\\\\\n\t\u{0}:# -]{1,6}x",
+        ) {
+            let injection = inject(doc, format, &translate(doc, format, |_| translation.clone())).unwrap();
+            let after = extract(&injection.document, format).unwrap();
+            prop_assert_eq!(after.len(), extract(doc, format).unwrap().len());
+            for segment in after {
+                prop_assert_eq!(&segment.text, &translation);
+            }
+        }
+    }
+}
@@ -0,0 +1,381 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Translatable text extraction from the polyglot bridge
+//! (`bridges/polyglot/src/main.zig`).
+//!
+//! [`extract`] splits a document into the [`TextSegment`]s a translator sees:
+//! headings, paragraphs, table cells, string values and so on, each with the
+//! 1-based line and byte column it starts at and a context describing where
+//! it came from. The rules for each [`Format`] are listed in the polyglot
+//! bridge README; the `pure-rust` backend reimplements them segment for
+//! segment. [`inject`] writes translations of those segments back into the
+//! document.
+//!
+//! Translators get the segments as a gettext catalog ([`po`]) or an XLIFF 2.0
+//! document ([`xliff`]); reading their work back gives a [`Catalog`], whose
+//! [`translations`](Catalog::translations) go straight into [`inject`].
+//!
+//! ```
+//! # #[cfg(any(zig_linked, feature = "pure-rust"))] {
+//! use rust_zig_ffi::polyglot::{self, Format};
+//!
+//! let segments = polyglot::extract("# Hello\n\nSome *text*.\n", Format::Markdown)?;
+//! assert_eq!(segments[0].text, "Hello");
+//! assert_eq!(segments[0].context.as_deref(), Some("heading"));
+//! assert_eq!((segments[1].line, segments[1].column), (3, 1));
+//! # }
+//! # Ok::<(), rust_zig_ffi::polyglot::PolyglotError>(())
+//! ```
+
+use std::error::Error;
+use std::fmt;
+
+use crate::backend;
+
+mod catalog;
+mod inject;
+pub mod po;
+pub mod xliff;
+
+pub use catalog::{Catalog, CatalogError, Translation};
+pub use inject::{inject, Injection};
+
+pub const POLYGLOT_OK: i32 = 0;
+pub const POLYGLOT_ERR_NULL_PTR: i32 = -1;
+pub const POLYGLOT_ERR_UNSUPPORTED_FORMAT: i32 = -2;
+pub const POLYGLOT_ERR_PARSE_FAILED: i32 = -3;
+pub const POLYGLOT_ERR_ALLOC_FAILED: i32 = -4;
+
+/// Document formats understood by [`extract`]; the discriminant is the
+/// `format` argument of `polyglot_extract`.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    PlainText = 0,
+    Markdown = 1,
+    Asciidoc = 2,
+    Html = 3,
+    Json = 4,
+    Yaml = 5,
+}
+
+impl Format {
+    pub const ALL: [Format; 6] = [
+        Format::PlainText,
+        Format::Markdown,
+        Format::Asciidoc,
+        Format::Html,
+        Format::Json,
+        Format::Yaml,
+    ];
+
+    /// Guess the format from a file extension, without the dot.
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension.to_ascii_lowercase().as_str() {
+            "txt" | "text" => Some(Format::PlainText),
+            "md" | "markdown" => Some(Format::Markdown),
+            "adoc" | "asciidoc" | "asc" => Some(Format::Asciidoc),
+            "html" | "htm" | "xhtml" => Some(Format::Html),
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// One piece of translatable text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextSegment {
+    pub text: String,
+    /// 1-based line of the first byte of the segment in the source.
+    pub line: usize,
+    /// 1-based byte column; for quoted JSON and YAML strings, the quote.
+    pub column: usize,
+    /// Where the text sits: a block kind (`heading`, `table_cell`, ...), the
+    /// enclosing HTML element or `element@attribute`, a JSON pointer, or a
+    /// YAML path such as `menu.items[0].label`. `None` for plain text and
+    /// HTML text outside any element.
+    pub context: Option<String>,
+}
+
+/// A non-zero `POLYGLOT_ERR_*` code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolyglotError {
+    NullPointer,
+    UnsupportedFormat,
+    /// The document is not valid in its format; only JSON is parsed
+    /// strictly.
+    ParseFailed,
+    AllocFailed,
+    /// A code this crate does not know about.
+    Foreign(i32),
+}
+
+impl PolyglotError {
+    /// Map a non-zero `POLYGLOT_ERR_*` code.
+    pub fn from_code(code: i32) -> PolyglotError {
+        match code {
+            POLYGLOT_ERR_NULL_PTR => PolyglotError::NullPointer,
+            POLYGLOT_ERR_UNSUPPORTED_FORMAT => PolyglotError::UnsupportedFormat,
+            POLYGLOT_ERR_PARSE_FAILED => PolyglotError::ParseFailed,
+            POLYGLOT_ERR_ALLOC_FAILED => PolyglotError::AllocFailed,
+            other => PolyglotError::Foreign(other),
+        }
+    }
+
+    /// The `POLYGLOT_ERR_*` code for this error.
+    pub fn code(self) -> i32 {
+        match self {
+            PolyglotError::NullPointer => POLYGLOT_ERR_NULL_PTR,
+            PolyglotError::UnsupportedFormat => POLYGLOT_ERR_UNSUPPORTED_FORMAT,
+            PolyglotError::ParseFailed => POLYGLOT_ERR_PARSE_FAILED,
+            PolyglotError::AllocFailed => POLYGLOT_ERR_ALLOC_FAILED,
+            PolyglotError::Foreign(code) => code,
+        }
+    }
+}
+
+impl fmt::Display for PolyglotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolyglotError::NullPointer => f.write_str("null pointer"),
+            PolyglotError::UnsupportedFormat => f.write_str("unsupported document format"),
+            PolyglotError::ParseFailed => f.write_str("document could not be parsed"),
+            PolyglotError::AllocFailed => f.write_str("allocation failed"),
+            PolyglotError::Foreign(code) => {
+                write!(f, "polyglot core returned error code {code}")
+            }
+        }
+    }
+}
+
+impl Error for PolyglotError {}
+
+/// Extract the translatable segments of `content`, in document order.
+pub fn extract(content: &str, format: Format) -> Result<Vec<TextSegment>, PolyglotError> {
+    backend::active::extract_text(content, format)
+}
+
+#[cfg(all(test, any(zig_linked, feature = "pure-rust")))]
+mod tests {
+    use super::*;
+
+    fn seg(text: &str, line: usize, column: usize, context: Option<&str>) -> TextSegment {
+        TextSegment {
+            text: text.to_owned(),
+            line,
+            column,
+            context: context.map(str::to_owned),
+        }
+    }
+
+    fn extract_ok(content: &str, format: Format) -> Vec<TextSegment> {
+        extract(content, format).unwrap()
+    }
+
+    #[test]
+    fn plain_text_lines() {
+        assert_eq!(
+            extract_ok("  Hello, world  \r\n\n\tsecond line", Format::PlainText),
+            [
+                seg("Hello, world", 1, 3, None),
+                seg("second line", 3, 2, None)
+            ]
+        );
+        assert_eq!(extract_ok("", Format::PlainText), []);
+    }
+
+    #[test]
+    fn empty_documents_have_no_segments() {
+        for format in Format::ALL {
+            if format != Format::Json {
+                assert_eq!(extract_ok("", format), [], "{format:?}");
+            }
+        }
+        assert_eq!(extract("", Format::Json), Err(PolyglotError::ParseFailed));
+    }
+
+    #[test]
+    fn markdown_blocks() {
+        let doc = "---\ntitle: x\n---\n# Title\n\nIntro *text*\n\n```rust\nlet x = 1;\n```\n\
+                   - item one\n2. item two\n> quoted\n\n| A | B \\| C |\n|---|:-:|\n| 1 | 2 |\n\
+                   <!-- note\nstill -->\n***\n";
+        assert_eq!(
+            extract_ok(doc, Format::Markdown),
+            [
+                seg("Title", 4, 3, Some("heading")),
+                seg("Intro *text*", 6, 1, Some("paragraph")),
+                seg("item one", 11, 3, Some("list_item")),
+                seg("item two", 12, 4, Some("list_item")),
+                seg("quoted", 13, 3, Some("blockquote")),
+                seg("A", 15, 3, Some("table_cell")),
+                seg("B \\| C", 15, 7, Some("table_cell")),
+                seg("1", 17, 3, Some("table_cell")),
+                seg("2", 17, 7, Some("table_cell")),
+            ]
+        );
+    }
+
+    #[test]
+    fn markdown_fences_close_on_a_long_enough_run() {
+        let doc = "````\n```\nhidden\n````\nshown";
+        assert_eq!(
+            extract_ok(doc, Format::Markdown),
+            [seg("shown", 5, 1, Some("paragraph"))]
+        );
+    }
+
+    #[test]
+    fn asciidoc_blocks() {
+        let doc =
+            "= Document\n:toc: left\n\n== Section\n\n.Caption\n[source,rust]\n----\ncode\n----\n\
+                   * first\n. second\nNOTE: Careful.\nTerm::\n// comment\nimage::a.png[]\n\
+                   ====\nInside\n====\n|===\n| a | b\n|===\n";
+        assert_eq!(
+            extract_ok(doc, Format::Asciidoc),
+            [
+                seg("Document", 1, 3, Some("title")),
+                seg("Section", 4, 4, Some("title")),
+                seg("Caption", 6, 2, Some("block_title")),
+                seg("first", 11, 3, Some("list_item")),
+                seg("second", 12, 3, Some("list_item")),
+                seg("Careful.", 13, 7, Some("admonition")),
+                seg("Term", 14, 1, Some("term")),
+                seg("Inside", 18, 1, Some("paragraph")),
+                seg("a", 21, 3, Some("table_cell")),
+                seg("b", 21, 7, Some("table_cell")),
+            ]
+        );
+    }
+
+    #[test]
+    fn html_text_and_attributes() {
+        let doc = "<!DOCTYPE html>\n<html><body>\n<h1 class=x>Hello</h1>\n\
+                   <p>Line one\n  line two<br>after</p>\n<img src=a.png alt=\"A cat\"/>\n\
+                   <script>if (a < b) {}</script><!-- hi --><input placeholder='Name'>\n\
+                   </body></html>";
+        assert_eq!(
+            extract_ok(doc, Format::Html),
+            [
+                seg("Hello", 3, 13, Some("h1")),
+                seg("Line one", 4, 4, Some("p")),
+                seg("line two", 5, 3, Some("p")),
+                seg("after", 5, 15, Some("p")),
+                seg("A cat", 6, 21, Some("img@alt")),
+                seg("Name", 7, 62, Some("input@placeholder")),
+            ]
+        );
+    }
+
+    #[test]
+    fn html_text_outside_elements_has_no_context() {
+        assert_eq!(
+            extract_ok("a < b <i>c</i>", Format::Html),
+            [
+                seg("a", 1, 1, None),
+                seg("< b", 1, 3, None),
+                seg("c", 1, 10, Some("i"))
+            ]
+        );
+    }
+
+    #[test]
+    fn json_string_values_with_pointers() {
+        let doc = "{\n  \"title\": \"Caf\\u00e9\",\n  \"a/b\": [1, \"x\", \" \", {\"~k\": \"\\ud83d\\ude00\"}],\n  \"n\": null\n}";
+        assert_eq!(
+            extract_ok(doc, Format::Json),
+            [
+                seg("Café", 2, 12, Some("/title")),
+                seg("x", 3, 14, Some("/a~1b/1")),
+                seg("😀", 3, 31, Some("/a~1b/3/~0k")),
+            ]
+        );
+        assert_eq!(
+            extract_ok("\"top\"", Format::Json),
+            [seg("top", 1, 1, Some(""))]
+        );
+    }
+
+    #[test]
+    fn json_rejects_invalid_documents() {
+        for doc in [
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "\"\\ud800\"",
+            "\"tab\there\"",
+            "01",
+            "[] []",
+            "tru",
+        ] {
+            assert_eq!(
+                extract(doc, Format::Json),
+                Err(PolyglotError::ParseFailed),
+                "{doc}"
+            );
+        }
+        let deep = "[".repeat(200) + &"]".repeat(200);
+        assert_eq!(
+            extract(&deep, Format::Json),
+            Err(PolyglotError::ParseFailed)
+        );
+    }
+
+    #[test]
+    fn yaml_scalars_with_paths() {
+        let doc = "# config\n---\ntitle: Hello world # greeting\ncount: 3\nenabled: yes\n\
+                   menu:\n  items:\n    - label: \"Open\\tfile\"\n      key: o\n    - label: 'It''s'\n\
+                   \x20   - plain item\n  help: |\n    Line one\n    Line two\nalias: *ref\nlist: [a, b]\n";
+        assert_eq!(
+            extract_ok(doc, Format::Yaml),
+            [
+                seg("Hello world", 3, 8, Some("title")),
+                seg("Open\tfile", 8, 14, Some("menu.items[0].label")),
+                seg("o", 9, 12, Some("menu.items[0].key")),
+                seg("It's", 10, 14, Some("menu.items[1].label")),
+                seg("plain item", 11, 7, Some("menu.items[2]")),
+                seg("Line one", 13, 5, Some("menu.help")),
+                seg("Line two", 14, 5, Some("menu.help")),
+            ]
+        );
+    }
+
+    #[test]
+    fn yaml_quoted_colons_are_not_keys() {
+        let doc = "- \"\\\": x\"\n'a''b': c\n\"a\\\"\": d\n";
+        assert_eq!(
+            extract_ok(doc, Format::Yaml),
+            [
+                seg("\": x", 1, 3, Some("[0]")),
+                seg("c", 2, 9, Some("a''b")),
+                seg("d", 3, 8, Some("a\\\"")),
+            ]
+        );
+    }
+
+    #[test]
+    fn yaml_nested_sequences() {
+        let doc = "- - a\n  - b\n- c\n";
+        assert_eq!(
+            extract_ok(doc, Format::Yaml),
+            [
+                seg("a", 1, 5, Some("[0][0]")),
+                seg("b", 2, 5, Some("[0][1]")),
+                seg("c", 3, 3, Some("[1]")),
+            ]
+        );
+    }
+
+    #[test]
+    fn formats_from_extensions() {
+        assert_eq!(Format::from_extension("MD"), Some(Format::Markdown));
+        assert_eq!(Format::from_extension("yml"), Some(Format::Yaml));
+        assert_eq!(Format::from_extension("rs"), None);
+    }
+
+    #[test]
+    fn error_codes_round_trip() {
+        for code in -5..0 {
+            assert_eq!(PolyglotError::from_code(code).code(), code);
+        }
+    }
+}
@@ -0,0 +1,737 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! gettext catalogs.
+//!
+//! [`export`] writes a PO template for the segments of one document.
+//! Segments with the same text and context share an entry, the context
+//! becomes its `msgctxt`, and every position is a `#: source:line:column`
+//! reference. [`import`] reads the translated PO file back and keys each
+//! translation by the segments named in its references; entries without
+//! one, such as those a translator added by hand, are skipped. A compiled MO
+//! file has no references, so [`import_mo`] matches its messages against the
+//! segments of the document instead.
+//!
+//! ```
+//! use rust_zig_ffi::polyglot::{po, TextSegment};
+//!
+//! let title = TextSegment {
+//!     text: "Title".to_owned(),
+//!     line: 1,
+//!     column: 3,
+//!     context: Some("heading".to_owned()),
+//! };
+//! let template = po::export("README.md", &[title.clone()]);
+//! assert!(template.ends_with("#: README.md:1:3\nmsgctxt \"heading\"\nmsgid \"Title\"\nmsgstr \"\"\n"));
+//!
+//! let translated = template.replace(
+//!     "msgid \"Title\"\nmsgstr \"\"",
+//!     "msgid \"Title\"\nmsgstr \"Titel\"",
+//! );
+//! let catalog = po::import(&translated)?;
+//! assert_eq!(catalog.entries[&title].text(), Some("Titel"));
+//! # Ok::<(), rust_zig_ffi::polyglot::CatalogError>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::str;
+
+use super::catalog::{group, parse_location, read_header};
+use super::{Catalog, CatalogError, TextSegment, Translation};
+
+const HEADER: &str = "msgid \"\"\nmsgstr \"\"\n\
+                      \"MIME-Version: 1.0\\n\"\n\
+                      \"Content-Type: text/plain; charset=UTF-8\\n\"\n\
+                      \"Content-Transfer-Encoding: 8bit\\n\"\n";
+
+/// gettext wraps `#:` lines at this width.
+const REFERENCE_WIDTH: usize = 79;
+
+/// A PO template for `segments`, with `source` as the file name in
+/// references. File names containing whitespace cannot be read back.
+pub fn export(source: &str, segments: &[TextSegment]) -> String {
+    let mut out = String::from(HEADER);
+    for group in group(segments) {
+        out.push_str("\n#:");
+        let mut width = 2;
+        for segment in &group {
+            let reference = format!("{source}:{}:{}", segment.line, segment.column);
+            if width > 2 && width + 1 + reference.len() > REFERENCE_WIDTH {
+                out.push_str("\n#:");
+                width = 2;
+            }
+            out.push(' ');
+            out.push_str(&reference);
+            width += 1 + reference.len();
+        }
+        out.push('\n');
+        let first = group[0];
+        if let Some(context) = &first.context {
+            write_string(&mut out, "msgctxt", context);
+        }
+        write_string(&mut out, "msgid", &first.text);
+        out.push_str("msgstr \"\"\n");
+    }
+    out
+}
+
+/// `keyword "text"`, split after each line break the way gettext does.
+fn write_string(out: &mut String, keyword: &str, text: &str) {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    if lines.len() > 1 {
+        out.push_str(keyword);
+        out.push_str(" \"\"\n");
+        for line in lines {
+            write_quoted(out, line);
+            out.push('\n');
+        }
+    } else {
+        out.push_str(keyword);
+        out.push(' ');
+        write_quoted(out, text);
+        out.push('\n');
+    }
+}
+
+fn write_quoted(out: &mut String, text: &str) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            // Octal escapes stop after three digits, so a digit after one is
+            // not swallowed the way it would be after `\x`.
+            c if c.is_ascii_control() => {
+                let _ = write!(out, "\\{:03o}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// The string being continued by a line that starts with `"`.
+#[derive(Clone, Copy)]
+enum Field {
+    Context,
+    Id,
+    Plural,
+    Form(usize),
+}
+
+#[derive(Default)]
+struct Entry {
+    locations: Vec<(usize, usize)>,
+    fuzzy: bool,
+    context: Option<String>,
+    id: Option<String>,
+    plural: Option<String>,
+    forms: Vec<Option<String>>,
+}
+
+impl Entry {
+    fn is_started(&self) -> bool {
+        self.context.is_some() || self.id.is_some()
+    }
+}
+
+/// Read a translated PO file.
+///
+/// Understands everything gettext writes: multi-line strings, every escape
+/// sequence, `msgctxt`, plural entries, flags and obsolete (`#~`) entries,
+/// which are ignored. An entry whose `msgstr` is empty, or whose plural forms
+/// are all empty, is untranslated and left out.
+pub fn import(po: &str) -> Result<Catalog, CatalogError> {
+    let mut catalog = Catalog::default();
+    let mut entry = Entry::default();
+    let mut entry_line = 1;
+    let mut field = None;
+    for (i, line) in po
+        .strip_prefix('\u{feff}')
+        .unwrap_or(po)
+        .lines()
+        .enumerate()
+    {
+        let line_no = i + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            if entry.is_started() {
+                finish(&mut catalog, std::mem::take(&mut entry), entry_line)?;
+            }
+            field = None;
+            if let Some(references) = comment.strip_prefix(':') {
+                let locations = references.split_whitespace().filter_map(parse_location);
+                entry.locations.extend(locations);
+            } else if let Some(flags) = comment.strip_prefix(',') {
+                entry.fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            }
+            continue;
+        }
+        if line.starts_with('"') {
+            let text = parse_string(line, line_no)?;
+            let target = match field {
+                Some(Field::Context) => entry.context.as_mut(),
+                Some(Field::Id) => entry.id.as_mut(),
+                Some(Field::Plural) => entry.plural.as_mut(),
+                Some(Field::Form(index)) => entry.forms[index].as_mut(),
+                None => None,
+            };
+            let Some(target) = target else {
+                return Err(po_error(line_no, "string without a keyword"));
+            };
+            target.push_str(&text);
+            continue;
+        }
+
+        let Some((keyword, rest)) = line.split_once([' ', '\t']) else {
+            return Err(po_error(line_no, "expected a keyword and a string"));
+        };
+        let text = parse_string(rest.trim_start(), line_no)?;
+        if keyword == "msgctxt" || keyword == "msgid" && entry.id.is_some() {
+            if entry.id.is_some() {
+                finish(&mut catalog, std::mem::take(&mut entry), entry_line)?;
+            }
+            if entry.context.is_some() {
+                return Err(po_error(line_no, "msgctxt without msgid"));
+            }
+        }
+        if !entry.is_started() {
+            entry_line = line_no;
+        }
+        field = Some(match keyword {
+            "msgctxt" => {
+                entry.context = Some(text);
+                Field::Context
+            }
+            "msgid" => {
+                entry.id = Some(text);
+                Field::Id
+            }
+            "msgid_plural" => {
+                if entry.id.is_none() || entry.plural.is_some() || !entry.forms.is_empty() {
+                    return Err(po_error(line_no, "misplaced msgid_plural"));
+                }
+                entry.plural = Some(text);
+                Field::Plural
+            }
+            "msgstr" => {
+                if entry.id.is_none() || entry.plural.is_some() || !entry.forms.is_empty() {
+                    return Err(po_error(line_no, "misplaced msgstr"));
+                }
+                entry.forms.push(Some(text));
+                Field::Form(0)
+            }
+            _ => {
+                let index = keyword
+                    .strip_prefix("msgstr[")
+                    .and_then(|index| index.strip_suffix(']'))
+                    .ok_or(po_error(line_no, "unknown keyword"))?
+                    .parse::<usize>()
+                    .map_err(|_| po_error(line_no, "invalid plural form index"))?;
+                if entry.plural.is_none() {
+                    return Err(po_error(line_no, "msgstr[n] without msgid_plural"));
+                }
+                if index >= entry.forms.len() {
+                    entry.forms.resize(index + 1, None);
+                }
+                if entry.forms[index].is_some() {
+                    return Err(po_error(line_no, "duplicate plural form"));
+                }
+                entry.forms[index] = Some(text);
+                Field::Form(index)
+            }
+        });
+    }
+    if entry.is_started() {
+        finish(&mut catalog, entry, entry_line)?;
+    }
+    Ok(catalog)
+}
+
+fn finish(catalog: &mut Catalog, entry: Entry, line: usize) -> Result<(), CatalogError> {
+    let Some(id) = entry.id else {
+        return Err(po_error(line, "msgctxt without msgid"));
+    };
+    if entry.forms.is_empty() {
+        return Err(po_error(line, "msgid without msgstr"));
+    }
+    if entry.forms.iter().any(Option::is_none) {
+        return Err(po_error(line, "missing plural form"));
+    }
+    let forms: Vec<String> = entry.forms.into_iter().flatten().collect();
+    if id.is_empty() && entry.context.is_none() {
+        read_header(catalog, &forms[0]);
+        return Ok(());
+    }
+    if forms.iter().all(String::is_empty) {
+        return Ok(());
+    }
+    let translation = Translation {
+        forms,
+        plural: entry.plural,
+        fuzzy: entry.fuzzy,
+    };
+    for (line, column) in entry.locations {
+        let segment = TextSegment {
+            text: id.clone(),
+            line,
+            column,
+            context: entry.context.clone(),
+        };
+        catalog.entries.insert(segment, translation.clone());
+    }
+    Ok(())
+}
+
+/// Decode the quoted string at the start of `s`; only whitespace may follow.
+fn parse_string(s: &str, line: usize) -> Result<String, CatalogError> {
+    let Some(body) = s.strip_prefix('"') else {
+        return Err(po_error(line, "expected a string"));
+    };
+    // Escapes may spell out single bytes of a UTF-8 sequence.
+    let mut bytes = Vec::with_capacity(body.len());
+    let mut chars = body.char_indices();
+    loop {
+        let Some((i, c)) = chars.next() else {
+            return Err(po_error(line, "unterminated string"));
+        };
+        match c {
+            '"' => {
+                if !body[i + 1..].trim().is_empty() {
+                    return Err(po_error(line, "text after string"));
+                }
+                break;
+            }
+            '\\' => {
+                let Some((_, escape)) = chars.next() else {
+                    return Err(po_error(line, "unterminated string"));
+                };
+                let byte = match escape {
+                    'n' => b'\n',
+                    't' => b'\t',
+                    'r' => b'\r',
+                    'a' => 0x07,
+                    'b' => 0x08,
+                    'f' => 0x0c,
+                    'v' => 0x0b,
+                    '\\' | '"' | '\'' | '?' => escape as u8,
+                    '0'..='7' => {
+                        let mut value = escape as u32 - '0' as u32;
+                        for _ in 0..2 {
+                            let rest = chars.as_str();
+                            match rest.chars().next().and_then(|c| c.to_digit(8)) {
+                                Some(digit) => {
+                                    value = value * 8 + digit;
+                                    chars.next();
+                                }
+                                None => break,
+                            }
+                        }
+                        u8::try_from(value).map_err(|_| po_error(line, "invalid escape"))?
+                    }
+                    'x' => {
+                        let rest = chars.as_str();
+                        let digits = rest.len()
+                            - rest
+                                .trim_start_matches(|c: char| c.is_ascii_hexdigit())
+                                .len();
+                        let value = u8::from_str_radix(&rest[..digits], 16)
+                            .map_err(|_| po_error(line, "invalid escape"))?;
+                        for _ in 0..digits {
+                            chars.next();
+                        }
+                        value
+                    }
+                    _ => return Err(po_error(line, "invalid escape")),
+                };
+                bytes.push(byte);
+            }
+            c => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| po_error(line, "string is not valid UTF-8"))
+}
+
+fn po_error(line: usize, reason: &'static str) -> CatalogError {
+    CatalogError::Po { line, reason }
+}
+
+const MO_MAGIC: u32 = 0x9504_12de;
+
+/// Read a compiled MO file and look up every segment in it by text and
+/// context.
+///
+/// msgfmt drops fuzzy entries unless told otherwise, and the ones it keeps
+/// are not marked, so no [`Translation`] from here is fuzzy.
+pub fn import_mo(mo: &[u8], segments: &[TextSegment]) -> Result<Catalog, CatalogError> {
+    let magic = mo
+        .get(..4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()));
+    let little_endian = match magic {
+        Some(MO_MAGIC) => true,
+        Some(magic) if magic.swap_bytes() == MO_MAGIC => false,
+        _ => return Err(CatalogError::Mo("not an MO file")),
+    };
+    let word = |at: usize| -> Result<usize, CatalogError> {
+        let bytes = at
+            .checked_add(4)
+            .and_then(|end| mo.get(at..end))
+            .ok_or(CatalogError::Mo("truncated file"))?;
+        let bytes = bytes.try_into().unwrap();
+        let word = if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        };
+        Ok(word as usize)
+    };
+    if word(4)? >> 16 > 1 {
+        return Err(CatalogError::Mo("unsupported revision"));
+    }
+    let count = word(8)?;
+    let (originals, translations) = (word(12)?, word(16)?);
+    let string = |table: usize, index: usize| -> Result<&str, CatalogError> {
+        let at = index
+            .checked_mul(8)
+            .and_then(|offset| table.checked_add(offset))
+            .ok_or(CatalogError::Mo("truncated file"))?;
+        let (len, offset) = (word(at)?, word(at + 4)?);
+        let bytes = offset
+            .checked_add(len)
+            .and_then(|end| mo.get(offset..end))
+            .ok_or(CatalogError::Mo("string out of bounds"))?;
+        str::from_utf8(bytes).map_err(|_| CatalogError::Mo("string is not valid UTF-8"))
+    };
+
+    let mut catalog = Catalog::default();
+    let mut messages = HashMap::new();
+    for index in 0..count {
+        let original = string(originals, index)?;
+        let translated = string(translations, index)?;
+        let (context, id) = match original.split_once('\u{4}') {
+            Some((context, id)) => (Some(context), id),
+            None => (None, original),
+        };
+        let (id, plural) = match id.split_once('\0') {
+            Some((id, plural)) => (id, Some(plural.to_owned())),
+            None => (id, None),
+        };
+        if id.is_empty() && context.is_none() {
+            read_header(&mut catalog, translated);
+            continue;
+        }
+        let forms: Vec<String> = translated.split('\0').map(str::to_owned).collect();
+        if forms.iter().all(String::is_empty) {
+            continue;
+        }
+        let translation = Translation {
+            forms,
+            plural,
+            fuzzy: false,
+        };
+        messages.insert((context, id), translation);
+    }
+    for segment in segments {
+        let key = (segment.context.as_deref(), segment.text.as_str());
+        if let Some(translation) = messages.get(&key) {
+            catalog.entries.insert(segment.clone(), translation.clone());
+        }
+    }
+    Ok(catalog)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn seg(text: &str, line: usize, column: usize, context: Option<&str>) -> TextSegment {
+        TextSegment {
+            text: text.to_owned(),
+            line,
+            column,
+            context: context.map(str::to_owned),
+        }
+    }
+
+    fn translation(forms: &[&str], plural: Option<&str>, fuzzy: bool) -> Translation {
+        Translation {
+            forms: forms.iter().map(|form| form.to_string()).collect(),
+            plural: plural.map(str::to_owned),
+            fuzzy,
+        }
+    }
+
+    /// Fill in the `msgstr` of every entry of an exported template with `f`
+    /// of its text.
+    fn fill(template: &str, segments: &[TextSegment], f: impl Fn(&str) -> String) -> String {
+        let mut parts = template.split("msgstr \"\"\n");
+        let header = parts.next().unwrap();
+        let mut out = format!("{header}msgstr \"\"\n{}", parts.next().unwrap());
+        for (group, part) in group(segments).iter().zip(parts) {
+            write_string(&mut out, "msgstr", &f(&group[0].text));
+            out.push_str(part);
+        }
+        out
+    }
+
+    #[test]
+    fn export_merges_segments_and_escapes_strings() {
+        let segments = [
+            seg("Hello", 1, 3, Some("heading")),
+            seg("Say \"hi\"\\\tnow\r\u{1}7", 2, 1, None),
+            seg("Hello", 9, 3, Some("heading")),
+            seg("Hello", 4, 5, Some("")),
+            seg("one\ntwo\n", 5, 12, Some("/a")),
+        ];
+        let expected = format!(
+            "{HEADER}\n\
+             #: a.md:1:3 a.md:9:3\nmsgctxt \"heading\"\nmsgid \"Hello\"\nmsgstr \"\"\n\n\
+             #: a.md:2:1\nmsgid \"Say \\\"hi\\\"\\\\\\tnow\\r\\0017\"\nmsgstr \"\"\n\n\
+             #: a.md:4:5\nmsgctxt \"\"\nmsgid \"Hello\"\nmsgstr \"\"\n\n\
+             #: a.md:5:12\nmsgctxt \"/a\"\nmsgid \"\"\n\"one\\n\"\n\"two\\n\"\nmsgstr \"\"\n"
+        );
+        assert_eq!(export("a.md", &segments), expected);
+    }
+
+    #[test]
+    fn long_reference_lists_wrap() {
+        let segments: Vec<_> = (1..=10).map(|line| seg("x", line * 100, 1, None)).collect();
+        let template = export("docs/guide.md", &segments);
+        let references: Vec<_> = template.lines().filter(|l| l.starts_with("#:")).collect();
+        assert_eq!(references.len(), 4);
+        assert!(references.iter().all(|line| line.len() <= REFERENCE_WIDTH));
+    }
+
+    #[test]
+    fn import_reads_plurals_flags_and_header() {
+        let po = "\u{feff}# Translator comment\nmsgid \"\"\nmsgstr \"\"\n\
+                  \"Language: de\\n\"\n\"Plural-Forms: nplurals=2; plural=(n != 1);\\n\"\n\n\
+                  #. extracted\n#: a.md:1:3 a.md:9:3\n#, fuzzy, c-format\n\
+                  msgctxt \"heading\"\nmsgid \"Hello\"\nmsgstr \"Hallo\"\n\
+                  #: a.md:2:1\nmsgid \"\"\n  \"one \"\n\"file\"\nmsgid_plural \"%d files\"\n\
+                  msgstr[0] \"eine \\x44\\303\\244tei\"\nmsgstr[1] \"%d Dateien\"\n\n\
+                  #: a.md:3:1\nmsgid \"untranslated\"\nmsgstr \"\"\n\n\
+                  #: a.md:4:1 other.md:7\nmsgid \"plain\"\nmsgstr \"\\a\\b\\f\\v\\'\\?\"\n\n\
+                  #~ msgid \"old\"\n#~ msgstr \"alt\"\n";
+        let catalog = import(po).unwrap();
+        assert_eq!(catalog.language.as_deref(), Some("de"));
+        assert_eq!(
+            catalog.plural_forms.as_deref(),
+            Some("nplurals=2; plural=(n != 1);")
+        );
+        let hallo = translation(&["Hallo"], None, true);
+        let files = translation(&["eine Dätei", "%d Dateien"], Some("%d files"), false);
+        assert_eq!(
+            catalog.entries,
+            HashMap::from([
+                (seg("Hello", 1, 3, Some("heading")), hallo.clone()),
+                (seg("Hello", 9, 3, Some("heading")), hallo),
+                (seg("one file", 2, 1, None), files),
+                (
+                    seg("plain", 4, 1, None),
+                    translation(&["\x07\x08\x0c\x0b'?"], None, false)
+                ),
+            ])
+        );
+        assert_eq!(
+            catalog.translations(),
+            HashMap::from([
+                (seg("one file", 2, 1, None), "eine Dätei".to_owned()),
+                (seg("plain", 4, 1, None), "\x07\x08\x0c\x0b'?".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn import_reports_the_line() {
+        for (po, line, reason) in [
+            (
+                "msgid \"a\"\nmsgid \"b\"\nmsgstr \"\"",
+                1,
+                "msgid without msgstr",
+            ),
+            (
+                "msgid \"a\"\nmsgstr \"b\"\nmsgstr \"c\"",
+                3,
+                "misplaced msgstr",
+            ),
+            ("\n\"dangling\"", 2, "string without a keyword"),
+            ("msgid \"a\nmsgstr \"\"", 1, "unterminated string"),
+            ("msgid \"a\" x", 1, "text after string"),
+            ("msgid \"\\q\"", 1, "invalid escape"),
+            (
+                "msgid \"\\377\"\nmsgstr \"\"",
+                1,
+                "string is not valid UTF-8",
+            ),
+            (
+                "msgid \"a\"\nmsgstr[0] \"b\"",
+                2,
+                "msgstr[n] without msgid_plural",
+            ),
+            (
+                "msgid \"a\"\nmsgid_plural \"b\"\nmsgstr[1] \"c\"",
+                1,
+                "missing plural form",
+            ),
+            ("msgctxt \"a\"\nmsgctxt \"b\"", 2, "msgctxt without msgid"),
+            ("msgid", 1, "expected a keyword and a string"),
+            ("msgfoo \"a\"", 1, "unknown keyword"),
+        ] {
+            assert_eq!(import(po), Err(CatalogError::Po { line, reason }), "{po}");
+        }
+    }
+
+    /// An MO file holding `messages`, as msgfmt would write it without a hash
+    /// table.
+    fn mo_file(messages: &[(&str, &str)], big_endian: bool) -> Vec<u8> {
+        let word = |value: usize| {
+            let value = value as u32;
+            if big_endian {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            }
+        };
+        let mut header = Vec::new();
+        for value in [
+            MO_MAGIC as usize,
+            0,
+            messages.len(),
+            28,
+            28 + 8 * messages.len(),
+            0,
+            0,
+        ] {
+            header.extend(word(value));
+        }
+        let mut strings = Vec::new();
+        let mut tables = [Vec::new(), Vec::new()];
+        let base = 28 + 16 * messages.len();
+        for (table, pick) in tables.iter_mut().zip([0, 1]) {
+            for message in messages {
+                let text = if pick == 0 { message.0 } else { message.1 };
+                table.extend(word(text.len()));
+                table.extend(word(base + strings.len()));
+                strings.extend(text.as_bytes());
+                strings.push(0);
+            }
+        }
+        [header, tables.concat(), strings].concat()
+    }
+
+    #[test]
+    fn mo_messages_match_segments_by_context_and_text() {
+        let messages = [
+            (
+                "",
+                "Language: fr\nPlural-Forms: nplurals=2; plural=(n > 1);\n",
+            ),
+            ("heading\u{4}Hello", "Bonjour"),
+            ("Hello", "Salut"),
+            ("file\0files", "fichier\0fichiers"),
+            ("empty", ""),
+        ];
+        let segments = [
+            seg("Hello", 1, 3, Some("heading")),
+            seg("Hello", 2, 1, None),
+            seg("Hello", 3, 1, Some("paragraph")),
+            seg("file", 4, 1, None),
+            seg("empty", 5, 1, None),
+        ];
+        for big_endian in [false, true] {
+            let catalog = import_mo(&mo_file(&messages, big_endian), &segments).unwrap();
+            assert_eq!(catalog.language.as_deref(), Some("fr"));
+            assert_eq!(
+                catalog.plural_forms.as_deref(),
+                Some("nplurals=2; plural=(n > 1);")
+            );
+            assert_eq!(
+                catalog.entries,
+                HashMap::from([
+                    (segments[0].clone(), translation(&["Bonjour"], None, false)),
+                    (segments[1].clone(), translation(&["Salut"], None, false)),
+                    (
+                        segments[3].clone(),
+                        translation(&["fichier", "fichiers"], Some("files"), false)
+                    ),
+                ])
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_mo_files_are_rejected() {
+        let mo = mo_file(&[("a", "b")], false);
+        assert_eq!(
+            import_mo(b"\0\0\0\0", &[]),
+            Err(CatalogError::Mo("not an MO file"))
+        );
+        assert_eq!(
+            import_mo(&mo[..20], &[]),
+            Err(CatalogError::Mo("truncated file"))
+        );
+        assert_eq!(
+            import_mo(&mo[..mo.len() - 2], &[]),
+            Err(CatalogError::Mo("string out of bounds"))
+        );
+        assert_eq!(
+            import_mo(&[&mo[..mo.len() - 2], &[0xff, 0]].concat(), &[]),
+            Err(CatalogError::Mo("string is not valid UTF-8"))
+        );
+    }
+
+    #[cfg(any(zig_linked, feature = "pure-rust"))]
+    #[test]
+    fn extract_export_import_inject() {
+        use crate::polyglot::{extract, inject, Format};
+
+        let doc = "# Hello\n\nHello\n\n- Say \"hi\"\n";
+        let segments = extract(doc, Format::Markdown).unwrap();
+        let translated = fill(&export("doc.md", &segments), &segments, str::to_uppercase);
+        let catalog = import(&translated).unwrap();
+        let injection = inject(doc, Format::Markdown, &catalog.translations()).unwrap();
+        assert_eq!(injection.document, "# HELLO\n\nHELLO\n\n- SAY \"HI\"\n");
+    }
+
+    fn segments() -> impl Strategy<Value = Vec<TextSegment>> {
+        let segment = (
+            "[a-z \"\\\\\n\t\r\u{1}é😀]{0,8}[a-z]",
+            1..500usize,
+            1..80usize,
+            prop::option::of("[a-z/@.\\[\\]0-9 \"\\\\\n]{0,6}"),
+        )
+            .prop_map(|(text, line, column, context)| TextSegment {
+                text,
+                line,
+                column,
+                context,
+            });
+        prop::collection::vec(segment, 0..12)
+    }
+
+    proptest! {
+        #[test]
+        fn export_then_import_round_trips(segments in segments()) {
+            let translate = |text: &str| format!("«{text}»");
+            let catalog = import(&fill(&export("a.md", &segments), &segments, translate)).unwrap();
+            let expected: HashMap<_, _> = segments
+                .iter()
+                .map(|segment| (segment.clone(), translation(&[&translate(&segment.text)], None, false)))
+                .collect();
+            prop_assert_eq!(catalog.entries, expected);
+            prop_assert_eq!(catalog.language, None);
+        }
+
+        #[test]
+        fn templates_have_no_translations(segments in segments()) {
+            prop_assert_eq!(import(&export("a.md", &segments)).unwrap(), Catalog::default());
+        }
+    }
+}
@@ -0,0 +1,654 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! XLIFF 2.0 documents.
+//!
+//! [`export`] writes one `<unit>` per distinct text and context, with the
+//! context as a `context` note and every position as a `location` note
+//! (`line:column`); the document name goes in `<file original="...">`.
+//! Characters XML cannot carry are written as `<cp hex="..."/>` in the source
+//! text. In notes, which cannot hold markup, they become U+FFFD.
+//!
+//! [`import`] reads a translated document back. The targets of all segments of
+//! a unit make up its translation, and a unit whose segment is still in the
+//! `initial` state with a target filled in is [fuzzy](Translation::fuzzy).
+//! Inline markup in targets is dropped, keeping the text of `<pc>` and `<mrk>`.
+//! XLIFF has no plural forms, so every translation has one form.
+//!
+//! ```
+//! use rust_zig_ffi::polyglot::{xliff, TextSegment};
+//!
+//! let title = TextSegment {
+//!     text: "Fish & Chips".to_owned(),
+//!     line: 1,
+//!     column: 3,
+//!     context: Some("heading".to_owned()),
+//! };
+//! let exported = xliff::export("menu.md", "en", &[title.clone()]);
+//! assert!(exported.contains("<source>Fish &amp; Chips</source>"));
+//!
+//! let translated = exported
+//!     .replace("srcLang=\"en\"", "srcLang=\"en\" trgLang=\"fr\"")
+//!     .replace("</source>", "</source>\n        <target>Poisson-frites</target>");
+//! let catalog = xliff::import(&translated)?;
+//! assert_eq!(catalog.language.as_deref(), Some("fr"));
+//! assert_eq!(catalog.entries[&title].text(), Some("Poisson-frites"));
+//! # Ok::<(), rust_zig_ffi::polyglot::CatalogError>(())
+//! ```
+
+use std::fmt::Write;
+
+use super::catalog::{group, parse_location};
+use super::{Catalog, CatalogError, TextSegment, Translation};
+
+const NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:2.0";
+
+/// An XLIFF 2.0 document for `segments`, in `source_language` (a BCP 47
+/// tag such as `en`), with `source` as the document name.
+pub fn export(source: &str, source_language: &str, segments: &[TextSegment]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<xliff xmlns=\"{NAMESPACE}\" version=\"2.0\" srcLang=\"{}\">",
+        escape(source_language, false)
+    );
+    let _ = writeln!(
+        out,
+        "  <file id=\"f1\" original=\"{}\">",
+        escape(source, false)
+    );
+    for (i, group) in group(segments).iter().enumerate() {
+        let _ = writeln!(out, "    <unit id=\"u{}\">", i + 1);
+        out.push_str("      <notes>\n");
+        if let Some(context) = &group[0].context {
+            let _ = writeln!(
+                out,
+                "        <note category=\"context\">{}</note>",
+                escape(context, false)
+            );
+        }
+        for segment in group {
+            let _ = writeln!(
+                out,
+                "        <note category=\"location\">{}:{}</note>",
+                segment.line, segment.column
+            );
+        }
+        out.push_str("      </notes>\n      <segment>\n");
+        let _ = writeln!(
+            out,
+            "        <source>{}</source>",
+            escape(&group[0].text, true)
+        );
+        out.push_str("      </segment>\n    </unit>\n");
+    }
+    out.push_str("  </file>\n</xliff>\n");
+    out
+}
+
+/// Escape `text` for element content or a double-quoted attribute. With
+/// `code_points`, characters XML 1.0 does not allow are written as XLIFF
+/// `<cp>` elements; otherwise they are replaced.
+fn escape(text: &str, code_points: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            // A raw carriage return would be read back as a line feed.
+            '\r' => out.push_str("&#13;"),
+            c if is_xml_char(c) => out.push(c),
+            c if code_points => {
+                let _ = write!(out, "<cp hex=\"{:04X}\"/>", c as u32);
+            }
+            _ => out.push(char::REPLACEMENT_CHARACTER),
+        }
+    }
+    out
+}
+
+fn is_xml_char(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | ' '..='\u{d7ff}' | '\u{e000}'..='\u{fffd}' | '\u{10000}'..)
+}
+
+/// What is collected from the `<unit>` being read.
+#[derive(Default)]
+struct Unit {
+    context: Option<String>,
+    locations: Vec<(usize, usize)>,
+    source: String,
+    target: Option<String>,
+    fuzzy: bool,
+}
+
+/// Where character data goes.
+enum Capture {
+    Source,
+    Target,
+    Note {
+        category: Option<String>,
+        text: String,
+    },
+}
+
+/// Read a translated XLIFF 2.x document.
+pub fn import(xliff: &str) -> Result<Catalog, CatalogError> {
+    let mut reader = Reader { xml: xliff, pos: 0 };
+    let mut catalog = Catalog::default();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut unit: Option<Unit> = None;
+    // The capture and the depth of the element that started it.
+    let mut capture: Option<(Capture, usize)> = None;
+    let mut seen_root = false;
+    while let Some(event) = reader.next()? {
+        match event {
+            Event::Start {
+                name,
+                attributes,
+                empty,
+            } => {
+                let name = local_name(name);
+                let attribute = |wanted: &str| {
+                    attributes
+                        .iter()
+                        .find(|(name, _)| local_name(name) == wanted)
+                        .map(|(_, value)| value.as_str())
+                };
+                let parent = stack.last().copied();
+                match (parent, name) {
+                    (None, _) if seen_root => {
+                        return Err(reader.error("more than one root element"));
+                    }
+                    (None, "xliff") => {
+                        seen_root = true;
+                        if !attribute("version").is_some_and(|v| v.starts_with("2.")) {
+                            return Err(reader.error("only XLIFF 2 is supported"));
+                        }
+                        catalog.language = attribute("trgLang").map(str::to_owned);
+                    }
+                    (None, _) => return Err(reader.error("not an XLIFF document")),
+                    (Some("file"), "unit") => unit = Some(Unit::default()),
+                    (Some("notes"), "note") if unit.is_some() && capture.is_none() => {
+                        let category = attribute("category").map(str::to_owned);
+                        let text = String::new();
+                        capture = Some((Capture::Note { category, text }, stack.len()));
+                    }
+                    (Some("unit"), "segment") => {
+                        if let Some(unit) = &mut unit {
+                            unit.fuzzy |= attribute("state") == Some("initial");
+                        }
+                    }
+                    (Some("segment" | "ignorable"), "source") if unit.is_some() => {
+                        capture = Some((Capture::Source, stack.len()));
+                    }
+                    (Some("segment" | "ignorable"), "target") => {
+                        if let Some(unit) = &mut unit {
+                            unit.target.get_or_insert_with(String::new);
+                            capture = Some((Capture::Target, stack.len()));
+                        }
+                    }
+                    (_, "cp") => {
+                        let c = attribute("hex")
+                            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| reader.error("invalid cp element"))?;
+                        push_text(&mut unit, &mut capture, &c.to_string());
+                    }
+                    _ => {}
+                }
+                if !empty {
+                    stack.push(name);
+                }
+            }
+            Event::End(name) => {
+                if stack.pop() != Some(local_name(name)) {
+                    return Err(reader.error("mismatched end tag"));
+                }
+                if capture
+                    .as_ref()
+                    .is_some_and(|(_, depth)| *depth == stack.len())
+                {
+                    let (finished, _) = capture.take().unwrap();
+                    if let (Capture::Note { category, text }, Some(unit)) = (finished, &mut unit) {
+                        match category.as_deref() {
+                            Some("context") => unit.context = Some(text),
+                            Some("location") => unit.locations.extend(parse_location(&text)),
+                            _ => {}
+                        }
+                    }
+                }
+                if local_name(name) == "unit" {
+                    if let Some(unit) = unit.take() {
+                        finish(&mut catalog, unit);
+                    }
+                }
+            }
+            Event::Text(text) => {
+                if capture.is_some() {
+                    push_text(&mut unit, &mut capture, &text);
+                } else if stack.is_empty() && !text.trim().is_empty() {
+                    return Err(reader.error("text outside the root element"));
+                }
+            }
+        }
+    }
+    if !seen_root {
+        return Err(reader.error("not an XLIFF document"));
+    }
+    if !stack.is_empty() {
+        return Err(reader.error("unexpected end of document"));
+    }
+    Ok(catalog)
+}
+
+fn push_text(unit: &mut Option<Unit>, capture: &mut Option<(Capture, usize)>, text: &str) {
+    match (capture, unit) {
+        (Some((Capture::Note { text: note, .. }, _)), _) => note.push_str(text),
+        (Some((Capture::Source, _)), Some(unit)) => unit.source.push_str(text),
+        (
+            Some((Capture::Target, _)),
+            Some(Unit {
+                target: Some(target),
+                ..
+            }),
+        ) => target.push_str(text),
+        _ => {}
+    }
+}
+
+fn finish(catalog: &mut Catalog, unit: Unit) {
+    let Some(target) = unit.target.filter(|target| !target.is_empty()) else {
+        return;
+    };
+    let translation = Translation {
+        forms: vec![target],
+        plural: None,
+        fuzzy: unit.fuzzy,
+    };
+    for (line, column) in unit.locations {
+        let segment = TextSegment {
+            text: unit.source.clone(),
+            line,
+            column,
+            context: unit.context.clone(),
+        };
+        catalog.entries.insert(segment, translation.clone());
+    }
+}
+
+/// `name` without its namespace prefix. XLIFF elements are matched by local
+/// name; those of other namespaces are ignored anyway.
+fn local_name(name: &str) -> &str {
+    name.rsplit_once(':').map_or(name, |(_, local)| local)
+}
+
+enum Event<'a> {
+    Start {
+        name: &'a str,
+        attributes: Vec<(&'a str, String)>,
+        empty: bool,
+    },
+    End(&'a str),
+    Text(String),
+}
+
+/// Just enough XML: elements, attributes, character and predefined entity
+/// references, CDATA, comments and processing instructions. Document type
+/// declarations are refused.
+struct Reader<'a> {
+    xml: &'a str,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, reason: &'static str) -> CatalogError {
+        let line = 1 + self.xml[..self.pos].matches('\n').count();
+        CatalogError::Xliff { line, reason }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.xml[self.pos..]
+    }
+
+    /// Move past the next `end`, failing with `reason` if there is none.
+    fn skip_past(&mut self, end: &str, reason: &'static str) -> Result<&'a str, CatalogError> {
+        let rest = self.rest();
+        let Some(i) = rest.find(end) else {
+            return Err(self.error(reason));
+        };
+        self.pos += i + end.len();
+        Ok(&rest[..i])
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\r', '\n']).len();
+    }
+
+    fn name(&mut self) -> Result<&'a str, CatalogError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_ascii_whitespace() || matches!(c, '/' | '>' | '=' | '<'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn next(&mut self) -> Result<Option<Event<'a>>, CatalogError> {
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Ok(None);
+            }
+            if rest.starts_with("<!--") {
+                self.skip_past("-->", "unterminated comment")?;
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>", "unterminated processing instruction")?;
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                self.pos += rest.len() - cdata.len();
+                let text = self.skip_past("]]>", "unterminated CDATA section")?;
+                return Ok(Some(Event::Text(normalize_newlines(text))));
+            } else if rest.starts_with("<!") {
+                return Err(self.error("document type declarations are not supported"));
+            } else if rest.starts_with("</") {
+                self.pos += 2;
+                let name = self.name()?;
+                self.skip_whitespace();
+                if !self.rest().starts_with('>') {
+                    return Err(self.error("malformed end tag"));
+                }
+                self.pos += 1;
+                return Ok(Some(Event::End(name)));
+            } else if rest.starts_with('<') {
+                self.pos += 1;
+                return self.start_tag().map(Some);
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                let text = self.decode(&rest[..len])?;
+                self.pos += len;
+                return Ok(Some(Event::Text(text)));
+            }
+        }
+    }
+
+    fn start_tag(&mut self) -> Result<Event<'a>, CatalogError> {
+        let name = self.name()?;
+        let mut attributes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok(Event::Start {
+                    name,
+                    attributes,
+                    empty: true,
+                });
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                return Ok(Event::Start {
+                    name,
+                    attributes,
+                    empty: false,
+                });
+            }
+            let attribute = self.name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error("expected `=` after attribute name"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(quote @ ('"' | '\'')) => quote,
+                _ => return Err(self.error("expected a quoted attribute value")),
+            };
+            self.pos += 1;
+            let raw = self.skip_past(
+                if quote == '"' { "\"" } else { "'" },
+                "unterminated attribute value",
+            )?;
+            if raw.contains('<') {
+                return Err(self.error("`<` in attribute value"));
+            }
+            // Literal whitespace in attribute values reads as spaces;
+            // character references to it do not.
+            let value = self.decode(&normalize_newlines(raw).replace(['\t', '\n'], " "))?;
+            attributes.push((attribute, value));
+        }
+    }
+
+    /// Resolve entity references in character data.
+    fn decode(&self, raw: &str) -> Result<String, CatalogError> {
+        let raw = normalize_newlines(raw);
+        let mut out = String::with_capacity(raw.len());
+        let mut rest = raw.as_str();
+        while let Some(amp) = rest.find('&') {
+            out.push_str(&rest[..amp]);
+            let Some(semi) = rest[amp..].find(';') else {
+                return Err(self.error("unterminated entity reference"));
+            };
+            let entity = &rest[amp + 1..amp + semi];
+            let c = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => {
+                    let code = match entity.strip_prefix("#x") {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => entity.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+                    };
+                    code.and_then(char::from_u32)
+                }
+            };
+            out.push(c.ok_or_else(|| self.error("unknown entity reference"))?);
+            rest = &rest[amp + semi + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+/// XML reads `\r\n` and a lone `\r` as `\n`.
+fn normalize_newlines(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    fn seg(text: &str, line: usize, column: usize, context: Option<&str>) -> TextSegment {
+        TextSegment {
+            text: text.to_owned(),
+            line,
+            column,
+            context: context.map(str::to_owned),
+        }
+    }
+
+    fn translation(text: &str, fuzzy: bool) -> Translation {
+        Translation {
+            forms: vec![text.to_owned()],
+            plural: None,
+            fuzzy,
+        }
+    }
+
+    /// Give every unit of an exported document a target of `f` of its text.
+    fn fill(exported: &str, segments: &[TextSegment], f: impl Fn(&str) -> String) -> String {
+        let mut parts = exported.split("</source>");
+        let mut out = parts.next().unwrap().to_owned();
+        for (group, part) in group(segments).iter().zip(parts) {
+            let target = escape(&f(&group[0].text), true);
+            let _ = write!(out, "</source>\n        <target>{target}</target>{part}");
+        }
+        out
+    }
+
+    #[test]
+    fn export_writes_one_unit_per_text_and_context() {
+        let segments = [
+            seg("A < B & \"C\"\r", 1, 3, Some("h1")),
+            seg("Bell\u{7}", 2, 1, Some("k\u{1}")),
+            seg("A < B & \"C\"\r", 9, 3, Some("h1")),
+            seg("Plain", 4, 5, None),
+        ];
+        let expected = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <xliff xmlns=\"urn:oasis:names:tc:xliff:document:2.0\" version=\"2.0\" srcLang=\"en-GB\">\n\
+            \x20 <file id=\"f1\" original=\"a&amp;b.html\">\n\
+            \x20   <unit id=\"u1\">\n\
+            \x20     <notes>\n\
+            \x20       <note category=\"context\">h1</note>\n\
+            \x20       <note category=\"location\">1:3</note>\n\
+            \x20       <note category=\"location\">9:3</note>\n\
+            \x20     </notes>\n\
+            \x20     <segment>\n\
+            \x20       <source>A &lt; B &amp; &quot;C&quot;&#13;</source>\n\
+            \x20     </segment>\n\
+            \x20   </unit>\n\
+            \x20   <unit id=\"u2\">\n\
+            \x20     <notes>\n\
+            \x20       <note category=\"context\">k\u{fffd}</note>\n\
+            \x20       <note category=\"location\">2:1</note>\n\
+            \x20     </notes>\n\
+            \x20     <segment>\n\
+            \x20       <source>Bell<cp hex=\"0007\"/></source>\n\
+            \x20     </segment>\n\
+            \x20   </unit>\n\
+            \x20   <unit id=\"u3\">\n\
+            \x20     <notes>\n\
+            \x20       <note category=\"location\">4:5</note>\n\
+            \x20     </notes>\n\
+            \x20     <segment>\n\
+            \x20       <source>Plain</source>\n\
+            \x20     </segment>\n\
+            \x20   </unit>\n\
+            \x20 </file>\n\
+            </xliff>\n";
+        assert_eq!(export("a&b.html", "en-GB", &segments), expected);
+    }
+
+    #[test]
+    fn import_reads_targets_states_and_inline_markup() {
+        let xliff = "<?xml version='1.0'?>\r\n<!-- exported -->\r\n\
+            <x:xliff xmlns:x='urn:oasis:names:tc:xliff:document:2.0' version='2.1'\n\
+            \x20   srcLang='en' trgLang='de'>\n\
+            <x:file id='f'><x:notes><x:note category='context'>file</x:note></x:notes>\n\
+            <x:unit id='a'><x:notes><x:note category='context'>h1</x:note>\
+            <x:note category='location'>1:3</x:note><x:note category='location'>9:3</x:note>\
+            <x:note category='comment'>ignored</x:note></x:notes>\
+            <x:segment state='final'><x:source>Hello <x:ph id='1'/>world</x:source>\
+            <x:target>Hallo <x:pc id='2'>Welt</x:pc><x:cp hex='1F600'/> &amp;&#x21;&#33;</x:target></x:segment>\
+            <x:ignorable><x:source> </x:source></x:ignorable>\
+            <x:segment><x:source>Bye</x:source><x:target><![CDATA[<Tschüss>]]></x:target></x:segment></x:unit>\n\
+            <x:unit id='b'><x:notes><x:note category='location'>2:1</x:note></x:notes>\
+            <x:segment state='initial'><x:source>Maybe</x:source><x:target>Vielleicht</x:target></x:segment></x:unit>\n\
+            <x:unit id='c'><x:notes><x:note category='location'>3:1</x:note></x:notes>\
+            <x:segment><x:source>Empty</x:source><x:target/></x:segment></x:unit>\n\
+            <x:unit id='d'><x:segment><x:source>Nowhere</x:source><x:target>Nirgends</x:target></x:segment></x:unit>\n\
+            </x:file></x:xliff>\n";
+        let catalog = import(xliff).unwrap();
+        assert_eq!(catalog.language.as_deref(), Some("de"));
+        let hello = translation("Hallo Welt😀 &!!<Tschüss>", false);
+        assert_eq!(
+            catalog.entries,
+            HashMap::from([
+                (seg("Hello world Bye", 1, 3, Some("h1")), hello.clone()),
+                (seg("Hello world Bye", 9, 3, Some("h1")), hello),
+                (seg("Maybe", 2, 1, None), translation("Vielleicht", true)),
+            ])
+        );
+        assert_eq!(catalog.translations().len(), 2);
+    }
+
+    #[test]
+    fn import_reports_the_line() {
+        for (xliff, line, reason) in [
+            ("", 1, "not an XLIFF document"),
+            ("<xliff version=\"1.2\"/>", 1, "only XLIFF 2 is supported"),
+            ("<html/>", 1, "not an XLIFF document"),
+            (
+                "<xliff version=\"2.0\">\n<file>\n</xliff>",
+                3,
+                "mismatched end tag",
+            ),
+            (
+                "<xliff version=\"2.0\">\n<file>",
+                2,
+                "unexpected end of document",
+            ),
+            (
+                "<xliff version=\"2.0\">&nbsp;</xliff>",
+                1,
+                "unknown entity reference",
+            ),
+            (
+                "<xliff version=\"2.0\"/><xliff version=\"2.0\"/>",
+                1,
+                "more than one root element",
+            ),
+            (
+                "<!DOCTYPE xliff>",
+                1,
+                "document type declarations are not supported",
+            ),
+            (
+                "<xliff version=2.0/>",
+                1,
+                "expected a quoted attribute value",
+            ),
+            (
+                "<xliff version=\"2.0\">\n<!-- open",
+                2,
+                "unterminated comment",
+            ),
+            ("text", 1, "text outside the root element"),
+        ] {
+            assert_eq!(
+                import(xliff),
+                Err(CatalogError::Xliff { line, reason }),
+                "{xliff}"
+            );
+        }
+    }
+
+    fn segments() -> impl Strategy<Value = Vec<TextSegment>> {
+        let segment = (
+            "[a-z <>&\"'\n\t\r\u{0}\u{1}\u{fffe}é😀]{0,8}[a-z]",
+            1..500usize,
+            1..80usize,
+            prop::option::of("[a-z/@.\\[\\]0-9 <>&\"'\n\r]{0,6}"),
+        )
+            .prop_map(|(text, line, column, context)| TextSegment {
+                text,
+                line,
+                column,
+                context,
+            });
+        prop::collection::vec(segment, 0..12)
+    }
+
+    proptest! {
+        #[test]
+        fn export_then_import_round_trips(segments in segments()) {
+            let translate = |text: &str| format!("«{text}»");
+            let exported = export("a.md", "en", &segments);
+            let catalog = import(&fill(&exported, &segments, translate)).unwrap();
+            let expected: HashMap<_, _> = segments
+                .iter()
+                .map(|segment| (segment.clone(), translation(&translate(&segment.text), false)))
+                .collect();
+            prop_assert_eq!(catalog.entries, expected);
+            prop_assert_eq!(import(&exported).unwrap(), Catalog::default());
+        }
+    }
+}
@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Secret byte strings for key-derivation inputs and outputs.
+//!
+//! [`Password`] and [`SecretKey`] keep their bytes in a single heap
+//! allocation that is zeroed on drop, never print their contents, and compare
+//! in constant time. The derivation API only accepts and returns these types.
+
+use std::fmt;
+
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Read access to the bytes of a secret type.
+///
+/// Sealed: implemented only by [`Password`] and [`SecretKey`], so generic
+/// derivation functions cannot be handed a plain slice.
+pub trait Secret: sealed::Sealed {
+    fn expose_secret(&self) -> &[u8];
+}
+
+/// Heap buffer zeroed on drop. Built so that no un-zeroed copy is left
+/// behind when converting from a `Vec` with spare capacity.
+struct SecretBytes(Box<[u8]>);
+
+impl SecretBytes {
+    fn zeroed(len: usize) -> SecretBytes {
+        SecretBytes(vec![0u8; len].into_boxed_slice())
+    }
+
+    fn from_vec(mut bytes: Vec<u8>) -> SecretBytes {
+        if bytes.len() == bytes.capacity() {
+            return SecretBytes(bytes.into_boxed_slice());
+        }
+        let copy = SecretBytes(bytes.as_slice().into());
+        bytes.zeroize();
+        copy
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+macro_rules! secret_type {
+    ($name:ident) => {
+        impl $name {
+            /// Take ownership of `bytes`; the original buffer is wiped.
+            pub fn new(bytes: Vec<u8>) -> $name {
+                $name(SecretBytes::from_vec(bytes))
+            }
+
+            /// Copy `bytes` into a new secret. The caller stays responsible
+            /// for wiping the source.
+            pub fn from_slice(bytes: &[u8]) -> $name {
+                $name(SecretBytes(bytes.into()))
+            }
+
+            pub fn len(&self) -> usize {
+                self.0 .0.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.0 .0.is_empty()
+            }
+
+            /// Borrow the secret bytes. Keep the borrow short-lived.
+            pub fn expose_secret(&self) -> &[u8] {
+                &self.0 .0
+            }
+        }
+
+        impl sealed::Sealed for $name {}
+
+        impl Secret for $name {
+            fn expose_secret(&self) -> &[u8] {
+                &self.0 .0
+            }
+        }
+
+        impl From<Vec<u8>> for $name {
+            fn from(bytes: Vec<u8>) -> $name {
+                $name::new(bytes)
+            }
+        }
+
+        impl ConstantTimeEq for $name {
+            fn ct_eq(&self, other: &$name) -> subtle::Choice {
+                self.expose_secret().ct_eq(other.expose_secret())
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &$name) -> bool {
+                self.ct_eq(other).into()
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(
+                    f,
+                    concat!(stringify!($name), "([REDACTED; {} bytes])"),
+                    self.len()
+                )
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("[REDACTED]")
+            }
+        }
+    };
+}
+
+/// A user-supplied password or other low-entropy secret.
+pub struct Password(SecretBytes);
+
+secret_type!(Password);
+
+impl From<String> for Password {
+    fn from(password: String) -> Password {
+        Password::new(password.into_bytes())
+    }
+}
+
+impl From<&str> for Password {
+    fn from(password: &str) -> Password {
+        Password::from_slice(password.as_bytes())
+    }
+}
+
+/// Derived key material.
+pub struct SecretKey(SecretBytes);
+
+secret_type!(SecretKey);
+
+impl SecretKey {
+    /// A zero-filled key of `len` bytes, for FFI calls to write into.
+    pub(crate) fn zeroed(len: usize) -> SecretKey {
+        SecretKey(SecretBytes::zeroed(len))
+    }
+
+    pub(crate) fn as_mut_bytes(&mut self) -> &mut [u8] {
+        &mut self.0 .0
+    }
+}
+
+impl Clone for SecretKey {
+    fn clone(&self) -> SecretKey {
+        SecretKey::from_slice(self.expose_secret())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_and_display_redact() {
+        let password = Password::from("hunter2");
+        assert_eq!(format!("{password:?}"), "Password([REDACTED; 7 bytes])");
+        assert_eq!(format!("{password}"), "[REDACTED]");
+
+        let key = SecretKey::from_slice(&[0xaa; 32]);
+        assert_eq!(format!("{key:?}"), "SecretKey([REDACTED; 32 bytes])");
+        assert!(!format!("{key:?}{key}").contains("aa"));
+    }
+
+    #[test]
+    fn equality_compares_contents() {
+        assert_eq!(
+            SecretKey::from_slice(b"same"),
+            SecretKey::from_slice(b"same")
+        );
+        assert_ne!(
+            SecretKey::from_slice(b"same"),
+            SecretKey::from_slice(b"diff")
+        );
+        assert_ne!(
+            SecretKey::from_slice(b"same"),
+            SecretKey::from_slice(b"same!")
+        );
+        assert!(bool::from(
+            Password::from("pw").ct_eq(&Password::from("pw"))
+        ));
+    }
+
+    #[test]
+    fn new_keeps_bytes_when_vec_has_spare_capacity() {
+        let mut bytes = Vec::with_capacity(64);
+        bytes.extend_from_slice(b"secret");
+        let key = SecretKey::new(bytes);
+        assert_eq!(key.expose_secret(), b"secret");
+        assert_eq!(key.len(), 6);
+    }
+
+    #[test]
+    fn clone_is_independent() {
+        let mut key = SecretKey::zeroed(4);
+        let copy = key.clone();
+        key.as_mut_bytes()[0] = 1;
+        assert_eq!(copy.expose_secret(), &[0, 0, 0, 0]);
+    }
+}
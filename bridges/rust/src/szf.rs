@@ -0,0 +1,506 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Rust binding for the Swift bridge's `szf_*` context API.
+//!
+//! The C ABI in `bridges/swift/src/lib.zig` is not Swift-specific; `build.rs`
+//! compiles it into the Zig core alongside `zig-lib/src/lib.zig`. This module
+//! mirrors its types with `#[repr(C)]` structs and wraps the context in an
+//! owning [`Context`] whose methods return `Result<Vec<u8>, SzfError>`.
+//!
+//! Long calls report progress to a closure and stop early when their
+//! [`CancellationToken`] is cancelled; see [`Context::process_with`].
+//!
+//! The types and error codes are always available, also for use with
+//! `dlopen::SzfApi` (feature `dlopen`). [`Context`] and [`version`] call the
+//! core linked into this crate, so `pure-rust` builds only have them when a
+//! Zig compiler was found.
+
+use std::error::Error;
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+use std::ffi::CStr;
+use std::ffi::{c_char, c_void};
+use std::fmt;
+use std::ptr;
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+use std::sync::OnceLock;
+
+use crate::abi::AbiError;
+
+pub const SZF_OK: i32 = 0;
+pub const SZF_ERR_NULL_PTR: i32 = -1;
+pub const SZF_ERR_INVALID_UTF8: i32 = -2;
+pub const SZF_ERR_ALLOC_FAILED: i32 = -3;
+pub const SZF_ERR_INVALID_LENGTH: i32 = -4;
+pub const SZF_ERR_NOT_FOUND: i32 = -5;
+pub const SZF_ERR_ALREADY_EXISTS: i32 = -6;
+pub const SZF_ERR_CALLBACK_FAILED: i32 = -7;
+pub const SZF_ERR_NOT_IMPLEMENTED: i32 = -99;
+
+/// Byte buffer for FFI. Data is not NUL-terminated.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SzfBytes {
+    pub ptr: *const u8,
+    pub len: usize,
+    /// Capacity (for owned buffers).
+    pub cap: usize,
+    /// Non-zero if the receiver should free the buffer.
+    pub owned: u8,
+}
+
+impl SzfBytes {
+    pub const fn empty() -> SzfBytes {
+        SzfBytes {
+            ptr: ptr::null(),
+            len: 0,
+            cap: 0,
+            owned: 0,
+        }
+    }
+
+    /// Borrow `bytes` for the duration of a call.
+    pub fn from_slice(bytes: &[u8]) -> SzfBytes {
+        SzfBytes {
+            ptr: if bytes.is_empty() {
+                ptr::null()
+            } else {
+                bytes.as_ptr()
+            },
+            len: bytes.len(),
+            cap: 0,
+            owned: 0,
+        }
+    }
+
+    /// View the buffer as a slice; null is treated as empty.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or valid for reads of `len` bytes for `'a`.
+    pub unsafe fn as_slice<'a>(&self) -> &'a [u8] {
+        crate::callback::bytes(self.ptr, self.len)
+    }
+}
+
+/// NUL-terminated C string with its length.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SzfString {
+    pub ptr: *const c_char,
+    pub len: usize,
+}
+
+/// Result passed to completion callbacks.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SzfResult {
+    /// Error code (0 = success).
+    pub code: i32,
+    /// Error message, NUL-terminated and owned by the library.
+    pub message: *const c_char,
+    /// Result data, if successful.
+    pub data: SzfBytes,
+}
+
+/// Opaque `SzfContext`.
+#[repr(C)]
+pub struct RawContext {
+    _private: [u8; 0],
+}
+
+pub type SzfProgressCallback =
+    unsafe extern "C" fn(current: usize, total: usize, context: *mut c_void) -> bool;
+pub type SzfResultCallback = unsafe extern "C" fn(result: SzfResult, context: *mut c_void);
+
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+extern "C" {
+    fn szf_version() -> u32;
+    fn szf_context_new() -> *mut RawContext;
+    fn szf_context_free(ctx: *mut RawContext);
+    fn szf_context_reset(ctx: *mut RawContext);
+    fn szf_context_get_error(ctx: *mut RawContext) -> *const c_char;
+    fn szf_process_data(
+        ctx: *mut RawContext,
+        input: SzfBytes,
+        progress_cb: Option<SzfProgressCallback>,
+        progress_ctx: *mut c_void,
+        result_cb: Option<SzfResultCallback>,
+        result_ctx: *mut c_void,
+    ) -> i32;
+    fn szf_transform_data(ctx: *mut RawContext, input: SzfBytes, out: *mut SzfBytes) -> i32;
+}
+
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+/// ABI version of the szf core, packed as `(major << 16) | (minor << 8) | patch`.
+pub fn version() -> u32 {
+    unsafe { szf_version() }
+}
+
+/// Errors reported by the szf core.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SzfError {
+    NullPointer,
+    InvalidUtf8,
+    AllocFailed,
+    InvalidLength,
+    NotFound,
+    AlreadyExists,
+    CallbackFailed,
+    NotImplemented,
+    /// The call was stopped through its [`CancellationToken`]. Reported by the
+    /// core as `SZF_ERR_CALLBACK_FAILED`, like any other refusing callback.
+    Cancelled,
+    /// The linked core's ABI version is not one this crate can use; see
+    /// [`abi::check_linked`](crate::abi::check_linked). The core has no code
+    /// for this, so it is reported as `SZF_ERR_NOT_IMPLEMENTED`.
+    Abi(AbiError),
+    /// A code this crate does not know about.
+    Foreign(i32),
+}
+
+impl SzfError {
+    /// Map a non-zero `SZF_ERR_*` code.
+    pub fn from_code(code: i32) -> SzfError {
+        match code {
+            SZF_ERR_NULL_PTR => SzfError::NullPointer,
+            SZF_ERR_INVALID_UTF8 => SzfError::InvalidUtf8,
+            SZF_ERR_ALLOC_FAILED => SzfError::AllocFailed,
+            SZF_ERR_INVALID_LENGTH => SzfError::InvalidLength,
+            SZF_ERR_NOT_FOUND => SzfError::NotFound,
+            SZF_ERR_ALREADY_EXISTS => SzfError::AlreadyExists,
+            SZF_ERR_CALLBACK_FAILED => SzfError::CallbackFailed,
+            SZF_ERR_NOT_IMPLEMENTED => SzfError::NotImplemented,
+            other => SzfError::Foreign(other),
+        }
+    }
+
+    /// The `SZF_ERR_*` code for this error.
+    pub fn code(self) -> i32 {
+        match self {
+            SzfError::NullPointer => SZF_ERR_NULL_PTR,
+            SzfError::InvalidUtf8 => SZF_ERR_INVALID_UTF8,
+            SzfError::AllocFailed => SZF_ERR_ALLOC_FAILED,
+            SzfError::InvalidLength => SZF_ERR_INVALID_LENGTH,
+            SzfError::NotFound => SZF_ERR_NOT_FOUND,
+            SzfError::AlreadyExists => SZF_ERR_ALREADY_EXISTS,
+            SzfError::CallbackFailed => SZF_ERR_CALLBACK_FAILED,
+            SzfError::NotImplemented => SZF_ERR_NOT_IMPLEMENTED,
+            SzfError::Cancelled => SZF_ERR_CALLBACK_FAILED,
+            SzfError::Abi(_) => SZF_ERR_NOT_IMPLEMENTED,
+            SzfError::Foreign(code) => code,
+        }
+    }
+}
+
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+fn check(code: i32) -> Result<(), SzfError> {
+    match code {
+        SZF_OK => Ok(()),
+        other => Err(SzfError::from_code(other)),
+    }
+}
+
+impl fmt::Display for SzfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SzfError::NullPointer => f.write_str("null pointer passed to the szf core"),
+            SzfError::InvalidUtf8 => f.write_str("invalid UTF-8"),
+            SzfError::AllocFailed => f.write_str("szf core allocation failed"),
+            SzfError::InvalidLength => f.write_str("invalid length"),
+            SzfError::NotFound => f.write_str("not found"),
+            SzfError::AlreadyExists => f.write_str("already exists"),
+            SzfError::CallbackFailed => f.write_str("callback failed"),
+            SzfError::NotImplemented => f.write_str("not implemented by the szf core"),
+            SzfError::Cancelled => f.write_str("cancelled"),
+            SzfError::Abi(err) => err.fmt(f),
+            SzfError::Foreign(code) => write!(f, "szf core returned error code {code}"),
+        }
+    }
+}
+
+impl Error for SzfError {}
+
+/// Shared flag asking a running call to stop.
+///
+/// Clones share the flag, so a token can be handed to the call while another
+/// thread keeps a clone to cancel it. The core checks it at every progress
+/// report (each 1 KiB for `szf_process_data`).
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+/// An `SzfContext`, freed on drop.
+///
+/// Output buffers live in the context's arena; the methods here copy them out,
+/// so returned data outlives [`Context::reset`].
+pub struct Context {
+    raw: NonNull<RawContext>,
+}
+
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+// The context holds no thread-local state; it is only unsafe to share.
+unsafe impl Send for Context {}
+
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+impl Context {
+    /// Fails with [`SzfError::Abi`] if the linked core has an ABI version
+    /// this crate cannot use. The version check runs on the first call only.
+    pub fn new() -> Result<Context, SzfError> {
+        static ABI: OnceLock<Result<(), AbiError>> = OnceLock::new();
+        (*ABI.get_or_init(crate::abi::check_linked)).map_err(SzfError::Abi)?;
+        NonNull::new(unsafe { szf_context_new() })
+            .map(|raw| Context { raw })
+            .ok_or(SzfError::AllocFailed)
+    }
+
+    /// Release arena allocations and clear the last error.
+    pub fn reset(&mut self) {
+        unsafe { szf_context_reset(self.raw.as_ptr()) }
+    }
+
+    /// Message describing the last failure, if the core recorded one.
+    pub fn last_error(&self) -> Option<String> {
+        let message = unsafe { szf_context_get_error(self.raw.as_ptr()) };
+        if message.is_null() {
+            return None;
+        }
+        Some(
+            unsafe { CStr::from_ptr(message) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    /// Run `szf_transform_data` (ASCII uppercase) over `input`.
+    pub fn transform(&mut self, input: &[u8]) -> Result<Vec<u8>, SzfError> {
+        let mut out = SzfBytes::empty();
+        check(unsafe {
+            szf_transform_data(self.raw.as_ptr(), SzfBytes::from_slice(input), &mut out)
+        })?;
+        Ok(unsafe { out.as_slice() }.to_vec())
+    }
+
+    /// Run `szf_process_data` over `input`, returning the data delivered to
+    /// its result callback.
+    pub fn process(&mut self, input: &[u8]) -> Result<Vec<u8>, SzfError> {
+        self.run(input, None)
+    }
+
+    /// Like [`Context::process`], calling `progress(current, total)` as the
+    /// core works through `input` and stopping with [`SzfError::Cancelled`]
+    /// once `token` is cancelled.
+    ///
+    /// A panic in `progress` stops the call with
+    /// [`SzfError::CallbackFailed`]; the message is available from
+    /// [`take_last_panic`](crate::guard::take_last_panic).
+    pub fn process_with(
+        &mut self,
+        input: &[u8],
+        mut progress: impl FnMut(usize, usize),
+        token: &CancellationToken,
+    ) -> Result<Vec<u8>, SzfError> {
+        if token.is_cancelled() {
+            return Err(SzfError::Cancelled);
+        }
+        let mut state = Progress {
+            callback: &mut progress,
+            token,
+            cancelled: false,
+        };
+        let result = self.run(input, Some(&mut state));
+        if state.cancelled {
+            return Err(SzfError::Cancelled);
+        }
+        result
+    }
+
+    fn run(
+        &mut self,
+        input: &[u8],
+        progress: Option<&mut Progress<'_>>,
+    ) -> Result<Vec<u8>, SzfError> {
+        let mut result: Option<Result<Vec<u8>, SzfError>> = None;
+        let (progress_cb, progress_ctx) = match progress {
+            Some(state) => (
+                Some(on_progress as SzfProgressCallback),
+                (state as *mut Progress<'_>).cast(),
+            ),
+            None => (None, ptr::null_mut()),
+        };
+        let code = unsafe {
+            szf_process_data(
+                self.raw.as_ptr(),
+                SzfBytes::from_slice(input),
+                progress_cb,
+                progress_ctx,
+                Some(on_result),
+                (&mut result as *mut Option<Result<Vec<u8>, SzfError>>).cast(),
+            )
+        };
+        check(code)?;
+        result.unwrap_or(Err(SzfError::CallbackFailed))
+    }
+
+    /// The underlying `SzfContext*`, still owned by `self`.
+    pub fn as_ptr(&self) -> *mut RawContext {
+        self.raw.as_ptr()
+    }
+}
+
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe { szf_context_free(self.raw.as_ptr()) }
+    }
+}
+
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Context").field(&self.raw).finish()
+    }
+}
+
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+/// State behind the progress trampoline of [`Context::process_with`].
+struct Progress<'a> {
+    callback: &'a mut dyn FnMut(usize, usize),
+    token: &'a CancellationToken,
+    cancelled: bool,
+}
+
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+/// Progress callback for [`Context::process_with`]; `context` points at its
+/// [`Progress`]. Returns `false` to stop the core.
+unsafe extern "C" fn on_progress(current: usize, total: usize, context: *mut c_void) -> bool {
+    crate::guard::contain(|| {
+        let state = &mut *context.cast::<Progress<'_>>();
+        if !state.token.is_cancelled() {
+            (state.callback)(current, total);
+        }
+        state.cancelled = state.token.is_cancelled();
+        !state.cancelled
+    })
+}
+
+#[cfg(any(zig_linked, not(feature = "pure-rust")))]
+/// Result callback for [`Context::process`]; `context` points at its
+/// `Option<Result<Vec<u8>, SzfError>>`.
+unsafe extern "C" fn on_result(result: SzfResult, context: *mut c_void) {
+    crate::guard::contain(|| {
+        let slot = &mut *context.cast::<Option<Result<Vec<u8>, SzfError>>>();
+        *slot = Some(match result.code {
+            SZF_OK => Ok(result.data.as_slice().to_vec()),
+            code => Err(SzfError::from_code(code)),
+        });
+    })
+}
+
+#[cfg(all(test, zig_linked))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_is_packed() {
+        assert_eq!(version() >> 16, 1);
+    }
+
+    #[test]
+    fn transform_uppercases_and_survives_reset() {
+        let mut ctx = Context::new().unwrap();
+        let out = ctx.transform(b"hello world").unwrap();
+        ctx.reset();
+        assert_eq!(out, b"HELLO WORLD");
+        assert_eq!(ctx.transform(b"").unwrap(), b"");
+    }
+
+    #[test]
+    fn process_returns_input_or_error_code() {
+        let mut ctx = Context::new().unwrap();
+        let input = vec![7u8; 3000];
+        assert_eq!(ctx.process(&input).unwrap(), input);
+        assert_eq!(ctx.last_error(), None);
+
+        assert_eq!(ctx.process(b""), Err(SzfError::InvalidLength));
+        assert_eq!(ctx.last_error().as_deref(), Some("empty input data"));
+        ctx.reset();
+        assert_eq!(ctx.last_error(), None);
+    }
+
+    #[test]
+    fn process_with_reports_progress_to_completion() {
+        let mut ctx = Context::new().unwrap();
+        let input = vec![1u8; 2500];
+        let mut seen = Vec::new();
+        let out = ctx
+            .process_with(
+                &input,
+                |cur, total| seen.push((cur, total)),
+                &CancellationToken::new(),
+            )
+            .unwrap();
+        assert_eq!(out, input);
+        assert_eq!(seen, [(1024, 2500), (2048, 2500), (2500, 2500)]);
+    }
+
+    #[test]
+    fn cancellation_stops_at_next_progress_report() {
+        let mut ctx = Context::new().unwrap();
+        let token = CancellationToken::new();
+        let mut calls = 0;
+        let result = ctx.process_with(
+            &vec![0u8; 1 << 20],
+            |_, _| {
+                calls += 1;
+                token.cancel();
+            },
+            &token,
+        );
+        assert_eq!(result, Err(SzfError::Cancelled));
+        assert_eq!(calls, 1);
+        assert_eq!(
+            ctx.process_with(b"data", |_, _| {}, &token),
+            Err(SzfError::Cancelled)
+        );
+    }
+
+    #[test]
+    fn panicking_progress_is_a_callback_failure() {
+        let mut ctx = Context::new().unwrap();
+        let result = ctx.process_with(
+            b"data",
+            |_, _| panic!("progress failed"),
+            &CancellationToken::new(),
+        );
+        assert_eq!(result, Err(SzfError::CallbackFailed));
+        assert_eq!(
+            crate::guard::take_last_panic().as_deref(),
+            Some("progress failed")
+        );
+        assert_eq!(ctx.last_error().as_deref(), Some("cancelled by user"));
+    }
+
+    #[test]
+    fn error_codes_round_trip() {
+        for code in [-1, -2, -3, -4, -5, -6, -7, -99, -42] {
+            assert_eq!(SzfError::from_code(code).code(), code);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Layout manifest of the extern structs the Rust crate mirrors.
+//!
+//! `build.rs` runs this as `zig run zig-lib/src/layout.zig -- OUTPUT`. It
+//! writes a Rust source file with one `Layout` per struct: its size,
+//! alignment, and the offset and size of every field, as Zig lays them out
+//! for the host. `src/layout.rs` includes the file and asserts the
+//! `#[repr(C)]` mirrors against it at compile time.
+
+const std = @import("std");
+const szf = @import("szf");
+const bebop = @import("bebop");
+const idris_rts = @import("idris_rts");
+
+/// The structs in the manifest, under the names the Rust mirrors use.
+const structs = .{
+    .{ "SzfBytes", szf.SzfBytes },
+    .{ "SzfString", szf.SzfString },
+    .{ "SzfResult", szf.SzfResult },
+    .{ "VBytes", bebop.VBytes },
+    .{ "VSensorReading", bebop.VSensorReading },
+    .{ "IdrisMaybeValue", idris_rts.IdrisMaybeValue },
+    .{ "IdrisEitherValue", idris_rts.IdrisEitherValue },
+};
+
+pub fn main() !void {
+    var gpa = std.heap.GeneralPurposeAllocator(.{}){};
+    defer _ = gpa.deinit();
+    const allocator = gpa.allocator();
+
+    const args = try std.process.argsAlloc(allocator);
+    defer std.process.argsFree(allocator, args);
+    if (args.len != 2) {
+        std.debug.print("usage: layout OUTPUT\n", .{});
+        std.process.exit(2);
+    }
+
+    const file = try std.fs.cwd().createFile(args[1], .{});
+    defer file.close();
+    try writeManifest(file.writer());
+}
+
+fn writeManifest(writer: anytype) !void {
+    try writer.writeAll("// Generated by zig-lib/src/layout.zig; do not edit.\n\n");
+    inline for (structs) |entry| {
+        try writeLayout(writer, entry[0], entry[1]);
+    }
+    try writer.writeAll("pub const ALL: &[(&str, Layout)] = &[\n");
+    inline for (structs) |entry| {
+        try writer.print("    (\"{s}\", {s}),\n", .{ entry[0], entry[0] });
+    }
+    try writer.writeAll("];\n");
+}
+
+fn writeLayout(writer: anytype, comptime name: []const u8, comptime T: type) !void {
+    try writer.print(
+        "pub const {s}: Layout = Layout {{\n    size: {d},\n    align: {d},\n    fields: &[\n",
+        .{ name, @sizeOf(T), @alignOf(T) },
+    );
+    inline for (@typeInfo(T).@"struct".fields) |field| {
+        try writer.print(
+            "        Field {{ name: \"{s}\", offset: {d}, size: {d} }},\n",
+            .{ field.name, @offsetOf(T, field.name), @sizeOf(field.type) },
+        );
+    }
+    try writer.writeAll("    ],\n};\n\n");
+}
+
+test "manifest lists every field" {
+    var out = std.ArrayList(u8).init(std.testing.allocator);
+    defer out.deinit();
+    try writeManifest(out.writer());
+
+    const sensor_type = try std.fmt.allocPrint(
+        std.testing.allocator,
+        "Field {{ name: \"sensor_type\", offset: {d}, size: 2 }}",
+        .{@offsetOf(bebop.VSensorReading, "sensor_type")},
+    );
+    defer std.testing.allocator.free(sensor_type);
+    try std.testing.expect(std.mem.indexOf(u8, out.items, sensor_type) != null);
+    try std.testing.expect(std.mem.indexOf(u8, out.items, "(\"IdrisEitherValue\", IdrisEitherValue),") != null);
+}
@@ -0,0 +1,427 @@
+// SPDX-License-Identifier: PMLP-1.0-or-later
+//! Rust-Zig FFI - Core Library
+//!
+//! Zig side of the Rust bridge. `build.rs` compiles this file with
+//! `zig build-lib` and links the result into the Rust crate; the matching
+//! declarations live in `src/backend/ffi.rs`.
+//!
+//! The Swift bridge's `szf_*` context API is compiled in as module `szf`
+//! (`bridges/swift/src/lib.zig`) so Rust can reuse the same core; see
+//! `src/szf.rs`.
+//!
+//! The Idris 2 bridge's allocator (`bridges/idris2/src/memory.zig`) is
+//! compiled in as module `idris2`. The `idris_*` exports below free through
+//! it, so Rust can own values that Idris code allocated; see
+//! `src/idris/memory.rs`.
+//!
+//! The polyglot bridge's `polyglot_extract` / `polyglot_free`
+//! (`bridges/polyglot/src/main.zig`) are compiled in as module `polyglot`;
+//! see `src/polyglot/mod.rs`.
+
+const std = @import("std");
+
+const HkdfSha256 = std.crypto.kdf.hkdf.HkdfSha256;
+const HkdfSha512 = std.crypto.kdf.hkdf.HkdfSha512;
+const HmacSha256 = std.crypto.auth.hmac.sha2.HmacSha256;
+const HmacSha512 = std.crypto.auth.hmac.sha2.HmacSha512;
+const pwhash = std.crypto.pwhash;
+const idris_memory = @import("idris2");
+
+// Re-export the szf_* C ABI from the shared Swift bridge core, and the
+// polyglot_* text extraction ABI.
+comptime {
+    _ = @import("szf");
+    _ = @import("polyglot");
+}
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// ABI version of the exports below, as returned by rzf_version. Bump the
+/// minor version when adding exports, the major version when changing one.
+pub const ABI_VERSION_MAJOR: u32 = 1;
+pub const ABI_VERSION_MINOR: u32 = 0;
+pub const ABI_VERSION_PATCH: u32 = 0;
+pub const ABI_VERSION: u32 = (ABI_VERSION_MAJOR << 16) | (ABI_VERSION_MINOR << 8) | ABI_VERSION_PATCH;
+
+/// Length of the key written by hkdf_derive
+pub const KEY_LENGTH: usize = 64;
+
+/// Hash selectors for hkdf_extract / hkdf_expand / kdf_pbkdf2
+pub const HKDF_SHA256: u32 = 0;
+pub const HKDF_SHA512: u32 = 1;
+
+// ============================================================================
+// Error Codes
+// ============================================================================
+
+pub const HKDF_OK: i32 = 0;
+pub const HKDF_ERR_INVALID_ALGORITHM: i32 = -1;
+pub const HKDF_ERR_INVALID_LENGTH: i32 = -2;
+pub const KDF_ERR_INVALID_PARAMS: i32 = -3;
+pub const KDF_ERR_ALLOC_FAILED: i32 = -4;
+pub const RZF_ERR_NULL_PTR: i32 = -5;
+pub const RZF_ERR_REGISTRY_FULL: i32 = -6;
+/// Returned by Rust exports whose body panicked; the message is available
+/// from `rzf_last_panic_message`.
+pub const RZF_ERR_PANIC: i32 = -7;
+
+// ============================================================================
+// Callback Types (Zig -> Rust)
+// ============================================================================
+
+/// Callback: Zig notifies Rust of data. `context` is whatever was passed to
+/// rzf_register_callback and is opaque to Zig.
+pub const RzfCallback = *const fn (context: ?*anyopaque, data: [*]const u8, len: usize) callconv(.c) void;
+
+pub const MAX_CALLBACKS: usize = 64;
+
+const CallbackSlot = struct {
+    callback: RzfCallback,
+    context: ?*anyopaque,
+};
+
+// ============================================================================
+// Global Callback Storage
+// ============================================================================
+
+var g_callbacks: [MAX_CALLBACKS]?CallbackSlot = [_]?CallbackSlot{null} ** MAX_CALLBACKS;
+var g_callbacks_lock: std.Thread.Mutex = .{};
+
+// ============================================================================
+// Exported C ABI Functions (Rust -> Zig)
+// ============================================================================
+
+/// Return ABI version for compatibility checks
+export fn rzf_version() callconv(.c) u32 {
+    return ABI_VERSION;
+}
+
+/// Derive a 64-byte key from password and salt (HKDF-SHA512, empty info).
+/// `key` must point to at least KEY_LENGTH writable bytes.
+export fn hkdf_derive(
+    password: [*]const u8,
+    password_len: usize,
+    salt: [*]const u8,
+    salt_len: usize,
+    key: [*]u8,
+) callconv(.c) void {
+    const prk = HkdfSha512.extract(salt[0..salt_len], password[0..password_len]);
+    HkdfSha512.expand(key[0..KEY_LENGTH], "", prk);
+}
+
+/// HKDF-Extract. Writes the hash-length PRK to `prk` (32 or 64 bytes).
+export fn hkdf_extract(
+    algorithm: u32,
+    salt: [*]const u8,
+    salt_len: usize,
+    ikm: [*]const u8,
+    ikm_len: usize,
+    prk: [*]u8,
+) callconv(.c) i32 {
+    return switch (algorithm) {
+        HKDF_SHA256 => extract(HkdfSha256, salt[0..salt_len], ikm[0..ikm_len], prk),
+        HKDF_SHA512 => extract(HkdfSha512, salt[0..salt_len], ikm[0..ikm_len], prk),
+        else => HKDF_ERR_INVALID_ALGORITHM,
+    };
+}
+
+/// HKDF-Expand. `prk_len` must equal the hash length and `okm_len` must not
+/// exceed 255 times the hash length.
+export fn hkdf_expand(
+    algorithm: u32,
+    prk: [*]const u8,
+    prk_len: usize,
+    info: [*]const u8,
+    info_len: usize,
+    okm: [*]u8,
+    okm_len: usize,
+) callconv(.c) i32 {
+    return switch (algorithm) {
+        HKDF_SHA256 => expand(HkdfSha256, prk[0..prk_len], info[0..info_len], okm[0..okm_len]),
+        HKDF_SHA512 => expand(HkdfSha512, prk[0..prk_len], info[0..info_len], okm[0..okm_len]),
+        else => HKDF_ERR_INVALID_ALGORITHM,
+    };
+}
+
+fn extract(comptime H: type, salt: []const u8, ikm: []const u8, prk: [*]u8) i32 {
+    prk[0..H.prk_length].* = H.extract(salt, ikm);
+    return HKDF_OK;
+}
+
+fn expand(comptime H: type, prk: []const u8, info: []const u8, okm: []u8) i32 {
+    if (prk.len != H.prk_length) return HKDF_ERR_INVALID_LENGTH;
+    if (okm.len > 255 * H.prk_length) return HKDF_ERR_INVALID_LENGTH;
+    H.expand(okm, info, prk[0..H.prk_length].*);
+    return HKDF_OK;
+}
+
+// ============================================================================
+// Password Hashing (Rust -> Zig)
+// ============================================================================
+
+/// PBKDF2-HMAC with the selected hash, filling `out[0..out_len]`.
+export fn kdf_pbkdf2(
+    algorithm: u32,
+    password: [*]const u8,
+    password_len: usize,
+    salt: [*]const u8,
+    salt_len: usize,
+    rounds: u32,
+    out: [*]u8,
+    out_len: usize,
+) callconv(.c) i32 {
+    const dk = out[0..out_len];
+    const pw = password[0..password_len];
+    const s = salt[0..salt_len];
+    const result = switch (algorithm) {
+        HKDF_SHA256 => pwhash.pbkdf2(dk, pw, s, rounds, HmacSha256),
+        HKDF_SHA512 => pwhash.pbkdf2(dk, pw, s, rounds, HmacSha512),
+        else => return HKDF_ERR_INVALID_ALGORITHM,
+    };
+    result catch return KDF_ERR_INVALID_PARAMS;
+    return HKDF_OK;
+}
+
+/// scrypt with N = 2^log_n, filling `out[0..out_len]`.
+export fn kdf_scrypt(
+    password: [*]const u8,
+    password_len: usize,
+    salt: [*]const u8,
+    salt_len: usize,
+    log_n: u8,
+    r: u32,
+    p: u32,
+    out: [*]u8,
+    out_len: usize,
+) callconv(.c) i32 {
+    if (log_n > 63 or r > std.math.maxInt(u30) or p > std.math.maxInt(u30)) return KDF_ERR_INVALID_PARAMS;
+    const params = pwhash.scrypt.Params{ .ln = @intCast(log_n), .r = @intCast(r), .p = @intCast(p) };
+    pwhash.scrypt.kdf(std.heap.page_allocator, out[0..out_len], password[0..password_len], salt[0..salt_len], params) catch |err| {
+        return if (err == error.OutOfMemory) KDF_ERR_ALLOC_FAILED else KDF_ERR_INVALID_PARAMS;
+    };
+    return HKDF_OK;
+}
+
+/// Argon2id (version 0x13), filling `out[0..out_len]`.
+export fn kdf_argon2id(
+    password: [*]const u8,
+    password_len: usize,
+    salt: [*]const u8,
+    salt_len: usize,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    out: [*]u8,
+    out_len: usize,
+) callconv(.c) i32 {
+    if (parallelism > std.math.maxInt(u24)) return KDF_ERR_INVALID_PARAMS;
+    const params = pwhash.argon2.Params{ .t = iterations, .m = memory_kib, .p = @intCast(parallelism) };
+    pwhash.argon2.kdf(std.heap.page_allocator, out[0..out_len], password[0..password_len], salt[0..salt_len], params, .argon2id) catch |err| {
+        return if (err == error.OutOfMemory) KDF_ERR_ALLOC_FAILED else KDF_ERR_INVALID_PARAMS;
+    };
+    return HKDF_OK;
+}
+
+// ============================================================================
+// Callback Registration (Rust -> Zig direction)
+// ============================================================================
+
+/// Register a callback to receive rzf_emit data
+export fn rzf_register_callback(callback: ?RzfCallback, context: ?*anyopaque) callconv(.c) i32 {
+    const cb = callback orelse return RZF_ERR_NULL_PTR;
+    g_callbacks_lock.lock();
+    defer g_callbacks_lock.unlock();
+    for (&g_callbacks) |*slot| {
+        if (slot.* == null) {
+            slot.* = .{ .callback = cb, .context = context };
+            return HKDF_OK;
+        }
+    }
+    return RZF_ERR_REGISTRY_FULL;
+}
+
+/// Remove every registration made with `context`
+export fn rzf_unregister_callback(context: ?*anyopaque) callconv(.c) void {
+    g_callbacks_lock.lock();
+    defer g_callbacks_lock.unlock();
+    for (&g_callbacks) |*slot| {
+        if (slot.*) |s| {
+            if (s.context == context) slot.* = null;
+        }
+    }
+}
+
+// ============================================================================
+// Callback Invocation (Zig -> Rust direction)
+// ============================================================================
+
+/// Deliver `data` to every registered callback; returns how many were called.
+/// The registry is snapshotted first, so callbacks may (un)register freely.
+export fn rzf_emit(data: [*]const u8, len: usize) callconv(.c) usize {
+    g_callbacks_lock.lock();
+    const snapshot = g_callbacks;
+    g_callbacks_lock.unlock();
+
+    var count: usize = 0;
+    for (snapshot) |slot| {
+        if (slot) |s| {
+            s.callback(s.context, data, len);
+            count += 1;
+        }
+    }
+    return count;
+}
+
+// ============================================================================
+// Idris 2 Memory (Rust -> Zig)
+// ============================================================================
+
+/// Allocate `size` bytes from the Idris 2 bridge allocator, the one behind
+/// `memory.alloc` and `Pool`. Returns null on failure.
+export fn idris_alloc(size: usize, alignment: usize) callconv(.c) ?*anyopaque {
+    const ptr = idris_memory.allocator.rawAlloc(size, .fromByteUnits(alignment), @returnAddress());
+    return @ptrCast(ptr);
+}
+
+/// Free memory from idris_alloc, `memory.alloc` or `Pool.create`. `size` and
+/// `alignment` must match the allocation.
+export fn idris_free(ptr: ?*anyopaque, size: usize, alignment: usize) callconv(.c) void {
+    const p: [*]u8 = @ptrCast(ptr orelse return);
+    idris_memory.allocator.rawFree(p[0..size], .fromByteUnits(alignment), @returnAddress());
+}
+
+/// Copy `len` bytes into a new Idris string buffer, as `toIdrisString` does.
+export fn idris_string_alloc(data: ?[*]const u8, len: usize) callconv(.c) ?[*]u8 {
+    const buf = idris_memory.allocator.alloc(u8, len) catch return null;
+    if (data) |d| @memcpy(buf, d[0..len]);
+    return buf.ptr;
+}
+
+/// Free the data of an Idris string, as `freeIdrisString` does.
+export fn idris_string_free(data: ?[*]u8, len: usize) callconv(.c) void {
+    const d = data orelse return;
+    idris_memory.allocator.free(d[0..len]);
+}
+
+/// Create an `Arena`. Returns null on failure.
+export fn idris_arena_new() callconv(.c) ?*anyopaque {
+    const arena = idris_memory.allocator.create(idris_memory.Arena) catch return null;
+    arena.* = idris_memory.Arena.init();
+    return arena;
+}
+
+/// Allocate `size` bytes from `arena`; freed by idris_arena_reset or
+/// idris_arena_free. Returns null on failure.
+export fn idris_arena_alloc(arena: *anyopaque, size: usize, alignment: usize) callconv(.c) ?*anyopaque {
+    const a: *idris_memory.Arena = @ptrCast(@alignCast(arena));
+    const ptr = a.arena.allocator().rawAlloc(size, .fromByteUnits(alignment), @returnAddress());
+    return @ptrCast(ptr);
+}
+
+/// Release every allocation in `arena`, keeping its capacity.
+export fn idris_arena_reset(arena: *anyopaque) callconv(.c) void {
+    const a: *idris_memory.Arena = @ptrCast(@alignCast(arena));
+    a.reset();
+}
+
+/// Destroy `arena` and everything allocated from it.
+export fn idris_arena_free(arena: ?*anyopaque) callconv(.c) void {
+    const a: *idris_memory.Arena = @ptrCast(@alignCast(arena orelse return));
+    a.deinit();
+    idris_memory.allocator.destroy(a);
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+test "version" {
+    try std.testing.expectEqual(@as(u32, 0x010000), rzf_version());
+}
+
+test "hkdf derivation" {
+    const password = "password";
+    const salt = "salt";
+    var key: [KEY_LENGTH]u8 = undefined;
+    hkdf_derive(password.ptr, password.len, salt.ptr, salt.len, &key);
+
+    var expected: [KEY_LENGTH]u8 = undefined;
+    _ = try std.fmt.hexToBytes(&expected, "dd1893e26644afff749b0e665ac7fda3b67ac4ebd9383de5f915f3df0cb8d39f9f07f79ea98dde92f997089a6fa6501ccbd29ccea55da02e9ce70774fe73018e");
+    try std.testing.expectEqualSlices(u8, &expected, &key);
+}
+
+test "rfc 5869 case 1" {
+    const ikm = [_]u8{0x0b} ** 22;
+    const salt = [_]u8{ 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c };
+    const info = [_]u8{ 0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9 };
+
+    var prk: [32]u8 = undefined;
+    try std.testing.expectEqual(HKDF_OK, hkdf_extract(HKDF_SHA256, &salt, salt.len, &ikm, ikm.len, &prk));
+
+    var okm: [42]u8 = undefined;
+    try std.testing.expectEqual(HKDF_OK, hkdf_expand(HKDF_SHA256, &prk, prk.len, &info, info.len, &okm, okm.len));
+
+    var expected: [42]u8 = undefined;
+    _ = try std.fmt.hexToBytes(&expected, "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865");
+    try std.testing.expectEqualSlices(u8, &expected, &okm);
+}
+
+test "expand rejects oversized output" {
+    const prk = [_]u8{0} ** 32;
+    var okm: [255 * 32 + 1]u8 = undefined;
+    try std.testing.expectEqual(HKDF_ERR_INVALID_LENGTH, hkdf_expand(HKDF_SHA256, &prk, prk.len, &prk, 0, &okm, okm.len));
+}
+
+test "pbkdf2 rfc 7914 vector" {
+    var dk: [64]u8 = undefined;
+    try std.testing.expectEqual(HKDF_OK, kdf_pbkdf2(HKDF_SHA256, "passwd", 6, "salt", 4, 1, &dk, dk.len));
+
+    var expected: [64]u8 = undefined;
+    _ = try std.fmt.hexToBytes(&expected, "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783");
+    try std.testing.expectEqualSlices(u8, &expected, &dk);
+}
+
+test "argon2id vector" {
+    var dk: [32]u8 = undefined;
+    try std.testing.expectEqual(HKDF_OK, kdf_argon2id("password", 8, "somesaltsomesalt", 16, 19456, 2, 1, &dk, dk.len));
+
+    var expected: [32]u8 = undefined;
+    _ = try std.fmt.hexToBytes(&expected, "2b5dc4054886ec957ef59c73b661c54dd6fb274590b278f657c6d96aac8fa6d1");
+    try std.testing.expectEqualSlices(u8, &expected, &dk);
+}
+
+var test_received: usize = 0;
+
+fn testCallback(context: ?*anyopaque, data: [*]const u8, len: usize) callconv(.c) void {
+    _ = context;
+    _ = data;
+    test_received += len;
+}
+
+test "callback registry" {
+    var marker: u8 = 0;
+    const ctx: *anyopaque = &marker;
+    try std.testing.expectEqual(HKDF_OK, rzf_register_callback(&testCallback, ctx));
+    try std.testing.expectEqual(@as(usize, 1), rzf_emit("hello", 5));
+    try std.testing.expectEqual(@as(usize, 5), test_received);
+
+    rzf_unregister_callback(ctx);
+    try std.testing.expectEqual(@as(usize, 0), rzf_emit("hello", 5));
+}
+
+test "idris memory" {
+    const p: *u64 = @ptrCast(@alignCast(idris_alloc(@sizeOf(u64), @alignOf(u64)).?));
+    p.* = 42;
+    idris_free(p, @sizeOf(u64), @alignOf(u64));
+
+    const s = idris_string_alloc("idris", 5).?;
+    try std.testing.expectEqualStrings("idris", s[0..5]);
+    idris_string_free(s, 5);
+
+    const arena = idris_arena_new().?;
+    defer idris_arena_free(arena);
+    try std.testing.expect(idris_arena_alloc(arena, 100, 8) != null);
+    idris_arena_reset(arena);
+}